@@ -81,8 +81,20 @@ fn main() {
         .allowlist_function("whisper_init_from_file_with_params")
         .allowlist_function("whisper_full_n_segments")
         .allowlist_function("whisper_full_get_segment_text")
+        .allowlist_function("whisper_full_get_segment_t0")
+        .allowlist_function("whisper_full_get_segment_t1")
+        .allowlist_function("whisper_full_n_tokens")
+        .allowlist_function("whisper_full_get_token_id")
+        .allowlist_function("whisper_full_get_token_p")
+        .allowlist_function("whisper_full_get_token_text")
+        .allowlist_function("whisper_token_eot")
+        .allowlist_function("whisper_lang_max_id")
+        .allowlist_function("whisper_lang_id")
+        .allowlist_function("whisper_lang_str")
         .allowlist_function("whisper_free")
         .allowlist_function("whisper_log_set")
+        .allowlist_function("whisper_version")
+        .allowlist_function("whisper_print_system_info")
         .allowlist_type("whisper_.*")
         .allowlist_type("ggml_.*")
         .allowlist_var("whisper_sampling_strategy_.*")
@@ -2,15 +2,59 @@ use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use ringbuf::{HeapConsumer, HeapProducer, HeapRb};
 use std::error::Error;
 use std::fmt;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use crate::types::PcmFormat;
+
 pub struct Capture {
     _stream: cpal::Stream,
     consumer: HeapConsumer<f32>,
     overflow: Arc<Mutex<Vec<f32>>>,
     overflow_count: Arc<AtomicUsize>,
+    format: CaptureFormat,
+    /// Mono audio at the device rate, before resampling, when requested.
+    native: Option<HeapConsumer<f32>>,
+    /// Set once the backend reports the device gone, e.g. an unplugged USB mic.
+    disconnected: Arc<AtomicBool>,
+}
+
+/// Stream format negotiated with the input device.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CaptureFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: cpal::SampleFormat,
+}
+
+/// One supported input config range, as reported by the backend.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct SupportedRange {
+    channels: u16,
+    min_rate: u32,
+    max_rate: u32,
+    sample_format: cpal::SampleFormat,
+}
+
+/// Linear interpolation resampler that keeps its phase across callbacks.
+/// When downsampling, the input is low-pass filtered first, so frequencies
+/// above the output's Nyquist limit do not fold back into speech.
+pub(crate) struct LinearResampler {
+    step: f64,
+    position: f64,
+    previous: Option<f32>,
+    anti_alias: Option<LowPass>,
+}
+
+/// Windowed-sinc FIR low-pass filter run over a stream sample by sample.
+struct LowPass {
+    taps: Vec<f32>,
+    /// Ring buffer of the last `taps.len()` inputs; `next` is the oldest.
+    history: Vec<f32>,
+    next: usize,
 }
 
 pub const DEFAULT_CHUNK_MS: u64 = 100;
@@ -34,10 +78,15 @@ pub(crate) struct SegmentInfo {
 pub enum AudioErrorKind {
     DeviceNotFound,
     DeviceUnavailable,
+    DeviceBusy,
     DeviceQuery,
     StreamConfig,
     StreamBuild,
     StreamStart,
+    /// The stream started but delivered no frames.
+    NoInput,
+    /// An audio file could not be opened or decoded.
+    InputFile,
 }
 
 #[derive(Debug)]
@@ -53,6 +102,32 @@ impl AudioError {
             message: message.into(),
         }
     }
+
+    /// Device busy or disconnected; a later attempt may succeed without config changes.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self.kind,
+            AudioErrorKind::DeviceBusy
+                | AudioErrorKind::DeviceUnavailable
+                | AudioErrorKind::NoInput
+        )
+    }
+
+    /// Names the device in busy/unavailable errors and says what to check.
+    fn with_device(self, device: &str) -> Self {
+        let message = match self.kind {
+            AudioErrorKind::DeviceBusy => format!(
+                "input device {device} is busy; another application may be using it exclusively ({})",
+                self.message
+            ),
+            AudioErrorKind::DeviceUnavailable => format!(
+                "input device {device} is unavailable; check that it is still connected ({})",
+                self.message
+            ),
+            _ => return self,
+        };
+        Self::new(self.kind, message)
+    }
 }
 
 impl fmt::Display for AudioError {
@@ -97,6 +172,11 @@ pub fn list_input_devices(host: &cpal::Host) -> Result<Vec<String>, AudioError>
         })?;
         names.push(name);
     }
+    for source in monitor_sources() {
+        if !names.contains(&source) {
+            names.push(source);
+        }
+    }
 
     if names.is_empty() {
         return Err(AudioError::new(
@@ -108,6 +188,54 @@ pub fn list_input_devices(host: &cpal::Host) -> Result<Vec<String>, AudioError>
     Ok(names)
 }
 
+/// PulseAudio and PipeWire name the source that captures a sink's output
+/// `<sink>.monitor`.
+pub fn is_monitor_source(name: &str) -> bool {
+    name.ends_with(".monitor")
+}
+
+/// Points the ALSA `pulse` device at `device` when it names a monitor source.
+/// The pulse plugin records from the source in `PULSE_SOURCE`, which
+/// PipeWire's pulse server honours as well. Setting the environment is only
+/// sound while no other thread runs, so call this once at startup.
+pub fn use_monitor_source(device: Option<&str>) {
+    if let Some(source) = device.filter(|name| is_monitor_source(name)) {
+        std::env::set_var("PULSE_SOURCE", source);
+    }
+}
+
+/// `name` as device listings show it, with monitor sources flagged.
+pub fn describe_device(name: &str) -> String {
+    if is_monitor_source(name) {
+        format!("{name} (monitor)")
+    } else {
+        name.to_string()
+    }
+}
+
+/// Monitor sources the sound server offers, or none when `pactl` is missing
+/// or no PulseAudio-compatible server runs. ALSA does not list them itself.
+fn monitor_sources() -> Vec<String> {
+    Command::new("pactl")
+        .args(["list", "short", "sources"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| parse_monitor_sources(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+/// Picks the monitor source names out of `pactl list short sources`, whose
+/// lines are tab-separated `id name driver format state`.
+fn parse_monitor_sources(listing: &str) -> Vec<String> {
+    listing
+        .lines()
+        .filter_map(|line| line.split('\t').nth(1))
+        .filter(|name| is_monitor_source(name))
+        .map(str::to_string)
+        .collect()
+}
+
 pub fn configure_alsa_logging(debug_audio: bool) {
     #[cfg(target_os = "linux")]
     {
@@ -144,10 +272,18 @@ unsafe extern "C" fn silence_alsa_error(
 ) {
 }
 
+/// Opens `device_name` (or the default input) and streams mono samples at `sample_rate`.
+///
+/// Multi-channel input is averaged to mono unless `input_channel` picks a
+/// single zero-based channel. With `keep_native`, the stream opens at the
+/// device's default rate instead and a mono copy at that rate is kept next
+/// to the resampled stream; see [`drain_native_samples`].
 pub fn start_capture(
     host: &cpal::Host,
     device_name: Option<&str>,
     sample_rate: u32,
+    input_channel: Option<usize>,
+    keep_native: bool,
 ) -> Result<Capture, AudioError> {
     let device = select_input_device(host, device_name)?;
     let device_label = device.name().map_err(|err| {
@@ -158,87 +294,162 @@ pub fn start_capture(
     })?;
     println!("Selected input device: {device_label}");
 
-    let (stream_config, sample_format) = select_stream_config(&device, sample_rate)?;
-    println!(
-        "Input stream: {:?}, {} ch, {} Hz",
-        sample_format, stream_config.channels, stream_config.sample_rate.0
-    );
+    let preferred_rate = if keep_native {
+        device
+            .default_input_config()
+            .map_or(sample_rate, |config| config.sample_rate().0)
+    } else {
+        sample_rate
+    };
+    let format = select_stream_config(&device, preferred_rate)
+        .map_err(|err| err.with_device(&device_label))?;
+    if let Some(channel) = input_channel {
+        if channel >= format.channels as usize {
+            return Err(AudioError::new(
+                AudioErrorKind::StreamConfig,
+                format!(
+                    "input channel {channel} is not available; {device_label} has {} channels",
+                    format.channels
+                ),
+            ));
+        }
+    }
+    let stream_config = cpal::StreamConfig {
+        channels: format.channels,
+        sample_rate: cpal::SampleRate(format.sample_rate),
+        buffer_size: cpal::BufferSize::Default,
+    };
+    let sample_format = format.sample_format;
+    let resampler = (format.sample_rate != sample_rate)
+        .then(|| LinearResampler::new(format.sample_rate, sample_rate));
+    if resampler.is_some() {
+        println!(
+            "Input stream: {:?}, {} ch, {} Hz (resampling to {} Hz)",
+            sample_format, format.channels, format.sample_rate, sample_rate
+        );
+    } else {
+        println!(
+            "Input stream: {:?}, {} ch, {} Hz",
+            sample_format, format.channels, format.sample_rate
+        );
+    }
 
     let ring = HeapRb::<f32>::new(sample_rate as usize * 30);
     let (producer, consumer) = ring.split();
+    let (native_producer, native) = if keep_native && format.sample_rate != sample_rate {
+        let (producer, consumer) = HeapRb::<f32>::new(format.sample_rate as usize * 30).split();
+        (Some(producer), Some(consumer))
+    } else {
+        (None, None)
+    };
     let overflow = Arc::new(Mutex::new(Vec::new()));
     let overflow_count = Arc::new(AtomicUsize::new(0));
+    let disconnected = Arc::new(AtomicBool::new(false));
 
     let stream = match sample_format {
         cpal::SampleFormat::F32 => build_input_stream::<f32>(
             &device,
             &stream_config,
             producer,
+            native_producer,
+            input_channel,
+            resampler,
             &overflow,
             &overflow_count,
-        )?,
+            &disconnected,
+        ),
         cpal::SampleFormat::F64 => build_input_stream::<f64>(
             &device,
             &stream_config,
             producer,
+            native_producer,
+            input_channel,
+            resampler,
             &overflow,
             &overflow_count,
-        )?,
+            &disconnected,
+        ),
         cpal::SampleFormat::I8 => build_input_stream::<i8>(
             &device,
             &stream_config,
             producer,
+            native_producer,
+            input_channel,
+            resampler,
             &overflow,
             &overflow_count,
-        )?,
+            &disconnected,
+        ),
         cpal::SampleFormat::I16 => build_input_stream::<i16>(
             &device,
             &stream_config,
             producer,
+            native_producer,
+            input_channel,
+            resampler,
             &overflow,
             &overflow_count,
-        )?,
+            &disconnected,
+        ),
         cpal::SampleFormat::I32 => build_input_stream::<i32>(
             &device,
             &stream_config,
             producer,
+            native_producer,
+            input_channel,
+            resampler,
             &overflow,
             &overflow_count,
-        )?,
+            &disconnected,
+        ),
         cpal::SampleFormat::U8 => build_input_stream::<u8>(
             &device,
             &stream_config,
             producer,
+            native_producer,
+            input_channel,
+            resampler,
             &overflow,
             &overflow_count,
-        )?,
+            &disconnected,
+        ),
         cpal::SampleFormat::U16 => build_input_stream::<u16>(
             &device,
             &stream_config,
             producer,
+            native_producer,
+            input_channel,
+            resampler,
             &overflow,
             &overflow_count,
-        )?,
+            &disconnected,
+        ),
         cpal::SampleFormat::U32 => build_input_stream::<u32>(
             &device,
             &stream_config,
             producer,
+            native_producer,
+            input_channel,
+            resampler,
             &overflow,
             &overflow_count,
-        )?,
-        format => {
-            return Err(AudioError::new(
-                AudioErrorKind::StreamConfig,
-                format!("unsupported sample format: {format:?}"),
-            ));
-        }
+            &disconnected,
+        ),
+        format => Err(AudioError::new(
+            AudioErrorKind::StreamConfig,
+            format!("unsupported sample format: {format:?}"),
+        )),
     };
+    let stream = stream.map_err(|err| err.with_device(&device_label))?;
 
     stream.play().map_err(|err| {
+        let not_available = matches!(err, cpal::PlayStreamError::DeviceNotAvailable);
+        let message = format!("failed to start input stream: {err}");
         AudioError::new(
-            AudioErrorKind::StreamStart,
-            format!("failed to start input stream: {err}"),
+            open_error_kind(not_available, &message, AudioErrorKind::StreamStart),
+            message,
         )
+        .with_device(&device_label)
     })?;
 
     Ok(Capture {
@@ -246,9 +457,39 @@ pub fn start_capture(
         consumer,
         overflow,
         overflow_count,
+        format,
+        native,
+        disconnected,
     })
 }
 
+impl Capture {
+    /// The device format the stream was opened with, before downmix and resampling.
+    pub fn format(&self) -> CaptureFormat {
+        self.format
+    }
+
+    /// Rate of the device-rate copy, when the capture keeps one.
+    pub fn native_rate(&self) -> Option<u32> {
+        self.native.as_ref().map(|_| self.format.sample_rate)
+    }
+
+    /// Whether the device went away while the stream was open. The stream
+    /// delivers nothing more after that.
+    pub fn disconnected(&self) -> bool {
+        self.disconnected.load(Ordering::Acquire)
+    }
+}
+
+/// Moves the device-rate copy captured so far into `output`. The copy is
+/// for archiving only: when its ring fills up, new audio is dropped rather
+/// than held back like the resampled stream.
+pub fn drain_native_samples(capture: &mut Capture, output: &mut Vec<f32>) {
+    if let Some(native) = capture.native.as_mut() {
+        output.extend(native.pop_iter());
+    }
+}
+
 pub fn drain_samples(capture: &mut Capture, output: &mut Vec<f32>) {
     while let Some(sample) = capture.consumer.pop() {
         output.push(sample);
@@ -265,13 +506,11 @@ pub fn drain_samples(capture: &mut Capture, output: &mut Vec<f32>) {
     }
 }
 
-pub(crate) fn trim_trailing_silence(
-    samples: &[f32],
-    sample_rate: u32,
-    vad: &VadConfig,
-) -> Vec<f32> {
+/// Returns where trailing silence starts, so callers can slice it off
+/// without copying the recording.
+pub(crate) fn trailing_silence_start(samples: &[f32], sample_rate: u32, vad: &VadConfig) -> usize {
     if samples.is_empty() || !vad.enabled {
-        return samples.to_vec();
+        return samples.len();
     }
 
     let chunk_samples = duration_to_samples(sample_rate, vad.chunk_size).max(1);
@@ -293,7 +532,143 @@ pub(crate) fn trim_trailing_silence(
         }
     }
 
-    samples[..end].to_vec()
+    end
+}
+
+/// Cuts quiet audio from both ends of `samples`, keeping `edge_pad_ms` of it
+/// around the first and last 10 ms frame whose RMS reaches `threshold`. The
+/// middle is left alone, and no VAD settings are needed. A recording with no
+/// loud frame trims to nothing.
+pub fn trim_silence(samples: &[f32], sample_rate: u32, threshold: f32, edge_pad_ms: u64) -> &[f32] {
+    let frame = duration_to_samples(sample_rate, Duration::from_millis(10)).max(1);
+    let loud = |(_, chunk): &(usize, &[f32])| rms_energy(chunk) >= threshold;
+    let Some((first, _)) = samples.chunks(frame).enumerate().find(loud) else {
+        return &[];
+    };
+    let (last, last_chunk) = samples
+        .chunks(frame)
+        .enumerate()
+        .rev()
+        .find(loud)
+        .unwrap_or((first, &[]));
+    let pad = duration_to_samples(sample_rate, Duration::from_millis(edge_pad_ms));
+    let start = (first * frame).saturating_sub(pad);
+    let end = (last * frame + last_chunk.len())
+        .saturating_add(pad)
+        .min(samples.len());
+    &samples[start..end]
+}
+
+/// Returns `(start, end)` sample ranges whose chunk energy reaches the VAD threshold.
+pub(crate) fn speech_segments(
+    samples: &[f32],
+    sample_rate: u32,
+    vad: &VadConfig,
+) -> Vec<(usize, usize)> {
+    let chunk_samples = duration_to_samples(sample_rate, vad.chunk_size).max(1);
+    let mut segments: Vec<(usize, usize)> = Vec::new();
+    for (index, chunk) in samples.chunks(chunk_samples).enumerate() {
+        if rms_energy(chunk) < vad.energy_threshold {
+            continue;
+        }
+        let start = index * chunk_samples;
+        let end = start + chunk.len();
+        match segments.last_mut() {
+            Some(last) if last.1 == start => last.1 = end,
+            _ => segments.push((start, end)),
+        }
+    }
+    segments
+}
+
+/// Splits a recording into consecutive `(start, end)` pieces at pauses of at
+/// least `min_pause_ms`, cutting in the middle of each pause so no audio is
+/// dropped. A cut that would leave a piece shorter than `min_piece_ms` is
+/// skipped, keeping that stretch joined to its neighbour.
+pub(crate) fn split_at_pauses(
+    samples: &[f32],
+    sample_rate: u32,
+    vad: &VadConfig,
+    min_pause_ms: u64,
+    min_piece_ms: u64,
+) -> Vec<(usize, usize)> {
+    let min_pause = duration_to_samples(sample_rate, Duration::from_millis(min_pause_ms));
+    let min_piece = duration_to_samples(sample_rate, Duration::from_millis(min_piece_ms));
+    let speech = speech_segments(samples, sample_rate, vad);
+    let mut cuts: Vec<usize> = Vec::new();
+    for pair in speech.windows(2) {
+        let (gap_start, gap_end) = (pair[0].1, pair[1].0);
+        let cut = gap_start + (gap_end - gap_start) / 2;
+        let previous = cuts.last().copied().unwrap_or(0);
+        if gap_end - gap_start >= min_pause && cut - previous >= min_piece {
+            cuts.push(cut);
+        }
+    }
+    if cuts
+        .last()
+        .is_some_and(|&cut| samples.len() - cut < min_piece)
+    {
+        cuts.pop();
+    }
+
+    let mut pieces = Vec::with_capacity(cuts.len() + 1);
+    let mut start = 0;
+    for cut in cuts {
+        pieces.push((start, cut));
+        start = cut;
+    }
+    if start < samples.len() {
+        pieces.push((start, samples.len()));
+    }
+    pieces
+}
+
+/// Keeps only the given speech segments, each widened by `pad_ms` on both sides.
+/// Padded ranges are clamped to the buffer and overlapping ranges are merged.
+pub fn trim_to_speech(
+    samples: &[f32],
+    sample_rate: u32,
+    segments: &[(usize, usize)],
+    pad_ms: u64,
+) -> Vec<f32> {
+    let pad = duration_to_samples(sample_rate, Duration::from_millis(pad_ms));
+    let len = samples.len();
+    let mut ranges: Vec<(usize, usize)> = segments
+        .iter()
+        .map(|&(start, end)| {
+            (
+                start.saturating_sub(pad).min(len),
+                end.saturating_add(pad).min(len),
+            )
+        })
+        .filter(|(start, end)| start < end)
+        .collect();
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    merged
+        .iter()
+        .flat_map(|&(start, end)| samples[start..end].iter().copied())
+        .collect()
+}
+
+/// Scales samples by `gain_db` decibels, clamping to [-1.0, 1.0] so a
+/// boosted peak clips instead of wrapping into noise.
+pub fn apply_gain(samples: &mut [f32], gain_db: f32) {
+    if gain_db == 0.0 {
+        return;
+    }
+    let factor = 10f32.powf(gain_db / 20.0);
+    for sample in samples {
+        *sample = (*sample * factor).clamp(-1.0, 1.0);
+    }
 }
 
 pub fn rms_energy(samples: &[f32]) -> f32 {
@@ -372,6 +747,73 @@ fn duration_to_samples(sample_rate: u32, duration: Duration) -> usize {
     (sample_rate as f32 * seconds).round() as usize
 }
 
+/// Converts raw little-endian mono PCM to normalized `f32` samples.
+pub fn decode_pcm(bytes: &[u8], format: PcmFormat) -> Result<Vec<f32>, AudioError> {
+    let width = match format {
+        PcmFormat::F32le => 4,
+        PcmFormat::S16le => 2,
+    };
+    if bytes.len() % width != 0 {
+        return Err(AudioError::new(
+            AudioErrorKind::StreamConfig,
+            format!(
+                "PCM input is {} bytes, not a whole number of {width}-byte {format:?} samples",
+                bytes.len()
+            ),
+        ));
+    }
+    let samples = bytes.chunks_exact(width);
+    Ok(match format {
+        PcmFormat::F32le => samples
+            .map(|sample| f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]))
+            .collect(),
+        PcmFormat::S16le => samples
+            .map(|sample| f32::from(i16::from_le_bytes([sample[0], sample[1]])) / 32_768.0)
+            .collect(),
+    })
+}
+
+/// Reads a WAV file as mono `f32` samples at `sample_rate`, averaging
+/// channels and resampling as needed.
+pub fn read_wav(path: &Path, sample_rate: u32) -> Result<Vec<f32>, AudioError> {
+    let file_error = |err: hound::Error| {
+        AudioError::new(
+            AudioErrorKind::InputFile,
+            format!("failed to read {}: {err}", path.display()),
+        )
+    };
+    let mut reader = hound::WavReader::open(path).map_err(file_error)?;
+    let spec = reader.spec();
+    let interleaved = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(file_error)?,
+        hound::SampleFormat::Int => {
+            let scale = (1_i64 << (spec.bits_per_sample.max(1) - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 / scale))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(file_error)?
+        }
+    };
+
+    let mut mono = Vec::with_capacity(interleaved.len() / usize::from(spec.channels.max(1)));
+    frames_to_mono(&interleaved, usize::from(spec.channels), None, &mut mono);
+    Ok(resample(&mono, spec.sample_rate, sample_rate))
+}
+
+/// Resamples a complete mono buffer from `from` Hz to `to` Hz.
+pub fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to {
+        return samples.to_vec();
+    }
+    let mut resampled = Vec::new();
+    LinearResampler::new(from, to).process(samples, &mut resampled);
+    resampled
+}
+
 pub fn samples_to_ms(samples: usize, sample_rate: u32) -> u64 {
     if sample_rate == 0 {
         return 0;
@@ -379,6 +821,90 @@ pub fn samples_to_ms(samples: usize, sample_rate: u32) -> u64 {
     ((samples as f32 / sample_rate as f32) * 1000.0).round() as u64
 }
 
+impl LinearResampler {
+    pub(crate) fn new(from_rate: u32, to_rate: u32) -> Self {
+        let step = from_rate as f64 / to_rate.max(1) as f64;
+        // The cutoff sits below the output Nyquist frequency (0.5 / step of
+        // the input rate) by about half the filter's transition band.
+        let anti_alias = (step > 1.0).then(|| {
+            let len = (32.0 * step).ceil() as usize | 1;
+            LowPass::new(0.4 / step, len)
+        });
+        Self {
+            step,
+            position: 0.0,
+            previous: None,
+            anti_alias,
+        }
+    }
+
+    pub(crate) fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        for &sample in input {
+            let sample = match self.anti_alias.as_mut() {
+                Some(filter) => filter.filter(sample),
+                None => sample,
+            };
+            let Some(previous) = self.previous else {
+                self.previous = Some(sample);
+                continue;
+            };
+            while self.position < 1.0 {
+                output.push(previous + (sample - previous) * self.position as f32);
+                self.position += self.step;
+            }
+            self.position -= 1.0;
+            self.previous = Some(sample);
+        }
+    }
+}
+
+impl LowPass {
+    /// `cutoff` is a fraction of the sample rate; `len` should be odd so the
+    /// filter delays every frequency by a whole number of samples.
+    fn new(cutoff: f64, len: usize) -> Self {
+        use std::f64::consts::PI;
+
+        let center = (len - 1) as f64 / 2.0;
+        let taps: Vec<f64> = (0..len)
+            .map(|index| {
+                let offset = index as f64 - center;
+                let sinc = if offset == 0.0 {
+                    2.0 * cutoff
+                } else {
+                    (2.0 * PI * cutoff * offset).sin() / (PI * offset)
+                };
+                let phase = 2.0 * PI * index as f64 / (len - 1).max(1) as f64;
+                let blackman = 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos();
+                sinc * blackman
+            })
+            .collect();
+        // Unity gain at DC, so levels and the VAD threshold are unchanged.
+        let sum: f64 = taps.iter().sum();
+        Self {
+            taps: taps.iter().map(|tap| (tap / sum) as f32).collect(),
+            history: Vec::new(),
+            next: 0,
+        }
+    }
+
+    fn filter(&mut self, sample: f32) -> f32 {
+        if self.history.is_empty() {
+            // Starting from the first sample instead of silence avoids a
+            // click at the start of the stream.
+            self.history = vec![sample; self.taps.len()];
+        }
+        self.history[self.next] = sample;
+        self.next = (self.next + 1) % self.history.len();
+        let (newest, oldest) = self.history.split_at(self.next);
+        oldest
+            .iter()
+            .chain(newest)
+            .zip(&self.taps)
+            .map(|(sample, tap)| sample * tap)
+            .sum()
+    }
+}
+
 fn select_input_device(host: &cpal::Host, name: Option<&str>) -> Result<cpal::Device, AudioError> {
     if let Some(target) = name {
         let target_lower = target.to_lowercase();
@@ -389,6 +915,7 @@ fn select_input_device(host: &cpal::Host, name: Option<&str>) -> Result<cpal::De
             )
         })?;
 
+        let mut pulse = None;
         for device in devices {
             let device_name = device.name().map_err(|err| {
                 AudioError::new(
@@ -399,6 +926,29 @@ fn select_input_device(host: &cpal::Host, name: Option<&str>) -> Result<cpal::De
             if device_name.to_lowercase() == target_lower {
                 return Ok(device);
             }
+            if device_name == "pulse" {
+                pulse = Some(device);
+            }
+        }
+
+        if is_monitor_source(target) {
+            if std::env::var_os("PULSE_SOURCE").as_deref() != Some(target.as_ref()) {
+                return Err(AudioError::new(
+                    AudioErrorKind::DeviceNotFound,
+                    format!(
+                        "monitor source {target} was not the device when sv started; restart sv to capture from it"
+                    ),
+                ));
+            }
+            let pulse = pulse.ok_or_else(|| {
+                AudioError::new(
+                    AudioErrorKind::DeviceNotFound,
+                    format!(
+                        "monitor source {target} needs the ALSA pulse device, which is not available"
+                    ),
+                )
+            })?;
+            return Ok(pulse);
         }
 
         return Err(AudioError::new(
@@ -418,47 +968,58 @@ fn select_input_device(host: &cpal::Host, name: Option<&str>) -> Result<cpal::De
 fn select_stream_config(
     device: &cpal::Device,
     sample_rate: u32,
-) -> Result<(cpal::StreamConfig, cpal::SampleFormat), AudioError> {
+) -> Result<CaptureFormat, AudioError> {
     let configs = device.supported_input_configs().map_err(|err| {
+        let not_available = matches!(err, cpal::SupportedStreamConfigsError::DeviceNotAvailable);
+        let message = format!("failed to query input configs: {err}");
         AudioError::new(
-            AudioErrorKind::StreamConfig,
-            format!("failed to query input configs: {err}"),
+            open_error_kind(not_available, &message, AudioErrorKind::StreamConfig),
+            message,
         )
     })?;
+    let ranges: Vec<SupportedRange> = configs
+        .map(|config| SupportedRange {
+            channels: config.channels(),
+            min_rate: config.min_sample_rate().0,
+            max_rate: config.max_sample_rate().0,
+            sample_format: config.sample_format(),
+        })
+        .collect();
 
-    let mut best: Option<(cpal::StreamConfig, cpal::SampleFormat)> = None;
-    let mut best_rank = 0u8;
-    for config in configs {
-        let min_rate = config.min_sample_rate().0;
-        let max_rate = config.max_sample_rate().0;
-        if sample_rate < min_rate || sample_rate > max_rate {
-            continue;
-        }
-
-        let channels = config.channels();
-        let stream_config = cpal::StreamConfig {
-            channels,
-            sample_rate: cpal::SampleRate(sample_rate),
-            buffer_size: cpal::BufferSize::Default,
-        };
-
-        let format = config.sample_format();
-        let candidate = (stream_config, format);
-        let rank = sample_format_rank(format);
-        if best.is_none() || rank > best_rank {
-            best = Some(candidate);
-            best_rank = rank;
-        }
-    }
-
-    best.ok_or_else(|| {
+    negotiate_format(&ranges, sample_rate).ok_or_else(|| {
         AudioError::new(
             AudioErrorKind::StreamConfig,
-            format!("no input config supports {sample_rate} Hz"),
+            "device reported no input configs",
         )
     })
 }
 
+/// Picks the requested rate when any config supports it, preferring mono and then
+/// the richest sample format. Otherwise falls back to the closest supported rate,
+/// which the capture callback resamples.
+fn negotiate_format(ranges: &[SupportedRange], sample_rate: u32) -> Option<CaptureFormat> {
+    ranges
+        .iter()
+        .filter(|range| range.channels > 0 && range.min_rate <= range.max_rate)
+        .map(|range| {
+            let rate = sample_rate.clamp(range.min_rate, range.max_rate);
+            let format = CaptureFormat {
+                sample_rate: rate,
+                channels: range.channels,
+                sample_format: range.sample_format,
+            };
+            let key = (
+                std::cmp::Reverse(rate.abs_diff(sample_rate)),
+                range.channels == 1,
+                sample_format_rank(range.sample_format),
+                std::cmp::Reverse(range.channels),
+            );
+            (key, format)
+        })
+        .max_by_key(|(key, _)| *key)
+        .map(|(_, format)| format)
+}
+
 fn sample_format_rank(format: cpal::SampleFormat) -> u8 {
     match format {
         cpal::SampleFormat::F32 => 6,
@@ -472,12 +1033,52 @@ fn sample_format_rank(format: cpal::SampleFormat) -> u8 {
     }
 }
 
+/// Converts interleaved frames to mono, either by averaging every channel or
+/// by taking `input_channel` alone. Returns the number of trailing samples
+/// that did not fill a whole frame; they are dropped, since averaging them
+/// would mix up channels.
+fn frames_to_mono<T>(
+    data: &[T],
+    channels: usize,
+    input_channel: Option<usize>,
+    out: &mut Vec<f32>,
+) -> usize
+where
+    T: cpal::Sample,
+    f32: cpal::FromSample<T>,
+{
+    if channels <= 1 {
+        out.extend(data.iter().map(|sample| sample.to_sample::<f32>()));
+        return 0;
+    }
+    let frames = data.chunks_exact(channels);
+    let partial = frames.remainder().len();
+    for frame in frames {
+        match input_channel.and_then(|channel| frame.get(channel)) {
+            Some(sample) => out.push(sample.to_sample::<f32>()),
+            None => {
+                let mut sum = 0.0f32;
+                for sample in frame {
+                    sum += sample.to_sample::<f32>();
+                }
+                out.push(sum / frame.len() as f32);
+            }
+        }
+    }
+    partial
+}
+
+#[allow(clippy::too_many_arguments)]
 fn build_input_stream<T>(
     device: &cpal::Device,
     config: &cpal::StreamConfig,
     mut producer: HeapProducer<f32>,
+    mut native: Option<HeapProducer<f32>>,
+    input_channel: Option<usize>,
+    mut resampler: Option<LinearResampler>,
     overflow: &Arc<Mutex<Vec<f32>>>,
     overflow_count: &Arc<AtomicUsize>,
+    disconnected: &Arc<AtomicBool>,
 ) -> Result<cpal::Stream, AudioError>
 where
     T: cpal::Sample + cpal::SizedSample,
@@ -486,35 +1087,42 @@ where
     let channels = config.channels as usize;
     let overflow = Arc::clone(overflow);
     let overflow_count = Arc::clone(overflow_count);
+    let disconnected = Arc::clone(disconnected);
     let mut overflow_scratch = Vec::new();
+    let mut mono_scratch = Vec::new();
+    let mut resampled_scratch = Vec::new();
+    let mut warned_partial_frame = false;
     device
         .build_input_stream(
             config,
             move |data: &[T], _| {
+                mono_scratch.clear();
+                let partial = frames_to_mono(data, channels, input_channel, &mut mono_scratch);
+                if partial > 0 && !warned_partial_frame {
+                    warned_partial_frame = true;
+                    eprintln!(
+                        "audio input buffer of {} samples is not a whole number of {channels}-channel frames; dropping {partial} samples",
+                        data.len()
+                    );
+                }
+                if let Some(native) = native.as_mut() {
+                    native.push_slice(&mono_scratch);
+                }
+                let samples = match resampler.as_mut() {
+                    Some(resampler) => {
+                        resampled_scratch.clear();
+                        resampler.process(&mono_scratch, &mut resampled_scratch);
+                        &resampled_scratch
+                    }
+                    None => &mono_scratch,
+                };
+
                 overflow_scratch.clear();
                 let mut force_overflow = overflow_count.load(Ordering::Acquire) > 0;
-                if channels == 1 {
-                    for sample in data {
-                        let value = sample.to_sample::<f32>();
-                        if force_overflow || producer.push(value).is_err() {
-                            overflow_scratch.push(value);
-                            force_overflow = true;
-                        }
-                    }
-                } else {
-                    for frame in data.chunks(channels) {
-                        if frame.is_empty() {
-                            continue;
-                        }
-                        let mut sum = 0.0f32;
-                        for sample in frame {
-                            sum += sample.to_sample::<f32>();
-                        }
-                        let mono = sum / frame.len() as f32;
-                        if force_overflow || producer.push(mono).is_err() {
-                            overflow_scratch.push(mono);
-                            force_overflow = true;
-                        }
+                for &value in samples {
+                    if force_overflow || producer.push(value).is_err() {
+                        overflow_scratch.push(value);
+                        force_overflow = true;
                     }
                 }
 
@@ -530,18 +1138,35 @@ where
                 }
             },
             move |err| {
+                if matches!(err, cpal::StreamError::DeviceNotAvailable) {
+                    disconnected.store(true, Ordering::Release);
+                }
                 eprintln!("audio input error: {err}");
             },
             None,
         )
         .map_err(|err| {
+            let not_available = matches!(err, cpal::BuildStreamError::DeviceNotAvailable);
+            let message = format!("failed to build input stream: {err}");
             AudioError::new(
-                AudioErrorKind::StreamBuild,
-                format!("failed to build input stream: {err}"),
+                open_error_kind(not_available, &message, AudioErrorKind::StreamBuild),
+                message,
             )
         })
 }
 
+/// Classifies a device-open failure. Backends report an exclusively held device
+/// (ALSA `EBUSY`) only through their error text.
+fn open_error_kind(not_available: bool, message: &str, fallback: AudioErrorKind) -> AudioErrorKind {
+    if not_available {
+        AudioErrorKind::DeviceUnavailable
+    } else if message.to_ascii_lowercase().contains("busy") {
+        AudioErrorKind::DeviceBusy
+    } else {
+        fallback
+    }
+}
+
 #[cfg(test)]
 mod speech_detector_tests {
     use super::*;
@@ -607,3 +1232,342 @@ mod speech_detector_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod pcm_tests {
+    use super::*;
+
+    #[test]
+    fn lists_only_monitor_sources_from_pactl() {
+        let listing = "\
+49\talsa_output.pci-0000_00_1f.3.analog-stereo.monitor\tPipeWire\ts32le 2ch 48000Hz\tSUSPENDED
+50\talsa_input.pci-0000_00_1f.3.analog-stereo\tPipeWire\ts32le 2ch 48000Hz\tRUNNING
+";
+
+        assert_eq!(
+            parse_monitor_sources(listing),
+            vec!["alsa_output.pci-0000_00_1f.3.analog-stereo.monitor".to_string()]
+        );
+        assert_eq!(
+            describe_device("alsa_output.usb.monitor"),
+            "alsa_output.usb.monitor (monitor)"
+        );
+        assert_eq!(describe_device("pulse"), "pulse");
+    }
+
+    #[test]
+    fn decodes_little_endian_pcm() {
+        let mut f32_bytes = Vec::new();
+        for sample in [0.5f32, -0.25] {
+            f32_bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        assert_eq!(
+            decode_pcm(&f32_bytes, PcmFormat::F32le).expect("f32 pcm"),
+            [0.5, -0.25]
+        );
+
+        let s16_bytes = [0x00, 0x40, 0x00, 0x80];
+        assert_eq!(
+            decode_pcm(&s16_bytes, PcmFormat::S16le).expect("s16 pcm"),
+            [0.5, -1.0]
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_samples() {
+        let err = decode_pcm(&[0, 0, 0], PcmFormat::S16le).expect_err("odd byte count");
+        assert_eq!(err.kind, AudioErrorKind::StreamConfig);
+    }
+}
+
+#[cfg(test)]
+mod wav_tests {
+    use super::*;
+
+    #[test]
+    fn reads_stereo_wav_as_mono_at_target_rate() {
+        let path = std::env::temp_dir().join(format!("sv-read-wav-{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 32_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).expect("create wav");
+        for _ in 0..320 {
+            writer.write_sample(i16::MAX / 2).expect("write sample");
+            writer.write_sample(0_i16).expect("write sample");
+        }
+        writer.finalize().expect("finalize wav");
+
+        let samples = read_wav(&path, 16_000).expect("read wav");
+        let _ = std::fs::remove_file(&path);
+
+        assert!((159..=160).contains(&samples.len()));
+        assert!(samples.iter().all(|sample| (sample - 0.25).abs() < 0.001));
+    }
+
+    #[test]
+    fn read_wav_reports_missing_file() {
+        let err = read_wav(Path::new("/nonexistent/sv.wav"), 16_000).expect_err("missing file");
+
+        assert_eq!(err.kind, AudioErrorKind::InputFile);
+        assert!(err.message.contains("/nonexistent/sv.wav"));
+    }
+}
+
+#[cfg(test)]
+mod trim_tests {
+    use super::*;
+
+    #[test]
+    fn trim_to_speech_pads_clamps_and_merges_segments() {
+        let samples: Vec<f32> = (0..100).map(|i| i as f32).collect();
+        // 1000 Hz: 5 ms of padding is 5 samples.
+        let trimmed = trim_to_speech(&samples, 1000, &[(2, 10), (40, 50), (12, 20), (97, 99)], 5);
+
+        let expected: Vec<f32> = (0..25)
+            .chain(35..55)
+            .chain(92..100)
+            .map(|i| i as f32)
+            .collect();
+        assert_eq!(trimmed, expected);
+    }
+
+    #[test]
+    fn trim_to_speech_without_segments_is_empty() {
+        assert!(trim_to_speech(&[0.5; 64], 16_000, &[], 200).is_empty());
+    }
+
+    #[test]
+    fn trim_silence_cuts_only_the_quiet_edges() {
+        // 1000 Hz: 10 ms frames of 10 samples.
+        let mut samples = vec![0.0; 50];
+        samples.extend(vec![0.5; 20]);
+        samples.extend(vec![0.0; 30]);
+        samples.extend(vec![0.5; 10]);
+        samples.extend(vec![0.0; 40]);
+
+        let trimmed = trim_silence(&samples, 1000, 0.1, 20);
+        assert_eq!(trimmed.len(), 20 + 20 + 30 + 10 + 20);
+        assert_eq!(trimmed, &samples[30..130]);
+        assert_eq!(trim_silence(&samples, 1000, 0.1, 1000), &samples[..]);
+        assert!(trim_silence(&[0.0; 100], 1000, 0.1, 20).is_empty());
+    }
+
+    #[test]
+    fn speech_segments_joins_adjacent_loud_chunks() {
+        let vad = VadConfig::new(true, 1200, 0.1, 10);
+        // 1000 Hz with 10 ms chunks: chunks of 10 samples.
+        let mut samples = vec![0.0; 10];
+        samples.extend(vec![0.5; 20]);
+        samples.extend(vec![0.0; 30]);
+        samples.extend(vec![0.5; 5]);
+
+        assert_eq!(
+            speech_segments(&samples, 1000, &vad),
+            vec![(10, 30), (60, 65)]
+        );
+    }
+
+    #[test]
+    fn split_at_pauses_cuts_mid_pause_and_keeps_short_pieces_joined() {
+        let vad = VadConfig::new(true, 0, 0.1, 10);
+        // 1000 Hz: speech 0-100, pause 100-300, speech 300-340, pause 340-360,
+        // speech 360-500, pause 500-700, speech 700-720.
+        let mut samples = vec![0.5; 100];
+        samples.extend(vec![0.0; 200]);
+        samples.extend(vec![0.5; 40]);
+        samples.extend(vec![0.0; 20]);
+        samples.extend(vec![0.5; 140]);
+        samples.extend(vec![0.0; 200]);
+        samples.extend(vec![0.5; 20]);
+
+        assert_eq!(
+            split_at_pauses(&samples, 1000, &vad, 150, 150),
+            vec![(0, 200), (200, 720)]
+        );
+        assert_eq!(
+            split_at_pauses(&samples, 1000, &vad, 150, 50),
+            vec![(0, 200), (200, 600), (600, 720)]
+        );
+        assert_eq!(split_at_pauses(&[], 1000, &vad, 150, 50), vec![]);
+    }
+}
+
+#[cfg(test)]
+mod capture_format_tests {
+    use super::*;
+
+    fn range(
+        channels: u16,
+        min_rate: u32,
+        max_rate: u32,
+        format: cpal::SampleFormat,
+    ) -> SupportedRange {
+        SupportedRange {
+            channels,
+            min_rate,
+            max_rate,
+            sample_format: format,
+        }
+    }
+
+    #[test]
+    fn negotiation_prefers_native_mono_f32_at_requested_rate() {
+        let ranges = [
+            range(2, 8_000, 48_000, cpal::SampleFormat::F32),
+            range(1, 8_000, 48_000, cpal::SampleFormat::I16),
+            range(1, 8_000, 48_000, cpal::SampleFormat::F32),
+        ];
+
+        assert_eq!(
+            negotiate_format(&ranges, 16_000),
+            Some(CaptureFormat {
+                sample_rate: 16_000,
+                channels: 1,
+                sample_format: cpal::SampleFormat::F32,
+            })
+        );
+    }
+
+    #[test]
+    fn negotiation_prefers_requested_rate_over_channel_layout() {
+        let ranges = [
+            range(1, 44_100, 48_000, cpal::SampleFormat::F32),
+            range(2, 16_000, 16_000, cpal::SampleFormat::I16),
+        ];
+
+        let format = negotiate_format(&ranges, 16_000).expect("format");
+        assert_eq!((format.sample_rate, format.channels), (16_000, 2));
+    }
+
+    #[test]
+    fn negotiation_falls_back_to_closest_supported_rate() {
+        let ranges = [
+            range(1, 96_000, 96_000, cpal::SampleFormat::F32),
+            range(2, 44_100, 48_000, cpal::SampleFormat::I16),
+        ];
+
+        let format = negotiate_format(&ranges, 16_000).expect("format");
+        assert_eq!((format.sample_rate, format.channels), (44_100, 2));
+        assert_eq!(negotiate_format(&[], 16_000), None);
+    }
+
+    #[test]
+    fn busy_backend_errors_name_the_device() {
+        let message = "failed to build input stream: A backend-specific error has occurred: \
+                       ALSA function 'snd_pcm_open' failed with error 'EBUSY: Device or resource busy'";
+        let kind = open_error_kind(false, message, AudioErrorKind::StreamBuild);
+        let err = AudioError::new(kind, message).with_device("USB Mic");
+
+        assert_eq!(err.kind, AudioErrorKind::DeviceBusy);
+        assert!(err.is_transient());
+        assert!(err.message.starts_with("input device USB Mic is busy;"));
+    }
+
+    #[test]
+    fn open_errors_keep_their_kind_unless_busy_or_gone() {
+        assert_eq!(
+            open_error_kind(true, "device gone", AudioErrorKind::StreamStart),
+            AudioErrorKind::DeviceUnavailable
+        );
+        let err = AudioError::new(
+            open_error_kind(false, "invalid argument", AudioErrorKind::StreamBuild),
+            "invalid argument",
+        )
+        .with_device("USB Mic");
+        assert_eq!(err.kind, AudioErrorKind::StreamBuild);
+        assert_eq!(err.message, "invalid argument");
+        assert!(!err.is_transient());
+    }
+
+    #[test]
+    fn linear_resampler_keeps_phase_across_buffers() {
+        let input: Vec<f32> = (0..300).map(|index| (index as f32 * 0.05).sin()).collect();
+        let mut whole = Vec::new();
+        LinearResampler::new(32_000, 16_000).process(&input, &mut whole);
+        let mut down = LinearResampler::new(32_000, 16_000);
+        let mut output = Vec::new();
+        down.process(&input[..101], &mut output);
+        down.process(&input[101..], &mut output);
+        assert_eq!(output.len(), 150);
+        assert_eq!(output, whole);
+
+        let mut up = LinearResampler::new(8_000, 16_000);
+        let mut output = Vec::new();
+        up.process(&[0.0, 1.0], &mut output);
+        up.process(&[2.0], &mut output);
+        assert_eq!(output, [0.0, 0.5, 1.0, 1.5]);
+    }
+
+    #[test]
+    fn downsampling_filters_out_what_would_alias() {
+        let rms = |frequency: f32| {
+            let input: Vec<f32> = (0..48_000)
+                .map(|index| {
+                    let time = index as f32 / 48_000.0;
+                    0.5 * (2.0 * std::f32::consts::PI * frequency * time).sin()
+                })
+                .collect();
+            let output = resample(&input, 48_000, 16_000);
+            // Skip the filter's start-up.
+            let settled = &output[200..];
+            (settled.iter().map(|sample| sample * sample).sum::<f32>() / settled.len() as f32)
+                .sqrt()
+        };
+
+        // 12 kHz would fold back to 4 kHz at 16 kHz without the filter.
+        assert!(rms(12_000.0) < 0.005, "{}", rms(12_000.0));
+        let speech_band = rms(1_000.0);
+        assert!(
+            (speech_band - 0.5 / 2f32.sqrt()).abs() < 0.01,
+            "{speech_band}"
+        );
+    }
+
+    #[test]
+    fn apply_gain_scales_and_clips() {
+        let mut samples = [0.1f32, -0.2, 0.6, -0.9];
+        apply_gain(&mut samples, 6.0206);
+        let expected = [0.2f32, -0.4, 1.0, -1.0];
+        for (sample, expected) in samples.iter().zip(expected) {
+            assert!((sample - expected).abs() < 1e-4, "{sample} != {expected}");
+        }
+
+        let mut quiet = [0.5f32];
+        apply_gain(&mut quiet, -20.0);
+        assert!((quiet[0] - 0.05).abs() < 1e-6);
+    }
+
+    #[test]
+    fn resample_converts_telephone_audio_to_whisper_rate() {
+        let telephone = vec![0.25f32; 8_000];
+        let resampled = resample(&telephone, 8_000, 16_000);
+        assert_eq!(samples_to_ms(resampled.len(), 16_000), 1000);
+        assert!(resampled.iter().all(|&sample| sample == 0.25));
+        assert_eq!(resample(&[0.1, 0.2], 16_000, 16_000), [0.1, 0.2]);
+    }
+
+    #[test]
+    fn frames_to_mono_averages_or_extracts_one_channel() {
+        let stereo = [0.2f32, 0.6, -0.4, 0.0];
+
+        let mut averaged = Vec::new();
+        frames_to_mono(&stereo, 2, None, &mut averaged);
+        assert_eq!(averaged, [0.4, -0.2]);
+
+        let mut second = Vec::new();
+        frames_to_mono(&stereo, 2, Some(1), &mut second);
+        assert_eq!(second, [0.6, 0.0]);
+    }
+
+    #[test]
+    fn frames_to_mono_drops_an_incomplete_trailing_frame() {
+        let stereo = [0.2f32, 0.6, -0.4, 0.0, 0.8];
+
+        let mut mono = Vec::new();
+        assert_eq!(frames_to_mono(&stereo, 2, None, &mut mono), 1);
+        assert_eq!(mono, [0.4, -0.2]);
+    }
+}
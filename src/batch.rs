@@ -0,0 +1,175 @@
+//! Transcription of every matching WAV file in a directory.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::audio;
+use crate::error::AppError;
+use crate::subtitles;
+use crate::types::{SidecarFormat, SubtitleFormat};
+use crate::whisper::{DecodeOptions, WhisperContext, WHISPER_SAMPLE_RATE};
+
+/// Model, decoder, and sidecar settings shared by every file in a batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchOptions {
+    pub model_path: PathBuf,
+    /// Language code passed to whisper; `auto` detects it per file.
+    pub language: String,
+    pub decode: DecodeOptions,
+    /// Also writes each transcript next to its input in this format.
+    pub sidecar: Option<SidecarFormat>,
+}
+
+/// One input file with its transcript or the error that stopped it.
+pub type FileResult = (PathBuf, Result<String, AppError>);
+
+/// Transcribes the files in `dir` whose names match `pattern`, in name
+/// order, with one model load for the whole batch. Each file gets its own
+/// result, so an unreadable file does not stop the rest. Only loading the
+/// model or listing `dir` fails the batch as a whole.
+pub fn transcribe_dir(
+    dir: &Path,
+    pattern: &str,
+    opts: &BatchOptions,
+) -> Result<Vec<FileResult>, AppError> {
+    let inputs = matching_files(dir, pattern)?;
+    if inputs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let context = WhisperContext::from_file(&opts.model_path)
+        .map_err(|err| AppError::runtime(err.to_string()))?;
+    Ok(inputs
+        .into_iter()
+        .map(|path| {
+            let result = transcribe_file(&context, &path, opts);
+            (path, result)
+        })
+        .collect())
+}
+
+fn transcribe_file(
+    context: &WhisperContext,
+    path: &Path,
+    opts: &BatchOptions,
+) -> Result<String, AppError> {
+    let samples =
+        audio::read_wav(path, WHISPER_SAMPLE_RATE).map_err(|err| AppError::audio(err.message))?;
+    let segments = context
+        .transcribe_segments(&samples, Some(&opts.language), &opts.decode)
+        .map_err(|err| AppError::runtime(err.to_string()))?;
+    let text = segments
+        .iter()
+        .map(|segment| segment.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if let Some(format) = opts.sidecar {
+        let contents = match format {
+            SidecarFormat::Txt => format!("{text}\n"),
+            SidecarFormat::Srt => subtitles::format_subtitles(&segments, SubtitleFormat::Srt),
+            SidecarFormat::Vtt => subtitles::format_subtitles(&segments, SubtitleFormat::Vtt),
+        };
+        let sidecar = sidecar_path(path, format);
+        fs::write(&sidecar, contents).map_err(|err| {
+            AppError::runtime(format!("failed to write {}: {err}", sidecar.display()))
+        })?;
+    }
+    Ok(text)
+}
+
+/// The input path with its extension replaced by the sidecar's.
+pub fn sidecar_path(input: &Path, format: SidecarFormat) -> PathBuf {
+    input.with_extension(match format {
+        SidecarFormat::Txt => "txt",
+        SidecarFormat::Srt => "srt",
+        SidecarFormat::Vtt => "vtt",
+    })
+}
+
+/// Regular files directly inside `dir` whose names match `pattern`, sorted.
+fn matching_files(dir: &Path, pattern: &str) -> Result<Vec<PathBuf>, AppError> {
+    let entries = fs::read_dir(dir)
+        .map_err(|err| AppError::config(format!("cannot read {}: {err}", dir.display())))?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+        .filter(|entry| name_matches(pattern, &entry.file_name().to_string_lossy()))
+        .map(|entry| entry.path())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Shell-style wildcard match where `*` spans any run of characters and `?`
+/// one character. Case is ignored so `*.wav` also finds `MEMO.WAV`.
+fn name_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&ch) if ch == '?' || ch == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&ch| ch == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards_match_whole_names_ignoring_case() {
+        assert!(name_matches("*.wav", "memo.wav"));
+        assert!(name_matches("*.wav", "MEMO.WAV"));
+        assert!(name_matches("memo-??.wav", "memo-01.wav"));
+        assert!(name_matches("*-*.wav", "a-b-c.wav"));
+        assert!(!name_matches("*.wav", "memo.wav.txt"));
+        assert!(!name_matches("memo-?.wav", "memo-10.wav"));
+        assert!(name_matches("*", ""));
+    }
+
+    #[test]
+    fn lists_matching_files_in_name_order() {
+        let dir = std::env::temp_dir().join(format!("sv-batch-list-{}", std::process::id()));
+        fs::create_dir_all(dir.join("nested.wav")).expect("create dirs");
+        for name in ["b.wav", "a.WAV", "notes.txt"] {
+            fs::write(dir.join(name), b"").expect("write file");
+        }
+
+        assert_eq!(
+            matching_files(&dir, "*.wav").expect("list files"),
+            [dir.join("a.WAV"), dir.join("b.wav")]
+        );
+        assert!(matching_files(&dir.join("missing"), "*.wav").is_err());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn sidecar_replaces_the_extension() {
+        assert_eq!(
+            sidecar_path(Path::new("/memos/day1.wav"), SidecarFormat::Srt),
+            Path::new("/memos/day1.srt")
+        );
+        assert_eq!(
+            sidecar_path(Path::new("/memos/day1"), SidecarFormat::Txt),
+            Path::new("/memos/day1.txt")
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! What this binary was built with, for bug reports.
+
+use std::fmt;
+
+use crate::whisper;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildInfo {
+    pub crate_version: &'static str,
+    pub whisper_version: String,
+    /// whisper.cpp's own report of its compiled CPU and backend features.
+    pub whisper_system_info: String,
+    /// Built with the `vulkan` feature, so whisper.cpp can decode on the GPU.
+    pub gpu_enabled: bool,
+    /// Built with the `clipboard` feature; without it paste mode types instead.
+    pub clipboard_enabled: bool,
+    /// Built with the `portal` feature for GNOME Wayland input.
+    pub portal_enabled: bool,
+}
+
+/// Reports the crate version, cargo features, and the linked whisper.cpp.
+pub fn build_info() -> BuildInfo {
+    BuildInfo {
+        crate_version: env!("CARGO_PKG_VERSION"),
+        whisper_version: whisper::version(),
+        whisper_system_info: whisper::system_info(),
+        gpu_enabled: cfg!(feature = "vulkan"),
+        clipboard_enabled: cfg!(feature = "clipboard"),
+        portal_enabled: cfg!(feature = "portal"),
+    }
+}
+
+impl fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let features = [
+            ("vulkan", self.gpu_enabled),
+            ("clipboard", self.clipboard_enabled),
+            ("portal", self.portal_enabled),
+        ]
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>();
+        write!(
+            f,
+            "sv {}, whisper.cpp {}, features: {}",
+            self.crate_version,
+            self.whisper_version,
+            if features.is_empty() {
+                "none".to_string()
+            } else {
+                features.join(", ")
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_crate_version_and_features() {
+        let info = build_info();
+        assert_eq!(info.crate_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(info.gpu_enabled, cfg!(feature = "vulkan"));
+        assert_eq!(info.clipboard_enabled, cfg!(feature = "clipboard"));
+    }
+
+    #[test]
+    fn display_lists_enabled_features() {
+        let info = BuildInfo {
+            crate_version: "0.1.0",
+            whisper_version: "1.7.4".to_string(),
+            whisper_system_info: "AVX = 1".to_string(),
+            gpu_enabled: true,
+            clipboard_enabled: false,
+            portal_enabled: true,
+        };
+        assert_eq!(
+            info.to_string(),
+            "sv 0.1.0, whisper.cpp 1.7.4, features: vulkan, portal"
+        );
+
+        let info = BuildInfo {
+            gpu_enabled: false,
+            portal_enabled: false,
+            ..info
+        };
+        assert_eq!(
+            info.to_string(),
+            "sv 0.1.0, whisper.cpp 1.7.4, features: none"
+        );
+    }
+}
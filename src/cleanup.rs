@@ -0,0 +1,163 @@
+//! Transcript cleanup through an external command, such as a local LLM.
+
+use std::fmt;
+use std::io::{Read, Write};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CleanupError {
+    /// The command did not finish within the timeout and was killed.
+    TimedOut(Duration),
+    /// The command could not run, failed, or printed nothing.
+    Failed(String),
+}
+
+impl fmt::Display for CleanupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TimedOut(timeout) => {
+                write!(
+                    f,
+                    "cleanup command timed out after {} ms",
+                    timeout.as_millis()
+                )
+            }
+            Self::Failed(message) => write!(f, "cleanup command failed: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for CleanupError {}
+
+/// Runs `command` through `sh -c` with `text` on stdin and returns its
+/// stdout without the trailing newline. A worker thread does the piping, so a
+/// command that hangs is killed once `timeout` passes. The shell runs in its
+/// own process group, and the whole group is killed, so a pipeline's other
+/// processes do not keep running or hold the output open.
+pub fn run_cleanup_command(
+    command: &str,
+    text: &str,
+    timeout: Duration,
+) -> Result<String, CleanupError> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()
+        .map_err(|err| CleanupError::Failed(err.to_string()))?;
+    let (Some(mut stdin), Some(mut stdout)) = (child.stdin.take(), child.stdout.take()) else {
+        kill_group(&mut child);
+        return Err(CleanupError::Failed("no pipes to the command".to_string()));
+    };
+    let input = text.as_bytes().to_vec();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        // A command may exit without reading its input; its output still counts.
+        let _ = stdin.write_all(&input);
+        drop(stdin);
+        let mut cleaned = String::new();
+        let _ = sender.send(stdout.read_to_string(&mut cleaned).map(|_| cleaned));
+    });
+
+    let cleaned = match receiver.recv_timeout(timeout) {
+        Ok(result) => result.map_err(|err| CleanupError::Failed(err.to_string())),
+        Err(_) => {
+            kill_group(&mut child);
+            return Err(CleanupError::TimedOut(timeout));
+        }
+    };
+    let status = child
+        .wait()
+        .map_err(|err| CleanupError::Failed(err.to_string()))?;
+    if !status.success() {
+        return Err(CleanupError::Failed(format!("exited with {status}")));
+    }
+    let cleaned = cleaned?.trim_end_matches(['\r', '\n']).to_string();
+    if cleaned.trim().is_empty() {
+        Err(CleanupError::Failed("no output".to_string()))
+    } else {
+        Ok(cleaned)
+    }
+}
+
+/// Kills the process group `child` leads and reaps `child`.
+fn kill_group(child: &mut Child) {
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    let _ = child.wait();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn pipes_text_through_the_command() {
+        assert_eq!(
+            run_cleanup_command("tr a-z A-Z", "hello there", TIMEOUT),
+            Ok("HELLO THERE".to_string())
+        );
+    }
+
+    #[test]
+    fn reports_failures_and_empty_output() {
+        assert_eq!(
+            run_cleanup_command("exit 3", "hello", TIMEOUT),
+            Err(CleanupError::Failed(
+                "exited with exit status: 3".to_string()
+            ))
+        );
+        assert_eq!(
+            run_cleanup_command("cat > /dev/null", "hello", TIMEOUT),
+            Err(CleanupError::Failed("no output".to_string()))
+        );
+    }
+
+    #[test]
+    fn kills_a_command_that_outlasts_the_timeout() {
+        let timeout = Duration::from_millis(100);
+        let started = std::time::Instant::now();
+
+        assert_eq!(
+            run_cleanup_command("sleep 5", "hello", timeout),
+            Err(CleanupError::TimedOut(timeout))
+        );
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn kills_the_processes_the_command_started() {
+        let path = std::env::temp_dir().join(format!("sv-cleanup-{}.pid", std::process::id()));
+        let command = format!("sleep 5 & echo $! > {}; wait", path.display());
+        let timeout = Duration::from_millis(200);
+
+        assert_eq!(
+            run_cleanup_command(&command, "hello", timeout),
+            Err(CleanupError::TimedOut(timeout))
+        );
+        let pid = std::fs::read_to_string(&path).expect("pid file");
+        let _ = std::fs::remove_file(&path);
+        let stat = format!("/proc/{}/stat", pid.trim());
+        // The orphaned sleep may linger as a zombie until it is reaped.
+        let gone = (0..50).any(|_| {
+            let running = std::fs::read_to_string(&stat).is_ok_and(|stat| !stat.contains(") Z "));
+            if running {
+                thread::sleep(Duration::from_millis(20));
+            }
+            !running
+        });
+        assert!(gone, "sleep {} survived the timeout", pid.trim());
+    }
+}
@@ -1,60 +1,192 @@
 use chrono::{Local, Utc};
 use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
-use signal_hook::consts::signal::{SIGINT, SIGTERM};
+use signal_hook::consts::signal::{SIGHUP, SIGINT, SIGTERM};
 use signal_hook::flag;
+use signal_hook::iterator::Signals;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::env;
 use std::fs;
 use std::io::{Read, Write};
 use std::net::Shutdown;
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
-use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
 use crate::audio;
+use crate::cleanup;
 use crate::error::AppError;
-use crate::feedback;
+use crate::feedback::{self, Feedback, FeedbackSounds, SoundFeedback};
 use crate::hotkey::{self, HotkeyConfig};
 use crate::model::{self, ModelLanguage, ModelSize, ModelSpec};
-use crate::output::{self, OutputConfig, OutputMode};
+use crate::output::{self, OutputBackend, OutputConfig, OutputMode};
+use crate::postprocess::{self, FillerFilter, VoiceCommands, WordFilter};
+use crate::profile::{self, Profile};
 use crate::segmentation::{self, CutReason, SegmentConfig, SegmentDecision};
+use crate::session_transcript::{DumpTarget, SessionTranscript};
 pub use crate::transcription_worker::Transcriber;
-use crate::transcription_worker::{TranscriptionJob, TranscriptionResult, TranscriptionWorker};
-use crate::types::{AudioHost, OutputFormat, VadMode};
-use crate::whisper::WhisperContext;
+use crate::transcription_worker::{
+    self, BufferPool, SegmentCallback, TranscriptionJob, TranscriptionResult, TranscriptionWorker,
+};
+use crate::types::{AudioHost, Casing, OutputFormat, QueueOverflow, VadMode};
+#[cfg(feature = "websocket")]
+use crate::websocket::Broadcaster;
+use crate::whisper::{
+    Candidate, CandidateOptions, CodeSwitching, DecodeOptions, TimedSegment, WhisperContext,
+    WhisperError, WHISPER_SAMPLE_RATE,
+};
 
 #[derive(Debug, Clone)]
 pub struct DaemonConfig {
     pub model_path: Option<PathBuf>,
     pub download_model: bool,
     pub language: String,
+    /// Languages an `auto` language may resolve to; empty allows every
+    /// language whisper knows.
+    pub candidate_languages: Vec<String>,
     pub device: Option<String>,
+    pub input_channel: Option<usize>,
     pub audio_host: AudioHost,
     pub sample_rate: u32,
+    /// Fixed gain in decibels applied to captured audio before VAD and
+    /// transcription.
+    pub input_gain_db: f32,
     pub format: OutputFormat,
     pub output: OutputConfig,
     pub vad: VadMode,
     pub vad_silence_ms: u64,
     pub vad_threshold: f32,
     pub vad_chunk_ms: u64,
+    /// With continuous VAD, loud audio must last this long to end a pause.
+    pub speech_confirm_ms: u64,
+    /// With continuous VAD, quiet audio must last this long before a
+    /// recording past `segment_target_ms` is cut at it.
+    pub silence_confirm_ms: u64,
     pub segment_target_ms: u64,
     pub segment_grace_ms: u64,
     pub segment_overlap_ms: u64,
     pub segment_min_ms: u64,
     pub debug_audio: bool,
     pub dump_audio: bool,
+    /// Overwrites `last_recording_path` with the audio of every transcription.
+    pub save_last_recording: bool,
+    pub last_recording_path: PathBuf,
+    /// Captures at the device's own rate and saves the last recording at
+    /// that rate instead of `sample_rate`.
+    pub save_native_rate: bool,
+    /// Appends transcripts that no output backend could deliver to
+    /// `unsent_path`, so they survive a broken session.
+    pub save_unsent: bool,
+    pub unsent_path: PathBuf,
+    /// Opens `unsent_path` with `xdg-open` after saving to it.
+    pub open_unsent: bool,
+    /// Keeps `state_file_path` holding `recording` or `idle` for status bars.
+    pub state_file: bool,
+    pub state_file_path: PathBuf,
     pub audio_feedback: bool,
+    pub processing_feedback: bool,
+    pub feedback_sink: Option<String>,
+    pub feedback_sounds: FeedbackSounds,
     pub no_speech_timeout_ms: u64,
+    /// Aborts a recording whose capture stream delivers no frames within this
+    /// many milliseconds of starting. `0` waits indefinitely.
+    pub capture_start_timeout_ms: u64,
+    pub min_recording_ms: u64,
+    pub post_stop_cooldown_ms: u64,
+    /// Continues a stopped recording when it is restarted within this many
+    /// milliseconds, so both parts are transcribed together. `0` disables it.
+    pub merge_gap_ms: u64,
+    /// Skips a transcript identical to the previous one injected within this
+    /// many milliseconds, guarding against a double trigger. `0` disables it.
+    pub repeat_guard_ms: u64,
+    /// Warns about words whose confidence falls below this, between `0.0`
+    /// and `1.0`. `0.0` disables the check.
+    pub min_word_confidence: f32,
+    /// Shell command that rewrites each final transcript from stdin to stdout.
+    pub cleanup_command: Option<String>,
+    /// How long to wait for `cleanup_command` before using the raw transcript.
+    pub cleanup_timeout_ms: u64,
+    /// Reads each transcript aloud with `tts_command` before injecting it;
+    /// pressing the hotkey during the readback cancels the injection.
+    pub tts_confirm: bool,
+    /// Speech command that gets the transcript as its final argument.
+    pub tts_command: String,
+    /// Keeps every injected transcript of this daemon run in memory for
+    /// `dump-transcript`.
+    pub session_transcript: bool,
+    /// Broadcasts final transcripts as JSON frames to websocket clients
+    /// connected to `websocket_addr`. Needs the `websocket` feature.
+    pub websocket: bool,
+    pub websocket_addr: String,
+    /// Also broadcasts partial results of `incremental_inject`.
+    pub websocket_partials: bool,
+    /// Runs one throwaway transcription after the startup model loads, so
+    /// whisper's first-use allocations do not slow the first dictation.
+    pub prewarm: bool,
+    pub idle_unload_secs: u64,
+    /// How long a stopping daemon keeps outputting queued transcripts
+    /// before it drops the rest; `0` waits for all of them.
+    pub shutdown_drain_ms: u64,
+    pub queue_depth: usize,
+    pub queue_overflow: QueueOverflow,
+    pub no_context: bool,
+    pub single_segment: bool,
+    pub temperature: f32,
+    pub temperature_inc: f32,
+    pub logprob_thold: f32,
+    pub suppress_nst: bool,
+    pub suppress_regex: Option<String>,
+    /// Aborts a decode that runs longer than this and keeps the segments
+    /// finished so far; `0` never aborts.
+    pub transcription_timeout_ms: u64,
+    pub trim_pad_ms: Option<u64>,
+    /// Trims quiet audio from both ends of each recording, keeping this much
+    /// of it, even when VAD is off.
+    pub trim_silence_pad_ms: Option<u64>,
+    /// Transcribes only the last this many seconds of a recording, after
+    /// trimming, for long holds where only the end matters.
+    pub tail_secs: Option<u32>,
+    pub incremental_inject: bool,
+    pub word_filter: WordFilter,
+    pub fillers: FillerFilter,
+    pub casing: Casing,
+    /// Per-application overrides, the first matching the focused window wins.
+    pub profiles: Vec<Profile>,
+    pub voice_commands: VoiceCommands,
+    pub code_switching: CodeSwitching,
     pub hotkey: HotkeyConfig,
 }
 
 pub trait DaemonOutput {
     fn stdout(&mut self, message: &str);
     fn stderr(&mut self, message: &str);
+
+    /// Injects `text` with the configured output backend.
+    fn inject(&mut self, text: &str, config: &OutputConfig) -> Result<(), output::OutputError> {
+        output::output_text(text, config)
+    }
+
+    /// Removes the last `count` characters typed into the focused window.
+    fn erase(&mut self, count: usize, config: &OutputConfig) -> Result<(), output::OutputError> {
+        output::erase_chars(count, config)
+    }
+
+    /// Class or app id of the window that will receive the text.
+    fn focused_window_class(&mut self) -> Option<String> {
+        output::focused_window_class()
+    }
+
+    /// Id of the window that will receive the text, unique among open windows.
+    fn focused_window_id(&mut self) -> Option<String> {
+        output::focused_window_id()
+    }
 }
 
 pub struct StdoutOutput;
@@ -71,6 +203,19 @@ impl DaemonOutput for StdoutOutput {
 
 pub trait CaptureSource {
     fn drain(&mut self, output: &mut Vec<f32>);
+
+    /// Rate of the device-rate copy, when the capture keeps one.
+    fn native_rate(&self) -> Option<u32> {
+        None
+    }
+
+    /// Moves the device-rate copy captured so far into `output`.
+    fn drain_native(&mut self, _output: &mut Vec<f32>) {}
+
+    /// Whether the device went away mid-recording, e.g. an unplugged USB mic.
+    fn disconnected(&self) -> bool {
+        false
+    }
 }
 
 pub trait AudioBackend {
@@ -80,23 +225,35 @@ pub trait AudioBackend {
         host: &cpal::Host,
         device_name: Option<&str>,
         sample_rate: u32,
+        input_channel: Option<usize>,
+        keep_native: bool,
     ) -> Result<Box<dyn CaptureSource>, audio::AudioError>;
 }
 
-pub trait TranscriberFactory {
+/// Loads transcribers. The first load runs on a helper thread so control
+/// events are answered while the model loads.
+pub trait TranscriberFactory: Sync {
     fn load(&self, model_path: Option<&Path>) -> Result<Box<dyn Transcriber>, AppError>;
 }
 
+/// Reads the configuration again for `reload`.
+pub type ConfigLoader = Box<dyn Fn() -> Result<DaemonConfig, AppError>>;
+
 pub struct DaemonDeps {
     pub audio: Box<dyn AudioBackend>,
     pub transcriber_factory: Box<dyn TranscriberFactory>,
+    pub feedback: Box<dyn Feedback>,
+    /// Without a loader, `reload` is refused.
+    pub config_loader: Option<ConfigLoader>,
 }
 
 impl Default for DaemonDeps {
     fn default() -> Self {
         Self {
             audio: Box::new(CpalAudioBackend),
-            transcriber_factory: Box::new(WhisperFactory),
+            transcriber_factory: Box::new(WhisperFactory::default()),
+            feedback: Box::new(SoundFeedback::default()),
+            config_loader: None,
         }
     }
 }
@@ -123,6 +280,14 @@ pub fn select_audio_host(audio_host: AudioHost) -> Result<cpal::Host, AppError>
 }
 
 const CONTROL_API_VERSION: &str = "1";
+const INCREMENTAL_PARTIAL_INTERVAL_MS: u64 = 1_000;
+const COLD_START_GRACE: Duration = Duration::from_millis(100);
+/// Length of the silent clip transcribed to prewarm the model.
+const PREWARM_MS: u64 = 500;
+/// Error for control requests that arrive once shutdown has begun.
+const SHUTTING_DOWN: &str = "daemon is shutting down";
+/// How often the shutdown drain answers control requests while it waits.
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(20);
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ControlResponse {
@@ -133,6 +298,8 @@ pub struct ControlResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub language: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
 }
 
@@ -143,6 +310,7 @@ impl ControlResponse {
             ok: true,
             state: state.map(str::to_string),
             language: language.map(str::to_string),
+            backend: None,
             message: None,
         }
     }
@@ -159,6 +327,7 @@ impl ControlResponse {
             ok: false,
             state: None,
             language: None,
+            backend: None,
             message: Some(message.into()),
         }
     }
@@ -174,6 +343,12 @@ pub enum ControlEvent {
         size: ModelSize,
         model_language: ModelLanguage,
     },
+    GetBackend,
+    SetBackend(OutputBackend),
+    SetGain(f32),
+    DumpTranscript(DumpTarget),
+    Repeat,
+    Reload,
     Error(String),
     Request {
         event: Box<ControlEvent>,
@@ -181,36 +356,258 @@ pub enum ControlEvent {
     },
 }
 
+/// Control events for the daemon loop. Events that arrive while a transcript
+/// is read back wait here until the loop gets to them.
+struct ControlInbox {
+    receiver: Receiver<ControlEvent>,
+    deferred: RefCell<VecDeque<ControlEvent>>,
+}
+
+impl ControlInbox {
+    fn new(receiver: Receiver<ControlEvent>) -> Self {
+        Self {
+            receiver,
+            deferred: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    fn recv_timeout(&self, timeout: Duration) -> Result<ControlEvent, RecvTimeoutError> {
+        match self.deferred.borrow_mut().pop_front() {
+            Some(event) => Ok(event),
+            None => self.receiver.recv_timeout(timeout),
+        }
+    }
+
+    fn defer(&self, event: ControlEvent) {
+        self.deferred.borrow_mut().push_back(event);
+    }
+
+    /// Answers every request still waiting with an error, once the loop
+    /// will not get to them any more.
+    fn refuse_requests(&self, message: &str) {
+        let deferred = std::mem::take(&mut *self.deferred.borrow_mut());
+        for event in deferred.into_iter().chain(self.receiver.try_iter()) {
+            if let ControlEvent::Request { respond_to, .. } = event {
+                let _ = respond_to.send(ControlResponse::error(message));
+            }
+        }
+    }
+
+    /// Whether the hotkey was pressed since the last check. Socket requests
+    /// and other events are kept for the loop.
+    fn take_hotkey_press(&self) -> bool {
+        while let Ok(event) = self.receiver.try_recv() {
+            if matches!(event, ControlEvent::StartRecording) {
+                return true;
+            }
+            self.defer(event);
+        }
+        false
+    }
+}
+
+/// Transcript state carried from one worker result to the next.
+#[derive(Default)]
+struct EmitState<'a> {
+    last_transcript: String,
+    /// Text already typed from partial results of the current recording.
+    incremental_injected: String,
+    partial_pending: bool,
+    settled: SettledAudio,
+    /// The last transcript sent to output and when, for `repeat_guard_ms`.
+    last_injected: Option<(String, std::time::Instant)>,
+    /// Exactly what the last transcript put into the focused window, for
+    /// the `undo` voice commands. Cleared once undone.
+    undoable: Option<String>,
+    /// The last transcript sent to output, for the `repeat` command.
+    repeatable: Option<(String, audio::SegmentInfo)>,
+    /// Where a readback looks for the hotkey press that cancels it.
+    control: Option<&'a ControlInbox>,
+    /// Injected transcripts, filled when `session_transcript` is on.
+    session: SessionTranscript,
+    /// Id, or class when there is none, of the window the last transcript
+    /// went to, for `focus_settle_ms`.
+    last_window: Option<String>,
+    /// Websocket clients that get every transcript, when `websocket` is on.
+    broadcast: Option<&'a Broadcaster>,
+}
+
+/// Stand-in for builds without the `websocket` feature, where no server
+/// ever starts.
+#[cfg(not(feature = "websocket"))]
+struct Broadcaster;
+
+#[cfg(not(feature = "websocket"))]
+impl Broadcaster {
+    fn send(&self, _frame: &serde_json::Value) {}
+}
+
+#[cfg(feature = "websocket")]
+fn start_broadcaster(config: &DaemonConfig, output: &mut dyn DaemonOutput) -> Option<Broadcaster> {
+    if !config.websocket {
+        return None;
+    }
+    match Broadcaster::start(&config.websocket_addr) {
+        Ok(broadcaster) => {
+            output.stdout(&format!(
+                "Broadcasting transcripts on ws://{}",
+                broadcaster.local_addr()
+            ));
+            Some(broadcaster)
+        }
+        Err(err) => {
+            output.stderr(&format!("warn: {err}"));
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "websocket"))]
+fn start_broadcaster(config: &DaemonConfig, output: &mut dyn DaemonOutput) -> Option<Broadcaster> {
+    if config.websocket {
+        output.stderr("warn: websocket output unavailable: built without the websocket feature");
+    }
+    None
+}
+
+/// Leading audio of the current recording whose partial transcript whisper
+/// has already split off into finished segments. Partial jobs start after
+/// it, so each tick decodes only the audio still being spoken instead of
+/// the whole recording again.
+#[derive(Default)]
+struct SettledAudio {
+    samples: usize,
+    text: String,
+}
+
+/// Device-rate copy of a recording for `save_native_rate`.
+struct NativeCopy {
+    samples: Vec<f32>,
+    sample_rate: u32,
+}
+
+impl NativeCopy {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            samples: Vec::new(),
+            sample_rate,
+        }
+    }
+
+    /// Splits off the audio matching the first `samples` samples at
+    /// `sample_rate`, for a segment cut from the recording.
+    fn split_front(&mut self, samples: usize, sample_rate: u32) -> NativeCopy {
+        let end = (samples as u64 * u64::from(self.sample_rate) / u64::from(sample_rate)) as usize;
+        NativeCopy {
+            samples: self.samples.drain(..end.min(self.samples.len())).collect(),
+            sample_rate: self.sample_rate,
+        }
+    }
+}
+
+/// Longest recording a device-rate copy is kept for; past it, the copy is
+/// dropped and the last recording is saved at `sample_rate` as usual. Ten
+/// minutes at 48 kHz already take about 115 MB on top of the recording.
+const MAX_NATIVE_COPY_MS: u64 = 10 * 60 * 1000;
+
 struct ActiveRecording {
     capture: Box<dyn CaptureSource>,
+    native: Option<NativeCopy>,
     buffer: Vec<f32>,
     has_leading_overlap: bool,
-    trailing_silence_samples: usize,
+    silence: segmentation::SilenceTracker,
+    captured_samples: usize,
     started: std::time::Instant,
+    last_partial: std::time::Instant,
+    last_auto_stop: Option<std::time::Instant>,
     speech_detector: audio::SpeechDetector,
 }
 
+/// Whether the configured input device was missing when the last recording
+/// started, so losing it and getting it back are each reported once.
+#[derive(Default)]
+struct DeviceWatch {
+    missing: bool,
+}
+
+/// Opens the configured input device, or the default input while that
+/// device is unplugged. Devices are looked up by name for every recording,
+/// so a reconnected device is used again as soon as it is back.
+fn open_capture(
+    deps: &DaemonDeps,
+    host: &cpal::Host,
+    config: &DaemonConfig,
+    watch: &mut DeviceWatch,
+    output: &mut dyn DaemonOutput,
+) -> Result<Box<dyn CaptureSource>, audio::AudioError> {
+    let open = |device_name: Option<&str>| {
+        deps.audio.start_capture(
+            host,
+            device_name,
+            config.sample_rate,
+            config.input_channel,
+            keeps_native_copy(config),
+        )
+    };
+    let Some(device) = config.device.as_deref() else {
+        return open(None);
+    };
+    // A monitor source is not unplugged; its errors mean sv cannot reach
+    // it at all, and recording the microphone instead would be a surprise.
+    if audio::is_monitor_source(device) {
+        return open(Some(device));
+    }
+    match open(Some(device)) {
+        Ok(capture) => {
+            if std::mem::take(&mut watch.missing) {
+                output.stdout(&format!("Input device {device} is back."));
+            }
+            Ok(capture)
+        }
+        Err(err)
+            if matches!(
+                err.kind,
+                audio::AudioErrorKind::DeviceNotFound | audio::AudioErrorKind::DeviceUnavailable
+            ) =>
+        {
+            if !watch.missing {
+                output.stderr(&format!(
+                    "warn: {}; recording from the default input until it is back",
+                    err.message
+                ));
+                watch.missing = true;
+            }
+            open(None)
+        }
+        Err(err) => Err(err),
+    }
+}
+
 impl ActiveRecording {
     fn start(
         deps: &DaemonDeps,
         host: &cpal::Host,
         config: &DaemonConfig,
+        buffers: &BufferPool,
+        watch: &mut DeviceWatch,
         output: &mut dyn DaemonOutput,
-    ) -> Result<Self, AppError> {
-        let capture = deps
-            .audio
-            .start_capture(host, config.device.as_deref(), config.sample_rate)
-            .map_err(|err| AppError::audio(err.message))?;
+    ) -> Result<Self, audio::AudioError> {
+        let capture = open_capture(deps, host, config, watch, output)?;
+        let native = capture.native_rate().map(NativeCopy::new);
         output.stdout("Recording started.");
         if config.audio_feedback {
-            feedback::play_start_sound();
+            deps.feedback.on_start();
         }
         Ok(Self {
             capture,
-            buffer: Vec::new(),
+            native,
+            buffer: buffers.take(),
             has_leading_overlap: false,
-            trailing_silence_samples: 0,
+            silence: segmentation::SilenceTracker::default(),
+            captured_samples: 0,
             started: std::time::Instant::now(),
+            last_partial: std::time::Instant::now(),
+            last_auto_stop: None,
             speech_detector: audio::SpeechDetector::new(
                 config.vad_threshold,
                 100,
@@ -219,22 +616,68 @@ impl ActiveRecording {
         })
     }
 
+    /// Moves captured audio into the buffer with `input_gain_db` applied and
+    /// returns how many samples arrived.
+    fn drain_capture(&mut self, gain_db: f32, output: &mut dyn DaemonOutput) -> usize {
+        let prev_len = self.buffer.len();
+        self.capture.drain(&mut self.buffer);
+        audio::apply_gain(&mut self.buffer[prev_len..], gain_db);
+        let new_samples = self.buffer.len() - prev_len;
+        self.captured_samples += new_samples;
+        if let Some(native) = self.native.as_mut() {
+            let prev_len = native.samples.len();
+            self.capture.drain_native(&mut native.samples);
+            audio::apply_gain(&mut native.samples[prev_len..], gain_db);
+            if audio::samples_to_ms(native.samples.len(), native.sample_rate) > MAX_NATIVE_COPY_MS {
+                output.stderr("warn: recording too long to keep at the device rate");
+                self.native = None;
+            }
+        }
+        new_samples
+    }
+
+    /// Drops audio captured while a recording is held for `merge_gap_ms`.
+    fn drop_gap(&mut self, scratch: &mut Vec<f32>) {
+        self.capture.drain(scratch);
+        if self.native.is_some() {
+            self.capture.drain_native(scratch);
+        }
+        scratch.clear();
+    }
+
+    /// Submits the remaining audio. Returns `false` when the whole recording was
+    /// shorter than `min_recording_ms` and was dropped instead.
     fn finish(
         mut self,
         worker: &mut TranscriptionWorker,
         config: &DaemonConfig,
         vad: &audio::VadConfig,
         output: &mut dyn DaemonOutput,
-    ) -> Result<(), AppError> {
-        self.capture.drain(&mut self.buffer);
-        submit_final_recording(
-            worker,
-            config,
-            vad,
-            &self.buffer,
-            self.has_leading_overlap,
-            output,
-        )
+    ) -> Result<bool, AppError> {
+        self.drain_capture(config.input_gain_db, output);
+        let long_enough = audio::samples_to_ms(self.captured_samples, config.sample_rate)
+            >= config.min_recording_ms;
+        let submitted = if long_enough {
+            submit_final_recording(
+                worker,
+                config,
+                vad,
+                &self.buffer,
+                self.native.as_ref(),
+                self.has_leading_overlap,
+                output,
+            )
+        } else {
+            Ok(())
+        };
+        self.discard(worker.buffers());
+        submitted.map(|()| long_enough)
+    }
+
+    /// Stops capturing and returns the recording buffer to the pool.
+    fn discard(self, buffers: &BufferPool) {
+        drop(self.capture);
+        buffers.recycle(self.buffer);
     }
 }
 
@@ -247,6 +690,7 @@ pub fn run_daemon(
     let (_guard, control_events, control_sender) = start_socket_listener(&socket_path)?;
     output.stdout(&format!("Daemon listening on {}", socket_path.display()));
 
+    forward_reload_signal(control_sender.clone())?;
     let _hotkey_listener = if config.hotkey.enabled {
         Some(hotkey::start_listener(&config.hotkey, control_sender)?)
     } else {
@@ -263,6 +707,20 @@ pub fn run_daemon(
     run_daemon_loop(config, deps, output, control_events, &shutdown)
 }
 
+/// Turns SIGHUP into a `reload` control event.
+fn forward_reload_signal(sender: Sender<ControlEvent>) -> Result<(), AppError> {
+    let mut signals = Signals::new([SIGHUP])
+        .map_err(|err| AppError::runtime(format!("failed to register signal handler: {err}")))?;
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            if sender.send(ControlEvent::Reload).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(())
+}
+
 pub fn run_daemon_loop(
     config: &DaemonConfig,
     deps: &DaemonDeps,
@@ -278,7 +736,7 @@ pub fn run_daemon_loop(
         .map_err(|err| AppError::audio(err.message))?;
     output.stdout("Input devices:");
     for name in &devices {
-        output.stdout(&format!("  - {name}"));
+        output.stdout(&format!("  - {}", audio::describe_device(name)));
     }
 
     if let Some(device) = config.device.as_deref() {
@@ -287,33 +745,84 @@ pub fn run_daemon_loop(
         }
     }
 
-    let transcriber = deps
-        .transcriber_factory
-        .load(config.model_path.as_deref())?;
-    let mut worker = TranscriptionWorker::start(transcriber);
+    let mut model_path = config.model_path.clone();
+    let control_events = ControlInbox::new(control_events);
+    let transcriber =
+        load_initial_model(config, deps, output, &control_events, model_path.as_deref())?;
+    let mut worker = TranscriptionWorker::start(transcriber)
+        .with_queue_limit(config.queue_depth, config.queue_overflow);
+    let mut last_activity = std::time::Instant::now();
 
-    let vad = audio::VadConfig::new(
-        config.vad == VadMode::On || config.vad == VadMode::Continuous,
-        config.vad_silence_ms,
-        config.vad_threshold,
-        config.vad_chunk_ms,
-    );
+    let mut vad = vad_config(config);
 
     let mut recording: Option<ActiveRecording> = None;
-    let mut last_emitted_transcript = String::new();
+    let mut device_watch = DeviceWatch::default();
+    // A stopped recording kept for `merge_gap_ms` in case it is restarted.
+    let mut held: Option<(ActiveRecording, std::time::Instant)> = None;
+    let mut gap_samples = Vec::new();
+    let broadcaster = start_broadcaster(config, output);
+    let mut emit_state = EmitState {
+        control: Some(&control_events),
+        broadcast: broadcaster.as_ref(),
+        ..EmitState::default()
+    };
     // Grace period after recording starts to ignore audio feedback pickup (500ms)
     let speech_detection_grace_ms: u64 = if config.audio_feedback { 500 } else { 0 };
-    let segment_config = segment_config(config);
+    let mut segment_config = segment_config(config);
+    // Output mode switches from the control socket land here and apply at the
+    // top of the next iteration, while nothing borrows the live config.
+    let mut live_config = config.clone();
+    let mut config_switch: Option<Box<DaemonConfig>> = None;
+    let mut backend_switch: Option<OutputMode> = None;
+    let mut gain_switch: Option<f32> = None;
+    let mut state_file = StateFile::new(config);
 
     loop {
-        drain_worker_results(&mut worker, config, output, &mut last_emitted_transcript);
+        if let Some(reloaded) = config_switch.take() {
+            let state_file_changed = reloaded.state_file != live_config.state_file
+                || reloaded.state_file_path != live_config.state_file_path;
+            live_config = *reloaded;
+            vad = vad_config(&live_config);
+            segment_config = self::segment_config(&live_config);
+            if state_file_changed {
+                // Dropping the old one removes its file.
+                state_file = StateFile::new(&live_config);
+            }
+        }
+        if let Some(mode) = backend_switch.take() {
+            live_config.output.mode = mode;
+        }
+        if let Some(gain_db) = gain_switch.take() {
+            live_config.input_gain_db = gain_db;
+        }
+        let config = &live_config;
+        // The transitions below write the state themselves; this catches a
+        // newly configured file.
+        state_file.update(recording.is_some() || held.is_some(), output);
+        drain_worker_results(
+            &mut worker,
+            config,
+            deps.feedback.as_ref(),
+            output,
+            &mut emit_state,
+        );
 
         if shutdown.load(Ordering::Relaxed) {
+            state_file.update(false, output);
             if let Some(active) = recording.take() {
                 active.finish(&mut worker, config, &vad, output)?;
             }
-            wait_for_pending_results(&mut worker, config, output, &mut last_emitted_transcript);
-            worker.shutdown()?;
+            if let Some((active, _)) = held.take() {
+                active.finish(&mut worker, config, &vad, output)?;
+            }
+            drain_for_shutdown(
+                &mut worker,
+                config,
+                deps.feedback.as_ref(),
+                output,
+                &mut emit_state,
+            )?;
+            control_events.refuse_requests(SHUTTING_DOWN);
             output.stdout("Daemon shutting down.");
             break;
         }
@@ -331,36 +840,91 @@ pub fn run_daemon_loop(
             };
             match event {
                 ControlEvent::StartRecording => {
+                    if recording.is_none() && !worker.is_loaded() {
+                        output.stdout("Loading model after idle unload.");
+                        if let Err(err) =
+                            ensure_model_loaded(&mut worker, deps, model_path.as_deref())
+                        {
+                            // The next recording tries loading it again.
+                            output.stderr(&format!("Model load failed: {err}"));
+                            if config.audio_feedback {
+                                deps.feedback.on_error(&err.to_string());
+                            }
+                            acknowledge_error(respond_to.as_ref(), &err);
+                            continue;
+                        }
+                    }
+                    if let Some((mut active, _)) = held.take() {
+                        // Audio from the gap between the two sessions is dropped.
+                        active.drop_gap(&mut gap_samples);
+                        output.stdout("Recording resumed.");
+                        if config.audio_feedback {
+                            deps.feedback.on_start();
+                        }
+                        recording = Some(active);
+                    }
                     if recording.is_none() {
-                        match ActiveRecording::start(deps, &host, config, output) {
-                            Ok(active) => recording = Some(active),
-                            Err(err) if respond_to.is_some() => {
+                        match ActiveRecording::start(
+                            deps,
+                            &host,
+                            config,
+                            worker.buffers(),
+                            &mut device_watch,
+                            output,
+                        ) {
+                            Ok(active) => {
+                                recording = Some(active);
+                                state_file.update(true, output);
+                            }
+                            Err(err) => {
+                                let transient = err.is_transient();
+                                let err = AppError::audio(err.message);
+                                if config.audio_feedback {
+                                    deps.feedback.on_error(&err.to_string());
+                                }
+                                if respond_to.is_none() {
+                                    // Hotkey presses have no caller to report to, so keep
+                                    // running when the microphone may free up later.
+                                    if !transient {
+                                        return Err(err);
+                                    }
+                                    output.stderr(&format!("Recording not started: {err}"));
+                                    continue;
+                                }
                                 acknowledge_error(respond_to.as_ref(), &err);
                                 continue;
                             }
-                            Err(err) => return Err(err),
                         }
                     }
                     acknowledge_success(respond_to.as_ref(), &recording, config, None);
                 }
                 ControlEvent::StopRecording => {
                     if let Some(active) = recording.take() {
-                        if let Err(err) = active.finish(&mut worker, config, &vad, output) {
-                            if respond_to.is_some() {
-                                acknowledge_error(respond_to.as_ref(), &err);
-                                continue;
+                        if config.merge_gap_ms > 0 {
+                            // Still capturing, so the state stays `recording`
+                            // until the hold ends.
+                            output.stdout(&format!(
+                                "Recording paused; start within {} ms to continue it.",
+                                config.merge_gap_ms
+                            ));
+                            held = Some((active, std::time::Instant::now()));
+                        } else {
+                            state_file.update(false, output);
+                            if let Err(err) = complete_recording(
+                                active,
+                                &mut worker,
+                                config,
+                                deps,
+                                &vad,
+                                output,
+                                &mut emit_state,
+                            ) {
+                                if respond_to.is_some() {
+                                    acknowledge_error(respond_to.as_ref(), &err);
+                                    continue;
+                                }
+                                return Err(err);
                             }
-                            return Err(err);
-                        }
-                        wait_for_pending_results(
-                            &mut worker,
-                            config,
-                            output,
-                            &mut last_emitted_transcript,
-                        );
-                        output.stdout("Ready for next utterance.");
-                        if config.audio_feedback {
-                            feedback::play_stop_sound();
                         }
                     }
                     acknowledge_success(respond_to.as_ref(), &recording, config, None);
@@ -380,11 +944,13 @@ pub fn run_daemon_loop(
                         size,
                         model_language,
                         &mut recording,
+                        &mut state_file,
                         &mut worker,
+                        &mut model_path,
                         config,
                         deps,
                         output,
-                        &mut last_emitted_transcript,
+                        &mut emit_state,
                     ) {
                         Ok(message) => {
                             output.stdout(&message);
@@ -401,6 +967,110 @@ pub fn run_daemon_loop(
                         }
                     }
                 }
+                ControlEvent::GetBackend => {
+                    acknowledge_backend(
+                        respond_to.as_ref(),
+                        &recording,
+                        config,
+                        config.output.mode,
+                    );
+                }
+                ControlEvent::SetBackend(backend) => {
+                    let mode = backend.resolve(config.output.auto_backend_by_compositor);
+                    output.stdout(&format!(
+                        "Output backend set to {mode} (requested {backend})."
+                    ));
+                    backend_switch = Some(mode);
+                    acknowledge_backend(respond_to.as_ref(), &recording, config, mode);
+                }
+                ControlEvent::SetGain(gain_db) => {
+                    output.stdout(&format!("Input gain set to {gain_db} dB."));
+                    gain_switch = Some(gain_db);
+                    acknowledge_success(
+                        respond_to.as_ref(),
+                        &recording,
+                        config,
+                        Some(format!("input gain {gain_db} dB")),
+                    );
+                }
+                ControlEvent::DumpTranscript(target) => {
+                    let result = if config.session_transcript {
+                        emit_state.session.dump(&target)
+                    } else {
+                        Err(AppError::config(
+                            "session_transcript is off, so there is no transcript to dump",
+                        ))
+                    };
+                    match result {
+                        Ok(()) => {
+                            let message = match &target {
+                                DumpTarget::File(path) => {
+                                    format!("Session transcript saved to {}.", path.display())
+                                }
+                                DumpTarget::Clipboard => {
+                                    "Session transcript copied to the clipboard.".to_string()
+                                }
+                            };
+                            output.stdout(&message);
+                            acknowledge_success(
+                                respond_to.as_ref(),
+                                &recording,
+                                config,
+                                Some(message),
+                            );
+                        }
+                        Err(err) => {
+                            output.stderr(&format!("Transcript dump failed: {err}"));
+                            acknowledge_error(respond_to.as_ref(), &err);
+                        }
+                    }
+                }
+                ControlEvent::Repeat => {
+                    match repeat_last_transcript(config, output, &mut emit_state) {
+                        Ok(message) => {
+                            output.stdout(&message);
+                            acknowledge_success(
+                                respond_to.as_ref(),
+                                &recording,
+                                config,
+                                Some(message),
+                            );
+                        }
+                        Err(err) => {
+                            output.stderr(&format!("Repeat failed: {err}"));
+                            acknowledge_error(respond_to.as_ref(), &err);
+                        }
+                    }
+                }
+                ControlEvent::Reload => {
+                    match reload_config(
+                        &mut recording,
+                        &mut state_file,
+                        &mut worker,
+                        &mut model_path,
+                        &host,
+                        config,
+                        deps,
+                        output,
+                        &mut emit_state,
+                    ) {
+                        Ok(reloaded) => {
+                            let message = "Configuration reloaded.".to_string();
+                            output.stdout(&message);
+                            config_switch = Some(Box::new(reloaded));
+                            acknowledge_success(
+                                respond_to.as_ref(),
+                                &recording,
+                                config,
+                                Some(message),
+                            );
+                        }
+                        Err(err) => {
+                            output.stderr(&format!("Config reload failed: {err}"));
+                            acknowledge_error(respond_to.as_ref(), &err);
+                        }
+                    }
+                }
                 ControlEvent::Error(message) => return Err(AppError::runtime(message)),
                 ControlEvent::Request { .. } => {
                     unreachable!("control request was already unwrapped")
@@ -408,10 +1078,34 @@ pub fn run_daemon_loop(
             }
         }
 
+        if let Some((active, stopped)) = held.as_mut() {
+            active.drop_gap(&mut gap_samples);
+            if stopped.elapsed() >= Duration::from_millis(config.merge_gap_ms) {
+                if let Some((active, _)) = held.take() {
+                    state_file.update(false, output);
+                    // Nobody waits on the timer, so a failure is reported and
+                    // the daemon stays up for the next recording.
+                    if let Err(err) = complete_recording(
+                        active,
+                        &mut worker,
+                        config,
+                        deps,
+                        &vad,
+                        output,
+                        &mut emit_state,
+                    ) {
+                        output.stderr(&format!("Recording failed: {err}"));
+                        if config.audio_feedback {
+                            deps.feedback.on_error(&err.to_string());
+                        }
+                    }
+                }
+            }
+        }
+
         if let Some(active) = recording.as_mut() {
             let prev_len = active.buffer.len();
-            active.capture.drain(&mut active.buffer);
-            let new_samples = active.buffer.len() - prev_len;
+            let new_samples = active.drain_capture(config.input_gain_db, output);
 
             // Check for speech in new samples
             if new_samples > 0 {
@@ -427,84 +1121,364 @@ pub fn run_daemon_loop(
                 }
 
                 if config.vad == VadMode::Continuous {
-                    if rms < config.vad_threshold {
-                        active.trailing_silence_samples += new_samples;
-                    } else {
-                        active.trailing_silence_samples = 0;
-                    }
-
-                    if let SegmentDecision::Cut { speech_end, reason } =
-                        segmentation::decide_segment(
-                            &segment_config,
-                            active.buffer.len(),
-                            active.trailing_silence_samples,
-                            rms,
-                        )
-                    {
-                        submit_segment(
-                            &mut worker,
-                            config,
-                            &active.buffer[..speech_end],
-                            active.has_leading_overlap,
-                            output,
-                        )?;
-                        active.buffer = segmentation::carry_after_cut(
-                            &active.buffer,
-                            speech_end,
-                            &segment_config,
-                            reason,
-                        );
-                        active.has_leading_overlap = reason != CutReason::Silence
-                            && carried_overlap_contains_speech(
-                                &active.buffer,
-                                segment_config.sample_rate,
-                                segment_config.vad_threshold,
-                                config.vad_chunk_ms,
+                    active.silence.process(&segment_config, new_samples, rms);
+
+                    let decision = segmentation::decide_segment(
+                        &segment_config,
+                        active.buffer.len(),
+                        active.silence.trailing_silence_samples(),
+                        rms,
+                    );
+                    let cooling_down = active.last_auto_stop.is_some_and(|at| {
+                        at.elapsed() < Duration::from_millis(config.post_stop_cooldown_ms)
+                    });
+                    match decision {
+                        // A pause right after the previous auto-stop keeps filling the
+                        // current segment instead of splitting off a short fragment.
+                        SegmentDecision::Cut {
+                            reason: CutReason::Silence,
+                            ..
+                        } if cooling_down => {}
+                        SegmentDecision::Cut { speech_end, reason } => {
+                            let native = active
+                                .native
+                                .as_mut()
+                                .map(|native| native.split_front(speech_end, config.sample_rate));
+                            submit_segment(
+                                &mut worker,
+                                config,
+                                &active.buffer[..speech_end],
+                                native.as_ref(),
+                                active.has_leading_overlap,
+                                output,
+                            )?;
+                            segmentation::carry_after_cut(
+                                &mut active.buffer,
+                                speech_end,
+                                &segment_config,
+                                reason,
                             );
-                        active.trailing_silence_samples = 0;
-                        active.speech_detector.reset();
-                        active.started = std::time::Instant::now();
+                            active.has_leading_overlap = reason != CutReason::Silence
+                                && carried_overlap_contains_speech(
+                                    &active.buffer,
+                                    segment_config.sample_rate,
+                                    segment_config.vad_threshold,
+                                    config.vad_chunk_ms,
+                                );
+                            active.silence.reset();
+                            active.speech_detector.reset();
+                            active.started = std::time::Instant::now();
+                            if reason == CutReason::Silence {
+                                active.last_auto_stop = Some(std::time::Instant::now());
+                            }
+                        }
+                        SegmentDecision::Continue => {}
                     }
                 }
             }
 
+            if incremental_inject_enabled(config)
+                && !emit_state.partial_pending
+                && active.speech_detector.is_detected()
+                && active.last_partial.elapsed()
+                    >= Duration::from_millis(INCREMENTAL_PARTIAL_INTERVAL_MS)
+            {
+                let unsettled =
+                    &active.buffer[emit_state.settled.samples.min(active.buffer.len())..];
+                let mut samples = worker.buffers().take();
+                samples.extend_from_slice(unsettled);
+                worker.submit(TranscriptionJob {
+                    samples,
+                    duration_ms: audio::samples_to_ms(unsettled.len(), config.sample_rate),
+                    language: Some(config.language.clone()),
+                    had_overlap: false,
+                    partial: true,
+                })?;
+                emit_state.partial_pending = true;
+                active.last_partial = std::time::Instant::now();
+            }
+
+            if config.capture_start_timeout_ms > 0
+                && active.captured_samples == 0
+                && active.started.elapsed()
+                    >= Duration::from_millis(config.capture_start_timeout_ms)
+            {
+                if let Some(active) = recording.take() {
+                    active.discard(worker.buffers());
+                }
+                state_file.update(false, output);
+                let err = audio::AudioError {
+                    kind: audio::AudioErrorKind::NoInput,
+                    message: format!(
+                        "no audio frames arrived within {} ms of starting",
+                        config.capture_start_timeout_ms
+                    ),
+                };
+                output.stderr(&format!("Recording aborted: {}", err.message));
+                if config.audio_feedback {
+                    deps.feedback.on_error(&err.message);
+                }
+                continue;
+            }
+
+            // What was captured before the device went away is still
+            // transcribed; the next recording opens whichever device is there.
+            if active.capture.disconnected() {
+                output.stderr("warn: input device disconnected; recording stopped");
+                if config.audio_feedback {
+                    deps.feedback.on_error("input device disconnected");
+                }
+                state_file.update(false, output);
+                if let Some(active) = recording.take() {
+                    complete_recording(
+                        active,
+                        &mut worker,
+                        config,
+                        deps,
+                        &vad,
+                        output,
+                        &mut emit_state,
+                    )?;
+                }
+                continue;
+            }
+
             // Check for no-speech timeout
             if config.no_speech_timeout_ms > 0
                 && !active.speech_detector.is_detected()
                 && (active.started.elapsed().as_millis() as u64) >= config.no_speech_timeout_ms
             {
-                recording = None;
+                if let Some(active) = recording.take() {
+                    active.discard(worker.buffers());
+                }
+                state_file.update(false, output);
                 output.stdout("No speech detected, cancelled.");
                 if config.audio_feedback {
-                    feedback::play_stop_sound();
+                    deps.feedback.on_stop();
                 }
             }
         }
+
+        if recording.is_some() || held.is_some() || worker.has_pending() {
+            last_activity = std::time::Instant::now();
+        } else if config.idle_unload_secs > 0
+            && worker.is_loaded()
+            && last_activity.elapsed() >= Duration::from_secs(config.idle_unload_secs)
+        {
+            worker.unload()?;
+            output.stdout(&format!(
+                "Model unloaded after {}s idle.",
+                config.idle_unload_secs
+            ));
+        }
     }
     Ok(())
 }
 
-fn acknowledge_success(
-    respond_to: Option<&SyncSender<ControlResponse>>,
-    recording: &Option<ActiveRecording>,
+/// Submits a stopped recording, waits for its transcript, and signals the
+/// outcome. A recording under `min_recording_ms` is dropped instead.
+fn complete_recording(
+    active: ActiveRecording,
+    worker: &mut TranscriptionWorker,
     config: &DaemonConfig,
-    message: Option<String>,
-) {
-    let Some(respond_to) = respond_to else {
-        return;
-    };
-    let state = if recording.is_some() {
-        "recording"
-    } else {
-        "idle"
-    };
-    let response = match message {
+    deps: &DaemonDeps,
+    vad: &audio::VadConfig,
+    output: &mut dyn DaemonOutput,
+    emit_state: &mut EmitState,
+) -> Result<(), AppError> {
+    if !active.finish(worker, config, vad, output)? {
+        output.stdout("Recording too short, ignored.");
+        if config.audio_feedback {
+            deps.feedback.on_too_short();
+        }
+        return Ok(());
+    }
+    if config.audio_feedback && config.processing_feedback {
+        deps.feedback.on_processing();
+    }
+    wait_for_pending_results(worker, config, deps.feedback.as_ref(), output, emit_state);
+    output.stdout("Ready for next utterance.");
+    if config.audio_feedback {
+        if config.processing_feedback {
+            deps.feedback.on_done();
+        } else {
+            deps.feedback.on_stop();
+        }
+    }
+    Ok(())
+}
+
+/// Loads the startup model on a helper thread. A recording requested before
+/// it is ready is rejected with the not-ready signal, since its first words
+/// would be lost; other control events wait for the daemon loop.
+fn load_initial_model(
+    config: &DaemonConfig,
+    deps: &DaemonDeps,
+    output: &mut dyn DaemonOutput,
+    control_events: &ControlInbox,
+    model_path: Option<&Path>,
+) -> Result<Box<dyn Transcriber>, AppError> {
+    let transcriber = thread::scope(|scope| {
+        let loading = scope.spawn(|| {
+            let transcriber = deps.transcriber_factory.load(model_path)?;
+            let prewarmed = config
+                .prewarm
+                .then(|| prewarm(transcriber.as_ref(), config));
+            Ok((transcriber, prewarmed))
+        });
+        // Nobody presses the hotkey within this window, so a quick load never
+        // reports the daemon as not ready.
+        let started = std::time::Instant::now();
+        while !loading.is_finished() && started.elapsed() < COLD_START_GRACE {
+            thread::sleep(Duration::from_millis(5));
+        }
+        while !loading.is_finished() {
+            let received = match control_events
+                .receiver
+                .recv_timeout(Duration::from_millis(20))
+            {
+                Ok(received) => received,
+                Err(RecvTimeoutError::Timeout) => continue,
+                // The loop reports the lost listener once the model is loaded.
+                Err(RecvTimeoutError::Disconnected) => {
+                    thread::sleep(Duration::from_millis(20));
+                    continue;
+                }
+            };
+            let (event, respond_to) = match received {
+                ControlEvent::Request { event, respond_to } => (*event, Some(respond_to)),
+                event => (event, None),
+            };
+            match (event, respond_to) {
+                (ControlEvent::StartRecording, respond_to) => {
+                    output.stdout("Model is still loading; recording not started.");
+                    if config.audio_feedback {
+                        deps.feedback.on_not_ready();
+                    }
+                    acknowledge_error(
+                        respond_to.as_ref(),
+                        &AppError::runtime("model is still loading"),
+                    );
+                }
+                // The hotkey release that belongs to a rejected press.
+                (ControlEvent::StopRecording, None) => {}
+                (event, Some(respond_to)) => control_events.defer(ControlEvent::Request {
+                    event: Box::new(event),
+                    respond_to,
+                }),
+                (event, None) => control_events.defer(event),
+            }
+        }
+        loading
+            .join()
+            .unwrap_or_else(|_| Err(AppError::runtime("model loading panicked")))
+    });
+    let (transcriber, prewarmed) = transcriber?;
+    match prewarmed {
+        Some(Ok(elapsed)) => {
+            output.stdout(&format!("Model prewarmed in {} ms.", elapsed.as_millis()))
+        }
+        Some(Err(err)) => output.stderr(&format!("warn: model prewarm failed: {err}")),
+        None => {}
+    }
+    Ok(transcriber)
+}
+
+/// Transcribes a short silent clip and discards the text. whisper.cpp
+/// allocates its compute buffers on the first decode, which would otherwise
+/// land on the first dictation.
+fn prewarm(transcriber: &dyn Transcriber, config: &DaemonConfig) -> Result<Duration, AppError> {
+    let started = std::time::Instant::now();
+    let silence = vec![0.0; samples_from_ms(PREWARM_MS, config.sample_rate)];
+    transcriber.transcribe(&silence, Some(&config.language))?;
+    Ok(started.elapsed())
+}
+
+/// Reloads a transcriber dropped by the idle unload timer.
+fn ensure_model_loaded(
+    worker: &mut TranscriptionWorker,
+    deps: &DaemonDeps,
+    model_path: Option<&Path>,
+) -> Result<(), AppError> {
+    if worker.is_loaded() {
+        return Ok(());
+    }
+    let transcriber = deps.transcriber_factory.load(model_path)?;
+    worker.reload(transcriber)
+}
+
+/// Transcribes one prerecorded buffer through the same trimming, filtering,
+/// and output path as a recording.
+pub fn transcribe_samples(
+    config: &DaemonConfig,
+    deps: &DaemonDeps,
+    samples: &[f32],
+    output: &mut dyn DaemonOutput,
+) -> Result<(), AppError> {
+    let transcriber = deps
+        .transcriber_factory
+        .load(config.model_path.as_deref())?;
+    let mut worker = TranscriptionWorker::start(transcriber);
+    let mut emit_state = EmitState::default();
+    submit_final_recording(
+        &mut worker,
+        config,
+        &vad_config(config),
+        samples,
+        None,
+        false,
+        output,
+    )?;
+    wait_for_pending_results(
+        &mut worker,
+        config,
+        deps.feedback.as_ref(),
+        output,
+        &mut emit_state,
+    );
+    worker.shutdown()
+}
+
+fn acknowledge_success(
+    respond_to: Option<&SyncSender<ControlResponse>>,
+    recording: &Option<ActiveRecording>,
+    config: &DaemonConfig,
+    message: Option<String>,
+) {
+    let Some(respond_to) = respond_to else {
+        return;
+    };
+    let state = if recording.is_some() {
+        "recording"
+    } else {
+        "idle"
+    };
+    let response = match message {
         Some(message) => ControlResponse::success_with_message(state, &config.language, message),
         None => ControlResponse::success(Some(state), Some(&config.language)),
     };
     let _ = respond_to.send(response);
 }
 
+fn acknowledge_backend(
+    respond_to: Option<&SyncSender<ControlResponse>>,
+    recording: &Option<ActiveRecording>,
+    config: &DaemonConfig,
+    mode: OutputMode,
+) {
+    let Some(respond_to) = respond_to else {
+        return;
+    };
+    let state = if recording.is_some() {
+        "recording"
+    } else {
+        "idle"
+    };
+    let mut response = ControlResponse::success(Some(state), Some(&config.language));
+    response.backend = Some(mode.to_string());
+    let _ = respond_to.send(response);
+}
+
 fn acknowledge_error(respond_to: Option<&SyncSender<ControlResponse>>, err: &AppError) {
     if let Some(respond_to) = respond_to {
         let _ = respond_to.send(ControlResponse::error(err.to_string()));
@@ -516,16 +1490,19 @@ fn reload_model(
     size: ModelSize,
     model_language: ModelLanguage,
     recording: &mut Option<ActiveRecording>,
+    state_file: &mut StateFile,
     worker: &mut TranscriptionWorker,
+    model_path: &mut Option<PathBuf>,
     config: &DaemonConfig,
     deps: &DaemonDeps,
     output: &mut dyn DaemonOutput,
-    last_emitted_transcript: &mut String,
+    emit_state: &mut EmitState,
 ) -> Result<String, AppError> {
     if recording.take().is_some() {
+        state_file.update(false, output);
         output.stdout("Recording stopped for model reload.");
     }
-    wait_for_pending_results(worker, config, output, last_emitted_transcript);
+    wait_for_pending_results(worker, config, deps.feedback.as_ref(), output, emit_state);
     let spec = ModelSpec::new(size, model_language);
     let prepared = model::prepare_model(None, &spec, config.download_model)?;
     if prepared.downloaded {
@@ -533,30 +1510,252 @@ fn reload_model(
     }
     let new_transcriber = deps.transcriber_factory.load(Some(&prepared.path))?;
     worker.reload(new_transcriber)?;
+    *model_path = Some(prepared.path);
     Ok(format!(
         "Model reloaded: size={size}, model-language={model_language}"
     ))
 }
 
+/// Reads the configuration again and checks it before the daemon switches
+/// to it: the input device must exist and a changed model must load. The
+/// model is only reloaded when its path changed. Settings that are set up
+/// once at startup keep their running values; feedback sounds switch over
+/// with the rest.
+#[allow(clippy::too_many_arguments)]
+fn reload_config(
+    recording: &mut Option<ActiveRecording>,
+    state_file: &mut StateFile,
+    worker: &mut TranscriptionWorker,
+    model_path: &mut Option<PathBuf>,
+    host: &cpal::Host,
+    config: &DaemonConfig,
+    deps: &DaemonDeps,
+    output: &mut dyn DaemonOutput,
+    emit_state: &mut EmitState,
+) -> Result<DaemonConfig, AppError> {
+    let Some(loader) = &deps.config_loader else {
+        return Err(AppError::runtime(
+            "this daemon cannot reload its configuration",
+        ));
+    };
+    let mut reloaded = loader()?;
+    let unchanged = keep_startup_settings(config, &mut reloaded);
+    if !unchanged.is_empty() {
+        output.stderr(&format!(
+            "warn: restart the daemon to apply {}",
+            unchanged.join(", ")
+        ));
+    }
+    if let Some(device) = reloaded.device.as_deref() {
+        let devices = deps
+            .audio
+            .list_input_devices(host)
+            .map_err(|err| AppError::audio(err.message))?;
+        if !devices.iter().any(|name| name.eq_ignore_ascii_case(device)) {
+            return Err(AppError::audio(format!("input device not found: {device}")));
+        }
+    }
+    if reloaded.model_path != *model_path {
+        if recording.take().is_some() {
+            state_file.update(false, output);
+            output.stdout("Recording stopped for model reload.");
+        }
+        wait_for_pending_results(worker, config, deps.feedback.as_ref(), output, emit_state);
+        // An unloaded model is loaded from the new path on the next recording.
+        if worker.is_loaded() {
+            let transcriber = deps
+                .transcriber_factory
+                .load(reloaded.model_path.as_deref())?;
+            worker.reload(transcriber)?;
+        }
+        model_path.clone_from(&reloaded.model_path);
+        if let Some(path) = model_path {
+            output.stdout(&format!("Model reloaded: {}", path.display()));
+        }
+    }
+    deps.feedback.reload(&reloaded);
+    Ok(reloaded)
+}
+
+/// Copies the settings that only take effect at startup from `running` to
+/// `reloaded`, and names those the reloaded configuration tried to change.
+fn keep_startup_settings(running: &DaemonConfig, reloaded: &mut DaemonConfig) -> Vec<&'static str> {
+    fn keep<T: PartialEq + Clone>(
+        name: &'static str,
+        running: &T,
+        reloaded: &mut T,
+        unchanged: &mut Vec<&'static str>,
+    ) {
+        if reloaded != running {
+            unchanged.push(name);
+            reloaded.clone_from(running);
+        }
+    }
+    let mut unchanged = Vec::new();
+    let names = &mut unchanged;
+    keep(
+        "audio_host",
+        &running.audio_host,
+        &mut reloaded.audio_host,
+        names,
+    );
+    keep(
+        "sample_rate",
+        &running.sample_rate,
+        &mut reloaded.sample_rate,
+        names,
+    );
+    keep("hotkey", &running.hotkey, &mut reloaded.hotkey, names);
+    keep(
+        "websocket",
+        &running.websocket,
+        &mut reloaded.websocket,
+        names,
+    );
+    keep(
+        "websocket_addr",
+        &running.websocket_addr,
+        &mut reloaded.websocket_addr,
+        names,
+    );
+    keep(
+        "queue_depth",
+        &running.queue_depth,
+        &mut reloaded.queue_depth,
+        names,
+    );
+    keep(
+        "queue_overflow",
+        &running.queue_overflow,
+        &mut reloaded.queue_overflow,
+        names,
+    );
+    // Decoding settings are baked into the transcriber factory.
+    keep(
+        "no_context",
+        &running.no_context,
+        &mut reloaded.no_context,
+        names,
+    );
+    keep(
+        "single_segment",
+        &running.single_segment,
+        &mut reloaded.single_segment,
+        names,
+    );
+    keep(
+        "temperature",
+        &running.temperature,
+        &mut reloaded.temperature,
+        names,
+    );
+    keep(
+        "temperature_inc",
+        &running.temperature_inc,
+        &mut reloaded.temperature_inc,
+        names,
+    );
+    keep(
+        "logprob_thold",
+        &running.logprob_thold,
+        &mut reloaded.logprob_thold,
+        names,
+    );
+    keep(
+        "suppress_nst",
+        &running.suppress_nst,
+        &mut reloaded.suppress_nst,
+        names,
+    );
+    keep(
+        "suppress_regex",
+        &running.suppress_regex,
+        &mut reloaded.suppress_regex,
+        names,
+    );
+    keep(
+        "transcription_timeout_ms",
+        &running.transcription_timeout_ms,
+        &mut reloaded.transcription_timeout_ms,
+        names,
+    );
+    // A monitor source is selected through the environment at startup, and
+    // the default device keeps recording from it afterwards.
+    if [&running.device, &reloaded.device]
+        .into_iter()
+        .flatten()
+        .any(|device| audio::is_monitor_source(device))
+    {
+        keep("device", &running.device, &mut reloaded.device, names);
+    }
+    keep(
+        "code_switching",
+        &running.code_switching,
+        &mut reloaded.code_switching,
+        names,
+    );
+    keep(
+        "candidate_languages",
+        &running.candidate_languages,
+        &mut reloaded.candidate_languages,
+        names,
+    );
+    // The new threshold drives the VAD right away, but code switching keeps
+    // splitting on the one it started with.
+    if running.code_switching.enabled && reloaded.vad_threshold != running.vad_threshold {
+        names.push("vad_threshold for code_switching");
+    }
+    unchanged
+}
+
 fn submit_final_recording(
     worker: &mut TranscriptionWorker,
     config: &DaemonConfig,
     vad: &audio::VadConfig,
     buffer: &[f32],
+    native: Option<&NativeCopy>,
     had_overlap: bool,
     output: &mut dyn DaemonOutput,
 ) -> Result<(), AppError> {
-    let trimmed = audio::trim_trailing_silence(buffer, config.sample_rate, vad);
-    if trimmed.is_empty() {
+    let trimmed = &buffer[..audio::trailing_silence_start(buffer, config.sample_rate, vad)];
+    let padded;
+    let samples = match config.trim_pad_ms.filter(|_| vad.enabled) {
+        Some(pad_ms) => {
+            let segments = audio::speech_segments(trimmed, config.sample_rate, vad);
+            padded = audio::trim_to_speech(trimmed, config.sample_rate, &segments, pad_ms);
+            &padded[..]
+        }
+        None => trimmed,
+    };
+    let samples = match config.trim_silence_pad_ms {
+        Some(pad_ms) => {
+            audio::trim_silence(samples, config.sample_rate, config.vad_threshold, pad_ms)
+        }
+        None => samples,
+    };
+    // The buffer is already at `sample_rate`, and trimming first keeps
+    // trailing silence from eating into the tail. A zero tail means off.
+    let samples = match config.tail_secs.filter(|&secs| secs > 0) {
+        Some(secs) => {
+            let tail_len = secs as usize * config.sample_rate as usize;
+            &samples[samples.len().saturating_sub(tail_len)..]
+        }
+        None => samples,
+    };
+    if samples.is_empty() {
         return Ok(());
     }
-    submit_segment(worker, config, &trimmed, had_overlap, output)
+    submit_segment(worker, config, samples, native, had_overlap, output)
 }
 
+/// Queues `samples` for transcription. `native`, the device-rate copy of
+/// the same audio before any trimming, is what gets saved as the last
+/// recording when there is one.
 fn submit_segment(
     worker: &mut TranscriptionWorker,
     config: &DaemonConfig,
     samples: &[f32],
+    native: Option<&NativeCopy>,
     had_overlap: bool,
     output: &mut dyn DaemonOutput,
 ) -> Result<(), AppError> {
@@ -567,143 +1766,673 @@ fn submit_segment(
     if config.dump_audio {
         dump_audio_samples(samples, config.sample_rate, output)?;
     }
+    if config.save_last_recording {
+        let (saved, sample_rate) = match native {
+            Some(native) => (&native.samples[..], native.sample_rate),
+            None => (samples, config.sample_rate),
+        };
+        if let Err(err) = save_last_recording(saved, sample_rate, config) {
+            output.stderr(&format!("warn: {err}"));
+        }
+    }
+    let mut job_samples = worker.buffers().take();
+    job_samples.extend_from_slice(samples);
     worker.submit(TranscriptionJob {
-        samples: samples.to_vec(),
+        samples: job_samples,
         duration_ms: audio::samples_to_ms(samples.len(), config.sample_rate),
         language: Some(config.language.clone()),
         had_overlap,
+        partial: false,
     })
 }
 
 fn drain_worker_results(
     worker: &mut TranscriptionWorker,
     config: &DaemonConfig,
+    feedback: &dyn Feedback,
     output: &mut dyn DaemonOutput,
-    last_emitted_transcript: &mut String,
+    emit_state: &mut EmitState,
 ) {
+    while let Some((index, span)) = worker.try_recv_segment() {
+        report_segment(output, index, span);
+    }
     while let Some(result) = worker.try_recv() {
-        emit_worker_result(config, output, result, last_emitted_transcript);
+        emit_worker_result(config, feedback, output, result, emit_state);
     }
 }
 
+/// Shows which piece of a split recording is being decoded.
+fn report_segment(output: &mut dyn DaemonOutput, index: usize, (start, end): (f32, f32)) {
+    output.stdout(&format!(
+        "Transcribing piece {} ({start:.1}-{end:.1} s).",
+        index + 1
+    ));
+}
+
 fn wait_for_pending_results(
     worker: &mut TranscriptionWorker,
     config: &DaemonConfig,
+    feedback: &dyn Feedback,
     output: &mut dyn DaemonOutput,
-    last_emitted_transcript: &mut String,
+    emit_state: &mut EmitState,
 ) {
     while worker.has_pending() {
-        match worker.recv() {
-            Some(result) => emit_worker_result(config, output, result, last_emitted_transcript),
+        let result =
+            worker.recv_reporting_segments(|index, span| report_segment(output, index, span));
+        match result {
+            Some(result) => emit_worker_result(config, feedback, output, result, emit_state),
             None => break,
         }
     }
+    // A recording that ended without a final job must not carry typed-ahead text over.
+    emit_state.incremental_injected.clear();
+    emit_state.settled = SettledAudio::default();
+}
+
+/// Outputs the transcripts still queued when the daemon stops, for up to
+/// `shutdown_drain_ms`. Whatever is left then is dropped, and a decode
+/// still running is aborted. Either way the worker thread is joined, which
+/// frees the whisper context before exit.
+fn drain_for_shutdown(
+    worker: &mut TranscriptionWorker,
+    config: &DaemonConfig,
+    feedback: &dyn Feedback,
+    output: &mut dyn DaemonOutput,
+    emit_state: &mut EmitState,
+) -> Result<(), AppError> {
+    let deadline = (config.shutdown_drain_ms > 0)
+        .then(|| std::time::Instant::now() + Duration::from_millis(config.shutdown_drain_ms));
+    // The loop handles no more requests, so they are answered while the
+    // drain waits instead of after it.
+    let control = emit_state.control;
+    let refuse = || {
+        if let Some(control) = control {
+            control.refuse_requests(SHUTTING_DOWN);
+        }
+    };
+    while worker.has_pending() {
+        refuse();
+        match worker.recv_polling(SHUTDOWN_POLL_INTERVAL, deadline, refuse) {
+            Some(result) => emit_worker_result(config, feedback, output, result, emit_state),
+            None => break,
+        }
+    }
+    if worker.has_pending() {
+        let dropped = worker.abandon();
+        output.stderr(&format!(
+            "warn: {dropped} transcription(s) dropped after waiting {} ms at shutdown",
+            config.shutdown_drain_ms
+        ));
+        return Ok(());
+    }
+    worker.shutdown()
+}
+
+/// Applies spoken punctuation, the configured word filter, filler removal
+/// and casing, in that order: casing sees the marks spoken punctuation
+/// added, so a spoken "period" starts a new sentence.
+fn clean_transcript(config: &DaemonConfig, text: &str) -> String {
+    let mut text = if config.voice_commands.punctuation {
+        postprocess::expand_spoken_punctuation(text)
+    } else {
+        text.to_string()
+    };
+    text = postprocess::filter_words(&text, &config.word_filter);
+    if config.fillers.enabled {
+        text = postprocess::remove_fillers(&text, &config.fillers.words_for(&config.language));
+    }
+    match config.casing {
+        Casing::Whisper => text,
+        Casing::SentenceStartOnly => postprocess::capitalize_sentence_starts(&text),
+        Casing::ForceLower => text.to_lowercase(),
+    }
 }
 
 fn emit_worker_result(
     config: &DaemonConfig,
+    feedback: &dyn Feedback,
     output: &mut dyn DaemonOutput,
     result: TranscriptionResult,
-    last_emitted_transcript: &mut String,
+    emit_state: &mut EmitState,
 ) {
-    match result.transcript {
-        Ok(transcript) => {
-            let text = if result.had_overlap && !last_emitted_transcript.trim().is_empty() {
-                segmentation::dedupe_boundary(last_emitted_transcript, &transcript)
+    if result.partial {
+        emit_state.partial_pending = false;
+        if result.transcript.is_ok() {
+            let transcript = settle_partial(
+                config.sample_rate,
+                &mut emit_state.settled,
+                &result.segments,
+            );
+            let transcript = clean_transcript(config, &transcript);
+            if config.websocket_partials {
+                let info = audio::SegmentInfo {
+                    index: result.index,
+                    duration_ms: result.duration_ms,
+                };
+                broadcast(config, emit_state, "partial", &transcript, info);
+            }
+            emit_partial_transcript(config, output, &transcript, emit_state);
+        }
+        return;
+    }
+
+    let config = &*profiled_config(config, output);
+
+    if config.min_word_confidence > 0.0 {
+        let uncertain = uncertain_words(&result.words, config.min_word_confidence);
+        if !uncertain.is_empty() {
+            output.stderr(&format!(
+                "warn: low-confidence words in transcript {}: {}",
+                result.index,
+                uncertain.join(", ")
+            ));
+        }
+    }
+
+    if let Ok(text) = &result.transcript {
+        if config.voice_commands.is_undo(text) {
+            let typed_ahead = std::mem::take(&mut emit_state.incremental_injected);
+            undo_last(config, output, &typed_ahead, emit_state);
+            return;
+        }
+    }
+
+    // Voice commands may expand to whitespace such as a newline, which is
+    // still worth injecting; cleaned speech that is only whitespace is not.
+    let transcript = result
+        .transcript
+        .map(|text| match config.voice_commands.resolve(&text) {
+            Some(command) => (command.to_string(), true),
+            None => (clean_transcript(config, &text), false),
+        })
+        .map(|(text, is_command)| match &config.cleanup_command {
+            Some(command) if !is_command => (run_cleanup(config, output, command, text), false),
+            _ => (text, is_command),
+        });
+    let typed_ahead = std::mem::take(&mut emit_state.incremental_injected);
+    match transcript {
+        Ok((transcript, is_command)) => {
+            let text = if result.had_overlap && !emit_state.last_transcript.trim().is_empty() {
+                segmentation::dedupe_boundary(&emit_state.last_transcript, &transcript)
             } else {
                 transcript
             };
-            if !text.trim().is_empty() {
-                emit_transcript(
-                    config,
-                    output,
-                    &text,
-                    audio::SegmentInfo {
-                        index: result.index,
-                        duration_ms: result.duration_ms,
-                    },
-                );
-                *last_emitted_transcript = text;
+            let info = audio::SegmentInfo {
+                index: result.index,
+                duration_ms: result.duration_ms,
+            };
+            if !typed_ahead.is_empty() {
+                let typed = complete_incremental_transcript(config, output, &typed_ahead, &text);
+                emit_state.undoable = typed.then(|| injected_text(config, &text));
+                if config.session_transcript {
+                    emit_state.session.push(&text);
+                }
+                emit_state.repeatable = Some((text.clone(), info));
+                broadcast(config, emit_state, "final", &text, info);
+                emit_state.last_transcript = text;
+            } else if text.is_empty() || (!is_command && text.trim().is_empty()) {
+                output.stdout("Nothing heard, output skipped.");
+                if config.audio_feedback {
+                    feedback.on_nothing_heard();
+                }
+            } else if !is_command && repeats_last_injection(config, emit_state, &text) {
+                output.stdout("Same transcript as the last one, output skipped.");
+            } else if config.tts_confirm
+                && !is_command
+                && readback_cancelled(config, output, &text, emit_state)
+            {
+                output.stdout("Readback cancelled, output skipped.");
+            } else {
+                settle_after_focus_change(config, output, emit_state);
+                let emitted = emit_transcript(config, output, &text, info);
+                emit_state.undoable =
+                    (emitted == Emitted::Injected).then(|| injected_text(config, &text));
+                if config.session_transcript {
+                    emit_state.session.push(&text);
+                }
+                emit_state.last_injected = Some((text.clone(), std::time::Instant::now()));
+                emit_state.repeatable = Some((text.clone(), info));
+                broadcast(config, emit_state, "final", &text, info);
+                emit_state.last_transcript = text;
+            }
+        }
+        Err(err) => {
+            output.stderr(&format!("Transcription error: {err}"));
+            if config.audio_feedback {
+                feedback.on_error(&err.to_string());
             }
         }
-        Err(err) => output.stderr(&format!("Transcription error: {err}")),
     }
 }
 
-fn segment_config(config: &DaemonConfig) -> SegmentConfig {
-    SegmentConfig {
-        sample_rate: config.sample_rate,
-        vad_threshold: config.vad_threshold,
-        silence_samples: samples_from_ms(config.vad_silence_ms, config.sample_rate),
-        target_samples: samples_from_ms(config.segment_target_ms, config.sample_rate),
-        grace_samples: samples_from_ms(config.segment_grace_ms, config.sample_rate),
-        overlap_samples: samples_from_ms(config.segment_overlap_ms, config.sample_rate),
-        min_segment_samples: samples_from_ms(config.segment_min_ms, config.sample_rate),
-    }
+/// Sends `text` to websocket clients as the `json` output object, tagged
+/// with `kind` and the utterance number.
+fn broadcast(
+    config: &DaemonConfig,
+    emit_state: &EmitState,
+    kind: &str,
+    text: &str,
+    info: audio::SegmentInfo,
+) {
+    let Some(broadcaster) = emit_state.broadcast else {
+        return;
+    };
+    let mut frame = json_result(text, &config.language, info);
+    frame["type"] = kind.into();
+    frame["utterance"] = info.index.into();
+    broadcaster.send(&frame);
 }
 
-fn samples_from_ms(ms: u64, sample_rate: u32) -> usize {
-    ((ms as f64 / 1000.0) * sample_rate as f64).round() as usize
+/// `config` with the focused window's profile applied.
+fn profiled_config<'a>(
+    config: &'a DaemonConfig,
+    output: &mut dyn DaemonOutput,
+) -> Cow<'a, DaemonConfig> {
+    match active_profile(config, output) {
+        Some(profile) => {
+            let mut adjusted = config.clone();
+            profile.apply(&mut adjusted);
+            Cow::Owned(adjusted)
+        }
+        None => Cow::Borrowed(config),
+    }
 }
 
-fn carried_overlap_contains_speech(
-    samples: &[f32],
-    sample_rate: u32,
-    vad_threshold: f32,
-    vad_chunk_ms: u64,
-) -> bool {
-    if samples.is_empty() {
-        return false;
+/// Outputs the last transcript again into the window that has focus now,
+/// with that window's output settings. Post-processing is not run again, so
+/// the text keeps what the profile of the window it was dictated into did.
+fn repeat_last_transcript(
+    config: &DaemonConfig,
+    output: &mut dyn DaemonOutput,
+    emit_state: &mut EmitState,
+) -> Result<String, AppError> {
+    let Some((text, info)) = emit_state.repeatable.clone() else {
+        return Err(AppError::runtime("no transcript to repeat yet"));
+    };
+    let config = &*profiled_config(config, output);
+    settle_after_focus_change(config, output, emit_state);
+    let emitted = emit_transcript(config, output, &text, info);
+    emit_state.undoable = (emitted == Emitted::Injected).then(|| injected_text(config, &text));
+    if emitted == Emitted::Failed {
+        return Err(AppError::runtime(format!(
+            "transcript {} could not be output",
+            info.index
+        )));
     }
-    let chunk_samples = samples_from_ms(vad_chunk_ms, sample_rate).max(1);
-    samples
-        .chunks(chunk_samples)
-        .any(|chunk| audio::rms_energy(chunk) >= vad_threshold)
+    emit_state.last_injected = Some((text, std::time::Instant::now()));
+    Ok(format!("Repeated transcript {}.", info.index))
 }
 
-fn emit_transcript(
+/// Erases the last transcript from the focused window, along with anything
+/// typed ahead from partials of the undo utterance itself.
+fn undo_last(
     config: &DaemonConfig,
     output: &mut dyn DaemonOutput,
-    text: &str,
-    info: audio::SegmentInfo,
+    typed_ahead: &str,
+    emit_state: &mut EmitState,
 ) {
-    match config.output.mode {
-        OutputMode::Stdout => emit_stdout(config.format, output, text, info),
-        OutputMode::Clipboard => {
-            if let Err(err) = output::output_text(text, &config.output) {
-                output.stderr(&format!("warn: {err}; falling back to stdout"));
-                emit_stdout(config.format, output, text, info)
-            }
-        }
-        OutputMode::Paste | OutputMode::Type | OutputMode::Ydotool => {
-            let insertion_text = segmentation::append_segment_space(text);
-            if let Err(err) = output::output_text(&insertion_text, &config.output) {
-                output.stderr(&format!("warn: {err}; falling back to stdout"));
-                emit_stdout(config.format, output, text, info)
-            }
+    let mut count = 0;
+    if !typed_ahead.is_empty() {
+        let prefix = config.output.prefix.as_deref().unwrap_or_default();
+        count += prefix.chars().count() + typed_ahead.chars().count();
+    }
+    let undone = emit_state.undoable.take();
+    count += undone.as_deref().map_or(0, |text| text.chars().count());
+    if undone.is_none() {
+        output.stdout("Nothing to undo.");
+    }
+    if count == 0 {
+        return;
+    }
+    match output.erase(count, &config.output) {
+        Ok(()) if undone.is_some() => {
+            emit_state.last_injected = None;
+            output.stdout("Last transcript undone.");
         }
+        Ok(()) => {}
+        Err(err) => output.stderr(&format!("warn: undo failed: {err}")),
     }
 }
 
-pub fn transcript_file_path() -> PathBuf {
-    let data_home = env::var_os("XDG_DATA_HOME")
-        .map(PathBuf::from)
-        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
-        .unwrap_or_else(|| PathBuf::from("/tmp"));
-    let date = Local::now().format("%Y-%m-%d").to_string();
-    data_home
-        .join("soundvibes")
-        .join("transcripts")
-        .join(format!("{date}.log"))
+/// The characters `emit_transcript` types for `text` in a window.
+fn injected_text(config: &DaemonConfig, text: &str) -> String {
+    segmentation::append_segment_space(&output::apply_affixes(text, &config.output))
 }
 
-fn emit_stdout(
-    format: OutputFormat,
+/// The profile for the focused window. The window is only queried when
+/// profiles are configured.
+fn active_profile<'a>(
+    config: &'a DaemonConfig,
+    output: &mut dyn DaemonOutput,
+) -> Option<&'a Profile> {
+    if config.profiles.is_empty() {
+        return None;
+    }
+    let window_class = output.focused_window_class()?;
+    let profile = profile::find_profile(&config.profiles, &window_class);
+    if let Some(profile) = profile {
+        log::debug!(
+            "profile {} applies to window {window_class}",
+            profile.match_class
+        );
+    }
+    profile
+}
+
+/// Passes `text` through the cleanup command, falling back to `text` itself
+/// when the command fails or runs past `cleanup_timeout_ms`.
+fn run_cleanup(
+    config: &DaemonConfig,
+    output: &mut dyn DaemonOutput,
+    command: &str,
+    text: String,
+) -> String {
+    if text.trim().is_empty() {
+        return text;
+    }
+    let timeout = Duration::from_millis(config.cleanup_timeout_ms);
+    match cleanup::run_cleanup_command(command, &text, timeout) {
+        Ok(cleaned) => cleaned,
+        Err(err) => {
+            output.stderr(&format!("warn: {err}; using the raw transcript"));
+            text
+        }
+    }
+}
+
+/// Reads `text` aloud with `tts_command` and reports whether the hotkey was
+/// pressed before the readback finished. A failed readback never blocks output.
+fn readback_cancelled(
+    config: &DaemonConfig,
+    output: &mut dyn DaemonOutput,
+    text: &str,
+    emit_state: &EmitState,
+) -> bool {
+    let mut speech = match feedback::start_readback(&config.tts_command, text) {
+        Ok(speech) => speech,
+        Err(err) => {
+            output.stderr(&format!("warn: transcript readback failed: {err}"));
+            return false;
+        }
+    };
+    let cancelled = loop {
+        if emit_state
+            .control
+            .is_some_and(ControlInbox::take_hotkey_press)
+        {
+            break true;
+        }
+        match speech.try_wait() {
+            Ok(None) => thread::sleep(Duration::from_millis(20)),
+            _ => break false,
+        }
+    };
+    if cancelled {
+        let _ = speech.kill();
+    }
+    let _ = speech.wait();
+    cancelled
+}
+
+/// Words scored below `min_confidence`, each with its score.
+fn uncertain_words(words: &[(String, f32)], min_confidence: f32) -> Vec<String> {
+    words
+        .iter()
+        .filter(|(_, confidence)| *confidence < min_confidence)
+        .map(|(word, confidence)| format!("{word} ({confidence:.2})"))
+        .collect()
+}
+
+/// Whether `text` matches the previous injection within `repeat_guard_ms`.
+/// Voice commands are exempt since repeating them is normal.
+fn repeats_last_injection(config: &DaemonConfig, emit_state: &EmitState, text: &str) -> bool {
+    config.repeat_guard_ms > 0
+        && emit_state.last_injected.as_ref().is_some_and(|(last, at)| {
+            last == text && at.elapsed() < Duration::from_millis(config.repeat_guard_ms)
+        })
+}
+
+fn vad_config(config: &DaemonConfig) -> audio::VadConfig {
+    audio::VadConfig::new(
+        config.vad == VadMode::On || config.vad == VadMode::Continuous,
+        config.vad_silence_ms,
+        config.vad_threshold,
+        config.vad_chunk_ms,
+    )
+}
+
+fn segment_config(config: &DaemonConfig) -> SegmentConfig {
+    SegmentConfig {
+        sample_rate: config.sample_rate,
+        vad_threshold: config.vad_threshold,
+        silence_samples: samples_from_ms(config.vad_silence_ms, config.sample_rate),
+        target_samples: samples_from_ms(config.segment_target_ms, config.sample_rate),
+        grace_samples: samples_from_ms(config.segment_grace_ms, config.sample_rate),
+        overlap_samples: samples_from_ms(config.segment_overlap_ms, config.sample_rate),
+        min_segment_samples: samples_from_ms(config.segment_min_ms, config.sample_rate),
+        speech_confirm_samples: samples_from_ms(config.speech_confirm_ms, config.sample_rate),
+        silence_confirm_samples: samples_from_ms(config.silence_confirm_ms, config.sample_rate),
+    }
+}
+
+fn samples_from_ms(ms: u64, sample_rate: u32) -> usize {
+    ((ms as f64 / 1000.0) * sample_rate as f64).round() as usize
+}
+
+fn carried_overlap_contains_speech(
+    samples: &[f32],
+    sample_rate: u32,
+    vad_threshold: f32,
+    vad_chunk_ms: u64,
+) -> bool {
+    if samples.is_empty() {
+        return false;
+    }
+    let chunk_samples = samples_from_ms(vad_chunk_ms, sample_rate).max(1);
+    samples
+        .chunks(chunk_samples)
+        .any(|chunk| audio::rms_energy(chunk) >= vad_threshold)
+}
+
+fn incremental_inject_enabled(config: &DaemonConfig) -> bool {
+    config.incremental_inject
+        && config.vad != VadMode::Continuous
+        && matches!(
+            config.output.mode,
+            OutputMode::Paste | OutputMode::Type | OutputMode::Ydotool | OutputMode::Portal
+        )
+}
+
+/// The partial transcript of the whole recording so far. Every segment but
+/// the last, which may still change as speech continues, joins the settled
+/// audio, so the next partial job skips it.
+fn settle_partial(
+    sample_rate: u32,
+    settled: &mut SettledAudio,
+    segments: &[TimedSegment],
+) -> String {
+    let transcript = join_text(
+        &settled.text,
+        &transcription_worker::join_segments(segments),
+    );
+    let finished = segments
+        .split_last()
+        .map_or(&[][..], |(_, finished)| finished);
+    if let Some(end_ms) = finished.last().map(|segment| segment.end_ms) {
+        if end_ms > 0 {
+            settled.samples += samples_from_ms(end_ms, sample_rate);
+            settled.text = join_text(
+                &settled.text,
+                &transcription_worker::join_segments(finished),
+            );
+        }
+    }
+    transcript
+}
+
+fn join_text(left: &str, right: &str) -> String {
+    match (left.is_empty(), right.is_empty()) {
+        (_, true) => left.to_string(),
+        (true, false) => right.to_string(),
+        (false, false) => format!("{left} {right}"),
+    }
+}
+
+fn emit_partial_transcript(
+    config: &DaemonConfig,
+    output: &mut dyn DaemonOutput,
+    transcript: &str,
+    emit_state: &mut EmitState,
+) {
+    let stable = segmentation::stable_prefix(transcript);
+    let (erase, addition) =
+        segmentation::incremental_correction(&emit_state.incremental_injected, stable);
+    // Partials only ever append here; revisions wait for the final result.
+    if erase > 0 || addition.is_empty() {
+        return;
+    }
+    // The prefix goes out with the first addition but stays out of the typed-ahead
+    // text, so later corrections never erase it.
+    let insertion_text = match config.output.prefix.as_deref() {
+        Some(prefix) if emit_state.incremental_injected.is_empty() => format!("{prefix}{addition}"),
+        _ => addition.to_string(),
+    };
+    match output.inject(&insertion_text, &config.output) {
+        Ok(()) => emit_state.incremental_injected.push_str(addition),
+        Err(err) => output.stderr(&format!("warn: incremental inject failed: {err}")),
+    }
+}
+
+fn complete_incremental_transcript(
+    config: &DaemonConfig,
+    output: &mut dyn DaemonOutput,
+    typed_ahead: &str,
+    text: &str,
+) -> bool {
+    let (erase, remainder) = segmentation::incremental_correction(typed_ahead, text);
+    if let Err(err) = output.erase(erase, &config.output) {
+        output.stderr(&format!("warn: incremental correction failed: {err}"));
+        return false;
+    }
+    let suffix = config.output.suffix.as_deref().unwrap_or_default();
+    let insertion_text = segmentation::append_segment_space(&format!("{remainder}{suffix}"));
+    if let Err(err) = output.inject(&insertion_text, &config.output) {
+        output.stderr(&format!("warn: {err}"));
+        return false;
+    }
+    injects_into_window(config.output.mode)
+}
+
+/// Whether `mode` types into the focused window rather than printing or
+/// copying.
+fn injects_into_window(mode: OutputMode) -> bool {
+    matches!(
+        mode,
+        OutputMode::Paste | OutputMode::Type | OutputMode::Ydotool | OutputMode::Portal
+    )
+}
+
+/// Waits `focus_settle_ms` when the focused window is not the one the last
+/// transcript went to. Windows are told apart by the id the compositor
+/// reports, so switching between two windows of one app counts. Without an
+/// id the class decides, and nothing waits when neither can be queried.
+fn settle_after_focus_change(
+    config: &DaemonConfig,
+    output: &mut dyn DaemonOutput,
+    emit_state: &mut EmitState,
+) {
+    if !injects_into_window(config.output.mode) || config.output.focus_settle_ms == 0 {
+        return;
+    }
+    let Some(window) = output
+        .focused_window_id()
+        .or_else(|| output.focused_window_class())
+    else {
+        return;
+    };
+    if emit_state.last_window.as_deref() != Some(window.as_str()) {
+        log::debug!(
+            "focus moved to {window}; waiting {} ms before injecting",
+            config.output.focus_settle_ms
+        );
+        thread::sleep(Duration::from_millis(config.output.focus_settle_ms));
+        emit_state.last_window = Some(window);
+    }
+}
+
+/// Sends `text` to the configured output. Returns whether all of it was
+/// typed into the focused window.
+/// How `emit_transcript` delivered a transcript.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Emitted {
+    /// Typed or pasted into the focused window, where undo can erase it.
+    Injected,
+    /// Printed or copied, as the output mode asks.
+    Shown,
+    /// Output failed, and the transcript was printed instead.
+    Failed,
+}
+
+fn emit_transcript(
+    config: &DaemonConfig,
+    output: &mut dyn DaemonOutput,
+    text: &str,
+    info: audio::SegmentInfo,
+) -> Emitted {
+    match config.output.mode {
+        OutputMode::Stdout => {
+            emit_stdout(config, output, text, info);
+            Emitted::Shown
+        }
+        OutputMode::Clipboard => {
+            let insertion_text = output::apply_affixes(text, &config.output);
+            if let Err(err) = output.inject(&insertion_text, &config.output) {
+                output.stderr(&format!("warn: {err}; falling back to stdout"));
+                emit_stdout(config, output, text, info);
+                save_unsent(config, output, text);
+                return Emitted::Failed;
+            }
+            Emitted::Shown
+        }
+        OutputMode::Paste | OutputMode::Type | OutputMode::Ydotool | OutputMode::Portal => {
+            let insertion_text = injected_text(config, text);
+            let pieces = if config.output.inject_sentences {
+                postprocess::split_sentences(&insertion_text)
+            } else {
+                vec![insertion_text.as_str()]
+            };
+            for piece in pieces {
+                if let Err(err) = output.inject(piece, &config.output) {
+                    output.stderr(&format!("warn: {err}; falling back to stdout"));
+                    emit_stdout(config, output, text, info);
+                    save_unsent(config, output, text);
+                    return Emitted::Failed;
+                }
+            }
+            Emitted::Injected
+        }
+    }
+}
+
+pub fn transcript_file_path() -> PathBuf {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .unwrap_or_else(|| PathBuf::from("/tmp"));
+    let date = Local::now().format("%Y-%m-%d").to_string();
+    data_home
+        .join("soundvibes")
+        .join("transcripts")
+        .join(format!("{date}.log"))
+}
+
+fn emit_stdout(
+    config: &DaemonConfig,
     output: &mut dyn DaemonOutput,
     text: &str,
     info: audio::SegmentInfo,
 ) {
-    match format {
+    match config.format {
         OutputFormat::Plain => {
             output.stdout(&format!("Transcript {}: {}", info.index, text));
         }
@@ -719,7 +2448,150 @@ fn emit_stdout(
                 .to_string(),
             );
         }
+        OutputFormat::Json => {
+            output.stdout(&json_result(text, &config.language, info).to_string());
+        }
+    }
+}
+
+/// Builds the `json` output object. Confidence and segments are not computed
+/// yet, and an `auto` language is unknown until detection reports it.
+fn json_result(text: &str, language: &str, info: audio::SegmentInfo) -> serde_json::Value {
+    let language = (language != "auto").then_some(language);
+    serde_json::json!({
+        "text": text,
+        "language": language,
+        "confidence": null,
+        "segments": null,
+        "duration_ms": info.duration_ms,
+    })
+}
+
+/// Default location of the last transcribed recording.
+pub fn last_recording_path() -> PathBuf {
+    env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join("soundvibes")
+        .join("last.wav")
+}
+
+/// Default location of transcripts that could not be delivered.
+pub fn unsent_file_path() -> PathBuf {
+    env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join("soundvibes")
+        .join("unsent.txt")
+}
+
+/// Default location of the recording state file.
+pub fn state_file_path() -> PathBuf {
+    env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join("soundvibes.state")
+}
+
+/// Mirrors the recording state into a file a status bar can poll, and
+/// removes the file when the daemon loop ends.
+struct StateFile {
+    path: Option<PathBuf>,
+    recording: Option<bool>,
+}
+
+impl StateFile {
+    fn new(config: &DaemonConfig) -> Self {
+        Self {
+            path: config.state_file.then(|| config.state_file_path.clone()),
+            recording: None,
+        }
+    }
+
+    /// Rewrites the file when the state differs from the last one written.
+    fn update(&mut self, recording: bool, output: &mut dyn DaemonOutput) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if self.recording == Some(recording) {
+            return;
+        }
+        self.recording = Some(recording);
+        let state = if recording { "recording\n" } else { "idle\n" };
+        if let Err(err) = fs::write(path, state) {
+            output.stderr(&format!(
+                "warn: failed to write state file {}: {err}",
+                path.display()
+            ));
+        }
+    }
+}
+
+impl Drop for StateFile {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn save_last_recording(
+    samples: &[f32],
+    sample_rate: u32,
+    config: &DaemonConfig,
+) -> Result<(), AppError> {
+    let path = &config.last_recording_path;
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|err| {
+            AppError::runtime(format!("failed to create {}: {err}", dir.display()))
+        })?;
+    }
+    write_wav(path, samples, sample_rate)
+}
+
+fn keeps_native_copy(config: &DaemonConfig) -> bool {
+    config.save_last_recording && config.save_native_rate
+}
+
+/// Last resort after output failed: appends `text` to the unsent file with
+/// the time it was dictated, then opens the file when `open_unsent` is set.
+fn save_unsent(config: &DaemonConfig, output: &mut dyn DaemonOutput, text: &str) {
+    if !config.save_unsent {
+        return;
+    }
+    let path = &config.unsent_path;
+    let line = format!("[{}] {text}\n", Local::now().format("%Y-%m-%d %H:%M:%S"));
+    if let Err(err) = append_to_file(path, &line) {
+        output.stderr(&format!("warn: {err}"));
+        return;
+    }
+    output.stderr(&format!("Transcript saved to {}.", path.display()));
+    if config.open_unsent {
+        if let Err(err) = Command::new("xdg-open")
+            .arg(path)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            output.stderr(&format!("warn: failed to run xdg-open: {err}"));
+        }
+    }
+}
+
+fn append_to_file(path: &Path, contents: &str) -> Result<(), AppError> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|err| {
+            AppError::runtime(format!("failed to create {}: {err}", dir.display()))
+        })?;
     }
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|err| AppError::runtime(format!("failed to write {}: {err}", path.display())))
 }
 
 fn dump_audio_samples(
@@ -732,13 +2604,20 @@ fn dump_audio_samples(
     let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
     let filename = format!("sv_{timestamp}.wav");
     let path = output_dir.join(filename);
+    write_wav(&path, samples, sample_rate)?;
+    output.stdout(&format!("Saved audio: {}", path.display()));
+    Ok(path)
+}
+
+/// Writes mono 16-bit PCM, replacing any existing file at `path`.
+fn write_wav(path: &Path, samples: &[f32], sample_rate: u32) -> Result<(), AppError> {
     let spec = hound::WavSpec {
         channels: 1,
         sample_rate,
         bits_per_sample: 16,
         sample_format: hound::SampleFormat::Int,
     };
-    let mut writer = hound::WavWriter::create(&path, spec)
+    let mut writer = hound::WavWriter::create(path, spec)
         .map_err(|err| AppError::runtime(format!("failed to create wav file: {err}")))?;
     for sample in samples {
         let clipped = sample.clamp(-1.0, 1.0);
@@ -749,9 +2628,7 @@ fn dump_audio_samples(
     }
     writer
         .finalize()
-        .map_err(|err| AppError::runtime(format!("failed to finalize wav: {err}")))?;
-    output.stdout(&format!("Saved audio: {}", path.display()));
-    Ok(path)
+        .map_err(|err| AppError::runtime(format!("failed to finalize wav: {err}")))
 }
 
 pub struct SocketGuard {
@@ -841,6 +2718,18 @@ pub fn start_socket_listener(
                         Ok(ControlEvent::Stop)
                     } else if command == "status" {
                         Ok(ControlEvent::Status)
+                    } else if command == "repeat" {
+                        Ok(ControlEvent::Repeat)
+                    } else if command == "reload" {
+                        Ok(ControlEvent::Reload)
+                    } else if command == "get-backend" {
+                        Ok(ControlEvent::GetBackend)
+                    } else if let Some(backend) = command.strip_prefix("set-backend") {
+                        parse_set_backend_command(backend).map(ControlEvent::SetBackend)
+                    } else if let Some(gain) = command.strip_prefix("set-gain") {
+                        parse_set_gain_command(gain).map(ControlEvent::SetGain)
+                    } else if let Some(target) = command.strip_prefix("dump-transcript") {
+                        parse_dump_transcript_command(target).map(ControlEvent::DumpTranscript)
                     } else if command.starts_with("set-model") {
                         match parse_set_model_command(command) {
                             Ok((size, model_language)) => Ok(ControlEvent::SetModel {
@@ -930,6 +2819,44 @@ fn parse_set_model_command(command: &str) -> Result<(ModelSize, ModelLanguage),
     Ok((size, model_language))
 }
 
+fn parse_set_backend_command(arguments: &str) -> Result<OutputBackend, String> {
+    let value = arguments
+        .split_whitespace()
+        .find_map(|token| token.strip_prefix("backend="))
+        .ok_or_else(|| "missing backend=<BACKEND>".to_string())?;
+    value.parse()
+}
+
+fn parse_set_gain_command(arguments: &str) -> Result<f32, String> {
+    let value = arguments
+        .split_whitespace()
+        .find_map(|token| token.strip_prefix("db="))
+        .ok_or_else(|| "missing db=<DB>".to_string())?;
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|gain_db| gain_db.is_finite())
+        .ok_or_else(|| format!("invalid gain: {value}"))
+}
+
+/// No arguments dumps to the clipboard; `path=<PATH>` takes the rest of the
+/// line, so the path may contain spaces.
+fn parse_dump_transcript_command(arguments: &str) -> Result<DumpTarget, String> {
+    let arguments = arguments.trim();
+    if arguments.is_empty() {
+        return Ok(DumpTarget::Clipboard);
+    }
+    let path = arguments
+        .strip_prefix("path=")
+        .map(PathBuf::from)
+        .ok_or_else(|| format!("invalid dump-transcript arguments: {arguments}"))?;
+    if path.is_absolute() {
+        Ok(DumpTarget::File(path))
+    } else {
+        Err(format!("dump path must be absolute: {}", path.display()))
+    }
+}
+
 pub fn send_record_start_command() -> Result<ControlResponse, AppError> {
     send_daemon_command("record-start")
 }
@@ -954,6 +2881,35 @@ pub fn send_set_model_command(
     send_daemon_command(&command)
 }
 
+pub fn send_get_backend_command() -> Result<ControlResponse, AppError> {
+    send_daemon_command("get-backend")
+}
+
+pub fn send_set_backend_command(backend: OutputBackend) -> Result<ControlResponse, AppError> {
+    send_daemon_command(&format!("set-backend backend={backend}"))
+}
+
+pub fn send_set_gain_command(gain_db: f32) -> Result<ControlResponse, AppError> {
+    send_daemon_command(&format!("set-gain db={gain_db}"))
+}
+
+pub fn send_repeat_command() -> Result<ControlResponse, AppError> {
+    send_daemon_command("repeat")
+}
+
+pub fn send_reload_command() -> Result<ControlResponse, AppError> {
+    send_daemon_command("reload")
+}
+
+pub fn send_dump_transcript_command(target: &DumpTarget) -> Result<ControlResponse, AppError> {
+    match target {
+        DumpTarget::File(path) => {
+            send_daemon_command(&format!("dump-transcript path={}", path.display()))
+        }
+        DumpTarget::Clipboard => send_daemon_command("dump-transcript"),
+    }
+}
+
 fn send_daemon_command(command: &str) -> Result<ControlResponse, AppError> {
     let socket_path = daemon_socket_path()?;
     if !socket_path.exists() {
@@ -1012,8 +2968,11 @@ impl AudioBackend for CpalAudioBackend {
         host: &cpal::Host,
         device_name: Option<&str>,
         sample_rate: u32,
+        input_channel: Option<usize>,
+        keep_native: bool,
     ) -> Result<Box<dyn CaptureSource>, audio::AudioError> {
-        let capture = audio::start_capture(host, device_name, sample_rate)?;
+        let capture =
+            audio::start_capture(host, device_name, sample_rate, input_channel, keep_native)?;
         Ok(Box::new(CpalCapture { inner: capture }))
     }
 }
@@ -1026,28 +2985,321 @@ impl CaptureSource for CpalCapture {
     fn drain(&mut self, output: &mut Vec<f32>) {
         audio::drain_samples(&mut self.inner, output);
     }
+
+    fn native_rate(&self) -> Option<u32> {
+        self.inner.native_rate()
+    }
+
+    fn drain_native(&mut self, output: &mut Vec<f32>) {
+        audio::drain_native_samples(&mut self.inner, output);
+    }
+
+    fn disconnected(&self) -> bool {
+        self.inner.disconnected()
+    }
+}
+
+/// Loads whisper models from disk, decoding with fixed options.
+#[derive(Debug, Default)]
+pub struct WhisperFactory {
+    decode: DecodeOptions,
+    code_switching: CodeSwitching,
+    pause_threshold: f32,
+    candidate_languages: Vec<String>,
 }
 
-struct WhisperFactory;
+impl WhisperFactory {
+    pub fn new(decode: DecodeOptions) -> Self {
+        Self {
+            decode,
+            ..Self::default()
+        }
+    }
+
+    /// Splits recordings where energy stays below `pause_threshold` when
+    /// code switching is enabled.
+    pub fn with_code_switching(
+        mut self,
+        code_switching: CodeSwitching,
+        pause_threshold: f32,
+    ) -> Self {
+        self.code_switching = code_switching;
+        self.pause_threshold = pause_threshold;
+        self
+    }
+
+    /// Restricts automatic language detection to these languages.
+    pub fn with_candidate_languages(mut self, languages: Vec<String>) -> Self {
+        self.candidate_languages = languages;
+        self
+    }
+}
 
 impl TranscriberFactory for WhisperFactory {
     fn load(&self, model_path: Option<&Path>) -> Result<Box<dyn Transcriber>, AppError> {
         let model_path = model_path.ok_or_else(|| AppError::config("model path is required"))?;
-        let context = WhisperContext::from_file(model_path)
-            .map_err(|err| AppError::runtime(err.to_string()))?;
-        Ok(Box::new(WhisperTranscriber { context }))
+        Ok(Box::new(
+            WhisperTranscriber::from_file(model_path)?
+                .with_decode_options(self.decode.clone())
+                .with_code_switching(self.code_switching.clone(), self.pause_threshold)
+                .with_candidate_languages(self.candidate_languages.clone()),
+        ))
     }
 }
 
-struct WhisperTranscriber {
-    context: WhisperContext,
+/// Transcribes with a whisper context that may be shared with the embedding application.
+///
+/// whisper.cpp decodes into state held by the context, so concurrent users take turns
+/// through the mutex.
+#[derive(Clone)]
+pub struct WhisperTranscriber {
+    context: Arc<Mutex<WhisperContext>>,
+    decode: DecodeOptions,
+    candidates: CandidateOptions,
+    code_switching: CodeSwitching,
+    pause_threshold: f32,
+    candidate_languages: Vec<String>,
+    segment_callback: Option<Arc<Mutex<Box<SegmentCallback>>>>,
 }
 
-impl Transcriber for WhisperTranscriber {
-    fn transcribe(&self, samples: &[f32], language: Option<&str>) -> Result<String, AppError> {
-        self.context
-            .transcribe(samples, language)
-            .map_err(|err| AppError::runtime(err.to_string()))
+/// Pieces shorter than this are too short for reliable language detection.
+const MIN_CODE_SWITCH_PIECE_MS: u64 = 1000;
+/// Analysis window used to find pauses between code-switched pieces.
+const CODE_SWITCH_CHUNK_MS: u64 = 20;
+
+impl WhisperTranscriber {
+    /// Uses a context the caller already loaded instead of loading a second copy.
+    pub fn from_shared(context: Arc<Mutex<WhisperContext>>) -> Self {
+        Self {
+            context,
+            decode: DecodeOptions::default(),
+            candidates: CandidateOptions::default(),
+            code_switching: CodeSwitching::default(),
+            pause_threshold: audio::DEFAULT_VAD_THRESHOLD,
+            candidate_languages: Vec::new(),
+            segment_callback: None,
+        }
+    }
+
+    /// Loads a private context from a model file.
+    pub fn from_file(model_path: &Path) -> Result<Self, AppError> {
+        let context = WhisperContext::from_file(model_path)
+            .map_err(|err| AppError::runtime(err.to_string()))?;
+        Ok(Self::from_shared(Arc::new(Mutex::new(context))))
+    }
+
+    pub fn with_decode_options(mut self, decode: DecodeOptions) -> Self {
+        self.decode = decode;
+        self
+    }
+
+    pub fn with_candidate_options(mut self, candidates: CandidateOptions) -> Self {
+        self.candidates = candidates;
+        self
+    }
+
+    pub fn with_code_switching(
+        mut self,
+        code_switching: CodeSwitching,
+        pause_threshold: f32,
+    ) -> Self {
+        self.code_switching = code_switching;
+        self.pause_threshold = pause_threshold;
+        self
+    }
+
+    /// Restricts automatic language detection to these languages. Empty
+    /// lets whisper choose from every language it knows.
+    pub fn with_candidate_languages(mut self, languages: Vec<String>) -> Self {
+        self.candidate_languages = languages;
+        self
+    }
+
+    /// Reports each piece of a code-switched recording before it is decoded,
+    /// so a UI can show progress through a long recording.
+    pub fn with_segment_callback(
+        mut self,
+        callback: impl FnMut(usize, (f32, f32)) + Send + 'static,
+    ) -> Self {
+        self.set_segment_callback(Box::new(callback));
+        self
+    }
+
+    /// Detects the language among `candidate_languages` when `language` asks
+    /// for automatic detection, and keeps any explicit language as is.
+    fn resolve_language(
+        &self,
+        context: &WhisperContext,
+        samples: &[f32],
+        language: Option<&str>,
+    ) -> Result<Option<String>, AppError> {
+        if self.candidate_languages.is_empty() || !matches!(language, None | Some("auto")) {
+            return Ok(language.map(str::to_string));
+        }
+        let detected = context
+            .detect_language(samples, &self.candidate_languages)
+            .map_err(|err| AppError::runtime(err.to_string()))?;
+        log::debug!("language detected among candidates: {detected}");
+        Ok(Some(detected))
+    }
+
+    /// Splits `samples` at pauses and decodes each piece in the language
+    /// detected for it, ignoring the configured language.
+    fn transcribe_code_switched(
+        &self,
+        context: &WhisperContext,
+        samples: &[f32],
+    ) -> Result<String, AppError> {
+        let report = |index: usize, span: (f32, f32)| {
+            if let Some(callback) = &self.segment_callback {
+                if let Ok(mut callback) = callback.lock() {
+                    callback(index, span);
+                }
+            }
+        };
+        decode_at_pauses(
+            samples,
+            self.pause_threshold,
+            self.code_switching.min_pause_ms,
+            report,
+            |piece| {
+                let language = context
+                    .detect_language(piece, &self.code_switching.languages)
+                    .map_err(|err| AppError::runtime(err.to_string()))?;
+                log::debug!(
+                    "code switching: {} ms piece detected as {language}",
+                    audio::samples_to_ms(piece.len(), WHISPER_SAMPLE_RATE)
+                );
+                keep_partial_on_timeout(context.transcribe_with(
+                    piece,
+                    Some(&language),
+                    &self.decode,
+                ))
+            },
+        )
+    }
+}
+
+/// Splits `samples` at pauses of at least `min_pause_ms` and joins the
+/// texts `decode` returns for the pieces. Each piece is passed to
+/// `on_piece` with its index and `(start, end)` time in seconds before it
+/// is decoded.
+fn decode_at_pauses(
+    samples: &[f32],
+    pause_threshold: f32,
+    min_pause_ms: u64,
+    mut on_piece: impl FnMut(usize, (f32, f32)),
+    mut decode: impl FnMut(&[f32]) -> Result<String, AppError>,
+) -> Result<String, AppError> {
+    let vad = audio::VadConfig::new(true, 0, pause_threshold, CODE_SWITCH_CHUNK_MS);
+    let pieces = audio::split_at_pauses(
+        samples,
+        WHISPER_SAMPLE_RATE,
+        &vad,
+        min_pause_ms,
+        MIN_CODE_SWITCH_PIECE_MS,
+    );
+    let seconds = |sample: usize| sample as f32 / WHISPER_SAMPLE_RATE as f32;
+    let mut texts = Vec::with_capacity(pieces.len());
+    for (index, (start, end)) in pieces.into_iter().enumerate() {
+        on_piece(index, (seconds(start), seconds(end)));
+        let text = decode(&samples[start..end])?;
+        if !text.is_empty() {
+            texts.push(text);
+        }
+    }
+    Ok(texts.join(" "))
+}
+
+/// Turns a timed-out decode into the text finished before the timeout.
+/// A timeout with nothing decoded stays an error.
+fn keep_partial_on_timeout(result: Result<String, WhisperError>) -> Result<String, AppError> {
+    match result {
+        Ok(text) => Ok(text),
+        Err(WhisperError::TimedOut { partial, .. }) if !partial.is_empty() => {
+            log::warn!("transcription timed out; keeping the segments decoded so far");
+            Ok(partial)
+        }
+        Err(err) => Err(AppError::runtime(err.to_string())),
+    }
+}
+
+impl Transcriber for WhisperTranscriber {
+    fn transcribe(&self, samples: &[f32], language: Option<&str>) -> Result<String, AppError> {
+        let context = self
+            .context
+            .lock()
+            .map_err(|_| AppError::runtime("whisper context lock poisoned"))?;
+        if self.code_switching.enabled {
+            return self.transcribe_code_switched(&context, samples);
+        }
+        let language = self.resolve_language(&context, samples, language)?;
+        keep_partial_on_timeout(context.transcribe_with(samples, language.as_deref(), &self.decode))
+    }
+
+    fn transcribe_candidates(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+    ) -> Result<Vec<Candidate>, AppError> {
+        let context = self
+            .context
+            .lock()
+            .map_err(|_| AppError::runtime("whisper context lock poisoned"))?;
+        if self.code_switching.enabled {
+            let text = self.transcribe_code_switched(&context, samples)?;
+            return Ok(vec![Candidate {
+                text,
+                avg_logprob: 0.0,
+            }]);
+        }
+        let language = self.resolve_language(&context, samples, language)?;
+        context
+            .transcribe_candidates(samples, language.as_deref(), &self.decode, &self.candidates)
+            .map_err(|err| AppError::runtime(err.to_string()))
+    }
+
+    fn word_confidences(&self) -> Vec<(String, f32)> {
+        // A code-switched transcript comes from several decodes, and the
+        // context only holds the last one.
+        if self.code_switching.enabled {
+            return Vec::new();
+        }
+        self.context
+            .lock()
+            .map(|context| context.word_confidences())
+            .unwrap_or_default()
+    }
+
+    fn transcribe_segments(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+    ) -> Result<Vec<TimedSegment>, AppError> {
+        let context = self
+            .context
+            .lock()
+            .map_err(|_| AppError::runtime("whisper context lock poisoned"))?;
+        if self.code_switching.enabled {
+            let text = self.transcribe_code_switched(&context, samples)?;
+            return Ok(vec![TimedSegment {
+                start_ms: 0,
+                end_ms: 0,
+                text,
+            }]);
+        }
+        let language = self.resolve_language(&context, samples, language)?;
+        context
+            .transcribe_segments(samples, language.as_deref(), &self.decode)
+            .map_err(|err| AppError::runtime(err.to_string()))
+    }
+
+    fn set_segment_callback(&mut self, callback: Box<SegmentCallback>) {
+        self.segment_callback = Some(Arc::new(Mutex::new(callback)));
+    }
+
+    fn abort_flag(&self) -> Option<Arc<AtomicBool>> {
+        self.context.lock().ok().map(|context| context.abort_flag())
     }
 }
 
@@ -1055,29 +3307,38 @@ impl Transcriber for WhisperTranscriber {
 pub mod test_support {
     use std::collections::VecDeque;
     use std::path::Path;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
     use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
 
     use super::{
         AudioBackend, CaptureSource, DaemonConfig, DaemonOutput, Transcriber, TranscriberFactory,
     };
     use crate::audio::{AudioError, AudioErrorKind};
     use crate::error::AppError;
+    use crate::feedback::FeedbackSounds;
     use crate::hotkey::HotkeyConfig;
-    use crate::output::{OutputConfig, OutputMode};
+    use crate::output::{OutputConfig, OutputError, OutputMode};
+    use crate::postprocess::{FillerFilter, VoiceCommands, WordFilter};
     use crate::segmentation::{
         DEFAULT_SEGMENT_GRACE_MS, DEFAULT_SEGMENT_MIN_MS, DEFAULT_SEGMENT_OVERLAP_MS,
         DEFAULT_SEGMENT_TARGET_MS,
     };
-    use crate::types::{AudioHost, OutputFormat, VadMode};
+    use crate::types::{AudioHost, Casing, OutputFormat, QueueOverflow, VadMode};
+    use crate::whisper::CodeSwitching;
 
     pub fn daemon_config() -> DaemonConfig {
         DaemonConfig {
             model_path: None,
             download_model: false,
             language: "en".to_string(),
+            candidate_languages: Vec::new(),
             device: None,
+            input_channel: None,
             audio_host: AudioHost::Default,
             sample_rate: 16_000,
+            input_gain_db: 0.0,
             format: OutputFormat::Plain,
             output: OutputConfig {
                 mode: OutputMode::Stdout,
@@ -1087,14 +3348,64 @@ pub mod test_support {
             vad_silence_ms: 800,
             vad_threshold: 0.015,
             vad_chunk_ms: 250,
+            speech_confirm_ms: 0,
+            silence_confirm_ms: 0,
             segment_target_ms: DEFAULT_SEGMENT_TARGET_MS,
             segment_grace_ms: DEFAULT_SEGMENT_GRACE_MS,
             segment_overlap_ms: DEFAULT_SEGMENT_OVERLAP_MS,
             segment_min_ms: DEFAULT_SEGMENT_MIN_MS,
             debug_audio: false,
             dump_audio: false,
+            save_last_recording: false,
+            save_native_rate: false,
+            last_recording_path: std::path::PathBuf::from("last.wav"),
+            save_unsent: false,
+            unsent_path: std::path::PathBuf::from("unsent.txt"),
+            open_unsent: false,
+            state_file: false,
+            state_file_path: std::path::PathBuf::from("soundvibes.state"),
             audio_feedback: false,
+            processing_feedback: false,
+            feedback_sink: None,
+            feedback_sounds: FeedbackSounds::default(),
             no_speech_timeout_ms: 0,
+            capture_start_timeout_ms: 0,
+            min_recording_ms: 0,
+            post_stop_cooldown_ms: 0,
+            merge_gap_ms: 0,
+            repeat_guard_ms: 0,
+            min_word_confidence: 0.0,
+            cleanup_command: None,
+            cleanup_timeout_ms: 2_000,
+            tts_confirm: false,
+            tts_command: "spd-say --wait".to_string(),
+            session_transcript: false,
+            websocket: false,
+            websocket_addr: "127.0.0.1:8765".to_string(),
+            websocket_partials: false,
+            prewarm: false,
+            idle_unload_secs: 0,
+            shutdown_drain_ms: 10_000,
+            queue_depth: 8,
+            queue_overflow: QueueOverflow::DropOldest,
+            no_context: true,
+            single_segment: true,
+            temperature: 0.0,
+            temperature_inc: 0.2,
+            logprob_thold: -1.0,
+            suppress_nst: false,
+            suppress_regex: None,
+            transcription_timeout_ms: 0,
+            trim_pad_ms: None,
+            trim_silence_pad_ms: None,
+            tail_secs: None,
+            incremental_inject: false,
+            word_filter: WordFilter::default(),
+            fillers: FillerFilter::default(),
+            casing: Casing::Whisper,
+            profiles: Vec::new(),
+            voice_commands: VoiceCommands::default(),
+            code_switching: CodeSwitching::default(),
             hotkey: HotkeyConfig::default(),
         }
     }
@@ -1103,9 +3414,37 @@ pub mod test_support {
     pub struct TestOutput {
         stdout: Vec<String>,
         stderr: Vec<String>,
+        injected: Vec<String>,
+        erased: Vec<usize>,
+        inject_errors: VecDeque<Option<OutputError>>,
+        window_class: Option<String>,
+        window_id: Option<String>,
     }
 
     impl TestOutput {
+        pub fn set_window_class(&mut self, window_class: &str) {
+            self.window_class = Some(window_class.to_string());
+        }
+
+        pub fn set_window_id(&mut self, window_id: &str) {
+            self.window_id = Some(window_id.to_string());
+        }
+
+        /// Queues the result of the next injection; `None` succeeds.
+        /// Injections past the queue succeed.
+        pub fn push_inject_result(&mut self, error: Option<OutputError>) {
+            self.inject_errors.push_back(error);
+        }
+
+        pub fn injected(&self) -> &[String] {
+            &self.injected
+        }
+
+        /// Character counts of each erasure, in order.
+        pub fn erased(&self) -> &[usize] {
+            &self.erased
+        }
+
         pub fn stdout_lines(&self) -> &[String] {
             &self.stdout
         }
@@ -1123,41 +3462,85 @@ pub mod test_support {
         fn stderr(&mut self, message: &str) {
             self.stderr.push(message.to_string());
         }
+
+        fn inject(&mut self, text: &str, _config: &OutputConfig) -> Result<(), OutputError> {
+            self.injected.push(text.to_string());
+            match self.inject_errors.pop_front().flatten() {
+                Some(err) => Err(err),
+                None => Ok(()),
+            }
+        }
+
+        fn erase(&mut self, count: usize, _config: &OutputConfig) -> Result<(), OutputError> {
+            if count > 0 {
+                self.erased.push(count);
+            }
+            Ok(())
+        }
+
+        fn focused_window_class(&mut self) -> Option<String> {
+            self.window_class.clone()
+        }
+
+        fn focused_window_id(&mut self) -> Option<String> {
+            self.window_id.clone()
+        }
     }
 
+    /// Clones share their devices, chunks, and errors, so a test can keep
+    /// one to unplug devices while the daemon owns the other.
+    #[derive(Clone)]
     pub struct TestAudioBackend {
-        devices: Vec<String>,
+        devices: Arc<Mutex<Vec<String>>>,
         chunks: Arc<Mutex<VecDeque<Vec<f32>>>>,
         start_error: Arc<Mutex<Option<AudioError>>>,
+        disconnected: Arc<AtomicBool>,
+        opened: Arc<Mutex<Vec<Option<String>>>>,
     }
 
     impl TestAudioBackend {
         pub fn new(devices: Vec<String>, chunks: Vec<Vec<f32>>) -> Self {
             Self {
-                devices,
+                devices: Arc::new(Mutex::new(devices)),
                 chunks: Arc::new(Mutex::new(chunks.into())),
                 start_error: Arc::new(Mutex::new(None)),
+                disconnected: Arc::new(AtomicBool::new(false)),
+                opened: Arc::new(Mutex::new(Vec::new())),
             }
         }
 
         pub fn with_start_error(devices: Vec<String>, error: AudioError) -> Self {
-            Self {
-                devices,
-                chunks: Arc::new(Mutex::new(VecDeque::new())),
-                start_error: Arc::new(Mutex::new(Some(error))),
-            }
+            let backend = Self::new(devices, Vec::new());
+            *backend.start_error.lock().expect("audio error lock") = Some(error);
+            backend
+        }
+
+        /// Replaces the devices that are plugged in.
+        pub fn set_devices(&self, devices: Vec<String>) {
+            *self.devices.lock().expect("device lock") = devices;
+        }
+
+        /// Makes the open capture report its device gone.
+        pub fn disconnect(&self) {
+            self.disconnected.store(true, Ordering::SeqCst);
+        }
+
+        /// The device each capture was opened with; `None` is the default.
+        pub fn opened(&self) -> Vec<Option<String>> {
+            self.opened.lock().expect("opened lock").clone()
         }
     }
 
     impl AudioBackend for TestAudioBackend {
         fn list_input_devices(&self, _host: &cpal::Host) -> Result<Vec<String>, AudioError> {
-            if self.devices.is_empty() {
+            let devices = self.devices.lock().expect("device lock");
+            if devices.is_empty() {
                 return Err(AudioError {
                     kind: AudioErrorKind::DeviceUnavailable,
                     message: "no input devices available".to_string(),
                 });
             }
-            Ok(self.devices.clone())
+            Ok(devices.clone())
         }
 
         fn start_capture(
@@ -1165,6 +3548,8 @@ pub mod test_support {
             _host: &cpal::Host,
             device_name: Option<&str>,
             _sample_rate: u32,
+            _input_channel: Option<usize>,
+            _keep_native: bool,
         ) -> Result<Box<dyn CaptureSource>, AudioError> {
             if let Some(err) = self.start_error.lock().expect("audio error lock").take() {
                 return Err(err);
@@ -1172,6 +3557,8 @@ pub mod test_support {
             if let Some(device) = device_name {
                 if !self
                     .devices
+                    .lock()
+                    .expect("device lock")
                     .iter()
                     .any(|name| name.eq_ignore_ascii_case(device))
                 {
@@ -1181,14 +3568,21 @@ pub mod test_support {
                     });
                 }
             }
+            self.opened
+                .lock()
+                .expect("opened lock")
+                .push(device_name.map(str::to_string));
+            self.disconnected.store(false, Ordering::SeqCst);
             Ok(Box::new(TestCapture {
                 chunks: Arc::clone(&self.chunks),
+                disconnected: Arc::clone(&self.disconnected),
             }))
         }
     }
 
     struct TestCapture {
         chunks: Arc<Mutex<VecDeque<Vec<f32>>>>,
+        disconnected: Arc<AtomicBool>,
     }
 
     impl CaptureSource for TestCapture {
@@ -1197,11 +3591,18 @@ pub mod test_support {
                 output.extend(chunk);
             }
         }
+
+        fn disconnected(&self) -> bool {
+            self.disconnected.load(Ordering::SeqCst)
+        }
     }
 
     #[derive(Clone)]
     pub struct TestTranscriberFactory {
         responses: Arc<Mutex<VecDeque<Result<String, AppError>>>>,
+        loads: Arc<AtomicUsize>,
+        load_delay: Duration,
+        transcribe_delay: Duration,
     }
 
     impl TestTranscriberFactory {
@@ -1209,26 +3610,53 @@ pub mod test_support {
             let responses = responses.into_iter().map(Ok).collect();
             Self {
                 responses: Arc::new(Mutex::new(responses)),
+                loads: Arc::new(AtomicUsize::new(0)),
+                load_delay: Duration::ZERO,
+                transcribe_delay: Duration::ZERO,
             }
         }
 
         pub fn with_results(responses: Vec<Result<String, AppError>>) -> Self {
             Self {
                 responses: Arc::new(Mutex::new(responses.into())),
+                loads: Arc::new(AtomicUsize::new(0)),
+                load_delay: Duration::ZERO,
+                transcribe_delay: Duration::ZERO,
             }
         }
+
+        /// Makes each load take `delay`, like a large model on a cold start.
+        pub fn with_load_delay(mut self, delay: Duration) -> Self {
+            self.load_delay = delay;
+            self
+        }
+
+        /// Makes each transcription take `delay`.
+        pub fn with_transcribe_delay(mut self, delay: Duration) -> Self {
+            self.transcribe_delay = delay;
+            self
+        }
+
+        /// Number of transcribers handed out so far.
+        pub fn load_count(&self) -> usize {
+            self.loads.load(Ordering::SeqCst)
+        }
     }
 
     impl TranscriberFactory for TestTranscriberFactory {
         fn load(&self, _model_path: Option<&Path>) -> Result<Box<dyn Transcriber>, AppError> {
+            thread::sleep(self.load_delay);
+            self.loads.fetch_add(1, Ordering::SeqCst);
             Ok(Box::new(TestTranscriber {
                 responses: Arc::clone(&self.responses),
+                delay: self.transcribe_delay,
             }))
         }
     }
 
     struct TestTranscriber {
         responses: Arc<Mutex<VecDeque<Result<String, AppError>>>>,
+        delay: Duration,
     }
 
     impl Transcriber for TestTranscriber {
@@ -1237,6 +3665,7 @@ pub mod test_support {
             _samples: &[f32],
             _language: Option<&str>,
         ) -> Result<String, AppError> {
+            thread::sleep(self.delay);
             let next = self
                 .responses
                 .lock()
@@ -1248,36 +3677,1536 @@ pub mod test_support {
             }
         }
     }
-}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use super::test_support::{
+        daemon_config, TestAudioBackend, TestOutput, TestTranscriberFactory,
+    };
+    use crate::audio::{AudioError, AudioErrorKind};
+    use crate::feedback::test_support::RecordingFeedback;
+    use crate::feedback::NoopFeedback;
+    use crate::output::OutputError;
+    use crate::profile::{ProfileOutput, ProfilePostprocess};
+    use crate::transcription_worker::test_support::PieceTranscriber;
+
+    #[test]
+    fn json_result_reports_unknown_fields_as_null() {
+        let info = audio::SegmentInfo {
+            index: 3,
+            duration_ms: 1_250,
+        };
+
+        assert_eq!(
+            json_result("hello", "en", info),
+            serde_json::json!({
+                "text": "hello",
+                "language": "en",
+                "confidence": null,
+                "segments": null,
+                "duration_ms": 1_250,
+            })
+        );
+        assert_eq!(
+            json_result("hola", "auto", info)["language"],
+            serde_json::Value::Null
+        );
+    }
+
+    const INFO: audio::SegmentInfo = audio::SegmentInfo {
+        index: 1,
+        duration_ms: 900,
+    };
+
+    fn injecting_config(mode: OutputMode) -> DaemonConfig {
+        let mut config = daemon_config();
+        config.output.mode = mode;
+        config
+    }
+
+    #[test]
+    fn focus_settle_waits_only_when_the_window_changed() {
+        let mut config = injecting_config(OutputMode::Paste);
+        config.output.focus_settle_ms = 40;
+        let mut output = TestOutput::default();
+        let mut emit_state = EmitState::default();
+
+        output.set_window_class("kitty");
+        let started = std::time::Instant::now();
+        settle_after_focus_change(&config, &mut output, &mut emit_state);
+        assert!(started.elapsed() >= Duration::from_millis(40));
+        assert_eq!(emit_state.last_window.as_deref(), Some("kitty"));
+
+        settle_after_focus_change(&config, &mut output, &mut emit_state);
+        assert_eq!(emit_state.last_window.as_deref(), Some("kitty"));
+
+        output.set_window_class("firefox");
+        let started = std::time::Instant::now();
+        settle_after_focus_change(&config, &mut output, &mut emit_state);
+        assert!(started.elapsed() >= Duration::from_millis(40));
+        assert_eq!(emit_state.last_window.as_deref(), Some("firefox"));
+
+        // Two windows of one app differ by id.
+        output.set_window_id("0x5a1");
+        settle_after_focus_change(&config, &mut output, &mut emit_state);
+        assert_eq!(emit_state.last_window.as_deref(), Some("0x5a1"));
+        output.set_window_id("0x5b2");
+        let started = std::time::Instant::now();
+        settle_after_focus_change(&config, &mut output, &mut emit_state);
+        assert!(started.elapsed() >= Duration::from_millis(40));
+        assert_eq!(emit_state.last_window.as_deref(), Some("0x5b2"));
+    }
+
+    #[test]
+    fn successful_injection_skips_the_stdout_fallback() {
+        let config = injecting_config(OutputMode::Paste);
+        let mut output = TestOutput::default();
+
+        emit_transcript(&config, &mut output, "hello there", INFO);
+
+        assert_eq!(output.injected(), ["hello there "]);
+        assert!(output.stdout_lines().is_empty());
+        assert!(output.stderr_lines().is_empty());
+    }
+
+    #[test]
+    fn unavailable_backend_falls_back_to_stdout() {
+        let config = injecting_config(OutputMode::Type);
+        let mut output = TestOutput::default();
+        output.push_inject_result(Some(OutputError::unavailable(
+            "type output unavailable: dotool not found in PATH",
+        )));
+
+        emit_transcript(&config, &mut output, "hello there", INFO);
+
+        assert_eq!(
+            output.stderr_lines(),
+            ["warn: type output unavailable: dotool not found in PATH; falling back to stdout"]
+        );
+        assert_eq!(output.stdout_lines(), ["Transcript 1: hello there"]);
+    }
+
+    #[test]
+    fn failed_injection_falls_back_to_stdout() {
+        for mode in [OutputMode::Clipboard, OutputMode::Ydotool] {
+            let config = injecting_config(mode);
+            let mut output = TestOutput::default();
+            output.push_inject_result(Some(OutputError::new("tool exited with status 1")));
+
+            emit_transcript(&config, &mut output, "hello there", INFO);
+
+            assert_eq!(output.injected().len(), 1, "{mode}");
+            assert_eq!(
+                output.stderr_lines(),
+                ["warn: tool exited with status 1; falling back to stdout"]
+            );
+            assert_eq!(output.stdout_lines(), ["Transcript 1: hello there"]);
+        }
+    }
+
+    #[test]
+    fn undelivered_transcripts_are_appended_to_the_unsent_file() {
+        let dir = env::temp_dir().join(format!("sv-unsent-{}", std::process::id()));
+        let mut config = injecting_config(OutputMode::Paste);
+        config.save_unsent = true;
+        config.unsent_path = dir.join("cache").join("unsent.txt");
+        let mut output = TestOutput::default();
+        output.push_inject_result(Some(OutputError::new("paste failed")));
+        output.push_inject_result(None);
+        output.push_inject_result(Some(OutputError::new("paste failed")));
+
+        emit_transcript(&config, &mut output, "first note", INFO);
+        emit_transcript(&config, &mut output, "delivered", INFO);
+        emit_transcript(&config, &mut output, "second note", INFO);
+
+        let saved = fs::read_to_string(&config.unsent_path).expect("read unsent file");
+        let _ = fs::remove_dir_all(dir);
+        let lines: Vec<&str> = saved.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("] first note"), "{saved}");
+        assert!(lines[1].ends_with("] second note"), "{saved}");
+    }
+
+    #[test]
+    fn failed_sentence_stops_the_rest_and_prints_the_whole_transcript() {
+        let mut config = injecting_config(OutputMode::Paste);
+        config.output.inject_sentences = true;
+        let mut output = TestOutput::default();
+        output.push_inject_result(None);
+        output.push_inject_result(Some(OutputError::new("paste failed")));
+
+        emit_transcript(&config, &mut output, "One. Two. Three.", INFO);
+
+        assert_eq!(output.injected(), ["One. ", "Two. "]);
+        assert_eq!(output.stdout_lines(), ["Transcript 1: One. Two. Three."]);
+    }
+
+    #[test]
+    fn transcribe_samples_emits_buffer_transcript() -> Result<(), AppError> {
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(Vec::new(), Vec::new())),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec!["piped".to_string()])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+
+        transcribe_samples(&daemon_config(), &deps, &[0.2; 160], &mut output)?;
+
+        assert!(output
+            .stdout_lines()
+            .iter()
+            .any(|line| line == "Transcript 1: piped"));
+        Ok(())
+    }
+
+    #[test]
+    fn state_file_follows_recording_and_is_removed_on_exit() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(
+                vec!["Mic".to_string()],
+                vec![vec![0.2; 160]],
+            )),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec!["done".to_string()])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let path = env::temp_dir().join(format!("sv-state-{}.state", std::process::id()));
+        let mut config = daemon_config();
+        config.state_file = true;
+        config.state_file_path = path.clone();
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let state_path = path.clone();
+        let control_thread = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            let before = fs::read_to_string(&state_path).unwrap_or_default();
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            thread::sleep(Duration::from_millis(50));
+            let during = fs::read_to_string(&state_path).unwrap_or_default();
+            let _ = control_sender.send(ControlEvent::StopRecording);
+            thread::sleep(Duration::from_millis(50));
+            let after = fs::read_to_string(&state_path).unwrap_or_default();
+            shutdown_trigger.store(true, Ordering::Relaxed);
+            [before, during, after]
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        let states = control_thread.join().expect("control thread failed");
+        result?;
+
+        assert_eq!(states, ["idle\n", "recording\n", "idle\n"]);
+        assert!(!path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn requests_during_the_shutdown_drain_are_refused_right_away() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(
+                vec!["Mic".to_string()],
+                vec![vec![0.2; 160]],
+            )),
+            transcriber_factory: Box::new(
+                TestTranscriberFactory::new(vec!["last words".to_string()])
+                    .with_transcribe_delay(Duration::from_millis(500)),
+            ),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let config = daemon_config();
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let control_thread = thread::spawn(move || {
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            thread::sleep(Duration::from_millis(50));
+            shutdown_trigger.store(true, Ordering::Relaxed);
+            thread::sleep(Duration::from_millis(50));
+            let (respond_to, response) = mpsc::sync_channel(1);
+            let _ = control_sender.send(ControlEvent::Request {
+                event: Box::new(ControlEvent::Status),
+                respond_to,
+            });
+            response.recv_timeout(Duration::from_millis(200))
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        let response = control_thread.join().expect("control thread failed");
+        result?;
+
+        let response = response.expect("answered before the drain ended");
+        assert!(!response.ok);
+        assert_eq!(response.message.as_deref(), Some("daemon is shutting down"));
+        assert!(output
+            .stdout_lines()
+            .iter()
+            .any(|line| line == "Transcript 1: last words"));
+        Ok(())
+    }
+
+    #[test]
+    fn state_file_covers_the_merge_hold_but_not_the_transcription() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(
+                vec!["Mic".to_string()],
+                vec![vec![0.2; 160]],
+            )),
+            transcriber_factory: Box::new(
+                TestTranscriberFactory::new(vec!["done".to_string()])
+                    .with_transcribe_delay(Duration::from_millis(300)),
+            ),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let path = env::temp_dir().join(format!("sv-state-hold-{}.state", std::process::id()));
+        let mut config = daemon_config();
+        config.state_file = true;
+        config.state_file_path = path.clone();
+        config.merge_gap_ms = 150;
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let state_path = path.clone();
+        let control_thread = thread::spawn(move || {
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            thread::sleep(Duration::from_millis(50));
+            let _ = control_sender.send(ControlEvent::StopRecording);
+            thread::sleep(Duration::from_millis(50));
+            let held = fs::read_to_string(&state_path).unwrap_or_default();
+            // The hold ends after 150 ms, and the transcript takes 300 ms more.
+            thread::sleep(Duration::from_millis(250));
+            let transcribing = fs::read_to_string(&state_path).unwrap_or_default();
+            shutdown_trigger.store(true, Ordering::Relaxed);
+            [held, transcribing]
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        let states = control_thread.join().expect("control thread failed");
+        result?;
+
+        assert_eq!(states, ["recording\n", "idle\n"]);
+        Ok(())
+    }
+
+    #[test]
+    fn save_last_recording_overwrites_the_wav_each_time() -> Result<(), AppError> {
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(Vec::new(), Vec::new())),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec![
+                "first".to_string(),
+                "second".to_string(),
+            ])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let dir = env::temp_dir().join(format!("sv-last-recording-{}", std::process::id()));
+        let mut config = daemon_config();
+        config.save_last_recording = true;
+        config.last_recording_path = dir.join("cache").join("last.wav");
+
+        transcribe_samples(&config, &deps, &[0.2; 3200], &mut output)?;
+        transcribe_samples(&config, &deps, &[0.2; 1600], &mut output)?;
+
+        let saved = audio::read_wav(&config.last_recording_path, config.sample_rate)
+            .map_err(|err| AppError::audio(err.message))?;
+        assert_eq!(saved.len(), 1600);
+        assert!(output.stderr_lines().is_empty());
+        let _ = fs::remove_dir_all(dir);
+        Ok(())
+    }
+
+    #[test]
+    fn saves_the_device_rate_copy_of_a_segment() -> Result<(), AppError> {
+        let mut output = TestOutput::default();
+        let factory = TestTranscriberFactory::new(vec!["hello".to_string()]);
+        let mut worker = TranscriptionWorker::start(factory.load(None)?);
+        let dir = env::temp_dir().join(format!("sv-native-rate-{}", std::process::id()));
+        let mut config = daemon_config();
+        config.save_last_recording = true;
+        config.last_recording_path = dir.join("last.wav");
+        let mut native = NativeCopy {
+            samples: vec![0.2; 9_600],
+            sample_rate: 48_000,
+        };
+
+        let segment = native.split_front(1_600, config.sample_rate);
+        submit_segment(
+            &mut worker,
+            &config,
+            &[0.2; 1_600],
+            Some(&segment),
+            false,
+            &mut output,
+        )?;
+        worker.shutdown()?;
+
+        assert_eq!(native.samples.len(), 4_800);
+        let saved = hound::WavReader::open(&config.last_recording_path).expect("open saved wav");
+        assert_eq!(saved.spec().sample_rate, 48_000);
+        assert_eq!(saved.len(), 4_800);
+        let _ = fs::remove_dir_all(dir);
+        Ok(())
+    }
+
+    #[test]
+    fn profile_for_the_focused_window_overrides_output_and_casing() -> Result<(), AppError> {
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(Vec::new(), Vec::new())),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec![
+                "ls -la. then exit".to_string(),
+                "ls -la. then exit".to_string(),
+            ])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let mut config = daemon_config();
+        config.casing = Casing::SentenceStartOnly;
+        config.profiles = vec![Profile {
+            match_class: "kitty".to_string(),
+            output: ProfileOutput {
+                mode: Some(OutputMode::Type),
+                ..ProfileOutput::default()
+            },
+            postprocess: ProfilePostprocess {
+                casing: Some(Casing::Whisper),
+                ..ProfilePostprocess::default()
+            },
+        }];
+
+        let mut terminal = TestOutput::default();
+        terminal.set_window_class("kitty");
+        transcribe_samples(&config, &deps, &[0.2; 1600], &mut terminal)?;
+        let mut browser = TestOutput::default();
+        browser.set_window_class("firefox");
+        transcribe_samples(&config, &deps, &[0.2; 1600], &mut browser)?;
+
+        assert_eq!(terminal.injected(), ["ls -la. then exit "]);
+        assert!(terminal.stdout_lines().is_empty());
+        assert!(browser.injected().is_empty());
+        assert!(browser
+            .stdout_lines()
+            .iter()
+            .any(|line| line == "Transcript 1: Ls -la. Then exit"));
+        Ok(())
+    }
+
+    #[test]
+    fn repeat_outputs_the_last_transcript_with_the_new_windows_profile() {
+        let mut config = injecting_config(OutputMode::Type);
+        config.profiles = vec![Profile {
+            match_class: "kitty".to_string(),
+            output: ProfileOutput {
+                suffix: Some("\n".to_string()),
+                ..ProfileOutput::default()
+            },
+            ..Profile::default()
+        }];
+        let mut output = TestOutput::default();
+        let mut emit_state = EmitState::default();
+
+        assert!(repeat_last_transcript(&config, &mut output, &mut emit_state).is_err());
+
+        output.set_window_class("firefox");
+        let result = TranscriptionResult {
+            index: 3,
+            duration_ms: 800,
+            transcript: Ok("order 42".to_string()),
+            words: Vec::new(),
+            segments: Vec::new(),
+            had_overlap: false,
+            partial: false,
+        };
+        emit_worker_result(&config, &NoopFeedback, &mut output, result, &mut emit_state);
+        output.set_window_class("kitty");
+        let message = repeat_last_transcript(&config, &mut output, &mut emit_state)
+            .expect("a transcript to repeat");
+
+        assert_eq!(message, "Repeated transcript 3.");
+        assert_eq!(output.injected(), ["order 42 ", "order 42\n"]);
+    }
+
+    #[test]
+    fn repeat_reports_a_failed_output() {
+        let config = injecting_config(OutputMode::Paste);
+        let mut output = TestOutput::default();
+        let mut emit_state = EmitState {
+            repeatable: Some(("order 42".to_string(), INFO)),
+            ..EmitState::default()
+        };
+        output.push_inject_result(Some(OutputError::new("paste failed")));
+
+        let err = repeat_last_transcript(&config, &mut output, &mut emit_state)
+            .expect_err("the paste failed");
+
+        assert_eq!(err.to_string(), "transcript 1 could not be output");
+        assert!(emit_state.undoable.is_none());
+        assert_eq!(output.stdout_lines(), ["Transcript 1: order 42"]);
+    }
+
+    #[test]
+    fn fuzzy_voice_command_replaces_the_utterance() -> Result<(), AppError> {
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(Vec::new(), Vec::new())),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec![
+                "New lion.".to_string()
+            ])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let mut config = daemon_config();
+        config.voice_commands = VoiceCommands {
+            phrases: [("new line".to_string(), "\n".to_string())].into(),
+            fuzzy: true,
+            ..VoiceCommands::default()
+        };
+
+        transcribe_samples(&config, &deps, &[0.2; 160], &mut output)?;
+
+        assert!(output
+            .stdout_lines()
+            .iter()
+            .any(|line| line == "Transcript 1: \n"));
+        Ok(())
+    }
+
+    #[test]
+    fn set_backend_switches_output_mode_for_later_requests() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(vec!["Mic".to_string()], Vec::new())),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(Vec::new())),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let config = daemon_config();
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let control_thread = thread::spawn(move || {
+            let mut backends = Vec::new();
+            for event in [
+                ControlEvent::GetBackend,
+                ControlEvent::SetBackend(OutputBackend::Mode(OutputMode::Clipboard)),
+                ControlEvent::GetBackend,
+            ] {
+                let (respond_to, response) = mpsc::sync_channel(1);
+                let _ = control_sender.send(ControlEvent::Request {
+                    event: Box::new(event),
+                    respond_to,
+                });
+                let response = response.recv().expect("control response");
+                backends.push(response.backend);
+            }
+            shutdown_trigger.store(true, Ordering::Relaxed);
+            backends
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        let backends = control_thread.join().expect("control thread failed");
+        result?;
+
+        assert_eq!(
+            backends,
+            [
+                Some("stdout".to_string()),
+                Some("clipboard".to_string()),
+                Some("clipboard".to_string()),
+            ]
+        );
+        assert!(output
+            .stdout_lines()
+            .iter()
+            .any(|line| line == "Output backend set to clipboard (requested clipboard)."));
+        Ok(())
+    }
+
+    #[test]
+    fn reload_swaps_settings_and_keeps_running_after_a_bad_config() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let factory = TestTranscriberFactory::new(Vec::new());
+        let reloads = AtomicUsize::new(0);
+        let feedback = RecordingFeedback::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(vec!["Mic".to_string()], Vec::new())),
+            transcriber_factory: Box::new(factory.clone()),
+            feedback: Box::new(feedback.clone()),
+            config_loader: Some(Box::new(move || {
+                if reloads.fetch_add(1, Ordering::SeqCst) == 0 {
+                    return Err(AppError::config("failed to parse config file"));
+                }
+                let mut config = daemon_config();
+                config.output.mode = OutputMode::Clipboard;
+                config.sample_rate = 48_000;
+                Ok(config)
+            })),
+        };
+        let config = daemon_config();
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let control_thread = thread::spawn(move || {
+            let mut responses = Vec::new();
+            for event in [
+                ControlEvent::Reload,
+                ControlEvent::GetBackend,
+                ControlEvent::Reload,
+                ControlEvent::GetBackend,
+            ] {
+                let (respond_to, response) = mpsc::sync_channel(1);
+                let _ = control_sender.send(ControlEvent::Request {
+                    event: Box::new(event),
+                    respond_to,
+                });
+                let response = response.recv().expect("control response");
+                responses.push((response.ok, response.backend));
+            }
+            shutdown_trigger.store(true, Ordering::Relaxed);
+            responses
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        let responses = control_thread.join().expect("control thread failed");
+        result?;
+
+        assert_eq!(
+            responses,
+            [
+                (false, None),
+                (true, Some("stdout".to_string())),
+                (true, None),
+                (true, Some("clipboard".to_string())),
+            ]
+        );
+        assert_eq!(factory.load_count(), 1);
+        assert_eq!(feedback.events(), ["reload"]);
+        assert!(output
+            .stderr_lines()
+            .iter()
+            .any(|line| line == "warn: restart the daemon to apply sample_rate"));
+        Ok(())
+    }
+
+    #[test]
+    fn reload_keeps_a_monitor_source_device() {
+        let mut running = daemon_config();
+        running.device = Some("alsa_output.usb.monitor".to_string());
+        let mut reloaded = daemon_config();
+        reloaded.device = Some("USB Mic".to_string());
+
+        assert_eq!(keep_startup_settings(&running, &mut reloaded), ["device"]);
+        assert_eq!(reloaded.device, running.device);
+
+        running.device = Some("Built-in".to_string());
+        assert_eq!(keep_startup_settings(&running, &mut reloaded), ["device"]);
+        assert_eq!(reloaded.device, running.device);
+
+        reloaded.device = Some("USB Mic".to_string());
+        assert!(keep_startup_settings(&running, &mut reloaded).is_empty());
+        assert_eq!(reloaded.device.as_deref(), Some("USB Mic"));
+    }
+
+    #[test]
+    fn recording_falls_back_while_the_device_is_unplugged() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let devices = vec!["USB Mic".to_string(), "Built-in".to_string()];
+        let audio = TestAudioBackend::new(devices.clone(), Vec::new());
+        let deps = DaemonDeps {
+            audio: Box::new(audio.clone()),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(Vec::new())),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let mut config = daemon_config();
+        config.device = Some("USB Mic".to_string());
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let plugs = audio.clone();
+        let control_thread = thread::spawn(move || {
+            let request = |event| {
+                let (respond_to, response) = mpsc::sync_channel(1);
+                let _ = control_sender.send(ControlEvent::Request {
+                    event: Box::new(event),
+                    respond_to,
+                });
+                response.recv().expect("control response").ok
+            };
+            plugs.set_devices(vec!["Built-in".to_string()]);
+            request(ControlEvent::StartRecording);
+            request(ControlEvent::StopRecording);
+            plugs.set_devices(devices);
+            request(ControlEvent::StartRecording);
+            plugs.disconnect();
+            // Each answer comes from a later loop pass than the last one,
+            // so the disconnect has been seen by the second.
+            request(ControlEvent::GetBackend);
+            request(ControlEvent::GetBackend);
+            shutdown_trigger.store(true, Ordering::Relaxed);
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        control_thread.join().expect("control thread failed");
+        result?;
+
+        assert_eq!(audio.opened(), [None, Some("USB Mic".to_string())]);
+        let stderr = output.stderr_lines();
+        assert!(stderr.iter().any(|line| line
+            == "warn: input device not found: USB Mic; recording from the default input until it is back"));
+        assert!(stderr
+            .iter()
+            .any(|line| line == "warn: input device disconnected; recording stopped"));
+        assert!(output
+            .stdout_lines()
+            .iter()
+            .any(|line| line == "Input device USB Mic is back."));
+        Ok(())
+    }
+
+    #[test]
+    fn tail_secs_keeps_the_end_of_the_trimmed_recording() -> Result<(), AppError> {
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(Vec::new(), Vec::new())),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec![
+                "the last sentence".to_string()
+            ])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let config = DaemonConfig {
+            sample_rate: 1_000,
+            format: OutputFormat::Jsonl,
+            trim_silence_pad_ms: Some(0),
+            tail_secs: Some(1),
+            ..daemon_config()
+        };
+        // Three seconds of speech, then two seconds of silence that the
+        // tail must not be cut from.
+        let mut samples = vec![0.2; 3_000];
+        samples.extend([0.0; 2_000]);
+
+        transcribe_samples(&config, &deps, &samples, &mut output)?;
+
+        let line = output.stdout_lines().last().cloned().unwrap_or_default();
+        let result: serde_json::Value = serde_json::from_str(&line).expect("jsonl line");
+        assert_eq!(result["text"], "the last sentence");
+        assert_eq!(result["duration_ms"], 1_000);
+        Ok(())
+    }
+
+    #[test]
+    fn zero_tail_secs_transcribes_the_whole_recording() -> Result<(), AppError> {
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(Vec::new(), Vec::new())),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec!["all".to_string()])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let config = DaemonConfig {
+            sample_rate: 1_000,
+            format: OutputFormat::Jsonl,
+            tail_secs: Some(0),
+            ..daemon_config()
+        };
+
+        transcribe_samples(&config, &deps, &[0.2; 3_000], &mut output)?;
+
+        let line = output.stdout_lines().last().cloned().unwrap_or_default();
+        let result: serde_json::Value = serde_json::from_str(&line).expect("jsonl line");
+        assert_eq!(result["text"], "all");
+        assert_eq!(result["duration_ms"], 3_000);
+        Ok(())
+    }
+
+    #[test]
+    fn enabled_filler_filter_cleans_transcripts() -> Result<(), AppError> {
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(Vec::new(), Vec::new())),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec![
+                "Um, take notes, uh, carefully.".to_string(),
+            ])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let mut config = daemon_config();
+        config.fillers.enabled = true;
+
+        transcribe_samples(&config, &deps, &[0.2; 160], &mut output)?;
+
+        assert!(output
+            .stdout_lines()
+            .iter()
+            .any(|line| line == "Transcript 1: Take notes, carefully."));
+        Ok(())
+    }
+
+    #[test]
+    fn force_lower_casing_runs_after_filler_removal() -> Result<(), AppError> {
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(Vec::new(), Vec::new())),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec![
+                "Um, take notes on the NASA launch.".to_string(),
+            ])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let mut config = daemon_config();
+        config.fillers.enabled = true;
+        config.casing = Casing::ForceLower;
+
+        transcribe_samples(&config, &deps, &[0.2; 160], &mut output)?;
+
+        assert!(output
+            .stdout_lines()
+            .iter()
+            .any(|line| line == "Transcript 1: take notes on the nasa launch."));
+        Ok(())
+    }
+
+    #[test]
+    fn spoken_punctuation_expands_before_sentence_casing() -> Result<(), AppError> {
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(Vec::new(), Vec::new())),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec![
+                "new sentence period  new sentence.".to_string(),
+            ])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let mut config = daemon_config();
+        config.voice_commands.punctuation = true;
+        config.casing = Casing::SentenceStartOnly;
+
+        transcribe_samples(&config, &deps, &[0.2; 160], &mut output)?;
+
+        assert!(output
+            .stdout_lines()
+            .iter()
+            .any(|line| line == "Transcript 1: New sentence. New sentence."));
+        Ok(())
+    }
+
+    #[test]
+    fn daemon_loop_emits_transcript_to_output() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(
+                vec!["Mic".to_string()],
+                vec![vec![0.2; 160]],
+            )),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec!["hello".to_string()])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let config = daemon_config();
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let control_thread = thread::spawn(move || {
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            let _ = control_sender.send(ControlEvent::StopRecording);
+            thread::sleep(Duration::from_millis(50));
+            shutdown_trigger.store(true, Ordering::Relaxed);
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        control_thread.join().expect("control thread failed");
+        result?;
+
+        assert!(output
+            .stdout_lines()
+            .iter()
+            .any(|line| line.contains("Transcript 1: hello")));
+        Ok(())
+    }
+
+    #[test]
+    fn dump_transcript_writes_the_session_so_far() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(
+                vec!["Mic".to_string()],
+                vec![vec![0.2; 160]],
+            )),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec!["hello".to_string()])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let config = DaemonConfig {
+            session_transcript: true,
+            ..daemon_config()
+        };
+        let path = std::env::temp_dir().join(format!("sv-dump-{}.txt", std::process::id()));
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let target = DumpTarget::File(path.clone());
+        let control_thread = thread::spawn(move || {
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            let _ = control_sender.send(ControlEvent::StopRecording);
+            thread::sleep(Duration::from_millis(200));
+            let (respond_to, response) = mpsc::sync_channel(1);
+            let _ = control_sender.send(ControlEvent::Request {
+                event: Box::new(ControlEvent::DumpTranscript(target)),
+                respond_to,
+            });
+            let response = response.recv().expect("control response");
+            shutdown_trigger.store(true, Ordering::Relaxed);
+            response
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        let response = control_thread.join().expect("control thread failed");
+        result?;
+
+        assert!(response.ok, "{:?}", response.message);
+        let dumped = fs::read_to_string(&path).expect("read dump");
+        let _ = fs::remove_file(&path);
+        assert!(dumped.ends_with("] hello\n"), "{dumped}");
+        assert_eq!(dumped.lines().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn hotkey_press_during_readback_cancels_the_injection() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(
+                vec!["Mic".to_string()],
+                vec![vec![0.2; 160]],
+            )),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec!["hello".to_string()])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let config = DaemonConfig {
+            tts_confirm: true,
+            tts_command: "sleep 5; :".to_string(),
+            ..daemon_config()
+        };
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let control_thread = thread::spawn(move || {
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            let _ = control_sender.send(ControlEvent::StopRecording);
+            thread::sleep(Duration::from_millis(300));
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            thread::sleep(Duration::from_millis(100));
+            shutdown_trigger.store(true, Ordering::Relaxed);
+        });
+
+        let started = std::time::Instant::now();
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        control_thread.join().expect("control thread failed");
+        result?;
+
+        assert!(started.elapsed() < Duration::from_secs(4));
+        let stdout = output.stdout_lines();
+        assert!(stdout
+            .iter()
+            .any(|line| line == "Readback cancelled, output skipped."));
+        assert!(!stdout.iter().any(|line| line.contains("Transcript 1:")));
+        Ok(())
+    }
+
+    #[test]
+    fn recording_requested_during_cold_start_is_rejected() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let feedback = RecordingFeedback::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(
+                vec!["Mic".to_string()],
+                vec![vec![0.2; 160]],
+            )),
+            transcriber_factory: Box::new(
+                TestTranscriberFactory::new(vec!["hello".to_string()])
+                    .with_load_delay(Duration::from_millis(300)),
+            ),
+            feedback: Box::new(feedback.clone()),
+            config_loader: None,
+        };
+        let config = DaemonConfig {
+            audio_feedback: true,
+            ..daemon_config()
+        };
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let control_thread = thread::spawn(move || {
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            let _ = control_sender.send(ControlEvent::StopRecording);
+            let (respond_to, response) = mpsc::sync_channel(1);
+            let _ = control_sender.send(ControlEvent::Request {
+                event: Box::new(ControlEvent::Status),
+                respond_to,
+            });
+            let status = response.recv().expect("status response");
+            shutdown_trigger.store(true, Ordering::Relaxed);
+            status
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        let status = control_thread.join().expect("control thread failed");
+        result?;
+
+        assert!(status.ok);
+        assert!(output
+            .stdout_lines()
+            .iter()
+            .any(|line| line == "Model is still loading; recording not started."));
+        assert!(!output
+            .stdout_lines()
+            .iter()
+            .any(|line| line == "Recording started."));
+        assert_eq!(feedback.events(), ["not_ready"]);
+        Ok(())
+    }
+
+    #[test]
+    fn audio_feedback_signals_start_error_and_stop() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let feedback = RecordingFeedback::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(
+                vec!["Mic".to_string()],
+                vec![vec![0.2; 160]],
+            )),
+            transcriber_factory: Box::new(TestTranscriberFactory::with_results(vec![Err(
+                AppError::runtime("decode failed"),
+            )])),
+            feedback: Box::new(feedback.clone()),
+            config_loader: None,
+        };
+        let config = DaemonConfig {
+            audio_feedback: true,
+            ..daemon_config()
+        };
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let control_thread = thread::spawn(move || {
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            let _ = control_sender.send(ControlEvent::StopRecording);
+            thread::sleep(Duration::from_millis(50));
+            shutdown_trigger.store(true, Ordering::Relaxed);
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        control_thread.join().expect("control thread failed");
+        result?;
+
+        assert_eq!(feedback.events(), ["start", "error: decode failed", "stop"]);
+        Ok(())
+    }
+
+    #[test]
+    fn busy_microphone_reports_error_and_keeps_daemon_running() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let feedback = RecordingFeedback::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::with_start_error(
+                vec!["Mic".to_string()],
+                AudioError {
+                    kind: AudioErrorKind::DeviceBusy,
+                    message: "input device Mic is busy".to_string(),
+                },
+            )),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(Vec::new())),
+            feedback: Box::new(feedback.clone()),
+            config_loader: None,
+        };
+        let config = DaemonConfig {
+            audio_feedback: true,
+            ..daemon_config()
+        };
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let control_thread = thread::spawn(move || {
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            thread::sleep(Duration::from_millis(50));
+            shutdown_trigger.store(true, Ordering::Relaxed);
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        control_thread.join().expect("control thread failed");
+        result?;
+
+        assert_eq!(feedback.events(), ["error: input device Mic is busy"]);
+        assert_eq!(
+            output.stderr_lines(),
+            ["Recording not started: input device Mic is busy"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn missing_monitor_source_does_not_fall_back_to_the_default_input() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let audio = TestAudioBackend::with_start_error(
+            vec!["Built-in".to_string(), "speakers.monitor".to_string()],
+            AudioError {
+                kind: AudioErrorKind::DeviceNotFound,
+                message: "monitor source speakers.monitor was not the device when sv started; restart sv to capture from it".to_string(),
+            },
+        );
+        let deps = DaemonDeps {
+            audio: Box::new(audio.clone()),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(Vec::new())),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let mut config = daemon_config();
+        config.device = Some("speakers.monitor".to_string());
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let control_thread = thread::spawn(move || {
+            let (respond_to, response) = mpsc::sync_channel(1);
+            let _ = control_sender.send(ControlEvent::Request {
+                event: Box::new(ControlEvent::StartRecording),
+                respond_to,
+            });
+            let response = response.recv().expect("control response");
+            shutdown_trigger.store(true, Ordering::Relaxed);
+            response
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        let response = control_thread.join().expect("control thread failed");
+        result?;
+
+        assert!(!response.ok);
+        assert!(response
+            .message
+            .as_deref()
+            .is_some_and(|error| error.contains("was not the device when sv started")));
+        assert!(audio.opened().is_empty());
+        assert!(output.stderr_lines().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn recordings_shorter_than_minimum_are_dropped() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let feedback = RecordingFeedback::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(
+                vec!["Mic".to_string()],
+                vec![vec![0.2; 160]],
+            )),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec!["stray".to_string()])),
+            feedback: Box::new(feedback.clone()),
+            config_loader: None,
+        };
+        let config = DaemonConfig {
+            audio_feedback: true,
+            min_recording_ms: 250,
+            ..daemon_config()
+        };
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let control_thread = thread::spawn(move || {
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            let _ = control_sender.send(ControlEvent::StopRecording);
+            thread::sleep(Duration::from_millis(50));
+            shutdown_trigger.store(true, Ordering::Relaxed);
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        control_thread.join().expect("control thread failed");
+        result?;
+
+        assert_eq!(feedback.events(), ["start", "too_short"]);
+        assert!(output
+            .stdout_lines()
+            .iter()
+            .any(|line| line == "Recording too short, ignored."));
+        assert!(!output
+            .stdout_lines()
+            .iter()
+            .any(|line| line.contains("stray")));
+        Ok(())
+    }
+
+    #[test]
+    fn processing_feedback_signals_processing_then_done() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let feedback = RecordingFeedback::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(
+                vec!["Mic".to_string()],
+                vec![vec![0.2; 160]],
+            )),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec!["hello".to_string()])),
+            feedback: Box::new(feedback.clone()),
+            config_loader: None,
+        };
+        let config = DaemonConfig {
+            audio_feedback: true,
+            processing_feedback: true,
+            ..daemon_config()
+        };
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let control_thread = thread::spawn(move || {
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            let _ = control_sender.send(ControlEvent::StopRecording);
+            thread::sleep(Duration::from_millis(50));
+            shutdown_trigger.store(true, Ordering::Relaxed);
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        control_thread.join().expect("control thread failed");
+        result?;
+
+        assert_eq!(feedback.events(), ["start", "processing", "done"]);
+        Ok(())
+    }
+
+    #[test]
+    fn restart_within_merge_gap_transcribes_both_sessions_together() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(
+                vec!["Mic".to_string()],
+                vec![vec![0.2; 160], vec![0.2; 160]],
+            )),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec![
+                "one thought".to_string(),
+                "second job".to_string(),
+            ])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let config = DaemonConfig {
+            merge_gap_ms: 100,
+            ..daemon_config()
+        };
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let control_thread = thread::spawn(move || {
+            for event in [
+                ControlEvent::StartRecording,
+                ControlEvent::StopRecording,
+                ControlEvent::StartRecording,
+                ControlEvent::StopRecording,
+            ] {
+                let _ = control_sender.send(event);
+            }
+            thread::sleep(Duration::from_millis(300));
+            shutdown_trigger.store(true, Ordering::Relaxed);
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        control_thread.join().expect("control thread failed");
+        result?;
+
+        let lines = output.stdout_lines();
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|line| *line == "Recording resumed.")
+                .count(),
+            1
+        );
+        let transcripts: Vec<&String> = lines
+            .iter()
+            .filter(|line| line.starts_with("Transcript"))
+            .collect();
+        assert_eq!(transcripts, ["Transcript 1: one thought"]);
+        Ok(())
+    }
+
+    #[test]
+    fn repeat_guard_skips_an_identical_transcript() {
+        let mut output = TestOutput::default();
+        let mut emit_state = EmitState::default();
+        let config = DaemonConfig {
+            repeat_guard_ms: 60_000,
+            ..daemon_config()
+        };
+        let result = |index, text: &str| TranscriptionResult {
+            index,
+            duration_ms: 500,
+            transcript: Ok(text.to_string()),
+            words: Vec::new(),
+            segments: Vec::new(),
+            had_overlap: false,
+            partial: false,
+        };
+
+        for (index, text) in [(1, "send it"), (2, "send it"), (3, "send it again")] {
+            emit_worker_result(
+                &config,
+                &NoopFeedback,
+                &mut output,
+                result(index, text),
+                &mut emit_state,
+            );
+        }
+
+        let lines = output.stdout_lines();
+        assert!(lines.iter().any(|line| line == "Transcript 1: send it"));
+        assert!(!lines.iter().any(|line| line.starts_with("Transcript 2")));
+        assert!(lines
+            .iter()
+            .any(|line| line == "Same transcript as the last one, output skipped."));
+        assert!(lines
+            .iter()
+            .any(|line| line == "Transcript 3: send it again"));
+    }
+
+    #[test]
+    fn undo_phrase_erases_the_last_injected_transcript() {
+        let mut output = TestOutput::default();
+        let mut emit_state = EmitState::default();
+        let mut config = daemon_config();
+        config.output.mode = OutputMode::Type;
+        config.output.prefix = Some("> ".to_string());
+        config.voice_commands.undo = vec!["scratch that".to_string()];
+        let result = |index, text: &str| TranscriptionResult {
+            index,
+            duration_ms: 500,
+            transcript: Ok(text.to_string()),
+            words: Vec::new(),
+            segments: Vec::new(),
+            had_overlap: false,
+            partial: false,
+        };
+
+        for (index, text) in [
+            (1, "Hello world."),
+            (2, "Scratch that."),
+            (3, "scratch that"),
+        ] {
+            emit_worker_result(
+                &config,
+                &NoopFeedback,
+                &mut output,
+                result(index, text),
+                &mut emit_state,
+            );
+        }
+
+        assert_eq!(output.injected(), ["> Hello world. "]);
+        assert_eq!(output.erased(), ["> Hello world. ".chars().count()]);
+        assert_eq!(
+            output.stdout_lines(),
+            ["Last transcript undone.", "Nothing to undo."]
+        );
+    }
+
+    #[test]
+    fn flags_words_below_the_minimum_confidence() {
+        let mut output = TestOutput::default();
+        let config = DaemonConfig {
+            min_word_confidence: 0.5,
+            ..daemon_config()
+        };
+        let result = TranscriptionResult {
+            index: 4,
+            duration_ms: 500,
+            transcript: Ok("meet at Kuederle's".to_string()),
+            words: vec![
+                ("meet".to_string(), 0.93),
+                ("at".to_string(), 0.88),
+                ("Kuederle's".to_string(), 0.31),
+            ],
+            segments: Vec::new(),
+            had_overlap: false,
+            partial: false,
+        };
+
+        emit_worker_result(
+            &config,
+            &NoopFeedback,
+            &mut output,
+            result,
+            &mut EmitState::default(),
+        );
+
+        assert_eq!(
+            output.stderr_lines(),
+            ["warn: low-confidence words in transcript 4: Kuederle's (0.31)"]
+        );
+        assert!(output
+            .stdout_lines()
+            .iter()
+            .any(|line| line == "Transcript 4: meet at Kuederle's"));
+    }
+
+    #[test]
+    fn partial_results_skip_audio_of_finished_segments() {
+        let mut output = TestOutput::default();
+        let mut emit_state = EmitState::default();
+        let config = DaemonConfig {
+            incremental_inject: true,
+            ..injecting_config(OutputMode::Type)
+        };
+        let segment = |start_ms, end_ms, text: &str| TimedSegment {
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+        };
+        let partial = |index, segments: Vec<TimedSegment>| TranscriptionResult {
+            index,
+            duration_ms: 2_000,
+            transcript: Ok(transcription_worker::join_segments(&segments)),
+            words: Vec::new(),
+            segments,
+            had_overlap: false,
+            partial: true,
+        };
+
+        let first = partial(
+            1,
+            vec![
+                segment(0, 1_200, "Hello there."),
+                segment(1_200, 1_800, "How are"),
+            ],
+        );
+        emit_worker_result(&config, &NoopFeedback, &mut output, first, &mut emit_state);
+        assert_eq!(
+            emit_state.settled.samples,
+            samples_from_ms(1_200, config.sample_rate)
+        );
+        assert_eq!(emit_state.settled.text, "Hello there.");
+
+        let second = partial(2, vec![segment(0, 1_100, "How are you doing")]);
+        emit_worker_result(&config, &NoopFeedback, &mut output, second, &mut emit_state);
+
+        assert_eq!(output.injected(), ["Hello there. How", " are you"]);
+        assert_eq!(emit_state.settled.text, "Hello there.");
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::collections::VecDeque;
-    use std::sync::atomic::{AtomicBool, Ordering};
-    use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
-    use std::sync::{Arc, Mutex};
-    use std::thread;
-    use std::time::Duration;
+    #[test]
+    fn cleanup_command_rewrites_transcripts_and_falls_back_on_timeout() {
+        let mut output = TestOutput::default();
+        let mut emit_state = EmitState::default();
+        let result = |index| TranscriptionResult {
+            index,
+            duration_ms: 500,
+            transcript: Ok("fix my grammar".to_string()),
+            words: Vec::new(),
+            segments: Vec::new(),
+            had_overlap: false,
+            partial: false,
+        };
+        let config = DaemonConfig {
+            cleanup_command: Some("tr a-z A-Z".to_string()),
+            ..daemon_config()
+        };
+        emit_worker_result(
+            &config,
+            &NoopFeedback,
+            &mut output,
+            result(1),
+            &mut emit_state,
+        );
 
-    use super::test_support::{
-        daemon_config, TestAudioBackend, TestOutput, TestTranscriberFactory,
-    };
+        let config = DaemonConfig {
+            cleanup_command: Some("sleep 5".to_string()),
+            cleanup_timeout_ms: 50,
+            ..daemon_config()
+        };
+        emit_worker_result(
+            &config,
+            &NoopFeedback,
+            &mut output,
+            result(2),
+            &mut emit_state,
+        );
+
+        let lines = output.stdout_lines();
+        assert!(lines
+            .iter()
+            .any(|line| line == "Transcript 1: FIX MY GRAMMAR"));
+        assert!(lines
+            .iter()
+            .any(|line| line == "Transcript 2: fix my grammar"));
+        assert_eq!(
+            output.stderr_lines(),
+            ["warn: cleanup command timed out after 50 ms; using the raw transcript"]
+        );
+    }
 
     #[test]
-    fn daemon_loop_emits_transcript_to_output() -> Result<(), AppError> {
+    fn whitespace_transcript_skips_output_and_signals_nothing_heard() -> Result<(), AppError> {
         let (sender, receiver) = mpsc::channel();
         let control_sender = sender.clone();
         let shutdown = Arc::new(AtomicBool::new(false));
         let mut output = TestOutput::default();
+        let feedback = RecordingFeedback::default();
         let deps = DaemonDeps {
             audio: Box::new(TestAudioBackend::new(
                 vec!["Mic".to_string()],
                 vec![vec![0.2; 160]],
             )),
-            transcriber_factory: Box::new(TestTranscriberFactory::new(vec!["hello".to_string()])),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec![" \t ".to_string()])),
+            feedback: Box::new(feedback.clone()),
+            config_loader: None,
+        };
+        let config = DaemonConfig {
+            audio_feedback: true,
+            ..daemon_config()
         };
-        let config = daemon_config();
 
         let shutdown_trigger = Arc::clone(&shutdown);
         let control_thread = thread::spawn(move || {
@@ -1291,10 +5220,15 @@ mod tests {
         control_thread.join().expect("control thread failed");
         result?;
 
+        assert_eq!(feedback.events(), ["start", "nothing_heard", "stop"]);
         assert!(output
             .stdout_lines()
             .iter()
-            .any(|line| line.contains("Transcript 1: hello")));
+            .any(|line| line == "Nothing heard, output skipped."));
+        assert!(!output
+            .stdout_lines()
+            .iter()
+            .any(|line| line.starts_with("Transcript")));
         Ok(())
     }
 
@@ -1310,6 +5244,8 @@ mod tests {
                 vec![vec![0.0; 100]],
             )),
             transcriber_factory: Box::new(TestTranscriberFactory::new(Vec::new())),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
         };
         let config = DaemonConfig {
             sample_rate: 1_000,
@@ -1335,6 +5271,50 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn capture_without_frames_aborts_after_start_timeout() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let feedback = RecordingFeedback::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(vec!["Mic".to_string()], Vec::new())),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(Vec::new())),
+            feedback: Box::new(feedback.clone()),
+            config_loader: None,
+        };
+        let config = DaemonConfig {
+            audio_feedback: true,
+            capture_start_timeout_ms: 30,
+            ..daemon_config()
+        };
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let control_thread = thread::spawn(move || {
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            thread::sleep(Duration::from_millis(80));
+            shutdown_trigger.store(true, Ordering::Relaxed);
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        control_thread.join().expect("control thread failed");
+        result?;
+
+        assert_eq!(
+            output.stderr_lines(),
+            ["Recording aborted: no audio frames arrived within 30 ms of starting"]
+        );
+        assert_eq!(
+            feedback.events(),
+            [
+                "start",
+                "error: no audio frames arrived within 30 ms of starting"
+            ]
+        );
+        Ok(())
+    }
+
     #[test]
     fn sustained_speech_prevents_no_speech_cancellation() -> Result<(), AppError> {
         let (sender, receiver) = mpsc::channel();
@@ -1347,6 +5327,8 @@ mod tests {
                 vec![vec![0.2; 100]],
             )),
             transcriber_factory: Box::new(TestTranscriberFactory::new(vec!["speech".to_string()])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
         };
         let config = DaemonConfig {
             sample_rate: 1_000,
@@ -1393,6 +5375,8 @@ mod tests {
             transcriber_factory: Box::new(TestTranscriberFactory::new(vec![
                 "pause transcript".to_string()
             ])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
         };
         let config = DaemonConfig {
             vad: VadMode::Continuous,
@@ -1440,6 +5424,8 @@ mod tests {
             transcriber_factory: Box::new(TestTranscriberFactory::new(vec![
                 "timed transcript".to_string()
             ])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
         };
         let config = DaemonConfig {
             sample_rate: 1_000,
@@ -1499,6 +5485,8 @@ mod tests {
                 "hello world".to_string(),
                 "world again".to_string(),
             ])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
         };
         let config = DaemonConfig {
             sample_rate: 1_000,
@@ -1540,6 +5528,162 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn pause_within_post_stop_cooldown_does_not_split_segment() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(
+                vec!["Mic".to_string()],
+                vec![
+                    vec![0.2; 20],
+                    vec![0.0; 20],
+                    vec![0.2; 20],
+                    vec![0.0; 20],
+                    vec![0.2; 20],
+                    vec![0.0; 20],
+                ],
+            )),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec![
+                "first".to_string(),
+                "rest".to_string(),
+                "fragment".to_string(),
+            ])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let config = DaemonConfig {
+            sample_rate: 1_000,
+            vad: VadMode::Continuous,
+            vad_silence_ms: 10,
+            vad_chunk_ms: 10,
+            segment_min_ms: 5,
+            post_stop_cooldown_ms: 60_000,
+            ..daemon_config()
+        };
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let control_thread = thread::spawn(move || {
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            thread::sleep(Duration::from_millis(300));
+            let _ = control_sender.send(ControlEvent::StopRecording);
+            thread::sleep(Duration::from_millis(50));
+            shutdown_trigger.store(true, Ordering::Relaxed);
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        control_thread.join().expect("control thread failed");
+        result?;
+
+        let lines = output.stdout_lines();
+        assert!(lines
+            .iter()
+            .any(|line| line.contains("Transcript 1: first")));
+        assert!(lines.iter().any(|line| line.contains("Transcript 2: rest")));
+        assert!(!lines.iter().any(|line| line.contains("fragment")));
+        Ok(())
+    }
+
+    #[test]
+    fn idle_unload_frees_model_and_reloads_on_next_recording() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let factory = TestTranscriberFactory::new(vec!["one".to_string()]);
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(
+                vec!["Mic".to_string()],
+                vec![vec![0.2; 160]],
+            )),
+            transcriber_factory: Box::new(factory.clone()),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let config = DaemonConfig {
+            idle_unload_secs: 1,
+            ..daemon_config()
+        };
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let control_thread = thread::spawn(move || {
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            thread::sleep(Duration::from_millis(50));
+            let _ = control_sender.send(ControlEvent::StopRecording);
+            thread::sleep(Duration::from_millis(1_200));
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            thread::sleep(Duration::from_millis(50));
+            let _ = control_sender.send(ControlEvent::StopRecording);
+            thread::sleep(Duration::from_millis(50));
+            shutdown_trigger.store(true, Ordering::Relaxed);
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        control_thread.join().expect("control thread failed");
+        result?;
+
+        let lines = output.stdout_lines();
+        assert!(lines
+            .iter()
+            .any(|line| line == "Model unloaded after 1s idle."));
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|line| *line == "Recording started.")
+                .count(),
+            2
+        );
+        assert_eq!(factory.load_count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn prewarm_runs_one_discarded_transcription_before_recording() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(
+                vec!["Mic".to_string()],
+                vec![vec![0.2; 160]],
+            )),
+            transcriber_factory: Box::new(TestTranscriberFactory::new(vec![
+                "you".to_string(),
+                "hello".to_string(),
+            ])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let config = DaemonConfig {
+            prewarm: true,
+            ..daemon_config()
+        };
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let control_thread = thread::spawn(move || {
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            thread::sleep(Duration::from_millis(50));
+            let _ = control_sender.send(ControlEvent::StopRecording);
+            thread::sleep(Duration::from_millis(100));
+            shutdown_trigger.store(true, Ordering::Relaxed);
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        control_thread.join().expect("control thread failed");
+        result?;
+
+        let lines = output.stdout_lines();
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("Model prewarmed in ")));
+        assert!(lines.iter().any(|line| line == "Transcript 1: hello"));
+        assert!(!lines.iter().any(|line| line.contains("you")));
+        Ok(())
+    }
+
     #[test]
     fn carried_silence_overlap_does_not_dedupe_following_segment() -> Result<(), AppError> {
         let (sender, receiver) = mpsc::channel();
@@ -1555,6 +5699,8 @@ mod tests {
                 "hello world".to_string(),
                 "world again".to_string(),
             ])),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
         };
         let config = DaemonConfig {
             sample_rate: 1_000,
@@ -1602,6 +5748,71 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn idle_unload_ignores_status_and_survives_a_failed_load() -> Result<(), AppError> {
+        let (sender, receiver) = mpsc::channel();
+        let control_sender = sender.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut output = TestOutput::default();
+        let load_count = Arc::new(AtomicUsize::new(0));
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(
+                vec!["Mic".to_string()],
+                vec![vec![0.2; 160]],
+            )),
+            transcriber_factory: Box::new(ReloadFailFactory {
+                responses: Arc::new(Mutex::new(vec![Ok("after failure".to_string())].into())),
+                load_count: Arc::clone(&load_count),
+            }),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+        let config = DaemonConfig {
+            idle_unload_secs: 1,
+            ..daemon_config()
+        };
+
+        let shutdown_trigger = Arc::clone(&shutdown);
+        let control_thread = thread::spawn(move || {
+            // Status polls from a bar are not activity.
+            for _ in 0..6 {
+                let _ = control_sender.send(ControlEvent::Status);
+                thread::sleep(Duration::from_millis(200));
+            }
+            // The first load after the unload fails; the hotkey keeps working.
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            thread::sleep(Duration::from_millis(50));
+            let _ = control_sender.send(ControlEvent::StartRecording);
+            thread::sleep(Duration::from_millis(50));
+            let _ = control_sender.send(ControlEvent::StopRecording);
+            thread::sleep(Duration::from_millis(50));
+            shutdown_trigger.store(true, Ordering::Relaxed);
+        });
+
+        let result = run_daemon_loop(&config, &deps, &mut output, receiver, &shutdown);
+        control_thread.join().expect("control thread failed");
+        result?;
+
+        assert!(output
+            .stdout_lines()
+            .iter()
+            .any(|line| line == "Model unloaded after 1s idle."));
+        assert!(output
+            .stderr_lines()
+            .iter()
+            .any(|line| line == "Model load failed: planned reload failure"));
+        assert_eq!(load_count.load(AtomicOrdering::SeqCst), 3);
+        assert_eq!(
+            output
+                .stdout_lines()
+                .iter()
+                .filter(|line| *line == "Recording started.")
+                .count(),
+            1
+        );
+        Ok(())
+    }
+
     #[test]
     fn failed_model_reload_keeps_existing_worker() -> Result<(), AppError> {
         let data_home = temp_data_home();
@@ -1626,6 +5837,8 @@ mod tests {
                 responses: Arc::new(Mutex::new(vec![Ok("after failure".to_string())].into())),
                 load_count: Arc::new(AtomicUsize::new(0)),
             }),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
         };
         let config = daemon_config();
 
@@ -1676,6 +5889,67 @@ mod tests {
         }
     }
 
+    #[test]
+    fn decode_at_pauses_reports_each_piece_before_decoding_it() -> Result<(), AppError> {
+        // 16 kHz: speech 0-1.5 s, pause 1.5-2.5 s, speech 2.5-4 s.
+        let mut samples = vec![0.5; 24_000];
+        samples.extend(vec![0.0; 16_000]);
+        samples.extend(vec![0.5; 24_000]);
+        let events = std::cell::RefCell::new(Vec::new());
+
+        let text = decode_at_pauses(
+            &samples,
+            0.1,
+            300,
+            |index, span| events.borrow_mut().push(format!("piece {index} {span:?}")),
+            |piece| {
+                events.borrow_mut().push(format!("decode {}", piece.len()));
+                Ok(format!("part{}", events.borrow().len()))
+            },
+        )?;
+
+        assert_eq!(
+            events.into_inner(),
+            [
+                "piece 0 (0.0, 2.0)",
+                "decode 32000",
+                "piece 1 (2.0, 4.0)",
+                "decode 32000",
+            ]
+        );
+        assert_eq!(text, "part2 part4");
+        Ok(())
+    }
+
+    #[test]
+    fn reported_pieces_reach_output_before_the_transcript() -> Result<(), AppError> {
+        let mut output = TestOutput::default();
+        let deps = DaemonDeps {
+            audio: Box::new(TestAudioBackend::new(Vec::new(), Vec::new())),
+            transcriber_factory: Box::new(PieceFactory),
+            feedback: Box::new(NoopFeedback),
+            config_loader: None,
+        };
+
+        transcribe_samples(&daemon_config(), &deps, &[0.2; 160], &mut output)?;
+
+        let lines = output.stdout_lines();
+        let position = |wanted: &str| lines.iter().position(|line| line == wanted);
+        let first = position("Transcribing piece 1 (0.0-2.0 s).").expect("first piece");
+        let second = position("Transcribing piece 2 (2.0-4.5 s).").expect("second piece");
+        let transcript = position("Transcript 1: two pieces").expect("transcript");
+        assert!(first < second && second < transcript);
+        Ok(())
+    }
+
+    struct PieceFactory;
+
+    impl TranscriberFactory for PieceFactory {
+        fn load(&self, _model_path: Option<&Path>) -> Result<Box<dyn Transcriber>, AppError> {
+            Ok(Box::new(PieceTranscriber::default()))
+        }
+    }
+
     struct ReloadFailFactory {
         responses: Arc<Mutex<VecDeque<Result<String, AppError>>>>,
         load_count: Arc<AtomicUsize>,
@@ -1755,6 +6029,59 @@ mod tests {
         assert_eq!(model_language, ModelLanguage::En);
     }
 
+    #[test]
+    fn parses_set_backend_command_tokens() {
+        assert_eq!(
+            parse_set_backend_command(" backend=auto"),
+            Ok(OutputBackend::Auto)
+        );
+        assert_eq!(
+            parse_set_backend_command(" backend=ydotool"),
+            Ok(OutputBackend::Mode(OutputMode::Ydotool))
+        );
+        assert!(parse_set_backend_command("")
+            .expect_err("expected parse error")
+            .contains("backend="));
+    }
+
+    #[test]
+    fn parses_set_gain_command_tokens() {
+        assert_eq!(parse_set_gain_command(" db=6"), Ok(6.0));
+        assert_eq!(parse_set_gain_command(" db=-3.5"), Ok(-3.5));
+        assert!(parse_set_gain_command("")
+            .expect_err("expected parse error")
+            .contains("db="));
+        assert!(parse_set_gain_command(" db=NaN").is_err());
+    }
+
+    #[test]
+    fn parses_dump_transcript_targets() {
+        assert_eq!(parse_dump_transcript_command(""), Ok(DumpTarget::Clipboard));
+        assert_eq!(
+            parse_dump_transcript_command(" path=/tmp/my notes.txt"),
+            Ok(DumpTarget::File(PathBuf::from("/tmp/my notes.txt")))
+        );
+        assert!(parse_dump_transcript_command(" path=notes.txt").is_err());
+        assert!(parse_dump_transcript_command(" clipboard").is_err());
+    }
+
+    #[test]
+    fn timed_out_decode_keeps_finished_segments() {
+        let timed_out = |partial: &str| {
+            Err(WhisperError::TimedOut {
+                timeout_ms: 5000,
+                partial: partial.to_string(),
+            })
+        };
+
+        assert_eq!(
+            keep_partial_on_timeout(timed_out("The first half")).expect("partial text"),
+            "The first half"
+        );
+        let err = keep_partial_on_timeout(timed_out("")).expect_err("nothing decoded");
+        assert_eq!(err.to_string(), "transcription timed out after 5000 ms");
+    }
+
     #[test]
     fn rejects_set_model_command_missing_language() {
         let err =
@@ -0,0 +1,337 @@
+//! Setup checks for `sv doctor`.
+
+use std::env;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::audio;
+use crate::build_info;
+use crate::daemon::{self, DaemonConfig};
+use crate::model::{self, ModelSpec};
+use crate::output::{self, BackendStatus, OutputMode};
+
+/// whisper.cpp model files start with the `ggml` magic stored little-endian.
+const GGML_MAGIC: &[u8; 4] = b"lmgg";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoctorCheck {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+    pub hint: Option<String>,
+}
+
+impl DoctorCheck {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            passed: true,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            passed: false,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DoctorReport {
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    pub fn failures(&self) -> usize {
+        self.checks.iter().filter(|check| !check.passed).count()
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failures() == 0
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            let status = if check.passed { "ok" } else { "fail" };
+            writeln!(f, "[{status}] {}: {}", check.name, check.detail)?;
+            if let Some(hint) = &check.hint {
+                writeln!(f, "       hint: {hint}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs every setup check that applies to `config` and collects the results.
+pub fn run(config: &DaemonConfig, spec: &ModelSpec) -> DoctorReport {
+    let path_var = env::var_os("PATH");
+    let path_var = path_var.as_deref();
+    let build = build_info::build_info();
+    let mut checks = vec![
+        DoctorCheck::pass("build", build.to_string()),
+        DoctorCheck::pass("whisper.cpp", build.whisper_system_info),
+        check_session(config.output.mode),
+        check_model(config.model_path.as_deref(), spec),
+        check_audio_device(config),
+    ];
+    let tools = check_output_tools(config.output.mode, path_var);
+    let tools_found = tools.iter().all(|check| check.passed);
+    checks.extend(tools);
+    if tools_found {
+        checks.push(check_backend(config.output.mode));
+    }
+    if config.hotkey.enabled {
+        checks.push(check_input_devices(Path::new("/dev/input")));
+    }
+    if config.audio_feedback {
+        checks.push(check_program("feedback sound", "paplay", path_var));
+    }
+    DoctorReport { checks }
+}
+
+fn check_session(mode: OutputMode) -> DoctorCheck {
+    let session = env::var("XDG_SESSION_TYPE").unwrap_or_default();
+    let wayland = env::var_os("WAYLAND_DISPLAY").is_some();
+    let detail = format!(
+        "XDG_SESSION_TYPE={}, WAYLAND_DISPLAY {}",
+        if session.is_empty() {
+            "unset"
+        } else {
+            &session
+        },
+        if wayland { "set" } else { "unset" }
+    );
+    if wayland || !matches!(mode, OutputMode::Paste | OutputMode::Clipboard) {
+        DoctorCheck::pass("session", detail)
+    } else {
+        DoctorCheck::fail(
+            "session",
+            detail,
+            "paste and clipboard modes need a Wayland session; use mode = \"type\" elsewhere",
+        )
+    }
+}
+
+fn check_model(explicit_path: Option<&Path>, spec: &ModelSpec) -> DoctorCheck {
+    let path = match model::resolve_model_path(explicit_path, spec) {
+        Ok(path) => path,
+        Err(err) => {
+            return DoctorCheck::fail("model", err.to_string(), "pick a supported model_size");
+        }
+    };
+    match model_file_looks_valid(&path) {
+        Ok(()) => DoctorCheck::pass("model", path.display().to_string()),
+        Err(reason) => DoctorCheck::fail(
+            "model",
+            format!("{}: {reason}", path.display()),
+            "run `sv` once with download_model = true, or point model_path at a ggml model",
+        ),
+    }
+}
+
+fn model_file_looks_valid(path: &Path) -> Result<(), String> {
+    let mut file = fs::File::open(path).map_err(|err| format!("cannot open: {err}"))?;
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic)
+        .map_err(|err| format!("cannot read header: {err}"))?;
+    if &magic == GGML_MAGIC {
+        Ok(())
+    } else {
+        Err("not a ggml model file".to_string())
+    }
+}
+
+fn check_audio_device(config: &DaemonConfig) -> DoctorCheck {
+    let devices = daemon::select_audio_host(config.audio_host)
+        .map_err(|err| err.to_string())
+        .and_then(|host| {
+            audio::configure_alsa_logging(config.debug_audio);
+            audio::list_input_devices(&host).map_err(|err| err.message)
+        });
+    let devices = match devices {
+        Ok(devices) => devices,
+        Err(err) => {
+            return DoctorCheck::fail(
+                "audio device",
+                err,
+                "check that PipeWire or PulseAudio is running",
+            );
+        }
+    };
+    match config.device.as_deref() {
+        Some(device) if devices.iter().any(|name| name.eq_ignore_ascii_case(device)) => {
+            DoctorCheck::pass("audio device", device)
+        }
+        Some(device) => DoctorCheck::fail(
+            "audio device",
+            format!("{device} not found among {} input devices", devices.len()),
+            "run `sv --list-devices` and update the device setting",
+        ),
+        None if devices.is_empty() => DoctorCheck::fail(
+            "audio device",
+            "no input devices found",
+            "connect a microphone or check audio_host",
+        ),
+        None => DoctorCheck::pass(
+            "audio device",
+            format!("{} input devices, using the default", devices.len()),
+        ),
+    }
+}
+
+fn check_output_tools(mode: OutputMode, path_var: Option<&OsStr>) -> Vec<DoctorCheck> {
+    mode.required_programs()
+        .iter()
+        .map(|program| check_program("output tool", program, path_var))
+        .collect()
+}
+
+fn check_program(name: &'static str, program: &str, path_var: Option<&OsStr>) -> DoctorCheck {
+    match find_program(program, path_var) {
+        Some(path) => DoctorCheck::pass(name, path.display().to_string()),
+        None => DoctorCheck::fail(
+            name,
+            format!("{program} not found in PATH"),
+            format!("install {program}"),
+        ),
+    }
+}
+
+pub(crate) fn find_program(program: &str, path_var: Option<&OsStr>) -> Option<PathBuf> {
+    env::split_paths(path_var?)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Goes past the PATH lookup: an installed tool can still be unable to
+/// reach the device or daemon it drives.
+fn check_backend(mode: OutputMode) -> DoctorCheck {
+    match output::probe_backend(mode) {
+        BackendStatus::Available => DoctorCheck::pass("output backend", format!("{mode} ready")),
+        BackendStatus::Unavailable(reason) => DoctorCheck::fail(
+            "output backend",
+            reason,
+            "install the missing tools or pick another mode",
+        ),
+        BackendStatus::InstalledButNonFunctional(reason) => {
+            let hint = if mode == OutputMode::Ydotool {
+                "start ydotoold (e.g. systemctl --user enable --now ydotool)"
+            } else {
+                "allow your user to write /dev/uinput, e.g. with the udev rule dotool ships"
+            };
+            DoctorCheck::fail(
+                "output backend",
+                format!("installed but not working: {reason}"),
+                hint,
+            )
+        }
+    }
+}
+
+fn check_input_devices(dir: &Path) -> DoctorCheck {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            return DoctorCheck::fail(
+                "hotkey input",
+                format!("cannot list {}: {err}", dir.display()),
+                "disable the hotkey or run on a system with evdev devices",
+            );
+        }
+    };
+    let readable = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("event"))
+        .filter(|entry| fs::File::open(entry.path()).is_ok())
+        .count();
+    if readable > 0 {
+        DoctorCheck::pass("hotkey input", format!("{readable} readable event devices"))
+    } else {
+        DoctorCheck::fail(
+            "hotkey input",
+            format!("no readable event devices in {}", dir.display()),
+            "add your user to the input group and log in again",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(label: &str) -> PathBuf {
+        let mut dir = env::temp_dir();
+        let stamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        dir.push(format!(
+            "soundvibes-doctor-{label}-{}-{stamp}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).expect("create temp dir");
+        dir
+    }
+
+    #[test]
+    fn report_lists_failures_with_hints() {
+        let report = DoctorReport {
+            checks: vec![
+                DoctorCheck::pass("model", "/models/ggml-small.bin"),
+                DoctorCheck::fail("output tool", "dotool not found in PATH", "install dotool"),
+            ],
+        };
+
+        assert_eq!(report.failures(), 1);
+        assert!(!report.passed());
+        assert_eq!(
+            report.to_string(),
+            "[ok] model: /models/ggml-small.bin\n\
+             [fail] output tool: dotool not found in PATH\n       hint: install dotool\n"
+        );
+    }
+
+    #[test]
+    fn model_check_requires_ggml_header() {
+        let dir = temp_dir("model");
+        let valid = dir.join("ggml-valid.bin");
+        let invalid = dir.join("ggml-invalid.bin");
+        fs::write(&valid, b"lmgg\x00\x00").expect("write model");
+        fs::write(&invalid, b"<html>").expect("write model");
+
+        assert_eq!(model_file_looks_valid(&valid), Ok(()));
+        assert_eq!(
+            model_file_looks_valid(&invalid),
+            Err("not a ggml model file".to_string())
+        );
+        assert!(model_file_looks_valid(&dir.join("missing.bin")).is_err());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn find_program_searches_each_path_entry() {
+        let empty = temp_dir("path-empty");
+        let bin = temp_dir("path-bin");
+        fs::write(bin.join("dotool"), b"").expect("write program");
+        let path_var = env::join_paths([&empty, &bin]).expect("join paths");
+
+        assert_eq!(
+            find_program("dotool", Some(&path_var)),
+            Some(bin.join("dotool"))
+        );
+        assert_eq!(find_program("ydotool", Some(&path_var)), None);
+        assert_eq!(find_program("dotool", None), None);
+        let _ = fs::remove_dir_all(empty);
+        let _ = fs::remove_dir_all(bin);
+    }
+}
@@ -1,19 +1,568 @@
 //! Audio feedback for recording state changes.
 
-use std::process::Command;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
 
-const SOUND_START: &str = "/usr/share/sounds/freedesktop/stereo/device-added.oga";
-const SOUND_STOP: &str = "/usr/share/sounds/freedesktop/stereo/complete.oga";
+use serde::{Deserialize, Serialize};
 
-pub(crate) fn play_start_sound() {
-    play_sound(SOUND_START);
+use crate::daemon::DaemonConfig;
+
+/// Theme every sound theme falls back to, shipped by sound-theme-freedesktop.
+const FALLBACK_THEME: &str = "freedesktop";
+const SOUND_EXTENSIONS: &[&str] = &["oga", "ogg", "wav"];
+
+/// The sound played for each signal: a file path, or a freedesktop logical
+/// name such as `dialog-error` looked up in the current sound theme.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FeedbackSounds {
+    pub start: String,
+    pub stop: String,
+    pub error: String,
+    pub processing: String,
+    pub done: String,
+    pub too_short: String,
+    pub nothing_heard: String,
+    pub not_ready: String,
+}
+
+impl Default for FeedbackSounds {
+    fn default() -> Self {
+        Self {
+            start: "device-added".to_string(),
+            stop: "complete".to_string(),
+            error: "dialog-error".to_string(),
+            processing: "message".to_string(),
+            done: "message-new-instant".to_string(),
+            too_short: "dialog-warning".to_string(),
+            nothing_heard: "dialog-information".to_string(),
+            not_ready: "bell".to_string(),
+        }
+    }
+}
+
+/// Receives recording state signals from the daemon loop.
+///
+/// Implementations must return quickly; the daemon calls them inline.
+pub trait Feedback {
+    fn on_start(&self);
+    fn on_stop(&self);
+    fn on_error(&self, message: &str);
+
+    /// Recording stopped and transcription is running.
+    fn on_processing(&self) {}
+
+    /// Transcription finished and its output was delivered.
+    fn on_done(&self) {}
+
+    /// Recording was discarded for being shorter than `min_recording_ms`.
+    fn on_too_short(&self) {}
+
+    /// Transcription returned no text, so nothing was injected.
+    fn on_nothing_heard(&self) {}
+
+    /// Recording was requested before the model finished loading.
+    fn on_not_ready(&self) {}
+
+    /// Takes the feedback settings of a reloaded configuration.
+    fn reload(&self, _config: &DaemonConfig) {}
+}
+
+/// Plays freedesktop theme sounds through `paplay`. The settings sit behind
+/// a lock so `reload` can replace them while the daemon runs.
+#[derive(Debug)]
+pub struct SoundFeedback {
+    settings: Mutex<SoundSettings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SoundSettings {
+    sink: Option<String>,
+    sounds: FeedbackSounds,
+}
+
+impl Default for SoundFeedback {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl SoundFeedback {
+    /// Plays on `sink` when set, otherwise on the default output.
+    pub fn new(sink: Option<String>) -> Self {
+        Self {
+            settings: Mutex::new(SoundSettings {
+                sink,
+                sounds: FeedbackSounds::default(),
+            }),
+        }
+    }
+
+    /// Replaces the default theme sounds.
+    pub fn with_sounds(mut self, sounds: FeedbackSounds) -> Self {
+        self.settings_mut().sounds = sounds;
+        self
+    }
+
+    fn settings_mut(&mut self) -> &mut SoundSettings {
+        self.settings
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn settings(&self) -> SoundSettings {
+        self.settings
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    fn play(&self, sound: impl Fn(&FeedbackSounds) -> &String) {
+        let settings = self.settings();
+        if let Some(path) = sound_path(sound(&settings.sounds)) {
+            play_sound(&path, settings.sink.as_deref());
+        }
+    }
+}
+
+impl Feedback for SoundFeedback {
+    fn on_start(&self) {
+        self.play(|sounds| &sounds.start);
+    }
+
+    fn on_stop(&self) {
+        self.play(|sounds| &sounds.stop);
+    }
+
+    fn on_error(&self, _message: &str) {
+        self.play(|sounds| &sounds.error);
+    }
+
+    fn on_processing(&self) {
+        self.play(|sounds| &sounds.processing);
+    }
+
+    fn on_done(&self) {
+        self.play(|sounds| &sounds.done);
+    }
+
+    fn on_too_short(&self) {
+        self.play(|sounds| &sounds.too_short);
+    }
+
+    fn on_nothing_heard(&self) {
+        self.play(|sounds| &sounds.nothing_heard);
+    }
+
+    fn on_not_ready(&self) {
+        self.play(|sounds| &sounds.not_ready);
+    }
+
+    fn reload(&self, config: &DaemonConfig) {
+        *self
+            .settings
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = SoundSettings {
+            sink: config.feedback_sink.clone(),
+            sounds: config.feedback_sounds.clone(),
+        };
+    }
+}
+
+/// Ignores every signal.
+pub struct NoopFeedback;
+
+impl Feedback for NoopFeedback {
+    fn on_start(&self) {}
+
+    fn on_stop(&self) {}
+
+    fn on_error(&self, _message: &str) {}
+}
+
+/// Forwards every signal to each inner feedback in order.
+#[derive(Default)]
+pub struct CompositeFeedback {
+    inner: Vec<Box<dyn Feedback>>,
+}
+
+impl CompositeFeedback {
+    pub fn new(inner: Vec<Box<dyn Feedback>>) -> Self {
+        Self { inner }
+    }
+
+    pub fn push(&mut self, feedback: Box<dyn Feedback>) {
+        self.inner.push(feedback);
+    }
+}
+
+impl Feedback for CompositeFeedback {
+    fn on_start(&self) {
+        for feedback in &self.inner {
+            feedback.on_start();
+        }
+    }
+
+    fn on_stop(&self) {
+        for feedback in &self.inner {
+            feedback.on_stop();
+        }
+    }
+
+    fn on_error(&self, message: &str) {
+        for feedback in &self.inner {
+            feedback.on_error(message);
+        }
+    }
+
+    fn on_processing(&self) {
+        for feedback in &self.inner {
+            feedback.on_processing();
+        }
+    }
+
+    fn on_done(&self) {
+        for feedback in &self.inner {
+            feedback.on_done();
+        }
+    }
+
+    fn on_too_short(&self) {
+        for feedback in &self.inner {
+            feedback.on_too_short();
+        }
+    }
+
+    fn on_nothing_heard(&self) {
+        for feedback in &self.inner {
+            feedback.on_nothing_heard();
+        }
+    }
+
+    fn on_not_ready(&self) {
+        for feedback in &self.inner {
+            feedback.on_not_ready();
+        }
+    }
+
+    fn reload(&self, config: &DaemonConfig) {
+        for feedback in &self.inner {
+            feedback.reload(config);
+        }
+    }
 }
 
-pub(crate) fn play_stop_sound() {
-    play_sound(SOUND_STOP);
+/// Starts reading `text` aloud. `command` runs through `sh -c` with the text
+/// appended as its final argument, so `spd-say --wait` and `espeak` both work.
+/// The shell execs the command, so killing the child stops the speech.
+pub fn start_readback(command: &str, text: &str) -> io::Result<Child> {
+    Command::new("sh")
+        .arg("-c")
+        .arg(format!("exec {command} \"$1\""))
+        .arg("sv")
+        .arg(text)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
 }
 
-fn play_sound(path: &str) {
+/// The file for a configured sound. Anything containing a `/` is a path;
+/// anything else is a logical name resolved through the sound theme.
+pub fn sound_path(sound: &str) -> Option<PathBuf> {
+    if sound.contains('/') {
+        Some(PathBuf::from(sound))
+    } else {
+        resolve_theme_sound(sound)
+    }
+}
+
+/// Looks up a logical sound name in the current sound theme following the
+/// XDG sound theme spec: each theme in the inheritance chain is searched,
+/// then `freedesktop`, and `message-new-instant` falls back to `message-new`
+/// and `message` when nothing provides the full name.
+pub fn resolve_theme_sound(name: &str) -> Option<PathBuf> {
+    resolve_sound_in(name, &current_sound_theme(), &sound_dirs())
+}
+
+fn resolve_sound_in(name: &str, theme: &str, dirs: &[PathBuf]) -> Option<PathBuf> {
+    let themes = theme_chain(theme, dirs);
+    let mut name = name;
+    loop {
+        for theme in &themes {
+            for dir in dirs {
+                for extension in SOUND_EXTENSIONS {
+                    let path = dir
+                        .join(theme)
+                        .join("stereo")
+                        .join(format!("{name}.{extension}"));
+                    if path.is_file() {
+                        return Some(path);
+                    }
+                }
+            }
+        }
+        name = &name[..name.rfind('-')?];
+    }
+}
+
+/// `theme` followed by the themes it inherits from, ending in `freedesktop`.
+fn theme_chain(theme: &str, dirs: &[PathBuf]) -> Vec<String> {
+    let mut chain = Vec::new();
+    let mut pending = vec![theme.to_string()];
+    while let Some(theme) = pending.pop() {
+        if chain.contains(&theme) {
+            continue;
+        }
+        let parents = dirs
+            .iter()
+            .find_map(|dir| fs::read_to_string(dir.join(&theme).join("index.theme")).ok())
+            .map(|index| theme_parents(&index))
+            .unwrap_or_default();
+        chain.push(theme);
+        pending.extend(parents.into_iter().rev());
+    }
+    if !chain.iter().any(|theme| theme == FALLBACK_THEME) {
+        chain.push(FALLBACK_THEME.to_string());
+    }
+    chain
+}
+
+fn theme_parents(index: &str) -> Vec<String> {
+    index
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Inherits="))
+        .map(|parents| {
+            parents
+                .split(',')
+                .map(str::trim)
+                .filter(|parent| !parent.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The sound theme chosen in the GTK settings, or `freedesktop`.
+fn current_sound_theme() -> String {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+    config_home
+        .and_then(|dir| fs::read_to_string(dir.join("gtk-3.0/settings.ini")).ok())
+        .and_then(|settings| {
+            settings.lines().find_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                (key.trim() == "gtk-sound-theme-name").then(|| value.trim().to_string())
+            })
+        })
+        .filter(|theme| !theme.is_empty())
+        .unwrap_or_else(|| FALLBACK_THEME.to_string())
+}
+
+/// `sounds` below `$XDG_DATA_HOME` and each of `$XDG_DATA_DIRS`.
+fn sound_dirs() -> Vec<PathBuf> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+    let data_dirs = env::var_os("XDG_DATA_DIRS")
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    data_home
+        .into_iter()
+        .chain(env::split_paths(&data_dirs))
+        .map(|dir| dir.join("sounds"))
+        .collect()
+}
+
+fn play_sound(path: &Path, sink: Option<&str>) {
     // Spawn paplay in background, ignore errors (sound is optional)
-    let _ = Command::new("paplay").arg(path).spawn();
+    let _ = Command::new("paplay").args(paplay_args(path, sink)).spawn();
+}
+
+fn paplay_args(path: &Path, sink: Option<&str>) -> Vec<OsString> {
+    let mut args = Vec::new();
+    if let Some(sink) = sink {
+        args.push(format!("--device={sink}").into());
+    }
+    args.push(path.as_os_str().to_owned());
+    args
+}
+
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support {
+    use std::sync::{Arc, Mutex};
+
+    use super::{DaemonConfig, Feedback};
+
+    /// Records each signal by name, with errors as `error: <message>`.
+    #[derive(Clone, Default)]
+    pub struct RecordingFeedback {
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl RecordingFeedback {
+        pub fn events(&self) -> Vec<String> {
+            self.events.lock().expect("feedback events lock").clone()
+        }
+
+        fn record(&self, event: String) {
+            self.events
+                .lock()
+                .expect("feedback events lock")
+                .push(event);
+        }
+    }
+
+    impl Feedback for RecordingFeedback {
+        fn on_start(&self) {
+            self.record("start".to_string());
+        }
+
+        fn on_stop(&self) {
+            self.record("stop".to_string());
+        }
+
+        fn on_error(&self, message: &str) {
+            self.record(format!("error: {message}"));
+        }
+
+        fn on_processing(&self) {
+            self.record("processing".to_string());
+        }
+
+        fn on_done(&self) {
+            self.record("done".to_string());
+        }
+
+        fn on_too_short(&self) {
+            self.record("too_short".to_string());
+        }
+
+        fn on_nothing_heard(&self) {
+            self.record("nothing_heard".to_string());
+        }
+
+        fn on_not_ready(&self) {
+            self.record("not_ready".to_string());
+        }
+
+        fn reload(&self, _config: &DaemonConfig) {
+            self.record("reload".to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::RecordingFeedback;
+    use super::*;
+
+    #[test]
+    fn composite_feedback_forwards_signals_in_order() {
+        let first = RecordingFeedback::default();
+        let second = RecordingFeedback::default();
+        let mut composite = CompositeFeedback::new(vec![Box::new(first.clone())]);
+        composite.push(Box::new(second.clone()));
+
+        composite.on_start();
+        composite.on_error("boom");
+        composite.on_stop();
+
+        let expected = ["start", "error: boom", "stop"];
+        assert_eq!(first.events(), expected);
+        assert_eq!(second.events(), expected);
+    }
+
+    #[test]
+    fn sound_feedback_takes_reloaded_settings() {
+        let feedback = SoundFeedback::new(None);
+        let mut config = crate::daemon::test_support::daemon_config();
+        config.feedback_sink = Some("alsa_output.usb-headset".to_string());
+        config.feedback_sounds.start = "/tmp/start.oga".to_string();
+
+        feedback.reload(&config);
+
+        let settings = feedback.settings();
+        assert_eq!(settings.sink.as_deref(), Some("alsa_output.usb-headset"));
+        assert_eq!(settings.sounds.start, "/tmp/start.oga");
+    }
+
+    #[test]
+    fn default_done_sound_differs_from_stop_and_processing() {
+        let sounds = FeedbackSounds::default();
+        assert_ne!(sounds.done, sounds.stop);
+        assert_ne!(sounds.done, sounds.processing);
+    }
+
+    #[test]
+    fn readback_passes_the_text_as_one_argument() {
+        let path = std::env::temp_dir().join(format!("sv-readback-{}", std::process::id()));
+        let command = format!("printf '%s' > '{}'", path.display());
+        let text = "it's \"quoted\" $HOME";
+
+        let status = start_readback(&command, text)
+            .expect("spawn readback")
+            .wait()
+            .expect("wait for readback");
+
+        assert!(status.success());
+        assert_eq!(std::fs::read_to_string(&path).expect("read output"), text);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn paplay_routes_to_the_configured_sink() {
+        let sound = "/usr/share/sounds/freedesktop/stereo/device-added.oga";
+        assert_eq!(paplay_args(Path::new(sound), None), [sound]);
+        assert_eq!(
+            paplay_args(Path::new(sound), Some("alsa_output.usb-headset")),
+            ["--device=alsa_output.usb-headset", sound]
+        );
+    }
+
+    #[test]
+    fn theme_sounds_follow_inheritance_and_name_fallback() {
+        let root = std::env::temp_dir().join(format!("sv-sound-theme-{}", std::process::id()));
+        let sounds = root.join("sounds");
+        for (theme, file) in [
+            ("ocean", "dialog-error.oga"),
+            ("freedesktop", "dialog-error.oga"),
+            ("freedesktop", "message.oga"),
+        ] {
+            let dir = sounds.join(theme).join("stereo");
+            std::fs::create_dir_all(&dir).expect("create theme dir");
+            std::fs::write(dir.join(file), b"").expect("write sound");
+        }
+        std::fs::write(
+            sounds.join("ocean/index.theme"),
+            "[Sound Theme]\nName=Ocean\nInherits=freedesktop\n",
+        )
+        .expect("write index");
+        let dirs = [root.join("missing"), sounds.clone()];
+
+        assert_eq!(
+            resolve_sound_in("dialog-error", "ocean", &dirs),
+            Some(sounds.join("ocean/stereo/dialog-error.oga"))
+        );
+        assert_eq!(
+            resolve_sound_in("message-new-instant", "ocean", &dirs),
+            Some(sounds.join("freedesktop/stereo/message.oga"))
+        );
+        assert_eq!(
+            resolve_sound_in("message", "unknown-theme", &dirs),
+            Some(sounds.join("freedesktop/stereo/message.oga"))
+        );
+        assert_eq!(resolve_sound_in("bell", "ocean", &dirs), None);
+        assert_eq!(
+            sound_path("./sounds/ding.wav"),
+            Some(PathBuf::from("./sounds/ding.wav"))
+        );
+        let _ = std::fs::remove_dir_all(root);
+    }
 }
@@ -9,7 +9,7 @@ use std::thread;
 use std::time::{Duration, Instant};
 
 use evdev::{Device, InputEventKind, Key};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use udev::{MonitorBuilder, MonitorSocket};
 
 use crate::daemon::ControlEvent;
@@ -103,7 +103,7 @@ impl<T> KeyboardDevices<T> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(default)]
 pub struct HotkeyConfig {
     pub enabled: bool,
@@ -1,11 +1,23 @@
 pub mod audio;
+pub mod batch;
+pub mod build_info;
+pub mod cleanup;
 pub mod daemon;
+pub mod diagnostics;
 pub mod error;
-mod feedback;
+pub mod feedback;
 pub mod hotkey;
 pub mod model;
 pub mod output;
+#[cfg(feature = "portal")]
+pub mod portal;
+pub mod postprocess;
+pub mod profile;
 pub mod segmentation;
+pub mod session_transcript;
+pub mod subtitles;
 mod transcription_worker;
 pub mod types;
+#[cfg(feature = "websocket")]
+pub mod websocket;
 pub mod whisper;
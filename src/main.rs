@@ -1,21 +1,32 @@
 use clap::parser::ValueSource;
 use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process;
 use sv::audio;
+use sv::batch::{self, BatchOptions};
 use sv::daemon;
+use sv::diagnostics;
 use sv::error::AppError;
+use sv::feedback::{FeedbackSounds, NoopFeedback, SoundFeedback};
 use sv::hotkey::HotkeyConfig;
 use sv::model::{model_language_for_transcription, ModelLanguage, ModelSize, ModelSpec};
-use sv::output::{OutputConfig, OutputMode};
+use sv::output::{OutputBackend, OutputConfig, OutputMode, PasteVia, CLEAR_MODIFIERS_WAIT_MS};
+use sv::postprocess::{FillerFilter, VoiceCommands, WordFilter};
+use sv::profile::Profile;
 use sv::segmentation::{
     DEFAULT_SEGMENT_GRACE_MS, DEFAULT_SEGMENT_MIN_MS, DEFAULT_SEGMENT_OVERLAP_MS,
     DEFAULT_SEGMENT_TARGET_MS,
 };
-use sv::types::{AudioHost, OutputFormat, VadMode};
+use sv::session_transcript::DumpTarget;
+use sv::subtitles::{self, SubtitleOptions};
+use sv::types::{
+    AudioHost, Casing, OutputFormat, PcmFormat, QueueOverflow, SidecarFormat, SubtitleFormat,
+    VadMode,
+};
+use sv::whisper::{self, CodeSwitching, DecodeOptions, WHISPER_SAMPLE_RATE};
 
 #[derive(Parser, Debug)]
 #[command(name = "sv", version, about = "Offline speech-to-text CLI")]
@@ -32,23 +43,46 @@ struct Cli {
     #[arg(long, default_value = "en", value_name = "CODE", global = true)]
     language: String,
 
+    #[arg(long, value_name = "CODES", value_delimiter = ',', global = true)]
+    candidate_languages: Vec<String>,
+
     #[arg(long, value_name = "NAME", global = true)]
     device: Option<String>,
 
+    #[arg(long, value_name = "INDEX", global = true)]
+    input_channel: Option<usize>,
+
     #[arg(long, value_name = "HOST", global = true)]
     audio_host: Option<AudioHost>,
 
     #[arg(long, default_value_t = 16_000, value_name = "HZ", global = true)]
     sample_rate: u32,
 
+    #[arg(
+        long,
+        default_value_t = 0.0,
+        value_name = "DB",
+        allow_negative_numbers = true,
+        global = true
+    )]
+    input_gain_db: f32,
+
     #[arg(long, default_value = "plain", value_name = "MODE", global = true)]
     format: OutputFormat,
 
     #[arg(long, default_value = "paste", value_name = "MODE", global = true)]
     mode: OutputMode,
 
-    #[arg(long, default_value = "ctrl+v", value_name = "KEYS", global = true)]
-    paste_keys: String,
+    #[arg(long, value_name = "KEYS", global = true)]
+    paste_keys: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "regular-with-restore",
+        value_name = "SELECTION",
+        global = true
+    )]
+    paste_via: PasteVia,
 
     #[arg(long, default_value_t = true, action = clap::ArgAction::Set, global = true)]
     restore_clipboard: bool,
@@ -56,8 +90,41 @@ struct Cli {
     #[arg(long, default_value_t = 100, value_name = "MS", global = true)]
     pre_paste_delay_ms: u64,
 
-    #[arg(long, default_value_t = 250, value_name = "MS", global = true)]
-    restore_clipboard_delay_ms: u64,
+    #[arg(long, value_name = "MS", global = true)]
+    restore_clipboard_delay_ms: Option<u64>,
+
+    #[arg(long, default_value_t = 0, value_name = "MS", global = true)]
+    focus_settle_ms: u64,
+
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set, global = true)]
+    restore_immediately_on_paste_failure: bool,
+
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, global = true)]
+    require_focused_window: bool,
+
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, global = true)]
+    auto_backend_by_compositor: bool,
+
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set, global = true)]
+    bracketed_paste: bool,
+
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set, global = true)]
+    release_caps_lock: bool,
+
+    #[arg(long, default_value_t = 0, value_name = "MS", global = true)]
+    modifier_release_wait_ms: u64,
+
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set, global = true)]
+    inject_sentences: bool,
+
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, global = true)]
+    hide_from_clipboard_history: bool,
+
+    #[arg(long, value_name = "TEXT", global = true)]
+    prefix: Option<String>,
+
+    #[arg(long, value_name = "TEXT", global = true)]
+    suffix: Option<String>,
 
     #[arg(long, default_value = "on", value_name = "MODE", global = true)]
     vad: VadMode,
@@ -86,6 +153,12 @@ struct Cli {
     )]
     vad_chunk_ms: u64,
 
+    #[arg(long, default_value_t = 0, value_name = "MS", global = true)]
+    speech_confirm_ms: u64,
+
+    #[arg(long, default_value_t = 0, value_name = "MS", global = true)]
+    silence_confirm_ms: u64,
+
     #[arg(long, default_value_t = DEFAULT_SEGMENT_TARGET_MS, value_name = "MS", global = true)]
     segment_target_ms: u64,
 
@@ -107,12 +180,159 @@ struct Cli {
     #[arg(long, default_value_t = false, global = true)]
     dump_audio: bool,
 
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set, global = true)]
+    save_last_recording: bool,
+
+    #[arg(long, value_name = "PATH", global = true)]
+    last_recording_path: Option<PathBuf>,
+
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set, global = true)]
+    save_native_rate: bool,
+
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set, global = true)]
+    save_unsent: bool,
+
+    #[arg(long, value_name = "PATH", global = true)]
+    unsent_path: Option<PathBuf>,
+
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set, global = true)]
+    open_unsent: bool,
+
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set, global = true)]
+    state_file: bool,
+
+    #[arg(long, value_name = "PATH", global = true)]
+    state_file_path: Option<PathBuf>,
+
     #[arg(long, default_value_t = false, global = true)]
     audio_feedback: bool,
 
+    #[arg(long, default_value_t = false, global = true)]
+    processing_feedback: bool,
+
+    #[arg(long, value_name = "SINK", global = true)]
+    feedback_sink: Option<String>,
+
     #[arg(long, default_value_t = 3000, global = true)]
     no_speech_timeout_ms: u64,
 
+    #[arg(long, default_value_t = 3000, value_name = "MS", global = true)]
+    capture_start_timeout_ms: u64,
+
+    #[arg(long, default_value_t = 0, value_name = "MS", global = true)]
+    min_recording_ms: u64,
+
+    #[arg(long, default_value_t = 0, value_name = "MS", global = true)]
+    post_stop_cooldown_ms: u64,
+
+    #[arg(long, default_value_t = 0, value_name = "MS", global = true)]
+    merge_gap_ms: u64,
+
+    #[arg(long, default_value_t = 0, value_name = "MS", global = true)]
+    repeat_guard_ms: u64,
+
+    #[arg(long, default_value_t = 0.0, value_name = "CONFIDENCE", global = true)]
+    min_word_confidence: f32,
+
+    #[arg(long, value_name = "COMMAND", global = true)]
+    cleanup_command: Option<String>,
+
+    #[arg(long, default_value_t = 2000, value_name = "MS", global = true)]
+    cleanup_timeout_ms: u64,
+
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set, global = true)]
+    tts_confirm: bool,
+
+    #[arg(
+        long,
+        default_value = "spd-say --wait",
+        value_name = "COMMAND",
+        global = true
+    )]
+    tts_command: String,
+
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set, global = true)]
+    session_transcript: bool,
+
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set, global = true)]
+    websocket: bool,
+
+    #[arg(
+        long,
+        default_value = "127.0.0.1:8765",
+        value_name = "ADDR",
+        global = true
+    )]
+    websocket_addr: String,
+
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set, global = true)]
+    websocket_partials: bool,
+
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, global = true)]
+    prewarm: bool,
+
+    #[arg(long, default_value_t = 0, value_name = "SECS", global = true)]
+    idle_unload_secs: u64,
+
+    #[arg(long, default_value_t = 10_000, value_name = "MS", global = true)]
+    shutdown_drain_ms: u64,
+
+    #[arg(long, default_value_t = 8, value_name = "JOBS", global = true)]
+    queue_depth: usize,
+
+    #[arg(
+        long,
+        default_value = "drop-oldest",
+        value_name = "POLICY",
+        global = true
+    )]
+    queue_overflow: QueueOverflow,
+
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, global = true)]
+    no_context: bool,
+
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, global = true)]
+    single_segment: bool,
+
+    #[arg(long, default_value_t = whisper::DEFAULT_TEMPERATURE, value_name = "TEMP", global = true)]
+    temperature: f32,
+
+    #[arg(long, default_value_t = whisper::DEFAULT_TEMPERATURE_INC, value_name = "STEP", global = true)]
+    temperature_inc: f32,
+
+    #[arg(
+        long,
+        default_value_t = whisper::DEFAULT_LOGPROB_THOLD,
+        value_name = "LOGPROB",
+        allow_negative_numbers = true,
+        global = true
+    )]
+    logprob_thold: f32,
+
+    #[arg(long, default_value_t = false, action = clap::ArgAction::Set, global = true)]
+    suppress_nst: bool,
+
+    #[arg(long, value_name = "REGEX", global = true)]
+    suppress_regex: Option<String>,
+
+    #[arg(long, default_value_t = 0, value_name = "MS", global = true)]
+    transcription_timeout_ms: u64,
+
+    #[arg(long, value_name = "MS", global = true)]
+    trim_pad_ms: Option<u64>,
+
+    #[arg(long, value_name = "MS", global = true)]
+    trim_silence_pad_ms: Option<u64>,
+
+    #[arg(long, value_name = "SECS", global = true)]
+    tail_secs: Option<u32>,
+
+    #[arg(long, default_value_t = false, global = true)]
+    incremental_inject: bool,
+
+    #[arg(long, default_value = "whisper", value_name = "MODE", global = true)]
+    casing: Casing,
+
     #[arg(long, default_value_t = true, action = clap::ArgAction::Set, global = true)]
     hotkey_enabled: bool,
 
@@ -122,6 +342,20 @@ struct Cli {
     #[arg(long, default_value_t = true, action = clap::ArgAction::Set, global = true)]
     download_model: bool,
 
+    /// Transcribe raw mono PCM read from stdin at --stdin-rate, then exit.
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "f32le"
+    )]
+    stdin_pcm: Option<PcmFormat>,
+
+    /// Sample rate of the --stdin-pcm input; defaults to --sample-rate.
+    #[arg(long, value_name = "HZ", value_parser = clap::value_parser!(u32).range(1..))]
+    stdin_rate: Option<u32>,
+
     #[command(subcommand)]
     command: Option<CliCommand>,
 }
@@ -134,9 +368,36 @@ enum CliCommand {
     },
     #[command(name = "transcript-path")]
     TranscriptPath,
+    /// Check the model, audio, and output setup and suggest fixes.
+    Doctor,
+    /// Inspect the configuration.
+    Config {
+        /// Print the effective configuration as TOML, usable as a config
+        /// file.
+        #[arg(long, required = true)]
+        dump: bool,
+    },
+    /// Transcribe a WAV file and print timed SRT or WebVTT captions.
+    Subtitles {
+        #[arg(value_name = "WAV")]
+        input: PathBuf,
+        #[arg(long, default_value = "srt", value_name = "FORMAT")]
+        subtitle_format: SubtitleFormat,
+    },
+    /// Transcribe every matching WAV file in a directory with one model load.
+    Batch {
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+        /// File name pattern; `*` and `?` wildcards, case-insensitive.
+        #[arg(long, default_value = "*.wav", value_name = "GLOB")]
+        pattern: String,
+        /// Also write each transcript next to its input file.
+        #[arg(long, value_name = "FORMAT")]
+        sidecar: Option<SidecarFormat>,
+    },
 }
 
-#[derive(Subcommand, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Subcommand, Debug, Clone, PartialEq)]
 enum DaemonCommand {
     Start,
     Status,
@@ -150,13 +411,46 @@ enum DaemonCommand {
     },
     #[command(name = "test-audio")]
     TestAudio,
+    /// Print the output mode the running daemon injects text with.
+    #[command(name = "get-backend")]
+    GetBackend,
+    /// Switch the running daemon's output mode; `auto` re-probes the session.
+    #[command(name = "set-backend")]
+    SetBackend {
+        #[arg(value_name = "BACKEND")]
+        backend: OutputBackend,
+    },
+    /// Change the running daemon's input gain in decibels.
+    #[command(name = "set-gain")]
+    SetGain {
+        #[arg(value_name = "DB", allow_negative_numbers = true)]
+        gain_db: f32,
+    },
+    /// Save the running daemon's session transcript; without PATH, copy it
+    /// to the clipboard.
+    #[command(name = "dump-transcript")]
+    DumpTranscript {
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+    },
+    /// Output the running daemon's last transcript again into the focused
+    /// window.
+    Repeat,
+    /// Make the running daemon read its config file again.
+    Reload,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 enum CliMode {
     RunDaemon,
     StatusDaemon,
     StopDaemon,
+    GetBackend,
+    SetBackend(OutputBackend),
+    SetGain(f32),
+    DumpTranscript(Option<PathBuf>),
+    Repeat,
+    Reload,
     ShowTranscriptPath,
     SetModel {
         size: ModelSize,
@@ -164,10 +458,22 @@ enum CliMode {
     },
     ListDevices,
     TestAudio,
+    Doctor,
+    DumpConfig,
+    TranscribeStdin(PcmFormat),
+    Subtitles {
+        input: PathBuf,
+        format: SubtitleFormat,
+    },
+    Batch {
+        dir: PathBuf,
+        pattern: String,
+        sidecar: Option<SidecarFormat>,
+    },
 }
 
 fn resolve_cli_mode(cli: &Cli) -> CliMode {
-    match cli.command {
+    match &cli.command {
         Some(CliCommand::Daemon {
             command: DaemonCommand::Start,
         }) => CliMode::RunDaemon,
@@ -178,6 +484,8 @@ fn resolve_cli_mode(cli: &Cli) -> CliMode {
             command: DaemonCommand::Stop,
         }) => CliMode::StopDaemon,
         Some(CliCommand::TranscriptPath) => CliMode::ShowTranscriptPath,
+        Some(CliCommand::Doctor) => CliMode::Doctor,
+        Some(CliCommand::Config { .. }) => CliMode::DumpConfig,
         Some(CliCommand::Daemon {
             command:
                 DaemonCommand::SetModel {
@@ -185,15 +493,51 @@ fn resolve_cli_mode(cli: &Cli) -> CliMode {
                     model_language,
                 },
         }) => CliMode::SetModel {
-            size,
-            model_language,
+            size: *size,
+            model_language: *model_language,
+        },
+        Some(CliCommand::Subtitles {
+            input,
+            subtitle_format,
+        }) => CliMode::Subtitles {
+            input: input.clone(),
+            format: *subtitle_format,
+        },
+        Some(CliCommand::Batch {
+            dir,
+            pattern,
+            sidecar,
+        }) => CliMode::Batch {
+            dir: dir.clone(),
+            pattern: pattern.clone(),
+            sidecar: *sidecar,
         },
         Some(CliCommand::Daemon {
             command: DaemonCommand::TestAudio,
         }) => CliMode::TestAudio,
+        Some(CliCommand::Daemon {
+            command: DaemonCommand::GetBackend,
+        }) => CliMode::GetBackend,
+        Some(CliCommand::Daemon {
+            command: DaemonCommand::SetBackend { backend },
+        }) => CliMode::SetBackend(*backend),
+        Some(CliCommand::Daemon {
+            command: DaemonCommand::SetGain { gain_db },
+        }) => CliMode::SetGain(*gain_db),
+        Some(CliCommand::Daemon {
+            command: DaemonCommand::DumpTranscript { path },
+        }) => CliMode::DumpTranscript(path.clone()),
+        Some(CliCommand::Daemon {
+            command: DaemonCommand::Repeat,
+        }) => CliMode::Repeat,
+        Some(CliCommand::Daemon {
+            command: DaemonCommand::Reload,
+        }) => CliMode::Reload,
         None => {
             if cli.list_devices {
                 CliMode::ListDevices
+            } else if let Some(format) = cli.stdin_pcm {
+                CliMode::TranscribeStdin(format)
             } else {
                 CliMode::RunDaemon
             }
@@ -234,6 +578,11 @@ impl Config {
     fn from_sources(cli: Cli, matches: &clap::ArgMatches, file: FileConfig) -> Self {
         let sources = ConfigSources { matches };
         let language = sources.value("language", cli.language, file.language);
+        let candidate_languages = sources.value(
+            "candidate_languages",
+            cli.candidate_languages,
+            file.candidate_languages,
+        );
         let model_size = sources.value("model_size", cli.model_size, file.model_size);
 
         let (model_language, model_language_explicit) =
@@ -253,16 +602,25 @@ impl Config {
         };
 
         let device = sources.optional("device", cli.device, file.device);
+        let input_channel =
+            sources.optional("input_channel", cli.input_channel, file.input_channel);
         let audio_host = sources
             .optional("audio_host", cli.audio_host, file.audio_host)
             .unwrap_or_else(AudioHost::default_for_platform);
         let sample_rate = sources.value("sample_rate", cli.sample_rate, file.sample_rate);
+        let input_gain_db = sources.value("input_gain_db", cli.input_gain_db, file.input_gain_db);
         let format = sources.value("format", cli.format, file.format);
 
         let output_file = file.output.unwrap_or_default();
         let output = OutputConfig {
-            mode: sources.value("mode", cli.mode, Some(output_file.mode)),
-            paste_keys: sources.value("paste_keys", cli.paste_keys, Some(output_file.paste_keys)),
+            mode: sources
+                .value("mode", cli.mode, Some(output_file.mode))
+                .supported(),
+            paste_keys: sources.optional("paste_keys", cli.paste_keys, output_file.paste_keys),
+            paste_modifier_keycodes: output_file.paste_modifier_keycodes,
+            paste_keycode: output_file.paste_keycode,
+            type_mode: output_file.type_mode,
+            paste_via: sources.value("paste_via", cli.paste_via, Some(output_file.paste_via)),
             restore_clipboard: sources.value(
                 "restore_clipboard",
                 cli.restore_clipboard,
@@ -273,11 +631,66 @@ impl Config {
                 cli.pre_paste_delay_ms,
                 Some(output_file.pre_paste_delay_ms),
             ),
-            restore_clipboard_delay_ms: sources.value(
+            restore_clipboard_delay_ms: sources.optional(
                 "restore_clipboard_delay_ms",
                 cli.restore_clipboard_delay_ms,
-                Some(output_file.restore_clipboard_delay_ms),
+                output_file.restore_clipboard_delay_ms,
+            ),
+            restore_immediately_on_paste_failure: sources.value(
+                "restore_immediately_on_paste_failure",
+                cli.restore_immediately_on_paste_failure,
+                Some(output_file.restore_immediately_on_paste_failure),
+            ),
+            require_focused_window: sources.value(
+                "require_focused_window",
+                cli.require_focused_window,
+                Some(output_file.require_focused_window),
+            ),
+            focus_settle_ms: sources.value(
+                "focus_settle_ms",
+                cli.focus_settle_ms,
+                Some(output_file.focus_settle_ms),
+            ),
+            auto_backend_by_compositor: sources.value(
+                "auto_backend_by_compositor",
+                cli.auto_backend_by_compositor,
+                Some(output_file.auto_backend_by_compositor),
+            ),
+            bracketed_paste: sources.value(
+                "bracketed_paste",
+                cli.bracketed_paste,
+                Some(output_file.bracketed_paste),
+            ),
+            release_caps_lock: sources.value(
+                "release_caps_lock",
+                cli.release_caps_lock,
+                Some(output_file.release_caps_lock),
+            ),
+            modifier_release_wait_ms: match sources.value(
+                "modifier_release_wait_ms",
+                cli.modifier_release_wait_ms,
+                Some(output_file.modifier_release_wait_ms),
+            ) {
+                0 if output_file.clear_modifiers_before_type == Some(true) => {
+                    CLEAR_MODIFIERS_WAIT_MS
+                }
+                wait_ms => wait_ms,
+            },
+            clear_modifiers_before_type: None,
+            inject_sentences: sources.value(
+                "inject_sentences",
+                cli.inject_sentences,
+                Some(output_file.inject_sentences),
             ),
+            clipboard_manager_delays: output_file.clipboard_manager_delays,
+            clipboard_blocklist: output_file.clipboard_blocklist,
+            hide_from_clipboard_history: sources.value(
+                "hide_from_clipboard_history",
+                cli.hide_from_clipboard_history,
+                Some(output_file.hide_from_clipboard_history),
+            ),
+            prefix: sources.optional("prefix", cli.prefix, output_file.prefix),
+            suffix: sources.optional("suffix", cli.suffix, output_file.suffix),
         };
 
         let vad = sources.value("vad", cli.vad, file.vad);
@@ -285,6 +698,16 @@ impl Config {
             sources.value("vad_silence_ms", cli.vad_silence_ms, file.vad_silence_ms);
         let vad_threshold = sources.value("vad_threshold", cli.vad_threshold, file.vad_threshold);
         let vad_chunk_ms = sources.value("vad_chunk_ms", cli.vad_chunk_ms, file.vad_chunk_ms);
+        let speech_confirm_ms = sources.value(
+            "speech_confirm_ms",
+            cli.speech_confirm_ms,
+            file.speech_confirm_ms,
+        );
+        let silence_confirm_ms = sources.value(
+            "silence_confirm_ms",
+            cli.silence_confirm_ms,
+            file.silence_confirm_ms,
+        );
         let segment_target_ms = sources.value(
             "segment_target_ms",
             cli.segment_target_ms,
@@ -304,13 +727,141 @@ impl Config {
             sources.value("segment_min_ms", cli.segment_min_ms, file.segment_min_ms);
         let debug_audio = sources.value("debug_audio", cli.debug_audio, file.debug_audio);
         let dump_audio = sources.value("dump_audio", cli.dump_audio, file.dump_audio);
+        let save_last_recording = sources.value(
+            "save_last_recording",
+            cli.save_last_recording,
+            file.save_last_recording,
+        );
+        let last_recording_path = sources
+            .optional(
+                "last_recording_path",
+                cli.last_recording_path,
+                file.last_recording_path,
+            )
+            .unwrap_or_else(daemon::last_recording_path);
+        let save_native_rate = sources.value(
+            "save_native_rate",
+            cli.save_native_rate,
+            file.save_native_rate,
+        );
+        let save_unsent = sources.value("save_unsent", cli.save_unsent, file.save_unsent);
+        let unsent_path = sources
+            .optional("unsent_path", cli.unsent_path, file.unsent_path)
+            .unwrap_or_else(daemon::unsent_file_path);
+        let open_unsent = sources.value("open_unsent", cli.open_unsent, file.open_unsent);
+        let state_file = sources.value("state_file", cli.state_file, file.state_file);
+        let state_file_path = sources
+            .optional("state_file_path", cli.state_file_path, file.state_file_path)
+            .unwrap_or_else(daemon::state_file_path);
         let audio_feedback =
             sources.value("audio_feedback", cli.audio_feedback, file.audio_feedback);
+        let processing_feedback = sources.value(
+            "processing_feedback",
+            cli.processing_feedback,
+            file.processing_feedback,
+        );
+        let feedback_sink =
+            sources.optional("feedback_sink", cli.feedback_sink, file.feedback_sink);
+        let feedback_sounds = file.feedback_sounds.unwrap_or_default();
         let no_speech_timeout_ms = sources.value(
             "no_speech_timeout_ms",
             cli.no_speech_timeout_ms,
             file.no_speech_timeout_ms,
         );
+        let capture_start_timeout_ms = sources.value(
+            "capture_start_timeout_ms",
+            cli.capture_start_timeout_ms,
+            file.capture_start_timeout_ms,
+        );
+        let min_recording_ms = sources.value(
+            "min_recording_ms",
+            cli.min_recording_ms,
+            file.min_recording_ms,
+        );
+        let post_stop_cooldown_ms = sources.value(
+            "post_stop_cooldown_ms",
+            cli.post_stop_cooldown_ms,
+            file.post_stop_cooldown_ms,
+        );
+        let merge_gap_ms = sources.value("merge_gap_ms", cli.merge_gap_ms, file.merge_gap_ms);
+        let repeat_guard_ms =
+            sources.value("repeat_guard_ms", cli.repeat_guard_ms, file.repeat_guard_ms);
+        let min_word_confidence = sources.value(
+            "min_word_confidence",
+            cli.min_word_confidence,
+            file.min_word_confidence,
+        );
+        let cleanup_command =
+            sources.optional("cleanup_command", cli.cleanup_command, file.cleanup_command);
+        let cleanup_timeout_ms = sources.value(
+            "cleanup_timeout_ms",
+            cli.cleanup_timeout_ms,
+            file.cleanup_timeout_ms,
+        );
+        let tts_confirm = sources.value("tts_confirm", cli.tts_confirm, file.tts_confirm);
+        let tts_command = sources.value("tts_command", cli.tts_command, file.tts_command);
+        let session_transcript = sources.value(
+            "session_transcript",
+            cli.session_transcript,
+            file.session_transcript,
+        );
+        let websocket = sources.value("websocket", cli.websocket, file.websocket);
+        let websocket_addr =
+            sources.value("websocket_addr", cli.websocket_addr, file.websocket_addr);
+        let websocket_partials = sources.value(
+            "websocket_partials",
+            cli.websocket_partials,
+            file.websocket_partials,
+        );
+        let prewarm = sources.value("prewarm", cli.prewarm, file.prewarm);
+        let idle_unload_secs = sources.value(
+            "idle_unload_secs",
+            cli.idle_unload_secs,
+            file.idle_unload_secs,
+        );
+        let shutdown_drain_ms = sources.value(
+            "shutdown_drain_ms",
+            cli.shutdown_drain_ms,
+            file.shutdown_drain_ms,
+        );
+        let queue_depth = sources.value("queue_depth", cli.queue_depth, file.queue_depth);
+        let queue_overflow =
+            sources.value("queue_overflow", cli.queue_overflow, file.queue_overflow);
+        let no_context = sources.value("no_context", cli.no_context, file.no_context);
+        let single_segment =
+            sources.value("single_segment", cli.single_segment, file.single_segment);
+        let temperature = sources.value("temperature", cli.temperature, file.temperature);
+        let temperature_inc =
+            sources.value("temperature_inc", cli.temperature_inc, file.temperature_inc);
+        let logprob_thold = sources.value("logprob_thold", cli.logprob_thold, file.logprob_thold);
+        let suppress_nst = sources.value("suppress_nst", cli.suppress_nst, file.suppress_nst);
+        let suppress_regex =
+            sources.optional("suppress_regex", cli.suppress_regex, file.suppress_regex);
+        let transcription_timeout_ms = sources.value(
+            "transcription_timeout_ms",
+            cli.transcription_timeout_ms,
+            file.transcription_timeout_ms,
+        );
+        let trim_pad_ms = sources.optional("trim_pad_ms", cli.trim_pad_ms, file.trim_pad_ms);
+        let trim_silence_pad_ms = sources.optional(
+            "trim_silence_pad_ms",
+            cli.trim_silence_pad_ms,
+            file.trim_silence_pad_ms,
+        );
+        let tail_secs = sources.optional("tail_secs", cli.tail_secs, file.tail_secs);
+        let incremental_inject = sources.value(
+            "incremental_inject",
+            cli.incremental_inject,
+            file.incremental_inject,
+        );
+
+        let casing = sources.value("casing", cli.casing, file.casing);
+
+        let word_filter = file.word_filter.unwrap_or_default();
+        let fillers = file.fillers.unwrap_or_default();
+        let voice_commands = file.voice_commands.unwrap_or_default();
+        let profiles = file.profiles.unwrap_or_default();
+        let code_switching = file.code_switching.unwrap_or_default();
 
         let hotkey_file = file.hotkey.unwrap_or_default();
         let hotkey = HotkeyConfig {
@@ -334,30 +885,170 @@ impl Config {
                 model_path,
                 download_model,
                 language,
+                candidate_languages,
                 device,
+                input_channel,
                 audio_host,
                 sample_rate,
+                input_gain_db,
                 format,
                 output,
                 vad,
                 vad_silence_ms,
                 vad_threshold,
                 vad_chunk_ms,
+                speech_confirm_ms,
+                silence_confirm_ms,
                 segment_target_ms,
                 segment_grace_ms,
                 segment_overlap_ms,
                 segment_min_ms,
                 debug_audio,
                 dump_audio,
+                save_last_recording,
+                last_recording_path,
+                save_native_rate,
+                save_unsent,
+                unsent_path,
+                open_unsent,
+                state_file,
+                state_file_path,
                 audio_feedback,
+                processing_feedback,
+                feedback_sink,
+                feedback_sounds,
                 no_speech_timeout_ms,
+                capture_start_timeout_ms,
+                min_recording_ms,
+                post_stop_cooldown_ms,
+                merge_gap_ms,
+                repeat_guard_ms,
+                min_word_confidence,
+                cleanup_command,
+                cleanup_timeout_ms,
+                tts_confirm,
+                tts_command,
+                session_transcript,
+                websocket,
+                websocket_addr,
+                websocket_partials,
+                prewarm,
+                idle_unload_secs,
+                shutdown_drain_ms,
+                queue_depth,
+                queue_overflow,
+                no_context,
+                single_segment,
+                temperature,
+                temperature_inc,
+                logprob_thold,
+                suppress_nst,
+                suppress_regex,
+                transcription_timeout_ms,
+                trim_pad_ms,
+                trim_silence_pad_ms,
+                tail_secs,
+                incremental_inject,
+                word_filter,
+                fillers,
+                casing,
+                profiles,
+                voice_commands,
+                code_switching,
                 hotkey,
             },
         }
     }
+
+    /// The effective settings as a config file: defaults, the file, and
+    /// command-line overrides merged, every option spelled out.
+    fn to_toml_string(&self) -> Result<String, AppError> {
+        let daemon = &self.daemon;
+        let file = FileConfig {
+            model: None,
+            model_size: Some(self.model_size),
+            model_language: Some(self.model_language),
+            model_path: daemon.model_path.clone(),
+            download_model: Some(daemon.download_model),
+            language: Some(daemon.language.clone()),
+            candidate_languages: Some(daemon.candidate_languages.clone()),
+            device: daemon.device.clone(),
+            input_channel: daemon.input_channel,
+            audio_host: Some(daemon.audio_host),
+            sample_rate: Some(daemon.sample_rate),
+            input_gain_db: Some(daemon.input_gain_db),
+            format: Some(daemon.format),
+            output: Some(daemon.output.clone()),
+            vad: Some(daemon.vad),
+            vad_silence_ms: Some(daemon.vad_silence_ms),
+            vad_threshold: Some(daemon.vad_threshold),
+            vad_chunk_ms: Some(daemon.vad_chunk_ms),
+            speech_confirm_ms: Some(daemon.speech_confirm_ms),
+            silence_confirm_ms: Some(daemon.silence_confirm_ms),
+            segment_target_ms: Some(daemon.segment_target_ms),
+            segment_grace_ms: Some(daemon.segment_grace_ms),
+            segment_overlap_ms: Some(daemon.segment_overlap_ms),
+            segment_min_ms: Some(daemon.segment_min_ms),
+            debug_audio: Some(daemon.debug_audio),
+            dump_audio: Some(daemon.dump_audio),
+            save_last_recording: Some(daemon.save_last_recording),
+            last_recording_path: Some(daemon.last_recording_path.clone()),
+            save_native_rate: Some(daemon.save_native_rate),
+            save_unsent: Some(daemon.save_unsent),
+            unsent_path: Some(daemon.unsent_path.clone()),
+            open_unsent: Some(daemon.open_unsent),
+            state_file: Some(daemon.state_file),
+            state_file_path: Some(daemon.state_file_path.clone()),
+            audio_feedback: Some(daemon.audio_feedback),
+            processing_feedback: Some(daemon.processing_feedback),
+            feedback_sink: daemon.feedback_sink.clone(),
+            feedback_sounds: Some(daemon.feedback_sounds.clone()),
+            no_speech_timeout_ms: Some(daemon.no_speech_timeout_ms),
+            capture_start_timeout_ms: Some(daemon.capture_start_timeout_ms),
+            min_recording_ms: Some(daemon.min_recording_ms),
+            post_stop_cooldown_ms: Some(daemon.post_stop_cooldown_ms),
+            merge_gap_ms: Some(daemon.merge_gap_ms),
+            repeat_guard_ms: Some(daemon.repeat_guard_ms),
+            min_word_confidence: Some(daemon.min_word_confidence),
+            cleanup_command: daemon.cleanup_command.clone(),
+            cleanup_timeout_ms: Some(daemon.cleanup_timeout_ms),
+            tts_confirm: Some(daemon.tts_confirm),
+            tts_command: Some(daemon.tts_command.clone()),
+            session_transcript: Some(daemon.session_transcript),
+            websocket: Some(daemon.websocket),
+            websocket_addr: Some(daemon.websocket_addr.clone()),
+            websocket_partials: Some(daemon.websocket_partials),
+            prewarm: Some(daemon.prewarm),
+            idle_unload_secs: Some(daemon.idle_unload_secs),
+            shutdown_drain_ms: Some(daemon.shutdown_drain_ms),
+            queue_depth: Some(daemon.queue_depth),
+            queue_overflow: Some(daemon.queue_overflow),
+            no_context: Some(daemon.no_context),
+            single_segment: Some(daemon.single_segment),
+            temperature: Some(daemon.temperature),
+            temperature_inc: Some(daemon.temperature_inc),
+            logprob_thold: Some(daemon.logprob_thold),
+            suppress_nst: Some(daemon.suppress_nst),
+            suppress_regex: daemon.suppress_regex.clone(),
+            transcription_timeout_ms: Some(daemon.transcription_timeout_ms),
+            trim_pad_ms: daemon.trim_pad_ms,
+            trim_silence_pad_ms: daemon.trim_silence_pad_ms,
+            tail_secs: daemon.tail_secs,
+            incremental_inject: Some(daemon.incremental_inject),
+            word_filter: Some(daemon.word_filter.clone()),
+            fillers: Some(daemon.fillers.clone()),
+            casing: Some(daemon.casing),
+            profiles: Some(daemon.profiles.clone()),
+            voice_commands: Some(daemon.voice_commands.clone()),
+            code_switching: Some(daemon.code_switching.clone()),
+            hotkey: Some(daemon.hotkey.clone()),
+        };
+        toml::to_string(&file)
+            .map_err(|err| AppError::config(format!("failed to serialize config: {err}")))
+    }
 }
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 #[serde(default)]
 struct FileConfig {
     model: Option<PathBuf>,
@@ -366,27 +1057,132 @@ struct FileConfig {
     model_language: Option<ModelLanguage>,
     download_model: Option<bool>,
     language: Option<String>,
+    candidate_languages: Option<Vec<String>>,
     device: Option<String>,
+    input_channel: Option<usize>,
     audio_host: Option<AudioHost>,
     sample_rate: Option<u32>,
+    #[serde(serialize_with = "serialize_short_f32")]
+    input_gain_db: Option<f32>,
     format: Option<OutputFormat>,
     output: Option<OutputConfig>,
     vad: Option<VadMode>,
     vad_silence_ms: Option<u64>,
+    #[serde(serialize_with = "serialize_short_f32")]
     vad_threshold: Option<f32>,
     vad_chunk_ms: Option<u64>,
+    speech_confirm_ms: Option<u64>,
+    silence_confirm_ms: Option<u64>,
     segment_target_ms: Option<u64>,
     segment_grace_ms: Option<u64>,
     segment_overlap_ms: Option<u64>,
     segment_min_ms: Option<u64>,
     debug_audio: Option<bool>,
     dump_audio: Option<bool>,
+    save_last_recording: Option<bool>,
+    last_recording_path: Option<PathBuf>,
+    save_native_rate: Option<bool>,
+    save_unsent: Option<bool>,
+    unsent_path: Option<PathBuf>,
+    open_unsent: Option<bool>,
+    state_file: Option<bool>,
+    state_file_path: Option<PathBuf>,
     audio_feedback: Option<bool>,
+    processing_feedback: Option<bool>,
+    feedback_sink: Option<String>,
+    feedback_sounds: Option<FeedbackSounds>,
     no_speech_timeout_ms: Option<u64>,
+    capture_start_timeout_ms: Option<u64>,
+    min_recording_ms: Option<u64>,
+    post_stop_cooldown_ms: Option<u64>,
+    merge_gap_ms: Option<u64>,
+    repeat_guard_ms: Option<u64>,
+    #[serde(serialize_with = "serialize_short_f32")]
+    min_word_confidence: Option<f32>,
+    cleanup_command: Option<String>,
+    cleanup_timeout_ms: Option<u64>,
+    tts_confirm: Option<bool>,
+    tts_command: Option<String>,
+    session_transcript: Option<bool>,
+    websocket: Option<bool>,
+    websocket_addr: Option<String>,
+    websocket_partials: Option<bool>,
+    prewarm: Option<bool>,
+    idle_unload_secs: Option<u64>,
+    shutdown_drain_ms: Option<u64>,
+    queue_depth: Option<usize>,
+    queue_overflow: Option<QueueOverflow>,
+    no_context: Option<bool>,
+    single_segment: Option<bool>,
+    #[serde(serialize_with = "serialize_short_f32")]
+    temperature: Option<f32>,
+    #[serde(serialize_with = "serialize_short_f32")]
+    temperature_inc: Option<f32>,
+    #[serde(serialize_with = "serialize_short_f32")]
+    logprob_thold: Option<f32>,
+    suppress_nst: Option<bool>,
+    suppress_regex: Option<String>,
+    transcription_timeout_ms: Option<u64>,
+    trim_pad_ms: Option<u64>,
+    trim_silence_pad_ms: Option<u64>,
+    tail_secs: Option<u32>,
+    incremental_inject: Option<bool>,
+    word_filter: Option<WordFilter>,
+    fillers: Option<FillerFilter>,
+    casing: Option<Casing>,
+    profiles: Option<Vec<Profile>>,
+    voice_commands: Option<VoiceCommands>,
+    code_switching: Option<CodeSwitching>,
     hotkey: Option<HotkeyConfig>,
 }
 
+/// Writes an `f32` in its shortest decimal form, so a dumped `0.01` does not
+/// come out as `0.009999999776482582`.
+fn serialize_short_f32<S: serde::Serializer>(
+    value: &Option<f32>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => {
+            let value = value.to_string().parse().unwrap_or(f64::from(*value));
+            serializer.serialize_some(&value)
+        }
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Prints `log` records to stderr with the `warn:` style prefixes the rest
+/// of the CLI uses.
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            let level = record.level().as_str().to_ascii_lowercase();
+            eprintln!("{level}: {}", record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Shows warnings by default; `RUST_LOG` (e.g. `debug`) changes the level.
+fn init_logging() {
+    let level = env::var("RUST_LOG")
+        .ok()
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(log::LevelFilter::Warn);
+    if log::set_logger(&StderrLogger).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
 fn main() {
+    init_logging();
     let matches = Cli::command().get_matches();
     let cli = Cli::from_arg_matches(&matches).expect("Failed to parse CLI arguments");
     let mode = resolve_cli_mode(&cli);
@@ -426,7 +1222,98 @@ fn main() {
             }
             return;
         }
-        CliMode::RunDaemon | CliMode::ListDevices | CliMode::TestAudio => {}
+        CliMode::SetGain(gain_db) => {
+            match daemon::send_set_gain_command(gain_db) {
+                Ok(response) => println!(
+                    "{}",
+                    response.message.as_deref().unwrap_or("input gain updated")
+                ),
+                Err(err) => {
+                    eprintln!("error: {err}");
+                    process::exit(err.exit_code());
+                }
+            }
+            return;
+        }
+        CliMode::DumpTranscript(path) => {
+            // The daemon runs elsewhere, so relative paths resolve here.
+            let target = match path {
+                Some(path) => DumpTarget::File(
+                    env::current_dir()
+                        .map(|dir| dir.join(&path))
+                        .unwrap_or(path),
+                ),
+                None => DumpTarget::Clipboard,
+            };
+            match daemon::send_dump_transcript_command(&target) {
+                Ok(response) => println!(
+                    "{}",
+                    response
+                        .message
+                        .as_deref()
+                        .unwrap_or("session transcript saved")
+                ),
+                Err(err) => {
+                    eprintln!("error: {err}");
+                    process::exit(err.exit_code());
+                }
+            }
+            return;
+        }
+        CliMode::Repeat => {
+            match daemon::send_repeat_command() {
+                Ok(response) => println!(
+                    "{}",
+                    response.message.as_deref().unwrap_or("transcript repeated")
+                ),
+                Err(err) => {
+                    eprintln!("error: {err}");
+                    process::exit(err.exit_code());
+                }
+            }
+            return;
+        }
+        CliMode::Reload => {
+            match daemon::send_reload_command() {
+                Ok(response) => println!(
+                    "{}",
+                    response
+                        .message
+                        .as_deref()
+                        .unwrap_or("configuration reloaded")
+                ),
+                Err(err) => {
+                    eprintln!("error: {err}");
+                    process::exit(err.exit_code());
+                }
+            }
+            return;
+        }
+        CliMode::GetBackend | CliMode::SetBackend(_) => {
+            let response = match mode {
+                CliMode::SetBackend(backend) => daemon::send_set_backend_command(backend),
+                _ => daemon::send_get_backend_command(),
+            };
+            match response {
+                Ok(response) => println!(
+                    "backend={}",
+                    response.backend.as_deref().unwrap_or("unknown")
+                ),
+                Err(err) => {
+                    eprintln!("error: {err}");
+                    process::exit(err.exit_code());
+                }
+            }
+            return;
+        }
+        CliMode::RunDaemon
+        | CliMode::ListDevices
+        | CliMode::TestAudio
+        | CliMode::Doctor
+        | CliMode::DumpConfig
+        | CliMode::TranscribeStdin(_)
+        | CliMode::Subtitles { .. }
+        | CliMode::Batch { .. } => {}
     }
     let file_config = match load_config_file() {
         Ok(config) => config,
@@ -435,9 +1322,41 @@ fn main() {
             process::exit(err.exit_code());
         }
     };
+    let stdin_rate = cli.stdin_rate;
     let mut config = Config::from_sources(cli, &matches, file_config);
+    // Still single-threaded here, so the environment can change safely.
+    audio::use_monitor_source(config.daemon.device.as_deref());
+
+    if mode == CliMode::DumpConfig {
+        match config.to_toml_string() {
+            Ok(toml) => print!("{toml}"),
+            Err(err) => {
+                eprintln!("error: {err}");
+                process::exit(err.exit_code());
+            }
+        }
+        return;
+    }
+
+    if mode == CliMode::Doctor {
+        let spec = ModelSpec::new(config.model_size, config.model_language);
+        let report = diagnostics::run(&config.daemon, &spec);
+        print!("{report}");
+        if !report.passed() {
+            let err = AppError::runtime(format!("doctor found {} problem(s)", report.failures()));
+            eprintln!("error: {err}");
+            process::exit(err.exit_code());
+        }
+        return;
+    }
 
-    let prepared_model = if mode == CliMode::RunDaemon {
+    let prepared_model = if matches!(
+        mode,
+        CliMode::RunDaemon
+            | CliMode::TranscribeStdin(_)
+            | CliMode::Subtitles { .. }
+            | CliMode::Batch { .. }
+    ) {
         let spec = ModelSpec::new(config.model_size, config.model_language);
         match sv::model::prepare_model(
             config.daemon.model_path.as_deref(),
@@ -454,6 +1373,44 @@ fn main() {
         None
     };
 
+    if let (CliMode::Subtitles { input, format }, Some(prepared)) = (&mode, &prepared_model) {
+        let opts = SubtitleOptions {
+            model_path: prepared.path.clone(),
+            language: config.daemon.language.clone(),
+            decode: decode_options(&config.daemon),
+        };
+        match subtitles::transcribe_to_subtitles(input, &opts, *format) {
+            Ok(captions) => print!("{captions}"),
+            Err(err) => {
+                eprintln!("error: {err}");
+                process::exit(err.exit_code());
+            }
+        }
+        return;
+    }
+
+    if let (
+        CliMode::Batch {
+            dir,
+            pattern,
+            sidecar,
+        },
+        Some(prepared),
+    ) = (&mode, &prepared_model)
+    {
+        let opts = BatchOptions {
+            model_path: prepared.path.clone(),
+            language: config.daemon.language.clone(),
+            decode: decode_options(&config.daemon),
+            sidecar: *sidecar,
+        };
+        if let Err(err) = run_batch(dir, pattern, &opts) {
+            eprintln!("error: {err}");
+            process::exit(err.exit_code());
+        }
+        return;
+    }
+
     println!("SoundVibes sv {}", env!("CARGO_PKG_VERSION"));
     if let Some(prepared) = &prepared_model {
         if prepared.downloaded {
@@ -463,6 +1420,12 @@ fn main() {
     }
     println!("Language: {}", config.daemon.language);
     println!("Sample rate: {} Hz", config.daemon.sample_rate);
+    if config.daemon.sample_rate != WHISPER_SAMPLE_RATE {
+        eprintln!(
+            "warn: sample_rate {} Hz differs from the {WHISPER_SAMPLE_RATE} Hz whisper expects; transcripts will be unreliable",
+            config.daemon.sample_rate
+        );
+    }
     println!("Format: {:?}", config.daemon.format);
     println!("Mode: {:?}", config.daemon.output.mode);
     println!("VAD: {:?}", config.daemon.vad);
@@ -474,6 +1437,12 @@ fn main() {
     println!("Segment overlap: {} ms", config.daemon.segment_overlap_ms);
     println!("Segment minimum: {} ms", config.daemon.segment_min_ms);
     println!("Dump audio: {}", config.daemon.dump_audio);
+    if config.daemon.save_last_recording {
+        println!(
+            "Last recording: {}",
+            config.daemon.last_recording_path.display()
+        );
+    }
     println!("Audio host: {:?}", config.daemon.audio_host);
     if let Some(device) = &config.daemon.device {
         println!("Device: {device}");
@@ -483,9 +1452,20 @@ fn main() {
         run_list_devices(&config.daemon)
     } else if mode == CliMode::TestAudio {
         run_test_audio(&config.daemon)
+    } else if let CliMode::TranscribeStdin(format) = mode {
+        config.daemon.model_path = prepared_model.map(|prepared| prepared.path);
+        run_transcribe_stdin(&config.daemon, format, stdin_rate)
     } else {
         config.daemon.model_path = prepared_model.map(|prepared| prepared.path);
-        let deps = daemon::DaemonDeps::default();
+        let deps = daemon::DaemonDeps {
+            transcriber_factory: Box::new(whisper_factory(&config.daemon)),
+            feedback: Box::new(
+                SoundFeedback::new(config.daemon.feedback_sink.clone())
+                    .with_sounds(config.daemon.feedback_sounds.clone()),
+            ),
+            config_loader: Some(Box::new(move || reload_daemon_config(&matches))),
+            ..daemon::DaemonDeps::default()
+        };
         let mut output = daemon::StdoutOutput;
         daemon::run_daemon(&config.daemon, &deps, &mut output)
     };
@@ -519,6 +1499,27 @@ fn load_config_file() -> Result<FileConfig, AppError> {
     })
 }
 
+/// Builds the daemon config again from the config file for `reload`.
+/// Command line arguments still win over the file, as at startup.
+fn reload_daemon_config(matches: &clap::ArgMatches) -> Result<daemon::DaemonConfig, AppError> {
+    let cli = Cli::from_arg_matches(matches).map_err(|err| AppError::config(err.to_string()))?;
+    let config = Config::from_sources(cli, matches, load_config_file()?);
+    let spec = ModelSpec::new(config.model_size, config.model_language);
+    let path = sv::model::resolve_model_path(config.daemon.model_path.as_deref(), &spec)?;
+    // A download would stall the daemon loop, which runs the reload, for
+    // minutes, so only a model already on disk is taken.
+    if !path.exists() {
+        return Err(AppError::config(format!(
+            "model file not found at {}; reload does not download models, so restart the daemon to fetch it",
+            path.display()
+        )));
+    }
+    let prepared = sv::model::prepare_model(Some(&path), &spec, false)?;
+    let mut daemon = config.daemon;
+    daemon.model_path = Some(prepared.path);
+    Ok(daemon)
+}
+
 fn config_path() -> Option<PathBuf> {
     let config_home = env::var_os("XDG_CONFIG_HOME")
         .map(PathBuf::from)
@@ -532,19 +1533,94 @@ fn run_list_devices(config: &daemon::DaemonConfig) -> Result<(), AppError> {
     let devices = audio::list_input_devices(&host).map_err(|err| AppError::audio(err.message))?;
     println!("Input devices:");
     for name in devices {
-        println!("  - {name}");
+        println!("  - {}", audio::describe_device(&name));
     }
     Ok(())
 }
 
-fn run_test_audio(config: &daemon::DaemonConfig) -> Result<(), AppError> {
-    use std::io::Write;
+fn decode_options(config: &daemon::DaemonConfig) -> DecodeOptions {
+    DecodeOptions {
+        no_context: config.no_context,
+        single_segment: config.single_segment,
+        temperature: config.temperature,
+        temperature_inc: config.temperature_inc,
+        logprob_thold: config.logprob_thold,
+        suppress_nst: config.suppress_nst,
+        suppress_regex: config.suppress_regex.clone(),
+        timeout_ms: config.transcription_timeout_ms,
+    }
+}
+
+/// Prints one `path: transcript` line per file and reports failed files on
+/// stderr; the first failure decides the exit status once all files ran.
+fn run_batch(dir: &Path, pattern: &str, opts: &BatchOptions) -> Result<(), AppError> {
+    let results = batch::transcribe_dir(dir, pattern, opts)?;
+    if results.is_empty() {
+        eprintln!("warn: no files in {} match {pattern}", dir.display());
+    }
+    let mut first_error = None;
+    for (path, result) in results {
+        match result {
+            Ok(text) => println!("{}: {text}", path.display()),
+            Err(err) => {
+                eprintln!("error: {}: {err}", path.display());
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+fn whisper_factory(config: &daemon::DaemonConfig) -> daemon::WhisperFactory {
+    daemon::WhisperFactory::new(decode_options(config))
+        .with_code_switching(config.code_switching.clone(), config.vad_threshold)
+        .with_candidate_languages(config.candidate_languages.clone())
+}
+
+fn run_transcribe_stdin(
+    config: &daemon::DaemonConfig,
+    format: PcmFormat,
+    input_rate: Option<u32>,
+) -> Result<(), AppError> {
+    use std::io::Read;
+
+    let mut bytes = Vec::new();
+    std::io::stdin()
+        .read_to_end(&mut bytes)
+        .map_err(|err| AppError::runtime(format!("failed to read stdin: {err}")))?;
+    let samples = audio::decode_pcm(&bytes, format).map_err(|err| AppError::audio(err.message))?;
+    // Trimming, VAD, and whisper all run at whisper's rate whatever the input
+    // rate; durations are reported in milliseconds, so they still match the
+    // original audio.
+    let input_rate = input_rate.unwrap_or(config.sample_rate);
+    let samples = audio::resample(&samples, input_rate, WHISPER_SAMPLE_RATE);
+    let config = daemon::DaemonConfig {
+        sample_rate: WHISPER_SAMPLE_RATE,
+        ..config.clone()
+    };
+    let deps = daemon::DaemonDeps {
+        transcriber_factory: Box::new(whisper_factory(&config)),
+        feedback: Box::new(NoopFeedback),
+        ..daemon::DaemonDeps::default()
+    };
+    let mut output = daemon::StdoutOutput;
+    daemon::transcribe_samples(&config, &deps, &samples, &mut output)
+}
+
+fn run_test_audio(config: &daemon::DaemonConfig) -> Result<(), AppError> {
+    use std::io::Write;
 
     let host = daemon::select_audio_host(config.audio_host)?;
     audio::configure_alsa_logging(config.debug_audio);
 
-    let mut capture = audio::start_capture(&host, config.device.as_deref(), config.sample_rate)
-        .map_err(|err| AppError::audio(err.message))?;
+    let mut capture = audio::start_capture(
+        &host,
+        config.device.as_deref(),
+        config.sample_rate,
+        config.input_channel,
+        false,
+    )
+    .map_err(|err| AppError::audio(err.message))?;
 
     let confirm_samples = (0.1 * config.sample_rate as f32) as usize; // 100ms
     let mut speech_detector =
@@ -565,6 +1641,7 @@ fn run_test_audio(config: &daemon::DaemonConfig) -> Result<(), AppError> {
 
     loop {
         audio::drain_samples(&mut capture, &mut buffer);
+        audio::apply_gain(&mut buffer, config.input_gain_db);
         if buffer.is_empty() {
             std::thread::sleep(std::time::Duration::from_millis(20));
             continue;
@@ -601,6 +1678,7 @@ mod tests {
     use std::sync::{Mutex, OnceLock};
     use std::thread;
     use std::time::Duration;
+    use sv::postprocess::FilterMode;
 
     static TEST_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
 
@@ -643,6 +1721,31 @@ mod tests {
         dir
     }
 
+    #[test]
+    fn reload_refuses_to_download_a_missing_model() {
+        let _lock = lock_tests();
+        let config_home = temp_runtime_dir();
+        let _guard = EnvGuard::set("XDG_CONFIG_HOME", &config_home);
+        let model = config_home.join("missing.bin");
+        let matches = Cli::command()
+            .try_get_matches_from([
+                "sv",
+                "daemon",
+                "start",
+                "--model",
+                model.to_str().expect("utf-8 temp path"),
+            ])
+            .expect("failed to parse cli");
+
+        let err = reload_daemon_config(&matches).expect_err("the model is missing");
+
+        assert!(
+            err.to_string().contains("reload does not download"),
+            "{err}"
+        );
+        assert!(!model.exists());
+    }
+
     #[test]
     fn record_start_command_reaches_daemon_socket() -> Result<(), AppError> {
         let _lock = lock_tests();
@@ -664,6 +1767,7 @@ mod tests {
                     ok: true,
                     state: Some("recording".to_string()),
                     language: Some("en".to_string()),
+                    backend: None,
                     message: None,
                 });
             }
@@ -750,6 +1854,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parses_daemon_backend_subcommands() {
+        let cli =
+            Cli::try_parse_from(["sv", "daemon", "get-backend"]).expect("failed to parse cli");
+        assert_eq!(resolve_cli_mode(&cli), CliMode::GetBackend);
+
+        let cli = Cli::try_parse_from(["sv", "daemon", "set-backend", "auto"])
+            .expect("failed to parse cli");
+        assert_eq!(
+            resolve_cli_mode(&cli),
+            CliMode::SetBackend(OutputBackend::Auto)
+        );
+
+        let cli = Cli::try_parse_from(["sv", "daemon", "set-backend", "type"])
+            .expect("failed to parse cli");
+        assert_eq!(
+            resolve_cli_mode(&cli),
+            CliMode::SetBackend(OutputBackend::Mode(OutputMode::Type))
+        );
+        assert!(Cli::try_parse_from(["sv", "daemon", "set-backend", "x11"]).is_err());
+
+        let cli =
+            Cli::try_parse_from(["sv", "daemon", "set-gain", "-6"]).expect("failed to parse cli");
+        assert_eq!(resolve_cli_mode(&cli), CliMode::SetGain(-6.0));
+
+        let cli = Cli::try_parse_from(["sv", "daemon", "dump-transcript", "notes.txt"])
+            .expect("failed to parse cli");
+        assert_eq!(
+            resolve_cli_mode(&cli),
+            CliMode::DumpTranscript(Some(PathBuf::from("notes.txt")))
+        );
+        let cli =
+            Cli::try_parse_from(["sv", "daemon", "dump-transcript"]).expect("failed to parse cli");
+        assert_eq!(resolve_cli_mode(&cli), CliMode::DumpTranscript(None));
+
+        let cli = Cli::try_parse_from(["sv", "daemon", "repeat"]).expect("failed to parse cli");
+        assert_eq!(resolve_cli_mode(&cli), CliMode::Repeat);
+
+        let cli = Cli::try_parse_from(["sv", "daemon", "reload"]).expect("failed to parse cli");
+        assert_eq!(resolve_cli_mode(&cli), CliMode::Reload);
+    }
+
     #[test]
     fn parses_large_v3_turbo_model_size() {
         let cli = Cli::try_parse_from([
@@ -784,6 +1930,55 @@ mod tests {
         assert_eq!(config.model_language, ModelLanguage::Auto);
     }
 
+    #[test]
+    fn parses_doctor_subcommand() {
+        let cli = Cli::try_parse_from(["sv", "doctor"]).expect("failed to parse cli");
+        assert_eq!(resolve_cli_mode(&cli), CliMode::Doctor);
+    }
+
+    #[test]
+    fn parses_stdin_pcm_with_default_and_explicit_format() {
+        let cli = Cli::try_parse_from(["sv", "--stdin-pcm"]).expect("failed to parse cli");
+        assert_eq!(
+            resolve_cli_mode(&cli),
+            CliMode::TranscribeStdin(PcmFormat::F32le)
+        );
+
+        let cli = Cli::try_parse_from(["sv", "--stdin-pcm=s16le"]).expect("failed to parse cli");
+        assert_eq!(
+            resolve_cli_mode(&cli),
+            CliMode::TranscribeStdin(PcmFormat::S16le)
+        );
+
+        let cli = Cli::try_parse_from(["sv", "--stdin-pcm", "--stdin-rate", "8000"])
+            .expect("failed to parse cli");
+        assert_eq!(cli.stdin_rate, Some(8_000));
+        assert!(Cli::try_parse_from(["sv", "--stdin-pcm", "--stdin-rate", "0"]).is_err());
+    }
+
+    #[test]
+    fn parses_subtitles_subcommand() {
+        let cli = Cli::try_parse_from(["sv", "subtitles", "talk.wav", "--subtitle-format", "vtt"])
+            .expect("failed to parse cli");
+        assert_eq!(
+            resolve_cli_mode(&cli),
+            CliMode::Subtitles {
+                input: PathBuf::from("talk.wav"),
+                format: SubtitleFormat::Vtt,
+            }
+        );
+
+        let cli =
+            Cli::try_parse_from(["sv", "subtitles", "talk.wav"]).expect("failed to parse cli");
+        assert_eq!(
+            resolve_cli_mode(&cli),
+            CliMode::Subtitles {
+                input: PathBuf::from("talk.wav"),
+                format: SubtitleFormat::Srt,
+            }
+        );
+    }
+
     #[test]
     fn parses_transcript_path_subcommand() {
         let cli = Cli::try_parse_from(["sv", "transcript-path"]).expect("failed to parse cli");
@@ -801,13 +1996,53 @@ mod tests {
 
         assert_eq!(config.daemon.output.mode, OutputMode::Paste);
         assert!(config.daemon.output.restore_clipboard);
-        assert_eq!(config.daemon.output.paste_keys, "ctrl+v");
+        assert!(config.daemon.output.hide_from_clipboard_history);
+        assert!(config.daemon.output.require_focused_window);
+        assert!(config.daemon.output.auto_backend_by_compositor);
+        assert!(!config.daemon.output.bracketed_paste);
+        assert_eq!(config.daemon.output.paste_keys, None);
         assert_eq!(config.daemon.output.pre_paste_delay_ms, 100);
-        assert_eq!(config.daemon.output.restore_clipboard_delay_ms, 250);
+        assert_eq!(config.daemon.output.restore_clipboard_delay_ms, None);
         assert!(config.daemon.hotkey.enabled);
         assert_eq!(config.daemon.hotkey.key, None);
     }
 
+    #[test]
+    fn dumped_config_merges_overrides_and_loads_back() {
+        let file: FileConfig = toml::from_str(
+            r#"
+            vad_threshold = 0.02
+
+            [voice_commands]
+            undo = ["scratch that"]
+            "#,
+        )
+        .expect("config should parse");
+        let command = Cli::command();
+        let matches = command
+            .try_get_matches_from(["sv", "--temperature", "0.3", "config", "--dump"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        assert_eq!(resolve_cli_mode(&cli), CliMode::DumpConfig);
+        let dump = Config::from_sources(cli, &matches, file)
+            .to_toml_string()
+            .expect("config should serialize");
+
+        assert!(dump.contains("\nvad_threshold = 0.02\n"));
+        assert!(dump.contains("\ntemperature = 0.3\n"));
+        assert!(dump.contains("undo = [\"scratch that\"]"));
+
+        let reloaded: FileConfig = toml::from_str(&dump).expect("dump should parse");
+        let matches = Cli::command()
+            .try_get_matches_from(["sv", "config", "--dump"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let again = Config::from_sources(cli, &matches, reloaded)
+            .to_toml_string()
+            .expect("config should serialize");
+        assert_eq!(again, dump);
+    }
+
     #[test]
     fn reads_hotkey_config_from_hotkey_table() {
         let file: FileConfig = toml::from_str(
@@ -829,6 +2064,348 @@ mod tests {
         assert_eq!(config.daemon.hotkey.key.as_deref(), Some("RIGHTCTRL"));
     }
 
+    #[test]
+    fn decode_options_default_on_and_cli_overrides_file() {
+        let file: FileConfig = toml::from_str(
+            r#"
+            single_segment = false
+            "#,
+        )
+        .expect("config should parse");
+        let command = Cli::command();
+        let matches = command
+            .try_get_matches_from(["sv", "--no-context", "false", "daemon", "start"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, file);
+
+        assert!(!config.daemon.no_context);
+        assert!(!config.daemon.single_segment);
+
+        let matches = Cli::command()
+            .try_get_matches_from(["sv", "daemon", "start"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, FileConfig::default());
+
+        assert!(config.daemon.no_context);
+        assert!(config.daemon.single_segment);
+    }
+
+    #[test]
+    fn token_suppression_reaches_decode_options() {
+        let file: FileConfig = toml::from_str(
+            r#"
+            suppress_nst = true
+            suppress_regex = "[\\[\\]()]"
+            "#,
+        )
+        .expect("config should parse");
+        let matches = Cli::command()
+            .try_get_matches_from(["sv", "--suppress-regex", "♪", "daemon", "start"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, file);
+        let decode = decode_options(&config.daemon);
+
+        assert!(decode.suppress_nst);
+        assert_eq!(decode.suppress_regex.as_deref(), Some("♪"));
+    }
+
+    #[test]
+    fn transcription_timeout_reaches_decode_options() {
+        let file: FileConfig =
+            toml::from_str("transcription_timeout_ms = 8000").expect("config should parse");
+        let matches = Cli::command()
+            .try_get_matches_from(["sv", "daemon", "start"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, file);
+
+        assert_eq!(decode_options(&config.daemon).timeout_ms, 8000);
+        assert_eq!(DecodeOptions::default().timeout_ms, 0);
+    }
+
+    #[test]
+    fn temperature_fallback_defaults_to_whisper_and_cli_overrides_file() {
+        let file: FileConfig = toml::from_str(
+            r#"
+            temperature = 0.4
+            temperature_inc = 0.0
+            "#,
+        )
+        .expect("config should parse");
+        let matches = Cli::command()
+            .try_get_matches_from(["sv", "--logprob-thold", "-0.5", "--temperature", "0.1"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, file);
+
+        assert_eq!(config.daemon.temperature, 0.1);
+        assert_eq!(config.daemon.temperature_inc, 0.0);
+        assert_eq!(config.daemon.logprob_thold, -0.5);
+
+        let matches = Cli::command()
+            .try_get_matches_from(["sv"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, FileConfig::default());
+        let decode = decode_options(&config.daemon);
+
+        assert_eq!(decode, DecodeOptions::default());
+    }
+
+    #[test]
+    fn audio_feedback_defaults_off_and_file_can_enable() {
+        let file = FileConfig {
+            audio_feedback: Some(true),
+            ..FileConfig::default()
+        };
+        let command = Cli::command();
+        let matches = command
+            .try_get_matches_from(["sv", "daemon", "start"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, file);
+        assert!(config.daemon.audio_feedback);
+
+        let command = Cli::command();
+        let matches = command
+            .try_get_matches_from(["sv", "daemon", "start"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, FileConfig::default());
+        assert!(!config.daemon.audio_feedback);
+    }
+
+    #[test]
+    fn reads_feedback_sounds_table_with_names_and_paths() {
+        let file: FileConfig = toml::from_str(
+            r#"
+            [feedback_sounds]
+            start = "message-new-instant"
+            error = "/home/me/sounds/oops.wav"
+            "#,
+        )
+        .expect("config should parse");
+        let command = Cli::command();
+        let matches = command
+            .try_get_matches_from(["sv", "daemon", "start"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, file);
+
+        let sounds = &config.daemon.feedback_sounds;
+        assert_eq!(sounds.start, "message-new-instant");
+        assert_eq!(sounds.error, "/home/me/sounds/oops.wav");
+        assert_eq!(sounds.stop, FeedbackSounds::default().stop);
+    }
+
+    #[test]
+    fn reads_word_filter_table() {
+        let file: FileConfig = toml::from_str(
+            r#"
+            [word_filter]
+            words = ["darn"]
+            mode = "remove"
+            "#,
+        )
+        .expect("config should parse");
+        let command = Cli::command();
+        let matches = command
+            .try_get_matches_from(["sv", "daemon", "start"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, file);
+
+        assert_eq!(config.daemon.word_filter.words, ["darn"]);
+        assert_eq!(config.daemon.word_filter.mode, FilterMode::Remove);
+    }
+
+    #[test]
+    fn filler_removal_is_off_unless_enabled_in_fillers_table() {
+        let matches = Cli::command()
+            .try_get_matches_from(["sv"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, FileConfig::default());
+        assert!(!config.daemon.fillers.enabled);
+
+        let file: FileConfig = toml::from_str(
+            r#"
+            [fillers]
+            enabled = true
+            words = ["um", "like"]
+            "#,
+        )
+        .expect("config should parse");
+        let matches = Cli::command()
+            .try_get_matches_from(["sv"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, file);
+
+        assert!(config.daemon.fillers.enabled);
+        assert_eq!(config.daemon.fillers.words_for("en"), ["um", "like"]);
+    }
+
+    #[test]
+    fn reads_profiles_in_order() {
+        let file: FileConfig = toml::from_str(
+            r#"
+            [[profiles]]
+            match_class = "kitty"
+            output = { paste_keys = "ctrl+shift+v", suffix = "" }
+
+            [[profiles]]
+            match_class = "org.telegram.desktop"
+            output = { mode = "type" }
+            postprocess = { casing = "sentence-start-only", fillers = true }
+            "#,
+        )
+        .expect("config should parse");
+        let matches = Cli::command()
+            .try_get_matches_from(["sv"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, file);
+
+        let profiles = &config.daemon.profiles;
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].match_class, "kitty");
+        assert_eq!(profiles[0].output.suffix.as_deref(), Some(""));
+        assert_eq!(profiles[1].output.mode, Some(OutputMode::Type));
+        assert_eq!(
+            profiles[1].postprocess.casing,
+            Some(Casing::SentenceStartOnly)
+        );
+        assert_eq!(profiles[1].postprocess.fillers, Some(true));
+    }
+
+    #[test]
+    fn reads_voice_commands_table_with_fuzzy_off_by_default() {
+        let file: FileConfig = toml::from_str(
+            r#"
+            [voice_commands.phrases]
+            "new line" = "\n"
+            "#,
+        )
+        .expect("config should parse");
+        let command = Cli::command();
+        let matches = command
+            .try_get_matches_from(["sv", "daemon", "start"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, file);
+
+        let commands = &config.daemon.voice_commands;
+        assert_eq!(
+            commands.phrases.get("new line").map(String::as_str),
+            Some("\n")
+        );
+        assert!(!commands.fuzzy);
+        assert_eq!(commands.max_distance, 2);
+    }
+
+    #[test]
+    fn casing_comes_from_file_unless_cli_overrides() {
+        let file: FileConfig = toml::from_str(
+            r#"
+            casing = "sentence-start-only"
+            "#,
+        )
+        .expect("config should parse");
+        let command = Cli::command();
+        let matches = command
+            .try_get_matches_from(["sv", "daemon", "start"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, file);
+        assert_eq!(config.daemon.casing, Casing::SentenceStartOnly);
+
+        let matches = Cli::command()
+            .try_get_matches_from(["sv", "--casing", "whisper", "daemon", "start"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, FileConfig::default());
+        assert_eq!(config.daemon.casing, Casing::Whisper);
+    }
+
+    #[test]
+    fn reads_code_switching_table() {
+        let file: FileConfig = toml::from_str(
+            r#"
+            [code_switching]
+            enabled = true
+            languages = ["de", "en"]
+            "#,
+        )
+        .expect("config should parse");
+        let command = Cli::command();
+        let matches = command
+            .try_get_matches_from(["sv", "daemon", "start"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, file);
+
+        let code_switching = &config.daemon.code_switching;
+        assert!(code_switching.enabled);
+        assert_eq!(code_switching.languages, ["de", "en"]);
+        assert_eq!(code_switching.min_pause_ms, 300);
+    }
+
+    #[test]
+    fn cli_candidate_languages_override_file() {
+        let file = || -> FileConfig {
+            toml::from_str(
+                r#"
+                language = "auto"
+                candidate_languages = ["de", "en"]
+                "#,
+            )
+            .expect("config should parse")
+        };
+        let matches = Cli::command()
+            .try_get_matches_from(["sv", "daemon", "start"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, file());
+        assert_eq!(config.daemon.candidate_languages, ["de", "en"]);
+
+        let matches = Cli::command()
+            .try_get_matches_from(["sv", "--candidate-languages", "fr,it", "daemon", "start"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, file());
+        assert_eq!(config.daemon.candidate_languages, ["fr", "it"]);
+    }
+
+    #[test]
+    fn reads_human_type_mode_from_output_table() {
+        let file: FileConfig = toml::from_str(
+            r#"
+            [output]
+            mode = "ydotool"
+            type_mode = { style = "human", mean_delay_ms = 90, jitter_ms = 35 }
+            "#,
+        )
+        .expect("config should parse");
+        let command = Cli::command();
+        let matches = command
+            .try_get_matches_from(["sv", "daemon", "start"])
+            .expect("failed to parse cli");
+        let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+        let config = Config::from_sources(cli, &matches, file);
+
+        assert_eq!(
+            config.daemon.output.type_mode,
+            sv::output::TypeMode::Human {
+                mean_delay_ms: 90,
+                jitter_ms: 35,
+            }
+        );
+    }
+
     #[test]
     fn reads_paste_output_config_from_output_table() {
         let file: FileConfig = toml::from_str(
@@ -836,9 +2413,24 @@ mod tests {
             [output]
             mode = "clipboard"
             paste_keys = "ctrl+shift+v"
+            paste_via = "primary-selection"
             restore_clipboard = false
             pre_paste_delay_ms = 150
             restore_clipboard_delay_ms = 400
+            require_focused_window = false
+            focus_settle_ms = 120
+            auto_backend_by_compositor = false
+            bracketed_paste = true
+            release_caps_lock = true
+            modifier_release_wait_ms = 500
+            inject_sentences = true
+            restore_immediately_on_paste_failure = true
+            hide_from_clipboard_history = false
+            clipboard_blocklist = ["remmina", "virt-manager"]
+            suffix = " "
+
+            [output.clipboard_manager_delays]
+            cliphist = 60
             "#,
         )
         .expect("config should parse");
@@ -850,10 +2442,66 @@ mod tests {
         let config = Config::from_sources(cli, &matches, file);
 
         assert_eq!(config.daemon.output.mode, OutputMode::Clipboard);
-        assert_eq!(config.daemon.output.paste_keys, "ctrl+shift+v");
+        assert_eq!(
+            config.daemon.output.paste_keys.as_deref(),
+            Some("ctrl+shift+v")
+        );
+        assert_eq!(config.daemon.output.paste_via, PasteVia::PrimarySelection);
         assert!(!config.daemon.output.restore_clipboard);
         assert_eq!(config.daemon.output.pre_paste_delay_ms, 150);
-        assert_eq!(config.daemon.output.restore_clipboard_delay_ms, 400);
+        assert_eq!(config.daemon.output.restore_clipboard_delay_ms, Some(400));
+        assert!(!config.daemon.output.require_focused_window);
+        assert_eq!(config.daemon.output.focus_settle_ms, 120);
+        assert!(!config.daemon.output.auto_backend_by_compositor);
+        assert!(config.daemon.output.bracketed_paste);
+        assert!(config.daemon.output.release_caps_lock);
+        assert_eq!(config.daemon.output.modifier_release_wait_ms, 500);
+        assert!(config.daemon.output.inject_sentences);
+        assert!(config.daemon.output.restore_immediately_on_paste_failure);
+        assert!(!config.daemon.output.hide_from_clipboard_history);
+        assert_eq!(
+            config.daemon.output.clipboard_blocklist,
+            ["remmina", "virt-manager"]
+        );
+        assert_eq!(config.daemon.output.prefix, None);
+        assert_eq!(config.daemon.output.suffix.as_deref(), Some(" "));
+        assert_eq!(
+            config
+                .daemon
+                .output
+                .clipboard_manager_delays
+                .get("cliphist"),
+            Some(&60)
+        );
+    }
+
+    #[test]
+    fn clear_modifiers_before_type_sets_a_modifier_wait() {
+        let config_from = |toml: &str, args: &[&str]| {
+            let file: FileConfig = toml::from_str(toml).expect("config should parse");
+            let matches = Cli::command()
+                .try_get_matches_from(args)
+                .expect("failed to parse cli");
+            let cli = Cli::from_arg_matches(&matches).expect("failed to build cli");
+            Config::from_sources(cli, &matches, file)
+        };
+        let start = ["sv", "daemon", "start"];
+
+        let config = config_from("[output]\nclear_modifiers_before_type = true\n", &start);
+        assert_eq!(
+            config.daemon.output.modifier_release_wait_ms,
+            CLEAR_MODIFIERS_WAIT_MS
+        );
+        assert_eq!(config.daemon.output.clear_modifiers_before_type, None);
+
+        let config = config_from(
+            "[output]\nclear_modifiers_before_type = true\nmodifier_release_wait_ms = 200\n",
+            &start,
+        );
+        assert_eq!(config.daemon.output.modifier_release_wait_ms, 200);
+
+        let config = config_from("[output]\nclear_modifiers_before_type = false\n", &start);
+        assert_eq!(config.daemon.output.modifier_release_wait_ms, 0);
     }
 
     #[test]
@@ -1,5 +1,5 @@
 use clap::ValueEnum;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::env;
 use std::fmt;
 use std::fs;
@@ -10,7 +10,7 @@ use crate::error::AppError;
 
 const DEFAULT_MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ModelSize {
     Auto,
@@ -40,7 +40,7 @@ impl fmt::Display for ModelSize {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ModelLanguage {
     Auto,
@@ -98,14 +98,22 @@ pub fn prepare_model(
     spec: &ModelSpec,
     allow_download: bool,
 ) -> Result<PreparedModel, AppError> {
-    let path = match explicit_path {
-        Some(path) => path.to_path_buf(),
-        None => default_model_dir().join(spec.filename_result()?),
-    };
+    let path = resolve_model_path(explicit_path, spec)?;
     let downloaded = ensure_model_available(&path, spec, allow_download)?;
     Ok(PreparedModel { path, downloaded })
 }
 
+/// Returns where the model for `spec` lives, without checking that it exists.
+pub fn resolve_model_path(
+    explicit_path: Option<&Path>,
+    spec: &ModelSpec,
+) -> Result<PathBuf, AppError> {
+    match explicit_path {
+        Some(path) => Ok(path.to_path_buf()),
+        None => Ok(default_model_dir().join(spec.filename_result()?)),
+    }
+}
+
 fn default_model_dir() -> PathBuf {
     let data_home = env::var_os("XDG_DATA_HOME")
         .map(PathBuf::from)
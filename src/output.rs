@@ -1,16 +1,82 @@
+use std::collections::BTreeMap;
 use std::env;
 use std::fmt;
 use std::io::Write as _;
-use std::process::{Command, Output, Stdio};
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::time::{Duration, Instant};
 
 use clap::ValueEnum;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 const MAX_CLIPBOARD_BYTES: usize = 100 * 1024 * 1024;
 const KDE_SECRET_MIME: &str = "x-kde-passwordManagerHint";
-
-#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize)]
+/// Linux input event code for KEY_BACKSPACE, as ydotool expects.
+const YDOTOOL_BACKSPACE: u16 = 14;
+const YDOTOOL_ESC: u16 = 1;
+/// Linux input event code for KEY_CAPSLOCK.
+const YDOTOOL_CAPSLOCK: u16 = 58;
+/// The left and right Ctrl, Shift, Alt, and Super keys.
+const MODIFIER_KEYS: [evdev::Key; 8] = [
+    evdev::Key::KEY_LEFTCTRL,
+    evdev::Key::KEY_RIGHTCTRL,
+    evdev::Key::KEY_LEFTSHIFT,
+    evdev::Key::KEY_RIGHTSHIFT,
+    evdev::Key::KEY_LEFTALT,
+    evdev::Key::KEY_RIGHTALT,
+    evdev::Key::KEY_LEFTMETA,
+    evdev::Key::KEY_RIGHTMETA,
+];
+/// Modifier wait that `clear_modifiers_before_type = true` stands for.
+pub const CLEAR_MODIFIERS_WAIT_MS: u64 = 500;
+/// How often `modifier_release_wait_ms` checks the keyboards.
+const MODIFIER_POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// ydotool's exit status when it cannot connect to ydotoold's socket.
+const YDOTOOL_CONNECT_FAILED: i32 = 2;
+/// Time a restarting ydotoold gets to start listening before the retry.
+const YDOTOOL_RECONNECT_DELAY: Duration = Duration::from_millis(250);
+
+/// Wait before the clipboard is restored when nothing else sets one.
+const DEFAULT_RESTORE_CLIPBOARD_DELAY_MS: u64 = 250;
+
+/// Paste chord sent when `paste_keys` is unset and the focused window is
+/// not a known terminal.
+const DEFAULT_PASTE_KEYS: &str = "ctrl+v";
+
+/// Paste chords for terminals whose window class is known, matched case-insensitively.
+/// Terminals reserve Ctrl+V for the shell, so most of them take Ctrl+Shift+V instead.
+/// xterm and urxvt are missing on purpose: their Shift+Insert pastes the primary
+/// selection, not the clipboard the transcript was copied to.
+const TERMINAL_PASTE_KEYS: &[(&str, &str)] = &[
+    ("com.mitchellh.ghostty", "ctrl+shift+v"),
+    ("ghostty", "ctrl+shift+v"),
+    ("org.wezfurlong.wezterm", "ctrl+shift+v"),
+    ("kitty", "ctrl+shift+v"),
+    ("alacritty", "ctrl+shift+v"),
+    ("foot", "ctrl+shift+v"),
+    ("org.kde.konsole", "ctrl+shift+v"),
+    ("org.gnome.terminal", "ctrl+shift+v"),
+    ("gnome-terminal-server", "ctrl+shift+v"),
+    ("org.gnome.console", "ctrl+shift+v"),
+    ("org.gnome.ptyxis", "ctrl+shift+v"),
+];
+
+/// Clipboard managers with a known restore delay: the manager name used in
+/// `clipboard_manager_delays`, the process that reveals it, and how long it
+/// needs to record the temporary clipboard before the restore overwrites it.
+/// Watchers started as `wl-paste --watch <manager> store` are matched by the
+/// watched program.
+const CLIPBOARD_MANAGERS: &[(&str, &str, u64)] = &[
+    ("klipper", "klipper", 300),
+    ("klipper", "plasmashell", 300),
+    ("copyq", "copyq", 200),
+    ("gpaste", "gpaste-daemon", 250),
+    ("clipcat", "clipcatd", 150),
+    ("cliphist", "cliphist", 100),
+    ("clipman", "clipman", 100),
+];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum OutputMode {
     Stdout,
@@ -18,50 +84,484 @@ pub enum OutputMode {
     Clipboard,
     Type,
     Ydotool,
+    /// Types through the RemoteDesktop portal, for GNOME Wayland.
+    Portal,
+}
+
+impl fmt::Display for OutputMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.to_possible_value().ok_or(fmt::Error)?;
+        f.write_str(value.get_name())
+    }
+}
+
+/// Where paste mode puts the transcript before pasting it.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PasteVia {
+    /// Copies to the regular clipboard, presses the paste keys, and restores
+    /// the previous clipboard afterwards when `restore_clipboard` is set.
+    #[default]
+    RegularWithRestore,
+    /// Copies to the primary selection and middle-clicks, leaving the regular
+    /// clipboard untouched. The text lands where the mouse pointer is, not at
+    /// the keyboard cursor.
+    PrimarySelection,
+}
+
+/// Output mode requested at runtime; `auto` picks one for the current session.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutputBackend {
+    Auto,
+    Mode(OutputMode),
+}
+
+impl OutputBackend {
+    /// `by_compositor` lets `auto` prefer the backend suited to the running
+    /// compositor; see [`detect_output_mode`].
+    pub fn resolve(self, by_compositor: bool) -> OutputMode {
+        match self {
+            Self::Auto => detect_output_mode(by_compositor),
+            Self::Mode(mode) => mode.supported(),
+        }
+    }
+}
+
+impl std::str::FromStr for OutputBackend {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.eq_ignore_ascii_case("auto") {
+            return Ok(Self::Auto);
+        }
+        OutputMode::from_str(value, true)
+            .map(Self::Mode)
+            .map_err(|_| {
+                format!(
+                    "invalid backend '{value}' (expected auto|stdout|paste|clipboard|type|ydotool|portal)"
+                )
+            })
+    }
+}
+
+impl fmt::Display for OutputBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Auto => f.write_str("auto"),
+            Self::Mode(mode) => mode.fmt(f),
+        }
+    }
+}
+
+/// Desktop family of the session, as reported by its environment.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Compositor {
+    Kde,
+    Gnome,
+    /// sway, Hyprland, and the other wlroots-based compositors.
+    Wlroots,
+    /// An X11 session without Wayland.
+    X11,
+    Unknown,
+}
+
+/// Names the session's compositor from `XDG_CURRENT_DESKTOP`,
+/// `WAYLAND_DISPLAY`, `DISPLAY`, and the sway and Hyprland socket variables.
+/// A display variable only counts while its socket exists, so one left over
+/// from an ended session does not decide.
+pub fn detect_compositor() -> Compositor {
+    compositor_from_env(|name| match name {
+        "WAYLAND_DISPLAY" => has_wayland_session(),
+        "DISPLAY" => has_x11_display(),
+        _ => env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .is_some(),
+    })
+    .unwrap_or_else(|| {
+        compositor_from_desktop(&env::var("XDG_CURRENT_DESKTOP").unwrap_or_default())
+    })
+}
+
+/// Classifies the session from which variables are set, or `None` when the
+/// desktop name has to decide.
+fn compositor_from_env(is_set: impl Fn(&str) -> bool) -> Option<Compositor> {
+    if !is_set("WAYLAND_DISPLAY") {
+        Some(if is_set("DISPLAY") {
+            Compositor::X11
+        } else {
+            Compositor::Unknown
+        })
+    } else if is_set("SWAYSOCK") || is_set("HYPRLAND_INSTANCE_SIGNATURE") {
+        Some(Compositor::Wlroots)
+    } else {
+        None
+    }
+}
+
+fn compositor_from_desktop(desktop: &str) -> Compositor {
+    desktop
+        .split(':')
+        .find_map(|name| match name.to_ascii_lowercase().as_str() {
+            "kde" => Some(Compositor::Kde),
+            "gnome" | "gnome-classic" | "gnome-flashback" => Some(Compositor::Gnome),
+            "sway" | "hyprland" | "river" | "wayfire" | "labwc" | "wlroots" => {
+                Some(Compositor::Wlroots)
+            }
+            _ => None,
+        })
+        .unwrap_or(Compositor::Unknown)
+}
+
+/// Picks paste for a live Wayland session, dotool typing for X11 (it works
+/// through uinput there), and stdout when neither display server is reachable.
+/// With `by_compositor`, wlroots compositors type instead of pasting: they
+/// take virtual keyboard input directly, which skips the clipboard round trip.
+/// GNOME uses the RemoteDesktop portal when built with the `portal` feature.
+/// Builds without the `clipboard` feature type under Wayland as well.
+pub fn detect_output_mode(by_compositor: bool) -> OutputMode {
+    if has_wayland_session() {
+        match detect_compositor() {
+            Compositor::Wlroots if by_compositor => OutputMode::Type,
+            Compositor::Gnome if by_compositor && cfg!(feature = "portal") => OutputMode::Portal,
+            _ => OutputMode::Paste.supported(),
+        }
+    } else if let Some(socket) = unreachable_wayland_socket() {
+        // The clipboard tools take the display from our environment, so
+        // pasting would fail even though a compositor is running.
+        log::warn!(
+            "found Wayland socket {socket}, but WAYLAND_DISPLAY does not point to it; \
+             restart the daemon inside that session to paste there"
+        );
+        if has_x11_session() {
+            OutputMode::Type
+        } else {
+            OutputMode::Stdout
+        }
+    } else if has_x11_session() {
+        OutputMode::Type
+    } else {
+        OutputMode::Stdout
+    }
+}
+
+/// Whether the compositor socket `WAYLAND_DISPLAY` names exists now. Only
+/// that one counts, since `wl-copy` and the other tools the daemon starts
+/// connect to it and not to whatever socket the runtime directory holds.
+pub fn has_wayland_session() -> bool {
+    let Some(display) = env::var_os("WAYLAND_DISPLAY").filter(|value| !value.is_empty()) else {
+        return false;
+    };
+    let display = PathBuf::from(display);
+    let socket = match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if display.is_relative() => PathBuf::from(dir).join(display),
+        _ => display,
+    };
+    socket.exists()
+}
+
+/// A compositor socket in the runtime directory, as left by a session that
+/// started after the daemon. Only meaningful when `has_wayland_session` is
+/// false, since the daemon's environment then cannot reach it.
+fn unreachable_wayland_socket() -> Option<String> {
+    let dir = PathBuf::from(env::var_os("XDG_RUNTIME_DIR")?);
+    wayland_sockets_in(&dir).next()
+}
+
+/// Whether an X server socket exists now.
+pub fn has_x11_session() -> bool {
+    x11_socket_in(Path::new("/tmp/.X11-unix"))
+}
+
+/// Whether `DISPLAY` names a local X server whose socket exists now. Remote
+/// displays such as `host:10.0` cannot be checked and count as set.
+fn has_x11_display() -> bool {
+    let display = env::var("DISPLAY").unwrap_or_default();
+    if display.is_empty() {
+        return false;
+    }
+    x11_display_socket(&display)
+        .is_none_or(|socket| Path::new("/tmp/.X11-unix").join(socket).exists())
+}
+
+/// The socket name of a local display: `:1.0` is served by `X1`.
+fn x11_display_socket(display: &str) -> Option<String> {
+    let number = display.strip_prefix(':')?;
+    let number = number.split_once('.').map_or(number, |(number, _)| number);
+    Some(format!("X{number}"))
+}
+
+fn wayland_sockets_in(dir: &Path) -> impl Iterator<Item = String> {
+    entry_names(dir).filter(|name| name.starts_with("wayland-") && !name.ends_with(".lock"))
+}
+
+fn x11_socket_in(dir: &Path) -> bool {
+    entry_names(dir).any(|name| {
+        name.strip_prefix('X')
+            .is_some_and(|number| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+    })
+}
+
+fn entry_names(dir: &Path) -> impl Iterator<Item = String> {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+}
+
+/// How the typing backends pace keystrokes.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "style", rename_all = "lowercase")]
+pub enum TypeMode {
+    /// Types the whole transcript as fast as the tool allows.
+    #[default]
+    Instant,
+    /// Types one character at a time with `mean_delay_ms` ± `jitter_ms`
+    /// between keys, for web fields that reject instant input.
+    Human { mean_delay_ms: u64, jitter_ms: u64 },
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(default)]
 pub struct OutputConfig {
     pub mode: OutputMode,
-    pub paste_keys: String,
+    /// Paste chord. Unset sends known terminals their own chord and every
+    /// other window `ctrl+v`.
+    pub paste_keys: Option<String>,
+    /// Linux keycodes held for the paste chord instead of the modifiers in
+    /// `paste_keys`, e.g. `[125]` for Super or `[29, 42]` for Ctrl+Shift.
+    pub paste_modifier_keycodes: Option<Vec<u16>>,
+    /// Linux keycode pressed for the paste chord instead of the final key in
+    /// `paste_keys`, e.g. `47` for the physical V key.
+    pub paste_keycode: Option<u16>,
+    /// Keystroke pacing for `type` and `ydotool` modes.
+    pub type_mode: TypeMode,
+    /// Which selection paste mode goes through.
+    pub paste_via: PasteVia,
     pub restore_clipboard: bool,
     pub pre_paste_delay_ms: u64,
-    pub restore_clipboard_delay_ms: u64,
+    /// Wait before restoring the clipboard. Unset waits as long as the
+    /// running clipboard manager needs, or 250 ms without a known one.
+    pub restore_clipboard_delay_ms: Option<u64>,
+    /// Restores the clipboard without the restore delay when copying or the
+    /// paste keypress fails.
+    pub restore_immediately_on_paste_failure: bool,
+    /// Skips paste and typing when the compositor reports no focused window,
+    /// leaving the transcript on the clipboard instead.
+    pub require_focused_window: bool,
+    /// Extra wait before injecting into a different window than the last
+    /// transcript went to, for windows that take input only a moment after
+    /// they gain focus. `0` never waits.
+    pub focus_settle_ms: u64,
+    /// Lets the `auto` backend choose by compositor rather than only by
+    /// which display server is running.
+    pub auto_backend_by_compositor: bool,
+    /// Pastes instead of typing when the focused window is a known terminal,
+    /// which brackets the text for shells that ask for it, so they insert it
+    /// without running it. Without paste tools the sequences are typed.
+    pub bracketed_paste: bool,
+    /// Turns Caps Lock off around key-based injection and back on after it.
+    pub release_caps_lock: bool,
+    /// Longest wait before typing for Ctrl, Shift, Alt, and Super to be
+    /// physically released, so a modifier still held from the hotkey does
+    /// not turn characters into shortcuts. `0` never waits.
+    pub modifier_release_wait_ms: u64,
+    /// Older switch for the same wait, read from config files only:
+    /// `true` waits `CLEAR_MODIFIERS_WAIT_MS` unless a wait is set.
+    pub clear_modifiers_before_type: Option<bool>,
+    /// Injects a transcript one sentence at a time instead of all at once.
+    pub inject_sentences: bool,
+    /// Restore delay per clipboard manager name, overriding the built-in
+    /// delay when that manager is running.
+    pub clipboard_manager_delays: BTreeMap<String, u64>,
+    /// Window classes that never get pasted into; paste mode types with
+    /// dotool there instead. Matched like profiles are.
+    pub clipboard_blocklist: Vec<String>,
+    /// Tags pasted text as a password so clipboard managers skip recording it.
+    pub hide_from_clipboard_history: bool,
+    /// Text inserted before every injected transcript.
+    pub prefix: Option<String>,
+    /// Text inserted after every injected transcript.
+    pub suffix: Option<String>,
+}
+
+impl OutputMode {
+    /// The mode this build can run: paste falls back to dotool typing when
+    /// compiled without the `clipboard` feature, and portal without `portal`.
+    pub fn supported(self) -> Self {
+        match self {
+            Self::Paste if !cfg!(feature = "clipboard") => Self::Type,
+            Self::Portal if !cfg!(feature = "portal") => Self::Type,
+            _ => self,
+        }
+    }
+
+    /// External programs the mode runs, all of which must be installed.
+    pub fn required_programs(self) -> &'static [&'static str] {
+        match self {
+            Self::Stdout => &[],
+            Self::Paste => &["wl-copy", "wl-paste", "dotool"],
+            Self::Clipboard => &["wl-copy"],
+            Self::Type => &["dotool"],
+            Self::Ydotool => &["ydotool"],
+            Self::Portal => &[],
+        }
+    }
+
+    /// Names the tools the mode drives, for log messages.
+    fn backend(self) -> &'static str {
+        match self {
+            Self::Stdout => "stdout",
+            Self::Paste => "wl-copy+dotool",
+            Self::Clipboard => "wl-copy",
+            Self::Type => "dotool",
+            Self::Ydotool => "ydotool",
+            Self::Portal => "remote desktop portal",
+        }
+    }
 }
 
 impl Default for OutputConfig {
     fn default() -> Self {
         Self {
             mode: OutputMode::Paste,
-            paste_keys: "ctrl+v".to_string(),
+            paste_keys: None,
+            paste_modifier_keycodes: None,
+            paste_keycode: None,
+            type_mode: TypeMode::Instant,
+            paste_via: PasteVia::RegularWithRestore,
             restore_clipboard: true,
             pre_paste_delay_ms: 100,
-            restore_clipboard_delay_ms: 250,
+            restore_clipboard_delay_ms: None,
+            restore_immediately_on_paste_failure: false,
+            require_focused_window: true,
+            focus_settle_ms: 0,
+            auto_backend_by_compositor: true,
+            bracketed_paste: false,
+            release_caps_lock: false,
+            modifier_release_wait_ms: 0,
+            clear_modifiers_before_type: None,
+            inject_sentences: false,
+            clipboard_manager_delays: BTreeMap::new(),
+            clipboard_blocklist: Vec::new(),
+            hide_from_clipboard_history: true,
+            prefix: None,
+            suffix: None,
         }
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutputErrorKind {
+    /// A tool ran and failed, possibly after changing the clipboard.
+    Failed,
+    /// A tool the mode needs is missing. Reported before any side effect.
+    Unavailable,
+}
+
 #[derive(Debug)]
-pub struct OutputError(String);
+pub struct OutputError {
+    pub kind: OutputErrorKind,
+    message: String,
+    ydotool: Option<YdotoolError>,
+}
 
 impl OutputError {
-    fn new(message: impl Into<String>) -> Self {
-        Self(message.into())
+    pub(crate) fn new(message: impl Into<String>) -> Self {
+        Self {
+            kind: OutputErrorKind::Failed,
+            message: message.into(),
+            ydotool: None,
+        }
+    }
+
+    pub(crate) fn unavailable(message: impl Into<String>) -> Self {
+        Self {
+            kind: OutputErrorKind::Unavailable,
+            message: message.into(),
+            ydotool: None,
+        }
+    }
+
+    /// Why ydotool could not reach ydotoold, when that is what failed.
+    pub fn ydotool_error(&self) -> Option<&YdotoolError> {
+        self.ydotool.as_ref()
     }
 }
 
 impl fmt::Display for OutputError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(&self.0)
+        f.write_str(&self.message)
     }
 }
 
 impl std::error::Error for OutputError {}
 
+/// ydotool could not deliver input because ydotoold was out of reach.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum YdotoolError {
+    /// Nothing listens at this socket path; ydotoold is not running.
+    SocketAbsent(PathBuf),
+    /// The socket exists but ydotoold refused the connection, even after
+    /// a retry.
+    DaemonUnresponsive(PathBuf),
+}
+
+impl fmt::Display for YdotoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            YdotoolError::SocketAbsent(path) => write!(
+                f,
+                "ydotoold socket {} not found; start ydotoold",
+                path.display()
+            ),
+            YdotoolError::DaemonUnresponsive(path) => write!(
+                f,
+                "ydotoold is not accepting connections on {}; restart ydotoold",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl From<YdotoolError> for OutputError {
+    fn from(err: YdotoolError) -> Self {
+        // A missing socket is found before anything is typed.
+        let kind = match err {
+            YdotoolError::SocketAbsent(_) => OutputErrorKind::Unavailable,
+            YdotoolError::DaemonUnresponsive(_) => OutputErrorKind::Failed,
+        };
+        Self {
+            kind,
+            message: err.to_string(),
+            ydotool: Some(err),
+        }
+    }
+}
+
+/// The socket ydotool connects to: `YDOTOOL_SOCKET`, else the one ydotoold
+/// creates in `XDG_RUNTIME_DIR`, else ydotool's built-in default.
+pub fn ydotool_socket_path() -> PathBuf {
+    env::var_os("YDOTOOL_SOCKET")
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("XDG_RUNTIME_DIR").map(|dir| PathBuf::from(dir).join(".ydotool_socket"))
+        })
+        .unwrap_or_else(|| PathBuf::from("/tmp/.ydotool_socket"))
+}
+
+/// Keyboard lock indicators, as the kernel's LED class reports them.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct LockState {
+    pub caps_lock: bool,
+    pub num_lock: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct ClipboardSnapshot {
     mime_type: String,
+    /// Raw clipboard bytes, never decoded as text, so images and other
+    /// binary or non-UTF-8 content restore byte for byte.
     data: Vec<u8>,
 }
 
@@ -80,6 +580,8 @@ enum KeyName {
     V,
     Insert,
     Enter,
+    /// A raw Linux input event code, sent as dotool's `k:<code>`.
+    Code(u16),
 }
 
 pub trait CommandRunner {
@@ -90,11 +592,36 @@ pub trait CommandRunner {
         args: &[String],
         stdin: &[u8],
     ) -> Result<std::process::ExitStatus, std::io::Error>;
-    fn copy_temporary_text(&mut self, text: &str) -> Result<(), OutputError>;
+    fn copy_temporary_text(
+        &mut self,
+        text: &str,
+        hide_from_history: bool,
+    ) -> Result<(), OutputError>;
     fn sleep(&mut self, duration: Duration);
+    /// Names of the known clipboard managers running right now.
+    fn clipboard_managers(&mut self) -> Vec<&'static str>;
+    /// Whether a window has keyboard focus, or `None` when it cannot be told.
+    fn focused_window(&mut self) -> Option<bool>;
+    /// Class or app id of the focused window, when it can be told.
+    fn focused_window_class(&mut self) -> Option<String>;
+    /// Whether `program` is installed.
+    fn has_program(&mut self, program: &str) -> bool;
+    /// Current lock key state, or `None` when no keyboard LEDs are visible.
+    fn lock_state(&mut self) -> Option<LockState>;
+    /// Whether a modifier key is physically held, or `None` when no
+    /// keyboard can be read.
+    fn modifiers_held(&mut self) -> Option<bool>;
+    /// Whether ydotoold's socket exists. Checked on every use, since a
+    /// restarted ydotoold creates a new one.
+    fn has_ydotool_socket(&mut self) -> bool;
 }
 
-struct SystemRunner;
+#[derive(Default)]
+struct SystemRunner {
+    /// Keyboards opened by the first modifier check and polled by the rest,
+    /// so a wait does not reopen every event device each time.
+    keyboards: Option<ModifierKeyboards>,
+}
 
 impl CommandRunner for SystemRunner {
     fn output(&mut self, program: &str, args: &[String]) -> Result<Output, std::io::Error> {
@@ -119,32 +646,78 @@ impl CommandRunner for SystemRunner {
         child.wait()
     }
 
-    fn copy_temporary_text(&mut self, text: &str) -> Result<(), OutputError> {
+    #[cfg(feature = "clipboard")]
+    fn copy_temporary_text(
+        &mut self,
+        text: &str,
+        hide_from_history: bool,
+    ) -> Result<(), OutputError> {
         use wl_clipboard_rs::copy::{MimeSource, MimeType, Options, Source};
 
-        let sources = vec![
-            MimeSource {
-                source: Source::Bytes(text.as_bytes().into()),
-                mime_type: MimeType::Text,
-            },
-            MimeSource {
+        let mut sources = vec![MimeSource {
+            source: Source::Bytes(text.as_bytes().into()),
+            mime_type: MimeType::Text,
+        }];
+        if hide_from_history {
+            sources.push(MimeSource {
                 source: Source::Bytes(b"secret"[..].into()),
                 mime_type: MimeType::Specific(KDE_SECRET_MIME.to_string()),
-            },
-        ];
+            });
+        }
 
         Options::new()
             .copy_multi(sources)
             .map_err(|err| OutputError::new(format!("clipboard copy failed: {err}")))
     }
 
+    #[cfg(not(feature = "clipboard"))]
+    fn copy_temporary_text(
+        &mut self,
+        _text: &str,
+        _hide_from_history: bool,
+    ) -> Result<(), OutputError> {
+        Err(OutputError::new(
+            "clipboard paste needs sv built with the clipboard feature",
+        ))
+    }
+
     fn sleep(&mut self, duration: Duration) {
         std::thread::sleep(duration);
     }
+
+    fn clipboard_managers(&mut self) -> Vec<&'static str> {
+        detect_clipboard_managers(Path::new("/proc"))
+    }
+
+    fn focused_window(&mut self) -> Option<bool> {
+        query_focused_window(self)
+    }
+
+    fn focused_window_class(&mut self) -> Option<String> {
+        query_focused_window_class(self)
+    }
+
+    fn has_program(&mut self, program: &str) -> bool {
+        crate::diagnostics::find_program(program, env::var_os("PATH").as_deref()).is_some()
+    }
+
+    fn lock_state(&mut self) -> Option<LockState> {
+        lock_state()
+    }
+
+    fn modifiers_held(&mut self) -> Option<bool> {
+        self.keyboards
+            .get_or_insert_with(ModifierKeyboards::open)
+            .held()
+    }
+
+    fn has_ydotool_socket(&mut self) -> bool {
+        ydotool_socket_path().exists()
+    }
 }
 
 pub fn output_text(text: &str, config: &OutputConfig) -> Result<(), OutputError> {
-    let mut runner = SystemRunner;
+    let mut runner = SystemRunner::default();
     output_text_with_runner(text, config, &mut runner)
 }
 
@@ -157,384 +730,2127 @@ pub fn output_text_with_runner(
         return Ok(());
     }
 
-    match config.mode {
-        OutputMode::Stdout => Ok(()),
-        OutputMode::Paste => paste_text(text, config, runner),
-        OutputMode::Clipboard => copy_plain_text(text, runner),
-        OutputMode::Type => type_text(text, runner),
-        OutputMode::Ydotool => type_text_ydotool(text, runner),
+    let backend = config.mode.backend();
+    let started = Instant::now();
+    log::debug!(
+        "output attempt: backend={backend} chars={}",
+        text.chars().count()
+    );
+    if let Err(err) = probe_programs(config, runner) {
+        log::debug!("output skipped: backend={backend} reason={err}");
+        return Err(err);
+    }
+    let injects = matches!(
+        config.mode,
+        OutputMode::Paste | OutputMode::Type | OutputMode::Ydotool | OutputMode::Portal
+    );
+    if injects && config.require_focused_window && runner.focused_window() == Some(false) {
+        log::debug!("output skipped: backend={backend} reason=no focused window");
+        return match copy_plain_text(text, runner) {
+            Ok(()) => Err(OutputError::new(
+                "no focused window; transcript left on the clipboard",
+            )),
+            Err(err) => Err(OutputError::new(format!("no focused window; {err}"))),
+        };
+    }
+    if config.modifier_release_wait_ms > 0
+        && matches!(config.mode, OutputMode::Type | OutputMode::Ydotool)
+    {
+        wait_for_modifier_release(config.modifier_release_wait_ms, runner);
     }
+    // Portal input sends keysyms, which come out the same whatever the locks.
+    let caps_lock = injects
+        && config.mode != OutputMode::Portal
+        && runner.lock_state().is_some_and(|state| state.caps_lock);
+    let release_caps_lock = caps_lock && config.release_caps_lock;
+    if release_caps_lock {
+        toggle_caps_lock(config.mode, runner)?;
+    } else if caps_lock {
+        log::warn!("Caps Lock is on; injected text may come out in the wrong case");
+    }
+    let result = inject_text(text, config, runner);
+    if release_caps_lock {
+        if let Err(err) = toggle_caps_lock(config.mode, runner) {
+            log::warn!("failed to turn Caps Lock back on: {err}");
+        }
+    }
+    let elapsed_ms = started.elapsed().as_millis();
+    match &result {
+        Ok(()) => log::debug!("output succeeded: backend={backend} elapsed_ms={elapsed_ms}"),
+        Err(err) => {
+            log::debug!("output failed: backend={backend} elapsed_ms={elapsed_ms} reason={err}")
+        }
+    }
+    result
 }
 
-fn paste_text(
+fn inject_text(
     text: &str,
     config: &OutputConfig,
     runner: &mut dyn CommandRunner,
 ) -> Result<(), OutputError> {
-    let paste_key = ParsedPasteKey::parse(&config.paste_keys)?;
-    let original = if config.restore_clipboard {
-        read_clipboard_snapshot(runner)?
-    } else {
-        None
+    if config.mode == OutputMode::Paste && paste_blocked(config, runner) {
+        log::debug!("paste is blocked in the focused window; typing instead");
+        let typing = OutputConfig {
+            mode: OutputMode::Type,
+            ..config.clone()
+        };
+        return inject_text(text, &typing, runner);
+    }
+    let keyed = matches!(config.mode, OutputMode::Type | OutputMode::Ydotool);
+    let paste = OutputConfig {
+        mode: OutputMode::Paste,
+        ..config.clone()
     };
-    let paste_result = (|| {
-        runner.copy_temporary_text(text)?;
-        runner.sleep(Duration::from_millis(config.pre_paste_delay_ms));
-        send_paste_key_dotool(&paste_key, runner)
-    })();
-
-    if config.restore_clipboard {
-        runner.sleep(Duration::from_millis(config.restore_clipboard_delay_ms));
-        if let Err(err) = restore_clipboard_snapshot(original.as_ref(), runner) {
-            eprintln!("warn: failed to restore clipboard: {err}");
+    let bracketed = keyed && config.bracketed_paste && is_focused_window_terminal(runner);
+    if bracketed && !paste_blocked(config, runner) {
+        // A pasting terminal brackets the text itself, and only when the
+        // program in it asked for that. Typed sequences reach every program
+        // as keystrokes, which vim and other editors take as commands.
+        if paste_available(&paste, runner) {
+            log::debug!("bracketed_paste in a terminal; pasting instead of typing");
+            return paste_text(text, &paste, runner);
+        }
+        log::warn!(
+            "bracketed_paste needs paste mode set up; typing the sequences instead, which only suits a shell prompt"
+        );
+    }
+    if keyed && has_astral_chars(text) && !paste_blocked(config, runner) {
+        // dotool and ydotool type through the keyboard layout, which has no
+        // keys for emoji and other characters beyond the BMP.
+        if paste_available(&paste, runner) {
+            log::debug!("text has characters beyond the BMP; pasting instead of typing");
+            return paste_text(text, &paste, runner);
         }
+        log::warn!(
+            "{} cannot type emoji and other characters beyond the BMP; set up paste mode to paste them",
+            config.mode
+        );
+    }
+    match config.mode {
+        OutputMode::Stdout => Ok(()),
+        OutputMode::Paste => paste_text(text, config, runner),
+        OutputMode::Clipboard => copy_plain_text(text, runner),
+        OutputMode::Type => {
+            let script = match config.type_mode {
+                TypeMode::Instant => dotool_type_script(text),
+                TypeMode::Human {
+                    mean_delay_ms,
+                    jitter_ms,
+                } => {
+                    let mut cadence = KeyCadence::new(mean_delay_ms, jitter_ms, random_seed());
+                    dotool_human_script(text, &mut cadence)
+                }
+            };
+            if bracketed {
+                run_dotool(&bracketed_dotool_script(&script), "typing", runner)
+            } else {
+                run_dotool(&script, "typing", runner)
+            }
+        }
+        OutputMode::Ydotool => {
+            if bracketed {
+                type_escape_ydotool(BRACKETED_PASTE_START, runner)?;
+            }
+            let typed = match config.type_mode {
+                TypeMode::Instant => type_text_ydotool(text, runner),
+                TypeMode::Human {
+                    mean_delay_ms,
+                    jitter_ms,
+                } => {
+                    let mut cadence = KeyCadence::new(mean_delay_ms, jitter_ms, random_seed());
+                    type_text_ydotool_human(text, &mut cadence, runner)
+                }
+            };
+            if bracketed {
+                // Closed even when typing failed, or the terminal would take
+                // every later key as pasted text.
+                let closed = type_escape_ydotool(BRACKETED_PASTE_END, runner);
+                typed.and(closed)
+            } else {
+                typed
+            }
+        }
+        OutputMode::Portal => portal_type_text(text),
     }
+}
 
-    paste_result
+/// Whether this build and the installed tools can paste with `config`.
+fn paste_available(config: &OutputConfig, runner: &mut dyn CommandRunner) -> bool {
+    OutputMode::Paste.supported() == OutputMode::Paste && probe_programs(config, runner).is_ok()
 }
 
-fn read_clipboard_snapshot(
-    runner: &mut dyn CommandRunner,
-) -> Result<Option<ClipboardSnapshot>, OutputError> {
-    if env::var_os("WAYLAND_DISPLAY").is_none() {
-        return Ok(None);
+/// Whether the focused window is on `clipboard_blocklist`.
+fn paste_blocked(config: &OutputConfig, runner: &mut dyn CommandRunner) -> bool {
+    if config.clipboard_blocklist.is_empty() {
+        return false;
     }
+    runner.focused_window_class().is_some_and(|class| {
+        config
+            .clipboard_blocklist
+            .iter()
+            .any(|name| window_class_matches(&class, name))
+    })
+}
 
-    let list_args = vec!["--list-types".to_string()];
-    let types = runner.output("wl-paste", &list_args).map_err(|err| {
-        if err.kind() == std::io::ErrorKind::NotFound {
-            OutputError::new("wl-paste not found; install wl-clipboard")
-        } else {
-            OutputError::new(format!("failed to run wl-paste: {err}"))
-        }
-    })?;
-    if !types.status.success() {
-        return Ok(None);
+/// Whether `text` has characters outside the Basic Multilingual Plane, such
+/// as most emoji.
+fn has_astral_chars(text: &str) -> bool {
+    text.chars().any(|ch| u32::from(ch) > 0xFFFF)
+}
+
+/// Presses Caps Lock once with the tool the mode already drives.
+fn toggle_caps_lock(mode: OutputMode, runner: &mut dyn CommandRunner) -> Result<(), OutputError> {
+    if mode == OutputMode::Ydotool {
+        press_key_ydotool(YDOTOOL_CAPSLOCK, 1, "caps lock", runner)
+    } else {
+        run_dotool("key capslock\n", "caps lock", runner)
     }
-    let mime_type = String::from_utf8_lossy(&types.stdout)
-        .lines()
-        .find(|line| !line.trim().is_empty())
-        .map(str::trim)
-        .unwrap_or_default()
-        .to_string();
-    if mime_type.is_empty() {
-        return Ok(None);
+}
+
+/// Waits until no keyboard reports a modifier held, for at most `wait_ms`.
+/// The held keys belong to the user's keyboard, and a release sent from the
+/// typing tool's own virtual device would not let go of them.
+fn wait_for_modifier_release(wait_ms: u64, runner: &mut dyn CommandRunner) {
+    let polls = wait_ms.div_ceil(MODIFIER_POLL_INTERVAL.as_millis() as u64);
+    for _ in 0..polls {
+        if runner.modifiers_held() != Some(true) {
+            return;
+        }
+        runner.sleep(MODIFIER_POLL_INTERVAL);
     }
+    log::warn!("modifier keys still held after {wait_ms} ms; typing anyway");
+}
 
-    let read_args = vec![
-        "--type".to_string(),
-        mime_type.clone(),
-        "--no-newline".to_string(),
-    ];
-    let content = runner
-        .output("wl-paste", &read_args)
-        .map_err(|err| OutputError::new(format!("failed to read clipboard: {err}")))?;
-    if !content.status.success() {
-        return Ok(None);
+/// The event devices under `/dev/input` that have modifier keys.
+struct ModifierKeyboards(Vec<evdev::Device>);
+
+impl ModifierKeyboards {
+    fn open() -> Self {
+        let devices = entry_names(Path::new("/dev/input"))
+            .filter(|name| name.starts_with("event"))
+            .filter_map(|name| evdev::Device::open(Path::new("/dev/input").join(name)).ok())
+            .filter(|device| {
+                device
+                    .supported_keys()
+                    .is_some_and(|keys| MODIFIER_KEYS.iter().any(|key| keys.contains(*key)))
+            })
+            .collect();
+        Self(devices)
     }
-    if content.stdout.len() > MAX_CLIPBOARD_BYTES {
-        return Err(OutputError::new(format!(
-            "clipboard content too large to preserve: {} bytes",
-            content.stdout.len()
-        )));
+
+    /// Whether any keyboard has a modifier pressed, or `None` when none can
+    /// be read.
+    fn held(&self) -> Option<bool> {
+        let mut readable = false;
+        for device in &self.0 {
+            let Ok(pressed) = device.get_key_state() else {
+                continue;
+            };
+            readable = true;
+            if MODIFIER_KEYS.iter().any(|key| pressed.contains(*key)) {
+                return Some(true);
+            }
+        }
+        readable.then_some(false)
     }
+}
 
-    Ok(Some(ClipboardSnapshot {
-        mime_type,
-        data: content.stdout,
-    }))
+/// Reads the lock LEDs of every keyboard from sysfs. A lock counts as on
+/// when any keyboard shows it lit.
+pub fn lock_state() -> Option<LockState> {
+    lock_state_in(Path::new("/sys/class/leds"))
 }
 
-fn restore_clipboard_snapshot(
-    snapshot: Option<&ClipboardSnapshot>,
-    runner: &mut dyn CommandRunner,
-) -> Result<(), OutputError> {
-    let (action, args, data) = match snapshot {
-        Some(snapshot) => (
-            "restore",
-            vec!["--type".to_string(), snapshot.mime_type.clone()],
-            snapshot.data.as_slice(),
-        ),
-        None => ("clear", vec!["--clear".to_string()], &[][..]),
-    };
-    let status = runner
-        .status_with_stdin("wl-copy", &args, data)
-        .map_err(|err| OutputError::new(format!("failed to {action} clipboard: {err}")))?;
-    if status.success() {
-        Ok(())
-    } else {
-        Err(OutputError::new(format!(
-            "wl-copy {action} exited with status {status}"
-        )))
+fn lock_state_in(leds_dir: &Path) -> Option<LockState> {
+    let mut state = None;
+    for name in entry_names(leds_dir) {
+        let (caps_lock, num_lock) = match name.rsplit_once("::") {
+            Some((_, "capslock")) => (true, false),
+            Some((_, "numlock")) => (false, true),
+            _ => continue,
+        };
+        let Ok(brightness) = std::fs::read_to_string(leds_dir.join(&name).join("brightness"))
+        else {
+            continue;
+        };
+        let lit = brightness
+            .trim()
+            .parse::<u32>()
+            .is_ok_and(|value| value > 0);
+        let state = state.get_or_insert_with(LockState::default);
+        state.caps_lock |= caps_lock && lit;
+        state.num_lock |= num_lock && lit;
     }
+    state
 }
 
-fn copy_plain_text(text: &str, runner: &mut dyn CommandRunner) -> Result<(), OutputError> {
-    let args = vec!["--type".to_string(), "text/plain".to_string()];
-    let status = runner
-        .status_with_stdin("wl-copy", &args, text.as_bytes())
-        .map_err(|err| OutputError::new(format!("failed to copy text: {err}")))?;
-    if status.success() {
-        Ok(())
+/// Checks that every program the mode runs is installed, so a missing tool is
+/// reported before the clipboard or focused window is touched.
+fn probe_programs(
+    config: &OutputConfig,
+    runner: &mut dyn CommandRunner,
+) -> Result<(), OutputError> {
+    let mode = config.mode;
+    let programs: &[&str] =
+        if mode == OutputMode::Paste && config.paste_via == PasteVia::PrimarySelection {
+            &["wl-copy", "dotool"]
+        } else {
+            mode.required_programs()
+        };
+    let missing: Vec<&str> = programs
+        .iter()
+        .copied()
+        // wl-paste snapshots the clipboard and wl-copy puts the snapshot back;
+        // the paste itself copies without them.
+        .filter(|&program| {
+            config.restore_clipboard
+                || mode != OutputMode::Paste
+                || !matches!(program, "wl-copy" | "wl-paste")
+        })
+        .filter(|program| !runner.has_program(program))
+        .collect();
+    if !missing.is_empty() {
+        return Err(OutputError::unavailable(format!(
+            "{mode} output unavailable: {} not found in PATH",
+            missing.join(", ")
+        )));
+    }
+    if mode == OutputMode::Ydotool && !runner.has_ydotool_socket() {
+        return Err(YdotoolError::SocketAbsent(ydotool_socket_path()).into());
+    }
+    Ok(())
+}
+
+/// Device dotool writes its virtual keyboard events to.
+const UINPUT_PATH: &str = "/dev/uinput";
+
+/// Whether an output mode can work here, beyond its tools being installed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackendStatus {
+    Available,
+    /// A tool the mode runs is missing.
+    Unavailable(String),
+    /// The tools are installed but something they need is not working, so
+    /// output would fail or silently do nothing.
+    InstalledButNonFunctional(String),
+}
+
+/// Best-effort liveness check of the tools `mode` drives: dotool needs
+/// write access to `/dev/uinput`, and ydotool needs ydotoold answering on
+/// its socket.
+pub fn probe_backend(mode: OutputMode) -> BackendStatus {
+    probe_backend_with(
+        mode,
+        &mut SystemRunner::default(),
+        Path::new(UINPUT_PATH),
+        &ydotool_socket_path(),
+    )
+}
+
+fn probe_backend_with(
+    mode: OutputMode,
+    runner: &mut dyn CommandRunner,
+    uinput: &Path,
+    ydotool_socket: &Path,
+) -> BackendStatus {
+    let missing: Vec<&str> = mode
+        .required_programs()
+        .iter()
+        .copied()
+        .filter(|program| !runner.has_program(program))
+        .collect();
+    if !missing.is_empty() {
+        return BackendStatus::Unavailable(format!("{} not found in PATH", missing.join(", ")));
+    }
+    match mode {
+        OutputMode::Paste | OutputMode::Type => {
+            if let Err(err) = std::fs::OpenOptions::new().write(true).open(uinput) {
+                return BackendStatus::InstalledButNonFunctional(format!(
+                    "dotool cannot write to {}: {err}",
+                    uinput.display()
+                ));
+            }
+        }
+        OutputMode::Ydotool => {
+            let answers = std::os::unix::net::UnixDatagram::unbound()
+                .and_then(|socket| socket.connect(ydotool_socket));
+            if let Err(err) = answers {
+                return BackendStatus::InstalledButNonFunctional(format!(
+                    "ydotoold is not answering on {}: {err}",
+                    ydotool_socket.display()
+                ));
+            }
+        }
+        OutputMode::Stdout | OutputMode::Clipboard | OutputMode::Portal => {}
+    }
+    BackendStatus::Available
+}
+
+/// Wraps `text` in the configured prefix and suffix.
+pub fn apply_affixes(text: &str, config: &OutputConfig) -> String {
+    format!(
+        "{}{text}{}",
+        config.prefix.as_deref().unwrap_or_default(),
+        config.suffix.as_deref().unwrap_or_default()
+    )
+}
+
+/// Returns the built-in paste chord for a terminal window class, if it has one.
+pub fn terminal_paste_keys(window_class: &str) -> Option<&'static str> {
+    window_class_parts(window_class).find_map(terminal_paste_keys_for_class)
+}
+
+/// Whether `window_class` names the application `name`, either exactly or by
+/// the last part of a reverse-DNS app id, so `konsole` matches
+/// `org.kde.konsole`. Case is ignored.
+pub fn window_class_matches(window_class: &str, name: &str) -> bool {
+    window_class_parts(window_class).any(|part| {
+        part.eq_ignore_ascii_case(name)
+            || part
+                .rsplit('.')
+                .next()
+                .is_some_and(|short| short.eq_ignore_ascii_case(name))
+    })
+}
+
+/// Splits a window class into the names it carries.
+///
+/// Besides native Wayland app ids this accepts XWayland `WM_CLASS` values, which
+/// KDE and `xprop` report as a bare instance/class pair (`"konsole", "konsole"`)
+/// instead of the reverse-DNS id. Some tool builds print the raw property
+/// instead, with the pair separated by NUL bytes or whitespace, so any of
+/// those separators splits the value and each part is tried on its own.
+fn window_class_parts(window_class: &str) -> impl Iterator<Item = &str> {
+    let value = window_class
+        .split_once('=')
+        .map_or(window_class, |(_, value)| value);
+    value
+        .split(|ch: char| ch == ',' || ch == '\0' || ch.is_whitespace())
+        .map(|part| part.trim_matches('"'))
+        .filter(|part| !part.is_empty())
+}
+
+fn terminal_paste_keys_for_class(class: &str) -> Option<&'static str> {
+    let exact = TERMINAL_PASTE_KEYS
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(class));
+    let short = || {
+        TERMINAL_PASTE_KEYS.iter().find(|(known, _)| {
+            known.contains('.')
+                && known
+                    .rsplit('.')
+                    .next()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(class))
+        })
+    };
+    exact.or_else(short).map(|(_, keys)| *keys)
+}
+
+/// Reads a focus query tool's stdout.
+type FocusParser = fn(&str) -> Option<bool>;
+
+/// Reads a window class query tool's stdout.
+type ClassParser = fn(&str) -> Option<String>;
+
+/// The tool that can tell which window has focus in the running session.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum FocusTool {
+    Hyprctl,
+    Swaymsg,
+    Kdotool,
+    Xdotool,
+}
+
+/// Picks `hyprctl` on Hyprland, `swaymsg` on sway, `kdotool` on KDE, and
+/// `xdotool` on X11.
+fn focus_tool() -> Option<FocusTool> {
+    let desktop = env::var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+    if env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        Some(FocusTool::Hyprctl)
+    } else if env::var_os("SWAYSOCK").is_some() {
+        Some(FocusTool::Swaymsg)
+    } else if desktop.split(':').any(|name| name == "KDE") {
+        Some(FocusTool::Kdotool)
+    } else if env::var_os("WAYLAND_DISPLAY").is_none() && env::var_os("DISPLAY").is_some() {
+        Some(FocusTool::Xdotool)
     } else {
-        Err(OutputError::new(format!(
-            "wl-copy exited with status {status}"
-        )))
+        None
     }
 }
 
-fn type_text(text: &str, runner: &mut dyn CommandRunner) -> Result<(), OutputError> {
-    run_dotool(&dotool_type_script(text), "typing", runner)
+/// Runs a focus query and returns its stdout. A missing or failing tool
+/// yields `None`.
+fn run_focus_query(runner: &mut dyn CommandRunner, program: &str, args: &[&str]) -> Option<String> {
+    let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    let output = runner.output(program, &args).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
 }
 
-fn type_text_ydotool(text: &str, runner: &mut dyn CommandRunner) -> Result<(), OutputError> {
-    let args = vec![
-        "type".to_string(),
+/// Asks the running compositor whether a window has focus.
+fn query_focused_window(runner: &mut dyn CommandRunner) -> Option<bool> {
+    let (program, args, parse): (&str, &[&str], FocusParser) = match focus_tool()? {
+        FocusTool::Hyprctl => ("hyprctl", &["activewindow", "-j"], hyprland_has_focus),
+        FocusTool::Swaymsg => ("swaymsg", &["-t", "get_tree"], sway_has_focus),
+        FocusTool::Kdotool => ("kdotool", &["getactivewindow"], window_id_has_focus),
+        FocusTool::Xdotool => ("xdotool", &["getactivewindow"], window_id_has_focus),
+    };
+    parse(&run_focus_query(runner, program, args)?)
+}
+
+/// Asks the running compositor for the focused window's class or app id.
+fn query_focused_window_class(runner: &mut dyn CommandRunner) -> Option<String> {
+    let class_name: &[&str] = &["getactivewindow", "getwindowclassname"];
+    let (program, args, parse): (&str, &[&str], ClassParser) = match focus_tool()? {
+        FocusTool::Hyprctl => ("hyprctl", &["activewindow", "-j"], hyprland_window_class),
+        FocusTool::Swaymsg => ("swaymsg", &["-t", "get_tree"], sway_window_class),
+        FocusTool::Kdotool => ("kdotool", class_name, plain_window_class),
+        FocusTool::Xdotool => ("xdotool", class_name, plain_window_class),
+    };
+    parse(&run_focus_query(runner, program, args)?)
+}
+
+/// Asks the running compositor for an id that tells the focused window apart
+/// from other windows of the same application.
+fn query_focused_window_id(runner: &mut dyn CommandRunner) -> Option<String> {
+    let (program, args, parse): (&str, &[&str], ClassParser) = match focus_tool()? {
+        FocusTool::Hyprctl => ("hyprctl", &["activewindow", "-j"], hyprland_window_id),
+        FocusTool::Swaymsg => ("swaymsg", &["-t", "get_tree"], sway_window_id),
+        FocusTool::Kdotool => ("kdotool", &["getactivewindow"], plain_window_id),
+        FocusTool::Xdotool => ("xdotool", &["getactivewindow"], plain_window_id),
+    };
+    parse(&run_focus_query(runner, program, args)?)
+}
+
+/// Whether the focused window is a terminal listed in the paste chord table.
+fn is_focused_window_terminal(runner: &mut dyn CommandRunner) -> bool {
+    runner
+        .focused_window_class()
+        .is_some_and(|class| terminal_paste_keys(&class).is_some())
+}
+
+/// `hyprctl activewindow -j` prints `{}` when nothing is focused.
+fn hyprland_has_focus(stdout: &str) -> Option<bool> {
+    let window: serde_json::Value = serde_json::from_str(stdout).ok()?;
+    Some(
+        window
+            .get("address")
+            .and_then(serde_json::Value::as_str)
+            .is_some_and(|address| !address.is_empty()),
+    )
+}
+
+/// The node sway marks as focused, searched depth-first.
+fn sway_focused_node(node: &serde_json::Value) -> Option<&serde_json::Value> {
+    if node.get("focused").and_then(serde_json::Value::as_bool) == Some(true) {
+        return Some(node);
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node.get(key).and_then(serde_json::Value::as_array))
+        .flatten()
+        .find_map(sway_focused_node)
+}
+
+/// Sway marks one tree node as focused; only a container counts as a
+/// window, since an empty workspace or output takes focus otherwise.
+fn sway_has_focus(stdout: &str) -> Option<bool> {
+    let tree: serde_json::Value = serde_json::from_str(stdout).ok()?;
+    Some(sway_focused_node(&tree).is_some_and(|node| {
+        matches!(
+            node.get("type").and_then(serde_json::Value::as_str),
+            Some("con" | "floating_con")
+        )
+    }))
+}
+
+/// Native sway windows carry an `app_id`; XWayland ones a `WM_CLASS`.
+fn sway_window_class(stdout: &str) -> Option<String> {
+    let tree: serde_json::Value = serde_json::from_str(stdout).ok()?;
+    let node = sway_focused_node(&tree)?;
+    node.get("app_id")
+        .and_then(serde_json::Value::as_str)
+        .or_else(|| node.pointer("/window_properties/class")?.as_str())
+        .map(str::to_string)
+}
+
+/// Hyprland names windows by address, e.g. `0x55d1c8e0a2b0`.
+fn hyprland_window_id(stdout: &str) -> Option<String> {
+    let window: serde_json::Value = serde_json::from_str(stdout).ok()?;
+    window
+        .get("address")
+        .and_then(serde_json::Value::as_str)
+        .filter(|address| !address.is_empty())
+        .map(str::to_string)
+}
+
+/// Sway numbers every tree node; only a container is a window.
+fn sway_window_id(stdout: &str) -> Option<String> {
+    let tree: serde_json::Value = serde_json::from_str(stdout).ok()?;
+    let node = sway_focused_node(&tree)?;
+    matches!(
+        node.get("type").and_then(serde_json::Value::as_str),
+        Some("con" | "floating_con")
+    )
+    .then(|| node.get("id")?.as_u64())
+    .flatten()
+    .map(|id| id.to_string())
+}
+
+fn hyprland_window_class(stdout: &str) -> Option<String> {
+    let window: serde_json::Value = serde_json::from_str(stdout).ok()?;
+    window
+        .get("class")
+        .and_then(serde_json::Value::as_str)
+        .filter(|class| !class.is_empty())
+        .map(str::to_string)
+}
+
+/// Trailing NUL bytes from the raw `WM_CLASS` property are dropped with the
+/// whitespace.
+fn plain_window_class(stdout: &str) -> Option<String> {
+    Some(
+        stdout
+            .trim_matches(|ch: char| ch == '\0' || ch.is_whitespace())
+            .to_string(),
+    )
+    .filter(|class| !class.is_empty())
+}
+
+fn plain_window_id(stdout: &str) -> Option<String> {
+    let id = stdout.trim();
+    (!id.is_empty() && id != "0").then(|| id.to_string())
+}
+
+/// Window-id tools print the active window's id, or nothing (X11: `0`, the
+/// root fallback) when no window is active.
+fn window_id_has_focus(stdout: &str) -> Option<bool> {
+    let id = stdout.trim();
+    Some(!id.is_empty() && id != "0")
+}
+
+/// Class or app id of the focused window, when the session's query tool can
+/// tell.
+pub fn focused_window_class() -> Option<String> {
+    SystemRunner::default().focused_window_class()
+}
+
+/// Id or address of the focused window, when the session's query tool can
+/// tell.
+pub fn focused_window_id() -> Option<String> {
+    query_focused_window_id(&mut SystemRunner::default())
+}
+
+/// Erases the last `count` characters before the cursor with Backspace.
+pub fn erase_chars(count: usize, config: &OutputConfig) -> Result<(), OutputError> {
+    let mut runner = SystemRunner::default();
+    erase_chars_with_runner(count, config, &mut runner)
+}
+
+pub fn erase_chars_with_runner(
+    count: usize,
+    config: &OutputConfig,
+    runner: &mut dyn CommandRunner,
+) -> Result<(), OutputError> {
+    if count == 0 {
+        return Ok(());
+    }
+
+    match config.mode {
+        OutputMode::Stdout | OutputMode::Clipboard => Ok(()),
+        OutputMode::Paste | OutputMode::Type => {
+            let mut script = "key backspace\n".repeat(count);
+            script.push('\n');
+            run_dotool(&script, "erase", runner)
+        }
+        OutputMode::Ydotool => press_key_ydotool(YDOTOOL_BACKSPACE, count, "erase", runner),
+        OutputMode::Portal => portal_erase_chars(count),
+    }
+}
+
+#[cfg(feature = "portal")]
+fn portal_type_text(text: &str) -> Result<(), OutputError> {
+    crate::portal::type_text(text)
+}
+
+#[cfg(feature = "portal")]
+fn portal_erase_chars(count: usize) -> Result<(), OutputError> {
+    crate::portal::erase_chars(count)
+}
+
+#[cfg(not(feature = "portal"))]
+fn portal_type_text(_text: &str) -> Result<(), OutputError> {
+    Err(portal_not_built())
+}
+
+#[cfg(not(feature = "portal"))]
+fn portal_erase_chars(_count: usize) -> Result<(), OutputError> {
+    Err(portal_not_built())
+}
+
+#[cfg(not(feature = "portal"))]
+fn portal_not_built() -> OutputError {
+    OutputError::unavailable("portal output unavailable: built without the portal feature")
+}
+
+/// Presses and releases the key with Linux keycode `code` `count` times.
+fn press_key_ydotool(
+    code: u16,
+    count: usize,
+    action: &str,
+    runner: &mut dyn CommandRunner,
+) -> Result<(), OutputError> {
+    let mut args = vec![
+        "key".to_string(),
         "--key-delay".to_string(),
         "0".to_string(),
-        "--key-hold".to_string(),
-        "0".to_string(),
-        "--file".to_string(),
-        "-".to_string(),
     ];
-    let status = runner
-        .status_with_stdin("ydotool", &args, text.as_bytes())
-        .map_err(|err| {
-            if err.kind() == std::io::ErrorKind::NotFound {
-                OutputError::new("ydotool not found; install ydotool and enable ydotoold")
-            } else {
-                OutputError::new(format!("failed to run ydotool: {err}"))
-            }
-        })?;
-    if status.success() {
-        Ok(())
-    } else {
-        Err(OutputError::new(format!(
-            "ydotool typing exited with status {status}; ensure ydotoold is running"
-        )))
+    for _ in 0..count {
+        args.push(format!("{code}:1"));
+        args.push(format!("{code}:0"));
     }
+    run_ydotool(action, runner, |runner| {
+        runner
+            .output("ydotool", &args)
+            .map(|output| output.status)
+            .map_err(|err| OutputError::new(format!("failed to run ydotool: {err}")))
+    })
 }
 
-fn run_dotool(
-    script: &str,
+/// Runs one ydotool command. When ydotool cannot connect, ydotoold may have
+/// restarted since the last command, so the socket is checked again and the
+/// command retried once. ydotool connects before sending any input, so the
+/// retry never repeats keys.
+fn run_ydotool(
     action: &str,
     runner: &mut dyn CommandRunner,
+    mut call: impl FnMut(&mut dyn CommandRunner) -> Result<ExitStatus, OutputError>,
 ) -> Result<(), OutputError> {
-    let status = runner
-        .status_with_stdin("dotool", &[], script.as_bytes())
-        .map_err(|err| {
-            if err.kind() == std::io::ErrorKind::NotFound {
-                OutputError::new("dotool not found; install dotool")
-            } else {
-                OutputError::new(format!("failed to run dotool: {err}"))
-            }
-        })?;
+    let mut status = call(runner)?;
+    if status.code() == Some(YDOTOOL_CONNECT_FAILED) {
+        let socket = ydotool_socket_path();
+        if !runner.has_ydotool_socket() {
+            return Err(YdotoolError::SocketAbsent(socket).into());
+        }
+        log::debug!("ydotool could not connect; retrying");
+        runner.sleep(YDOTOOL_RECONNECT_DELAY);
+        status = call(runner)?;
+        if status.code() == Some(YDOTOOL_CONNECT_FAILED) {
+            return Err(YdotoolError::DaemonUnresponsive(socket).into());
+        }
+    }
     if status.success() {
         Ok(())
     } else {
         Err(OutputError::new(format!(
-            "dotool {action} exited with status {status}"
+            "ydotool {action} exited with status {status}; ensure ydotoold is running"
         )))
     }
 }
 
-fn dotool_type_script(text: &str) -> String {
-    let mut lines = Vec::new();
-    let mut current = String::new();
-    for ch in text.chars() {
-        match ch {
-            '\n' => {
-                if !current.is_empty() {
-                    lines.push(format!("type {current}"));
-                    current.clear();
-                }
-                lines.push("key enter".to_string());
-            }
-            '\r' => {}
-            _ => current.push(ch),
-        }
+fn paste_text(
+    text: &str,
+    config: &OutputConfig,
+    runner: &mut dyn CommandRunner,
+) -> Result<(), OutputError> {
+    if config.paste_via == PasteVia::PrimarySelection {
+        return paste_with_primary_selection(text, config, runner);
     }
-    if !current.is_empty() {
-        lines.push(format!("type {current}"));
+    let paste_key = paste_key(config, runner)?;
+    if !config.restore_clipboard {
+        return paste_with_temporary_clipboard(text, &paste_key, config, runner);
     }
-    lines.push(String::new());
-    lines.join("\n")
+
+    let mut guard = ClipboardGuard::save_with(runner)?;
+    let managers = guard.runner().clipboard_managers();
+    let delay = restore_delay(config, &managers);
+    log::debug!(
+        "clipboard managers: {managers:?}, restore delay {}ms",
+        delay.as_millis()
+    );
+    guard.set_restore_delay(delay);
+    let result = paste_with_temporary_clipboard(text, &paste_key, config, guard.runner());
+    if result.is_err() && config.restore_immediately_on_paste_failure {
+        // Nothing was pasted, so no application can still be reading the
+        // temporary clipboard.
+        guard.set_restore_delay(Duration::ZERO);
+    }
+    result
 }
 
-fn send_paste_key_dotool(
-    key: &ParsedPasteKey,
+/// The chord for the focused window: while `paste_keys` is unset, a
+/// terminal from the built-in table gets its own chord, so XWayland
+/// terminals and Ctrl+Shift terminals need no profile. A `paste_keys` set in
+/// the config or a profile always wins, even when it is `ctrl+v`.
+/// `paste_modifier_keycodes` and `paste_keycode` describe the user's own
+/// chord, so a terminal's chord is sent as listed.
+fn paste_key(
+    config: &OutputConfig,
+    runner: &mut dyn CommandRunner,
+) -> Result<ParsedPasteKey, OutputError> {
+    let keys = match &config.paste_keys {
+        Some(keys) => keys.as_str(),
+        None => {
+            let terminal = runner
+                .focused_window_class()
+                .and_then(|class| terminal_paste_keys(&class));
+            if let Some(chord) = terminal {
+                return ParsedPasteKey::parse(chord);
+            }
+            DEFAULT_PASTE_KEYS
+        }
+    };
+    ParsedPasteKey::parse(keys)?
+        .with_modifier_keycodes(config.paste_modifier_keycodes.as_deref())?
+        .with_keycode(config.paste_keycode)
+}
+
+fn paste_with_temporary_clipboard(
+    text: &str,
+    paste_key: &ParsedPasteKey,
+    config: &OutputConfig,
     runner: &mut dyn CommandRunner,
 ) -> Result<(), OutputError> {
-    run_dotool(&key.to_dotool_script(), "paste key", runner)
+    runner.copy_temporary_text(text, config.hide_from_clipboard_history)?;
+    runner.sleep(Duration::from_millis(config.pre_paste_delay_ms));
+    send_paste_key_dotool(paste_key, runner)
 }
 
-impl ParsedPasteKey {
-    fn parse(value: &str) -> Result<Self, OutputError> {
-        let parts: Vec<&str> = value.split('+').map(str::trim).collect();
-        if parts.is_empty() || parts.iter().any(|part| part.is_empty()) {
-            return Err(OutputError::new("paste_keys has invalid format"));
-        }
+/// Pastes through the primary selection with a middle click. The regular
+/// clipboard is never read or written, so there is nothing to restore.
+///
+/// The click goes to the window under the pointer and most applications
+/// insert at the pointer rather than the text cursor. No key chord works
+/// instead: Shift+Insert pastes the primary selection in terminals, but the
+/// regular clipboard in GTK and Qt applications.
+fn paste_with_primary_selection(
+    text: &str,
+    config: &OutputConfig,
+    runner: &mut dyn CommandRunner,
+) -> Result<(), OutputError> {
+    let args = [
+        "--primary".to_string(),
+        "--type".to_string(),
+        "text/plain".to_string(),
+    ];
+    let status = runner
+        .status_with_stdin("wl-copy", &args, text.as_bytes())
+        .map_err(|err| OutputError::new(format!("failed to set primary selection: {err}")))?;
+    if !status.success() {
+        return Err(OutputError::new(format!(
+            "wl-copy --primary exited with status {status}"
+        )));
+    }
+    runner.sleep(Duration::from_millis(config.pre_paste_delay_ms));
+    run_dotool("click middle\n", "middle click", runner)
+}
 
-        let key = KeyName::parse(parts[parts.len() - 1])?;
-        let modifiers = parts[..parts.len() - 1]
-            .iter()
-            .map(|part| KeyName::parse_modifier(part))
-            .collect::<Result<Vec<_>, _>>()?;
+/// The longest delay among the running clipboard managers, each taken from
+/// `clipboard_manager_delays` or the built-in table. Without a known manager
+/// the global `restore_clipboard_delay_ms` applies. The built-in table is
+/// only used while that setting is unset, so an explicit one wins.
+fn restore_delay(config: &OutputConfig, managers: &[&str]) -> Duration {
+    let builtin = config.restore_clipboard_delay_ms.is_none();
+    let delay_ms = managers
+        .iter()
+        .filter_map(|name| {
+            config
+                .clipboard_manager_delays
+                .get(*name)
+                .copied()
+                .or_else(|| builtin.then(|| builtin_manager_delay(name)).flatten())
+        })
+        .max()
+        .unwrap_or(
+            config
+                .restore_clipboard_delay_ms
+                .unwrap_or(DEFAULT_RESTORE_CLIPBOARD_DELAY_MS),
+        );
+    Duration::from_millis(delay_ms)
+}
 
-        Ok(Self { modifiers, key })
-    }
+fn builtin_manager_delay(name: &str) -> Option<u64> {
+    CLIPBOARD_MANAGERS
+        .iter()
+        .find(|(manager, _, _)| *manager == name)
+        .map(|(_, _, delay_ms)| *delay_ms)
+}
 
-    fn to_dotool_script(&self) -> String {
-        let mut lines = Vec::new();
-        for modifier in &self.modifiers {
-            lines.push(format!("keydown {}", modifier.dotool_name()));
+/// Scans a procfs tree for known clipboard managers. Each process matches by
+/// its `comm` name; `wl-paste` watchers also match by the programs in their
+/// command line. Managers that only register over D-Bus are not detected.
+fn detect_clipboard_managers(proc_dir: &Path) -> Vec<&'static str> {
+    let mut found = Vec::new();
+    for pid in entry_names(proc_dir).filter(|name| name.bytes().all(|b| b.is_ascii_digit())) {
+        let process = proc_dir.join(&pid);
+        let Ok(comm) = std::fs::read_to_string(process.join("comm")) else {
+            continue;
+        };
+        let comm = comm.trim();
+        let mut programs = vec![comm.to_string()];
+        if comm == "wl-paste" {
+            let cmdline = std::fs::read(process.join("cmdline")).unwrap_or_default();
+            programs.extend(
+                cmdline
+                    .split(|&byte| byte == 0)
+                    .skip(1)
+                    .map(|arg| String::from_utf8_lossy(arg))
+                    .filter_map(|arg| {
+                        Path::new(arg.as_ref())
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                    }),
+            );
         }
-        lines.push(format!("key {}", self.key.dotool_name()));
-        for modifier in self.modifiers.iter().rev() {
-            lines.push(format!("keyup {}", modifier.dotool_name()));
+        for (manager, process_name, _) in CLIPBOARD_MANAGERS {
+            if programs.iter().any(|program| program == process_name) && !found.contains(manager) {
+                found.push(*manager);
+            }
         }
-        lines.push(String::new());
-        lines.join("\n")
     }
+    found
 }
 
-impl KeyName {
-    fn parse(value: &str) -> Result<Self, OutputError> {
-        match value.to_ascii_lowercase().as_str() {
-            "ctrl" | "control" | "leftctrl" => Ok(Self::Ctrl),
-            "shift" | "leftshift" => Ok(Self::Shift),
-            "alt" | "leftalt" => Ok(Self::Alt),
-            "super" | "meta" | "win" | "leftmeta" => Ok(Self::Super),
-            "v" => Ok(Self::V),
-            "insert" | "ins" => Ok(Self::Insert),
-            "enter" | "return" => Ok(Self::Enter),
-            other => Err(OutputError::new(format!("unknown paste key: {other}"))),
-        }
+/// Saves the Wayland clipboard and puts it back when dropped, or clears it
+/// if it was empty. Early returns and unwinds still restore it. The daemon
+/// defers SIGINT/SIGTERM to its shutdown flag, so a signal mid-paste lets
+/// this run before the process exits.
+///
+/// Content of any type is restored byte for byte under its first offered
+/// MIME type. Needs `wl-paste` and `wl-copy`.
+pub struct ClipboardGuard<'a> {
+    original: Option<ClipboardSnapshot>,
+    delay: Duration,
+    runner: GuardRunner<'a>,
+}
+
+enum GuardRunner<'a> {
+    System(SystemRunner),
+    Borrowed(&'a mut dyn CommandRunner),
+}
+
+impl ClipboardGuard<'static> {
+    /// Saves the current clipboard.
+    pub fn save() -> Result<Self, OutputError> {
+        let mut runner = SystemRunner::default();
+        let original = read_clipboard_snapshot(&mut runner)?;
+        Ok(Self {
+            original,
+            delay: Duration::ZERO,
+            runner: GuardRunner::System(runner),
+        })
     }
+}
 
-    fn parse_modifier(value: &str) -> Result<Self, OutputError> {
-        let key = Self::parse(value)?;
-        match key {
-            Self::Ctrl | Self::Shift | Self::Alt | Self::Super => Ok(key),
-            _ => Err(OutputError::new(format!(
-                "paste key modifier expected, got {value}"
-            ))),
-        }
+impl<'a> ClipboardGuard<'a> {
+    fn save_with(runner: &'a mut dyn CommandRunner) -> Result<Self, OutputError> {
+        let original = read_clipboard_snapshot(runner)?;
+        Ok(Self {
+            original,
+            delay: Duration::ZERO,
+            runner: GuardRunner::Borrowed(runner),
+        })
     }
 
-    fn dotool_name(self) -> &'static str {
-        match self {
-            Self::Ctrl => "leftctrl",
-            Self::Shift => "leftshift",
-            Self::Alt => "leftalt",
-            Self::Super => "leftmeta",
-            Self::V => "v",
-            Self::Insert => "insert",
-            Self::Enter => "enter",
+    /// Waits this long before restoring, giving the application that was
+    /// handed the temporary content time to read it. No wait by default.
+    pub fn set_restore_delay(&mut self, delay: Duration) {
+        self.delay = delay;
+    }
+
+    fn runner(&mut self) -> &mut dyn CommandRunner {
+        match &mut self.runner {
+            GuardRunner::System(runner) => runner,
+            GuardRunner::Borrowed(runner) => &mut **runner,
         }
     }
 }
 
-#[cfg(any(test, feature = "test-support"))]
-pub mod test_support {
-    use std::os::unix::process::ExitStatusExt;
-
-    use super::*;
+impl Drop for ClipboardGuard<'_> {
+    fn drop(&mut self) {
+        let delay = self.delay;
+        let original = self.original.take();
+        let runner = self.runner();
+        if !delay.is_zero() {
+            runner.sleep(delay);
+        }
+        if let Err(err) = restore_clipboard_snapshot(original.as_ref(), runner) {
+            eprintln!("warn: failed to restore clipboard: {err}");
+        }
+    }
+}
 
-    #[derive(Debug, Clone, PartialEq, Eq)]
-    pub struct RecordedCommand {
-        pub program: String,
-        pub args: Vec<String>,
-        pub stdin: Vec<u8>,
+fn read_clipboard_snapshot(
+    runner: &mut dyn CommandRunner,
+) -> Result<Option<ClipboardSnapshot>, OutputError> {
+    if env::var_os("WAYLAND_DISPLAY").is_none() {
+        return Ok(None);
     }
 
-    #[derive(Default)]
-    pub struct TestRunner {
-        pub commands: Vec<RecordedCommand>,
-        pub sleeps: Vec<Duration>,
-        outputs: Vec<Output>,
-        statuses: Vec<std::process::ExitStatus>,
+    let list_args = vec!["--list-types".to_string()];
+    let types = runner.output("wl-paste", &list_args).map_err(|err| {
+        if err.kind() == std::io::ErrorKind::NotFound {
+            OutputError::new("wl-paste not found; install wl-clipboard")
+        } else {
+            OutputError::new(format!("failed to run wl-paste: {err}"))
+        }
+    })?;
+    if !types.status.success() {
+        return Ok(None);
+    }
+    let mime_type = String::from_utf8_lossy(&types.stdout)
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .map(str::trim)
+        .unwrap_or_default()
+        .to_string();
+    if mime_type.is_empty() {
+        return Ok(None);
     }
 
-    impl TestRunner {
-        pub fn push_output(&mut self, status: i32, stdout: &[u8], stderr: &[u8]) {
-            self.outputs.push(Output {
-                status: std::process::ExitStatus::from_raw(status << 8),
-                stdout: stdout.to_vec(),
-                stderr: stderr.to_vec(),
-            });
+    let read_args = vec![
+        "--type".to_string(),
+        mime_type.clone(),
+        "--no-newline".to_string(),
+    ];
+    let content = runner
+        .output("wl-paste", &read_args)
+        .map_err(|err| OutputError::new(format!("failed to read clipboard: {err}")))?;
+    if !content.status.success() {
+        return Ok(None);
+    }
+    if content.stdout.len() > MAX_CLIPBOARD_BYTES {
+        return Err(OutputError::new(format!(
+            "clipboard content too large to preserve: {} bytes",
+            content.stdout.len()
+        )));
+    }
+
+    Ok(Some(ClipboardSnapshot {
+        mime_type,
+        data: content.stdout,
+    }))
+}
+
+fn restore_clipboard_snapshot(
+    snapshot: Option<&ClipboardSnapshot>,
+    runner: &mut dyn CommandRunner,
+) -> Result<(), OutputError> {
+    let (action, args, data) = match snapshot {
+        Some(snapshot) => (
+            "restore",
+            vec!["--type".to_string(), snapshot.mime_type.clone()],
+            snapshot.data.as_slice(),
+        ),
+        None => ("clear", vec!["--clear".to_string()], &[][..]),
+    };
+    let status = runner
+        .status_with_stdin("wl-copy", &args, data)
+        .map_err(|err| OutputError::new(format!("failed to {action} clipboard: {err}")))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(OutputError::new(format!(
+            "wl-copy {action} exited with status {status}"
+        )))
+    }
+}
+
+fn copy_plain_text(text: &str, runner: &mut dyn CommandRunner) -> Result<(), OutputError> {
+    let args = vec!["--type".to_string(), "text/plain".to_string()];
+    let status = runner
+        .status_with_stdin("wl-copy", &args, text.as_bytes())
+        .map_err(|err| OutputError::new(format!("failed to copy text: {err}")))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(OutputError::new(format!(
+            "wl-copy exited with status {status}"
+        )))
+    }
+}
+
+/// Typed after Esc to open a bracketed paste.
+const BRACKETED_PASTE_START: &str = "[200~";
+/// Typed after Esc to close a bracketed paste.
+const BRACKETED_PASTE_END: &str = "[201~";
+
+/// Surrounds a typing script with the bracketed paste start and end
+/// sequences. dotool cannot type a raw Esc, so it presses the key instead.
+fn bracketed_dotool_script(script: &str) -> String {
+    format!("key esc\ntype {BRACKETED_PASTE_START}\n{script}key esc\ntype {BRACKETED_PASTE_END}\n")
+}
+
+/// Presses Esc and types the rest of an escape sequence with ydotool.
+fn type_escape_ydotool(rest: &str, runner: &mut dyn CommandRunner) -> Result<(), OutputError> {
+    press_key_ydotool(YDOTOOL_ESC, 1, "escape", runner)?;
+    type_text_ydotool(rest, runner)
+}
+
+fn type_text_ydotool(text: &str, runner: &mut dyn CommandRunner) -> Result<(), OutputError> {
+    let args = vec![
+        "type".to_string(),
+        "--key-delay".to_string(),
+        "0".to_string(),
+        "--key-hold".to_string(),
+        "0".to_string(),
+        "--file".to_string(),
+        "-".to_string(),
+    ];
+    run_ydotool("typing", runner, |runner| {
+        runner
+            .status_with_stdin("ydotool", &args, text.as_bytes())
+            .map_err(|err| {
+                if err.kind() == std::io::ErrorKind::NotFound {
+                    OutputError::new("ydotool not found; install ydotool and enable ydotoold")
+                } else {
+                    OutputError::new(format!("failed to run ydotool: {err}"))
+                }
+            })
+    })
+}
+
+/// Types each character with its own ydotool call, sleeping a randomized
+/// delay between calls.
+fn type_text_ydotool_human(
+    text: &str,
+    cadence: &mut KeyCadence,
+    runner: &mut dyn CommandRunner,
+) -> Result<(), OutputError> {
+    let mut buf = [0u8; 4];
+    for (index, ch) in text.chars().filter(|&ch| ch != '\r').enumerate() {
+        if index > 0 {
+            runner.sleep(Duration::from_millis(cadence.next_delay_ms()));
         }
+        type_text_ydotool(ch.encode_utf8(&mut buf), runner)?;
+    }
+    Ok(())
+}
 
-        pub fn push_status(&mut self, status: i32) {
-            self.statuses
-                .push(std::process::ExitStatus::from_raw(status << 8));
+/// Randomized inter-key delays drawn uniformly from `mean ± jitter`.
+/// A xorshift generator is plenty for keystroke jitter.
+struct KeyCadence {
+    mean_ms: u64,
+    jitter_ms: u64,
+    state: u64,
+}
+
+impl KeyCadence {
+    fn new(mean_ms: u64, jitter_ms: u64, seed: u64) -> Self {
+        Self {
+            mean_ms,
+            jitter_ms,
+            state: seed | 1,
         }
     }
 
-    impl CommandRunner for TestRunner {
-        fn output(&mut self, program: &str, args: &[String]) -> Result<Output, std::io::Error> {
-            self.commands.push(RecordedCommand {
-                program: program.to_string(),
-                args: args.to_vec(),
-                stdin: Vec::new(),
-            });
-            Ok(self.outputs.remove(0))
+    fn next_delay_ms(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        let low = self.mean_ms.saturating_sub(self.jitter_ms);
+        let high = self.mean_ms.saturating_add(self.jitter_ms);
+        match (high - low).checked_add(1) {
+            Some(span) => low + self.state % span,
+            None => low.saturating_add(self.state),
         }
+    }
+}
 
-        fn status_with_stdin(
-            &mut self,
-            program: &str,
-            args: &[String],
-            stdin: &[u8],
-        ) -> Result<std::process::ExitStatus, std::io::Error> {
-            self.commands.push(RecordedCommand {
-                program: program.to_string(),
-                args: args.to_vec(),
-                stdin: stdin.to_vec(),
-            });
-            Ok(self.statuses.remove(0))
+fn random_seed() -> u64 {
+    use std::hash::BuildHasher;
+    std::collections::hash_map::RandomState::new().hash_one(std::process::id())
+}
+
+fn run_dotool(
+    script: &str,
+    action: &str,
+    runner: &mut dyn CommandRunner,
+) -> Result<(), OutputError> {
+    let status = runner
+        .status_with_stdin("dotool", &[], script.as_bytes())
+        .map_err(|err| {
+            if err.kind() == std::io::ErrorKind::NotFound {
+                OutputError::new("dotool not found; install dotool")
+            } else {
+                OutputError::new(format!("failed to run dotool: {err}"))
+            }
+        })?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(OutputError::new(format!(
+            "dotool {action} exited with status {status}"
+        )))
+    }
+}
+
+fn dotool_type_script(text: &str) -> String {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for ch in text.chars() {
+        match ch {
+            '\n' => {
+                if !current.is_empty() {
+                    lines.push(format!("type {current}"));
+                    current.clear();
+                }
+                lines.push("key enter".to_string());
+            }
+            '\r' => {}
+            _ => current.push(ch),
+        }
+    }
+    if !current.is_empty() {
+        lines.push(format!("type {current}"));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// A dotool script that types one character per command, setting a fresh
+/// `typedelay` (or `keydelay` for Enter) before each.
+fn dotool_human_script(text: &str, cadence: &mut KeyCadence) -> String {
+    let mut lines = Vec::new();
+    for ch in text.chars() {
+        match ch {
+            '\n' => {
+                lines.push(format!("keydelay {}", cadence.next_delay_ms()));
+                lines.push("key enter".to_string());
+            }
+            '\r' => {}
+            _ => {
+                lines.push(format!("typedelay {}", cadence.next_delay_ms()));
+                lines.push(format!("type {ch}"));
+            }
+        }
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn send_paste_key_dotool(
+    key: &ParsedPasteKey,
+    runner: &mut dyn CommandRunner,
+) -> Result<(), OutputError> {
+    run_dotool(&key.to_dotool_script(), "paste key", runner)
+}
+
+impl ParsedPasteKey {
+    fn parse(value: &str) -> Result<Self, OutputError> {
+        let parts: Vec<&str> = value.split('+').map(str::trim).collect();
+        if parts.is_empty() || parts.iter().any(|part| part.is_empty()) {
+            return Err(OutputError::new("paste_keys has invalid format"));
+        }
+
+        let key = KeyName::parse(parts[parts.len() - 1])?;
+        let modifiers = parts[..parts.len() - 1]
+            .iter()
+            .map(|part| KeyName::parse_modifier(part))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { modifiers, key })
+    }
+
+    /// Replaces the chord's modifiers with raw keycodes, for layouts where
+    /// the named modifiers land on the wrong physical keys.
+    fn with_modifier_keycodes(mut self, keycodes: Option<&[u16]>) -> Result<Self, OutputError> {
+        let Some(keycodes) = keycodes else {
+            return Ok(self);
+        };
+        if keycodes.contains(&0) {
+            return Err(OutputError::new(
+                "paste_modifier_keycodes must not contain 0",
+            ));
         }
+        self.modifiers = keycodes.iter().map(|&code| KeyName::Code(code)).collect();
+        Ok(self)
+    }
+
+    /// Replaces the chord's final key with a raw keycode, for physical
+    /// layouts where paste is not bound to the key named in `paste_keys`.
+    fn with_keycode(mut self, keycode: Option<u16>) -> Result<Self, OutputError> {
+        match keycode {
+            None => Ok(self),
+            Some(0) => Err(OutputError::new("paste_keycode must not be 0")),
+            Some(code) => {
+                self.key = KeyName::Code(code);
+                Ok(self)
+            }
+        }
+    }
+
+    fn to_dotool_script(&self) -> String {
+        let mut lines = Vec::new();
+        for modifier in &self.modifiers {
+            lines.push(format!("keydown {}", modifier.dotool_name()));
+        }
+        lines.push(format!("key {}", self.key.dotool_name()));
+        for modifier in self.modifiers.iter().rev() {
+            lines.push(format!("keyup {}", modifier.dotool_name()));
+        }
+        lines.push(String::new());
+        lines.join("\n")
+    }
+}
+
+impl KeyName {
+    fn parse(value: &str) -> Result<Self, OutputError> {
+        match value.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" | "leftctrl" => Ok(Self::Ctrl),
+            "shift" | "leftshift" => Ok(Self::Shift),
+            "alt" | "leftalt" => Ok(Self::Alt),
+            "super" | "meta" | "win" | "leftmeta" => Ok(Self::Super),
+            "v" => Ok(Self::V),
+            "insert" | "ins" => Ok(Self::Insert),
+            "enter" | "return" => Ok(Self::Enter),
+            other => Err(OutputError::new(format!("unknown paste key: {other}"))),
+        }
+    }
+
+    fn parse_modifier(value: &str) -> Result<Self, OutputError> {
+        let key = Self::parse(value)?;
+        match key {
+            Self::Ctrl | Self::Shift | Self::Alt | Self::Super => Ok(key),
+            _ => Err(OutputError::new(format!(
+                "paste key modifier expected, got {value}"
+            ))),
+        }
+    }
+
+    fn dotool_name(self) -> String {
+        match self {
+            Self::Ctrl => "leftctrl".to_string(),
+            Self::Shift => "leftshift".to_string(),
+            Self::Alt => "leftalt".to_string(),
+            Self::Super => "leftmeta".to_string(),
+            Self::V => "v".to_string(),
+            Self::Insert => "insert".to_string(),
+            Self::Enter => "enter".to_string(),
+            Self::Code(code) => format!("k:{code}"),
+        }
+    }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support {
+    use std::os::unix::process::ExitStatusExt;
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct RecordedCommand {
+        pub program: String,
+        pub args: Vec<String>,
+        pub stdin: Vec<u8>,
+    }
+
+    #[derive(Default)]
+    pub struct TestRunner {
+        pub commands: Vec<RecordedCommand>,
+        pub sleeps: Vec<Duration>,
+        pub clipboard_managers: Vec<&'static str>,
+        pub focused_window: Option<bool>,
+        pub focused_window_class: Option<String>,
+        /// Programs `has_program` reports as not installed.
+        pub missing_programs: Vec<&'static str>,
+        pub lock_state: Option<LockState>,
+        /// Answers to `modifiers_held`, in order; afterwards none are held.
+        pub modifiers_held: Vec<bool>,
+        pub ydotool_socket_missing: bool,
+        outputs: Vec<Output>,
+        statuses: Vec<std::process::ExitStatus>,
+    }
+
+    impl TestRunner {
+        pub fn push_output(&mut self, status: i32, stdout: &[u8], stderr: &[u8]) {
+            self.outputs.push(Output {
+                status: std::process::ExitStatus::from_raw(status << 8),
+                stdout: stdout.to_vec(),
+                stderr: stderr.to_vec(),
+            });
+        }
+
+        pub fn push_status(&mut self, status: i32) {
+            self.statuses
+                .push(std::process::ExitStatus::from_raw(status << 8));
+        }
+    }
+
+    impl CommandRunner for TestRunner {
+        fn output(&mut self, program: &str, args: &[String]) -> Result<Output, std::io::Error> {
+            self.commands.push(RecordedCommand {
+                program: program.to_string(),
+                args: args.to_vec(),
+                stdin: Vec::new(),
+            });
+            Ok(self.outputs.remove(0))
+        }
+
+        fn status_with_stdin(
+            &mut self,
+            program: &str,
+            args: &[String],
+            stdin: &[u8],
+        ) -> Result<std::process::ExitStatus, std::io::Error> {
+            self.commands.push(RecordedCommand {
+                program: program.to_string(),
+                args: args.to_vec(),
+                stdin: stdin.to_vec(),
+            });
+            Ok(self.statuses.remove(0))
+        }
+
+        fn copy_temporary_text(
+            &mut self,
+            text: &str,
+            hide_from_history: bool,
+        ) -> Result<(), OutputError> {
+            let mut args = vec!["text/plain".to_string()];
+            if hide_from_history {
+                args.push(KDE_SECRET_MIME.to_string());
+            }
+            self.commands.push(RecordedCommand {
+                program: "temporary-clipboard-copy".to_string(),
+                args,
+                stdin: text.as_bytes().to_vec(),
+            });
+            Ok(())
+        }
+
+        fn sleep(&mut self, duration: Duration) {
+            self.sleeps.push(duration);
+        }
+
+        fn clipboard_managers(&mut self) -> Vec<&'static str> {
+            self.clipboard_managers.clone()
+        }
+
+        fn focused_window(&mut self) -> Option<bool> {
+            self.focused_window
+        }
+
+        fn focused_window_class(&mut self) -> Option<String> {
+            self.focused_window_class.clone()
+        }
+
+        fn has_program(&mut self, program: &str) -> bool {
+            !self.missing_programs.contains(&program)
+        }
+
+        fn lock_state(&mut self) -> Option<LockState> {
+            self.lock_state
+        }
+
+        fn modifiers_held(&mut self) -> Option<bool> {
+            if self.modifiers_held.is_empty() {
+                return Some(false);
+            }
+            Some(self.modifiers_held.remove(0))
+        }
+
+        fn has_ydotool_socket(&mut self) -> bool {
+            !self.ydotool_socket_missing
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::TestRunner;
+    use super::*;
+
+    struct EnvGuard {
+        key: &'static str,
+        previous: Option<std::ffi::OsString>,
+    }
+
+    impl EnvGuard {
+        fn set(key: &'static str, value: &str) -> Self {
+            let previous = env::var_os(key);
+            env::set_var(key, value);
+            Self { key, previous }
+        }
+    }
+
+    impl Drop for EnvGuard {
+        fn drop(&mut self) {
+            match &self.previous {
+                Some(value) => env::set_var(self.key, value),
+                None => env::remove_var(self.key),
+            }
+        }
+    }
+
+    #[test]
+    fn parses_output_backend_names() {
+        assert_eq!("auto".parse(), Ok(OutputBackend::Auto));
+        assert_eq!("Type".parse(), Ok(OutputBackend::Mode(OutputMode::Type)));
+        assert!("x11".parse::<OutputBackend>().is_err());
+        assert_eq!(
+            OutputBackend::Mode(OutputMode::Ydotool).to_string(),
+            "ydotool"
+        );
+    }
+
+    #[test]
+    fn paste_needs_the_clipboard_feature() {
+        assert_eq!(
+            OutputMode::Paste.supported() == OutputMode::Paste,
+            cfg!(feature = "clipboard")
+        );
+        assert_eq!(OutputMode::Clipboard.supported(), OutputMode::Clipboard);
+        assert_eq!(
+            OutputMode::Portal.supported() == OutputMode::Portal,
+            cfg!(feature = "portal")
+        );
+        assert_eq!(
+            OutputBackend::Mode(OutputMode::Type).resolve(true),
+            OutputMode::Type
+        );
+    }
+
+    #[test]
+    fn classifies_compositors_from_the_environment() {
+        let session = |vars: &'static [&'static str]| move |name: &str| vars.contains(&name);
+
+        assert_eq!(
+            compositor_from_env(session(&["DISPLAY"])),
+            Some(Compositor::X11)
+        );
+        assert_eq!(compositor_from_env(session(&[])), Some(Compositor::Unknown));
+        assert_eq!(
+            compositor_from_env(session(&["WAYLAND_DISPLAY", "DISPLAY", "SWAYSOCK"])),
+            Some(Compositor::Wlroots)
+        );
+        assert_eq!(compositor_from_env(session(&["WAYLAND_DISPLAY"])), None);
+
+        assert_eq!(compositor_from_desktop("KDE"), Compositor::Kde);
+        assert_eq!(compositor_from_desktop("ubuntu:GNOME"), Compositor::Gnome);
+        assert_eq!(compositor_from_desktop("Hyprland"), Compositor::Wlroots);
+        assert_eq!(compositor_from_desktop("niri"), Compositor::Unknown);
+        assert_eq!(compositor_from_desktop(""), Compositor::Unknown);
+    }
+
+    #[test]
+    fn key_cadence_stays_within_jitter() {
+        let mut cadence = KeyCadence::new(80, 30, 42);
+        let delays: Vec<u64> = (0..200).map(|_| cadence.next_delay_ms()).collect();
+
+        assert!(delays.iter().all(|delay| (50..=110).contains(delay)));
+        assert!(delays.iter().any(|&delay| delay != delays[0]));
+        let mut short = KeyCadence::new(5, 20, 7);
+        assert!((0..100).all(|_| short.next_delay_ms() <= 25));
+        assert_eq!(KeyCadence::new(40, 0, 7).next_delay_ms(), 40);
+    }
+
+    #[test]
+    fn human_dotool_script_types_one_character_per_command() {
+        let mut cadence = KeyCadence::new(60, 0, 1);
+
+        assert_eq!(
+            dotool_human_script("a b\r\n", &mut cadence),
+            "typedelay 60\ntype a\ntypedelay 60\ntype  \ntypedelay 60\ntype b\n\
+             keydelay 60\nkey enter\n"
+        );
+    }
+
+    #[test]
+    fn human_ydotool_typing_sleeps_between_characters() {
+        let mut runner = TestRunner::default();
+        for _ in 0..3 {
+            runner.push_status(0);
+        }
+        let config = OutputConfig {
+            mode: OutputMode::Ydotool,
+            type_mode: TypeMode::Human {
+                mean_delay_ms: 100,
+                jitter_ms: 20,
+            },
+            ..OutputConfig::default()
+        };
+
+        output_text_with_runner("hi!", &config, &mut runner).expect("typing should succeed");
+
+        let typed: Vec<&[u8]> = runner
+            .commands
+            .iter()
+            .map(|command| command.stdin.as_slice())
+            .collect();
+        assert_eq!(typed, [b"h", b"i", b"!"]);
+        assert_eq!(runner.sleeps.len(), 2);
+        assert!(runner
+            .sleeps
+            .iter()
+            .all(|sleep| (80..=120).contains(&sleep.as_millis())));
+    }
+
+    #[test]
+    fn session_probes_look_for_display_sockets() {
+        let dir = env::temp_dir().join(format!("sv-session-probe-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create probe dir");
+        std::fs::write(dir.join("wayland-1.lock"), b"").expect("write lock");
+        std::fs::write(dir.join("X"), b"").expect("write file");
+
+        assert_eq!(wayland_sockets_in(&dir).count(), 0);
+        assert!(!x11_socket_in(&dir));
+
+        std::fs::write(dir.join("wayland-1"), b"").expect("write socket");
+        std::fs::write(dir.join("X0"), b"").expect("write socket");
+
+        assert_eq!(wayland_sockets_in(&dir).collect::<Vec<_>>(), ["wayland-1"]);
+        assert!(x11_socket_in(&dir));
+        assert_eq!(wayland_sockets_in(&dir.join("missing")).count(), 0);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn local_displays_map_to_their_x11_socket() {
+        assert_eq!(x11_display_socket(":0"), Some("X0".to_string()));
+        assert_eq!(x11_display_socket(":1.0"), Some("X1".to_string()));
+        assert_eq!(x11_display_socket("host:10.0"), None);
+    }
+
+    #[test]
+    fn detects_clipboard_managers_by_process_and_watcher() {
+        let dir = env::temp_dir().join(format!("sv-proc-probe-{}", std::process::id()));
+        for (pid, comm, cmdline) in [
+            ("1", "systemd", &b"/sbin/init\0"[..]),
+            (
+                "42",
+                "wl-paste",
+                b"wl-paste\0--watch\0/usr/bin/cliphist\0store\0",
+            ),
+            ("77", "copyq", b"copyq\0"),
+            ("self", "klipper", b""),
+        ] {
+            let process = dir.join(pid);
+            std::fs::create_dir_all(&process).expect("create process dir");
+            std::fs::write(process.join("comm"), format!("{comm}\n")).expect("write comm");
+            std::fs::write(process.join("cmdline"), cmdline).expect("write cmdline");
+        }
+
+        let mut managers = detect_clipboard_managers(&dir);
+        managers.sort_unstable();
+
+        assert_eq!(managers, ["cliphist", "copyq"]);
+        assert!(detect_clipboard_managers(&dir.join("missing")).is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn restore_delay_follows_running_clipboard_managers() {
+        let mut config = OutputConfig::default();
+
+        assert_eq!(restore_delay(&config, &[]), Duration::from_millis(250));
+        assert_eq!(
+            restore_delay(&config, &["cliphist"]),
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            restore_delay(&config, &["cliphist", "klipper"]),
+            Duration::from_millis(300)
+        );
+
+        config
+            .clipboard_manager_delays
+            .insert("klipper".to_string(), 500);
+        assert_eq!(
+            restore_delay(&config, &["klipper"]),
+            Duration::from_millis(500)
+        );
+
+        config.restore_clipboard_delay_ms = Some(50);
+        assert_eq!(
+            restore_delay(&config, &["cliphist", "copyq"]),
+            Duration::from_millis(50)
+        );
+        assert_eq!(
+            restore_delay(&config, &["klipper", "copyq"]),
+            Duration::from_millis(500)
+        );
+
+        config.restore_clipboard_delay_ms = Some(250);
+        assert_eq!(
+            restore_delay(&config, &["klipper"]),
+            Duration::from_millis(500)
+        );
+        assert_eq!(
+            restore_delay(&config, &["copyq"]),
+            Duration::from_millis(250)
+        );
+    }
+
+    #[test]
+    fn paste_mode_waits_for_detected_clipboard_manager_before_restore() {
+        let _guard = EnvGuard::set("WAYLAND_DISPLAY", "wayland-0");
+        let mut runner = TestRunner::default();
+        runner.clipboard_managers = vec!["cliphist"];
+        runner.push_output(0, b"text/plain\n", b"");
+        runner.push_output(0, b"old", b"");
+        runner.push_status(0);
+        runner.push_output(0, b"", b"");
+        runner.push_status(0);
+
+        output_text_with_runner("new text", &OutputConfig::default(), &mut runner)
+            .expect("paste should succeed");
+
+        assert_eq!(runner.sleeps.last(), Some(&Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn injection_without_focused_window_leaves_text_on_clipboard() {
+        let mut runner = TestRunner::default();
+        runner.focused_window = Some(false);
+        runner.push_status(0);
+
+        let err = output_text_with_runner("new text", &OutputConfig::default(), &mut runner)
+            .expect_err("injection should be refused");
+
+        assert_eq!(
+            err.to_string(),
+            "no focused window; transcript left on the clipboard"
+        );
+        assert_eq!(runner.commands.len(), 1);
+        assert_eq!(runner.commands[0].program, "wl-copy");
+        assert_eq!(runner.commands[0].args, ["--type", "text/plain"]);
+        assert_eq!(runner.commands[0].stdin, b"new text");
+    }
+
+    #[test]
+    fn missing_tool_fails_before_touching_the_clipboard() {
+        let mut runner = TestRunner::default();
+        runner.missing_programs = vec!["dotool"];
+        let config = OutputConfig::default();
+
+        let err = output_text_with_runner("new text", &config, &mut runner)
+            .expect_err("paste should be unavailable");
+
+        assert_eq!(err.kind, OutputErrorKind::Unavailable);
+        assert_eq!(
+            err.to_string(),
+            "paste output unavailable: dotool not found in PATH"
+        );
+        assert!(runner.commands.is_empty());
+    }
+
+    #[test]
+    fn paste_with_restore_needs_wl_copy() {
+        let mut runner = TestRunner::default();
+        runner.missing_programs = vec!["wl-copy"];
+        let config = OutputConfig {
+            pre_paste_delay_ms: 0,
+            ..OutputConfig::default()
+        };
+
+        let err = output_text_with_runner("new text", &config, &mut runner)
+            .expect_err("restoring needs wl-copy");
+
+        assert_eq!(
+            err.to_string(),
+            "paste output unavailable: wl-copy not found in PATH"
+        );
+        assert!(runner.commands.is_empty());
+    }
+
+    #[test]
+    fn paste_without_restore_does_not_need_wl_paste() {
+        let mut runner = TestRunner::default();
+        runner.missing_programs = vec!["wl-copy", "wl-paste"];
+        runner.push_status(0);
+        let config = OutputConfig {
+            restore_clipboard: false,
+            pre_paste_delay_ms: 0,
+            ..OutputConfig::default()
+        };
+
+        output_text_with_runner("new text", &config, &mut runner).expect("paste should succeed");
+
+        assert_eq!(
+            runner.commands.last().map(|c| c.program.as_str()),
+            Some("dotool")
+        );
+    }
+
+    #[test]
+    fn primary_selection_paste_leaves_the_clipboard_alone() {
+        let mut runner = TestRunner::default();
+        runner.missing_programs = vec!["wl-paste"];
+        runner.push_status(0);
+        runner.push_status(0);
+        let config = OutputConfig {
+            paste_via: PasteVia::PrimarySelection,
+            pre_paste_delay_ms: 0,
+            ..OutputConfig::default()
+        };
+
+        output_text_with_runner("new text", &config, &mut runner).expect("paste should succeed");
+
+        assert_eq!(runner.commands.len(), 2);
+        assert_eq!(runner.commands[0].program, "wl-copy");
+        assert_eq!(
+            runner.commands[0].args,
+            ["--primary", "--type", "text/plain"]
+        );
+        assert_eq!(runner.commands[0].stdin, b"new text");
+        assert_eq!(runner.commands[1].program, "dotool");
+        assert_eq!(runner.commands[1].stdin, b"click middle\n");
+    }
+
+    #[test]
+    fn release_caps_lock_wraps_injection_in_two_presses() {
+        let mut runner = TestRunner::default();
+        runner.lock_state = Some(LockState {
+            caps_lock: true,
+            num_lock: false,
+        });
+        for _ in 0..3 {
+            runner.push_status(0);
+        }
+        let config = OutputConfig {
+            mode: OutputMode::Type,
+            release_caps_lock: true,
+            ..OutputConfig::default()
+        };
+
+        output_text_with_runner("hi", &config, &mut runner).expect("type should succeed");
+
+        let scripts: Vec<&[u8]> = runner.commands.iter().map(|c| &c.stdin[..]).collect();
+        assert_eq!(
+            scripts,
+            [&b"key capslock\n"[..], b"type hi\n", b"key capslock\n"]
+        );
+    }
+
+    #[test]
+    fn typing_waits_until_modifiers_are_released() {
+        let mut runner = TestRunner::default();
+        runner.modifiers_held = vec![true, true];
+        runner.push_status(0);
+        let config = OutputConfig {
+            mode: OutputMode::Type,
+            modifier_release_wait_ms: 1_000,
+            ..OutputConfig::default()
+        };
+
+        output_text_with_runner("hi", &config, &mut runner).expect("type should succeed");
+
+        assert_eq!(runner.sleeps, [MODIFIER_POLL_INTERVAL; 2]);
+        assert_eq!(runner.commands.len(), 1);
+        assert_eq!(runner.commands[0].stdin, b"type hi\n");
+
+        let mut runner = TestRunner::default();
+        runner.modifiers_held = vec![true; 10];
+        runner.push_status(0);
+        let config = OutputConfig {
+            modifier_release_wait_ms: 30,
+            ..config
+        };
+
+        output_text_with_runner("hi", &config, &mut runner).expect("type should succeed");
+
+        assert_eq!(runner.sleeps.len(), 3, "gives up after the wait");
+        assert_eq!(runner.commands[0].stdin, b"type hi\n");
+    }
+
+    #[test]
+    fn probe_backend_tells_missing_tools_from_broken_ones() {
+        let dir = env::temp_dir().join(format!("sv-probe-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create probe dir");
+        let uinput = dir.join("uinput");
+        std::fs::write(&uinput, b"").expect("write fake uinput");
+        let stale_socket = dir.join("stale.sock");
+        std::fs::write(&stale_socket, b"").expect("write stale socket");
+        let live_socket = dir.join("live.sock");
+        let _ydotoold =
+            std::os::unix::net::UnixDatagram::bind(&live_socket).expect("bind ydotool socket");
+        let mut runner = TestRunner::default();
+        let probe = |mode, runner: &mut TestRunner, socket: &Path| {
+            probe_backend_with(mode, runner, &uinput, socket)
+        };
+
+        assert_eq!(
+            probe(OutputMode::Type, &mut runner, &live_socket),
+            BackendStatus::Available
+        );
+        assert_eq!(
+            probe(OutputMode::Ydotool, &mut runner, &live_socket),
+            BackendStatus::Available
+        );
+        assert!(matches!(
+            probe(OutputMode::Ydotool, &mut runner, &stale_socket),
+            BackendStatus::InstalledButNonFunctional(reason) if reason.contains("not answering")
+        ));
+        assert!(matches!(
+            probe_backend_with(OutputMode::Type, &mut runner, &dir.join("missing"), &live_socket),
+            BackendStatus::InstalledButNonFunctional(reason) if reason.contains("dotool cannot write")
+        ));
+        runner.missing_programs = vec!["dotool"];
+        assert_eq!(
+            probe(OutputMode::Type, &mut runner, &live_socket),
+            BackendStatus::Unavailable("dotool not found in PATH".to_string())
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn lock_state_reads_keyboard_leds() {
+        let dir = env::temp_dir().join(format!("sv-leds-{}", std::process::id()));
+        for (led, brightness) in [
+            ("input3::capslock", "0\n"),
+            ("input7::capslock", "1\n"),
+            ("input3::numlock", "0\n"),
+            ("input3::scrolllock", "1\n"),
+        ] {
+            std::fs::create_dir_all(dir.join(led)).expect("create led dir");
+            std::fs::write(dir.join(led).join("brightness"), brightness).expect("write led");
+        }
+
+        assert_eq!(
+            lock_state_in(&dir),
+            Some(LockState {
+                caps_lock: true,
+                num_lock: false,
+            })
+        );
+        assert_eq!(lock_state_in(&dir.join("missing")), None);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn focus_check_can_be_disabled() {
+        let mut runner = TestRunner::default();
+        runner.focused_window = Some(false);
+        runner.push_status(0);
+        let config = OutputConfig {
+            mode: OutputMode::Type,
+            require_focused_window: false,
+            ..OutputConfig::default()
+        };
+
+        output_text_with_runner("new text", &config, &mut runner).expect("type should succeed");
+
+        assert_eq!(runner.commands[0].program, "dotool");
+    }
+
+    #[test]
+    fn parses_focused_window_queries() {
+        assert_eq!(hyprland_has_focus("{}"), Some(false));
+        assert_eq!(
+            hyprland_has_focus(r#"{"address": "0x55d1", "class": "kitty"}"#),
+            Some(true)
+        );
+        assert_eq!(hyprland_has_focus("not json"), None);
+
+        let empty_workspace = r#"{"type": "root", "focused": false, "nodes": [
+            {"type": "output", "focused": false, "nodes": [
+                {"type": "workspace", "focused": true, "nodes": []}]}]}"#;
+        let floating_window = r#"{"type": "root", "focused": false, "nodes": [
+            {"type": "workspace", "focused": false, "nodes": [],
+             "floating_nodes": [{"type": "floating_con", "focused": true}]}]}"#;
+        assert_eq!(sway_has_focus(empty_workspace), Some(false));
+        assert_eq!(sway_has_focus(floating_window), Some(true));
+
+        assert_eq!(window_id_has_focus("62914566\n"), Some(true));
+        assert_eq!(window_id_has_focus("0\n"), Some(false));
+        assert_eq!(window_id_has_focus(""), Some(false));
+    }
+
+    #[test]
+    fn parses_focused_window_ids() {
+        assert_eq!(
+            hyprland_window_id(r#"{"address": "0x55d1", "class": "kitty"}"#),
+            Some("0x55d1".to_string())
+        );
+        assert_eq!(hyprland_window_id("{}"), None);
+
+        let window = r#"{"type": "root", "id": 1, "focused": false, "nodes": [
+            {"type": "con", "id": 17, "focused": true, "app_id": "foot"}]}"#;
+        let empty_workspace = r#"{"type": "root", "id": 1, "focused": false, "nodes": [
+            {"type": "workspace", "id": 4, "focused": true, "nodes": []}]}"#;
+        assert_eq!(sway_window_id(window), Some("17".to_string()));
+        assert_eq!(sway_window_id(empty_workspace), None);
+
+        assert_eq!(plain_window_id("62914566\n"), Some("62914566".to_string()));
+        assert_eq!(plain_window_id("0\n"), None);
+    }
+
+    #[test]
+    fn parses_focused_window_classes() {
+        assert_eq!(
+            hyprland_window_class(r#"{"address": "0x55d1", "class": "kitty"}"#),
+            Some("kitty".to_string())
+        );
+        assert_eq!(hyprland_window_class("{}"), None);
+
+        let native = r#"{"type": "root", "focused": false, "nodes": [
+            {"type": "con", "focused": true, "app_id": "foot"}]}"#;
+        let xwayland = r#"{"type": "root", "focused": false, "nodes": [
+            {"type": "con", "focused": true, "app_id": null,
+             "window_properties": {"class": "XTerm"}}]}"#;
+        assert_eq!(sway_window_class(native), Some("foot".to_string()));
+        assert_eq!(sway_window_class(xwayland), Some("XTerm".to_string()));
+
+        assert_eq!(
+            plain_window_class("org.kde.konsole\n"),
+            Some("org.kde.konsole".to_string())
+        );
+        assert_eq!(plain_window_class("\n"), None);
+    }
+
+    #[test]
+    fn bracketed_paste_pastes_into_terminals() {
+        if OutputMode::Paste.supported() != OutputMode::Paste {
+            return;
+        }
+        for mode in [OutputMode::Type, OutputMode::Ydotool] {
+            let mut runner = TestRunner::default();
+            runner.focused_window_class = Some("kitty".to_string());
+            runner.push_status(0);
+            let config = OutputConfig {
+                mode,
+                bracketed_paste: true,
+                restore_clipboard: false,
+                ..OutputConfig::default()
+            };
+
+            output_text_with_runner("ls -la\n", &config, &mut runner)
+                .expect("paste should succeed");
+
+            assert_eq!(runner.commands[0].program, "temporary-clipboard-copy");
+            assert_eq!(runner.commands[0].stdin, b"ls -la\n");
+            assert_eq!(
+                runner.commands[1].stdin,
+                b"keydown leftctrl\nkeydown leftshift\nkey v\nkeyup leftshift\nkeyup leftctrl\n"
+            );
+        }
+    }
+
+    #[test]
+    fn bracketed_paste_wraps_typing_in_terminals() {
+        let mut runner = TestRunner::default();
+        // Without the clipboard tools the sequences are typed instead.
+        runner.missing_programs = vec!["wl-paste"];
+        runner.focused_window_class = Some("kitty".to_string());
+        runner.push_status(0);
+        let config = OutputConfig {
+            mode: OutputMode::Type,
+            bracketed_paste: true,
+            ..OutputConfig::default()
+        };
+
+        output_text_with_runner("ls -la", &config, &mut runner).expect("typing should succeed");
+
+        assert_eq!(
+            runner.commands[0].stdin,
+            b"key esc\ntype [200~\ntype ls -la\nkey esc\ntype [201~\n"
+        );
+
+        runner.focused_window_class = Some("firefox".to_string());
+        runner.push_status(0);
+        output_text_with_runner("ls -la", &config, &mut runner).expect("typing should succeed");
+        assert_eq!(runner.commands[1].stdin, b"type ls -la\n");
+    }
+
+    #[test]
+    fn bracketed_paste_presses_escape_with_ydotool() {
+        let mut runner = TestRunner::default();
+        runner.missing_programs = vec!["wl-paste"];
+        runner.focused_window_class = Some("foot".to_string());
+        runner.push_output(0, b"", b"");
+        runner.push_status(0);
+        runner.push_status(0);
+        runner.push_output(0, b"", b"");
+        runner.push_status(0);
+        let config = OutputConfig {
+            mode: OutputMode::Ydotool,
+            bracketed_paste: true,
+            ..OutputConfig::default()
+        };
+
+        output_text_with_runner("echo", &config, &mut runner).expect("typing should succeed");
+
+        let calls: Vec<(&str, &[u8])> = runner
+            .commands
+            .iter()
+            .map(|command| (command.args[0].as_str(), command.stdin.as_slice()))
+            .collect();
+        assert_eq!(
+            calls,
+            [
+                ("key", &b""[..]),
+                ("type", b"[200~"),
+                ("type", b"echo"),
+                ("key", b""),
+                ("type", b"[201~"),
+            ]
+        );
+        assert_eq!(runner.commands[0].args[3..], ["1:1", "1:0"]);
+    }
+
+    #[test]
+    fn bracketed_paste_is_closed_when_ydotool_typing_fails() {
+        let mut runner = TestRunner::default();
+        runner.missing_programs = vec!["wl-paste"];
+        runner.focused_window_class = Some("foot".to_string());
+        runner.push_output(0, b"", b"");
+        runner.push_status(0);
+        runner.push_status(1);
+        runner.push_output(0, b"", b"");
+        runner.push_status(0);
+        let config = OutputConfig {
+            mode: OutputMode::Ydotool,
+            bracketed_paste: true,
+            ..OutputConfig::default()
+        };
 
-        fn copy_temporary_text(&mut self, text: &str) -> Result<(), OutputError> {
-            self.commands.push(RecordedCommand {
-                program: "temporary-clipboard-copy".to_string(),
-                args: vec!["text/plain".to_string(), KDE_SECRET_MIME.to_string()],
-                stdin: text.as_bytes().to_vec(),
-            });
-            Ok(())
-        }
+        let err =
+            output_text_with_runner("echo", &config, &mut runner).expect_err("typing should fail");
 
-        fn sleep(&mut self, duration: Duration) {
-            self.sleeps.push(duration);
-        }
+        assert!(err.to_string().contains("ydotool typing exited"), "{err}");
+        let last = runner.commands.last().expect("commands ran");
+        assert_eq!(last.stdin, b"[201~");
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::test_support::TestRunner;
-    use super::*;
+    #[test]
+    fn clipboard_snapshot_round_trips_arbitrary_bytes() {
+        let _guard = EnvGuard::set("WAYLAND_DISPLAY", "wayland-0");
+        let data: Vec<u8> = (0..=255).chain([0xff, 0xfe, 0x00, 0xc3]).collect();
+        let mut runner = TestRunner::default();
+        runner.push_output(0, b"image/png\ntext/plain\n", b"");
+        runner.push_output(0, &data, b"");
+        runner.push_status(0);
 
-    struct EnvGuard {
-        key: &'static str,
-        previous: Option<std::ffi::OsString>,
-    }
+        let snapshot = read_clipboard_snapshot(&mut runner).expect("snapshot should succeed");
+        restore_clipboard_snapshot(snapshot.as_ref(), &mut runner).expect("restore should succeed");
 
-    impl EnvGuard {
-        fn set(key: &'static str, value: &str) -> Self {
-            let previous = env::var_os(key);
-            env::set_var(key, value);
-            Self { key, previous }
-        }
+        let restore = runner.commands.last().expect("restore command");
+        assert_eq!(restore.args, ["--type", "image/png"]);
+        assert_eq!(restore.stdin, data);
     }
 
-    impl Drop for EnvGuard {
-        fn drop(&mut self) {
-            match &self.previous {
-                Some(value) => env::set_var(self.key, value),
-                None => env::remove_var(self.key),
-            }
+    #[test]
+    fn clipboard_guard_restores_after_its_delay_when_dropped() {
+        let _guard = EnvGuard::set("WAYLAND_DISPLAY", "wayland-0");
+        let mut runner = TestRunner::default();
+        runner.push_output(0, b"text/plain\n", b"");
+        runner.push_output(0, b"saved", b"");
+        runner.push_status(0);
+        runner.push_status(0);
+
+        {
+            let mut clipboard = ClipboardGuard::save_with(&mut runner).expect("save clipboard");
+            clipboard.set_restore_delay(Duration::from_millis(80));
+            copy_plain_text("temporary", clipboard.runner()).expect("copy");
         }
+
+        assert_eq!(runner.sleeps, [Duration::from_millis(80)]);
+        let restore = runner.commands.last().expect("restore command");
+        assert_eq!(restore.args, ["--type", "text/plain"]);
+        assert_eq!(restore.stdin, b"saved");
     }
 
     #[test]
@@ -559,7 +2875,7 @@ mod tests {
     fn paste_mode_rejects_invalid_paste_keys_before_changing_clipboard() {
         let mut runner = TestRunner::default();
         let config = OutputConfig {
-            paste_keys: "ctrl+".to_string(),
+            paste_keys: Some("ctrl+".to_string()),
             ..OutputConfig::default()
         };
 
@@ -592,6 +2908,234 @@ mod tests {
         assert_eq!(restore.stdin, b"old");
     }
 
+    #[test]
+    fn failed_paste_key_can_restore_without_delay() {
+        let _guard = EnvGuard::set("WAYLAND_DISPLAY", "wayland-0");
+        let mut runner = TestRunner::default();
+        runner.push_output(0, b"text/plain\n", b"");
+        runner.push_output(0, b"old", b"");
+        runner.push_status(1);
+        runner.push_status(0);
+        let config = OutputConfig {
+            restore_immediately_on_paste_failure: true,
+            ..OutputConfig::default()
+        };
+
+        output_text_with_runner("new text", &config, &mut runner)
+            .expect_err("paste key failure should be reported");
+
+        assert_eq!(runner.sleeps, [Duration::from_millis(100)]);
+        let restore = runner.commands.last().expect("restore command");
+        assert_eq!(restore.program, "wl-copy");
+        assert_eq!(restore.stdin, b"old");
+    }
+
+    #[test]
+    fn paste_mode_restores_clipboard_when_paste_unwinds() {
+        struct PanicOnDotool(TestRunner);
+
+        impl CommandRunner for PanicOnDotool {
+            fn output(&mut self, program: &str, args: &[String]) -> Result<Output, std::io::Error> {
+                self.0.output(program, args)
+            }
+
+            fn status_with_stdin(
+                &mut self,
+                program: &str,
+                args: &[String],
+                stdin: &[u8],
+            ) -> Result<std::process::ExitStatus, std::io::Error> {
+                assert_ne!(program, "dotool", "interrupted during paste");
+                self.0.status_with_stdin(program, args, stdin)
+            }
+
+            fn copy_temporary_text(
+                &mut self,
+                text: &str,
+                hide_from_history: bool,
+            ) -> Result<(), OutputError> {
+                self.0.copy_temporary_text(text, hide_from_history)
+            }
+
+            fn sleep(&mut self, duration: Duration) {
+                self.0.sleep(duration);
+            }
+
+            fn clipboard_managers(&mut self) -> Vec<&'static str> {
+                self.0.clipboard_managers()
+            }
+
+            fn focused_window(&mut self) -> Option<bool> {
+                self.0.focused_window()
+            }
+
+            fn focused_window_class(&mut self) -> Option<String> {
+                self.0.focused_window_class()
+            }
+
+            fn has_program(&mut self, program: &str) -> bool {
+                self.0.has_program(program)
+            }
+
+            fn lock_state(&mut self) -> Option<LockState> {
+                self.0.lock_state()
+            }
+
+            fn modifiers_held(&mut self) -> Option<bool> {
+                self.0.modifiers_held()
+            }
+
+            fn has_ydotool_socket(&mut self) -> bool {
+                self.0.has_ydotool_socket()
+            }
+        }
+
+        let _guard = EnvGuard::set("WAYLAND_DISPLAY", "wayland-0");
+        let mut runner = PanicOnDotool(TestRunner::default());
+        runner.0.push_output(0, b"text/plain\n", b"");
+        runner.0.push_output(0, b"old", b"");
+        runner.0.push_status(0);
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            output_text_with_runner("new text", &OutputConfig::default(), &mut runner)
+        }));
+
+        assert!(result.is_err());
+        let restore = runner.0.commands.last().expect("restore command");
+        assert_eq!(restore.program, "wl-copy");
+        assert_eq!(restore.args, ["--type", "text/plain"]);
+        assert_eq!(restore.stdin, b"old");
+    }
+
+    #[test]
+    fn paste_modifier_keycodes_replace_named_modifiers() {
+        let mut runner = TestRunner::default();
+        runner.push_status(0);
+        let config = OutputConfig {
+            restore_clipboard: false,
+            paste_modifier_keycodes: Some(vec![125, 42]),
+            ..OutputConfig::default()
+        };
+
+        output_text_with_runner("new text", &config, &mut runner).expect("paste should succeed");
+
+        let paste = runner.commands.last().expect("paste command");
+        assert_eq!(paste.program, "dotool");
+        assert_eq!(
+            String::from_utf8_lossy(&paste.stdin),
+            "keydown k:125\nkeydown k:42\nkey v\nkeyup k:42\nkeyup k:125\n"
+        );
+
+        let config = OutputConfig {
+            paste_modifier_keycodes: Some(vec![0]),
+            ..config
+        };
+        assert!(output_text_with_runner("new text", &config, &mut runner).is_err());
+    }
+
+    #[test]
+    fn paste_keycodes_leave_the_terminal_chord_alone() {
+        let mut runner = TestRunner::default();
+        runner.focused_window_class = Some("kitty".to_string());
+        runner.push_status(0);
+        let config = OutputConfig {
+            restore_clipboard: false,
+            paste_modifier_keycodes: Some(vec![125]),
+            paste_keycode: Some(52),
+            ..OutputConfig::default()
+        };
+
+        output_text_with_runner("new text", &config, &mut runner).expect("paste should succeed");
+
+        let paste = runner.commands.last().expect("paste command");
+        assert_eq!(
+            String::from_utf8_lossy(&paste.stdin),
+            "keydown leftctrl\nkeydown leftshift\nkey v\nkeyup leftshift\nkeyup leftctrl\n"
+        );
+    }
+
+    #[test]
+    fn paste_mode_sends_the_terminal_chord_in_known_terminals() {
+        for (class, script) in [
+            (
+                "com.mitchellh.ghostty",
+                "keydown leftctrl\nkeydown leftshift\nkey v\nkeyup leftshift\nkeyup leftctrl\n",
+            ),
+            (
+                r#""konsole", "konsole""#,
+                "keydown leftctrl\nkeydown leftshift\nkey v\nkeyup leftshift\nkeyup leftctrl\n",
+            ),
+            ("XTerm", "keydown leftctrl\nkey v\nkeyup leftctrl\n"),
+            ("firefox", "keydown leftctrl\nkey v\nkeyup leftctrl\n"),
+        ] {
+            let mut runner = TestRunner::default();
+            runner.focused_window_class = Some(class.to_string());
+            runner.push_status(0);
+            let config = OutputConfig {
+                restore_clipboard: false,
+                ..OutputConfig::default()
+            };
+
+            output_text_with_runner("new text", &config, &mut runner)
+                .expect("paste should succeed");
+
+            let paste = runner.commands.last().expect("paste command");
+            assert_eq!(String::from_utf8_lossy(&paste.stdin), script, "{class}");
+        }
+    }
+
+    #[test]
+    fn configured_paste_keys_win_over_the_terminal_chord() {
+        for (keys, script) in [
+            (
+                "shift+insert",
+                "keydown leftshift\nkey insert\nkeyup leftshift\n",
+            ),
+            ("ctrl+v", "keydown leftctrl\nkey v\nkeyup leftctrl\n"),
+        ] {
+            let mut runner = TestRunner::default();
+            runner.focused_window_class = Some("kitty".to_string());
+            runner.push_status(0);
+            let config = OutputConfig {
+                restore_clipboard: false,
+                paste_keys: Some(keys.to_string()),
+                ..OutputConfig::default()
+            };
+
+            output_text_with_runner("new text", &config, &mut runner)
+                .expect("paste should succeed");
+
+            let paste = runner.commands.last().expect("paste command");
+            assert_eq!(String::from_utf8_lossy(&paste.stdin), script, "{keys}");
+        }
+    }
+
+    #[test]
+    fn paste_keycode_replaces_the_named_key() {
+        let mut runner = TestRunner::default();
+        runner.push_status(0);
+        let config = OutputConfig {
+            restore_clipboard: false,
+            paste_modifier_keycodes: Some(vec![29]),
+            paste_keycode: Some(52),
+            ..OutputConfig::default()
+        };
+
+        output_text_with_runner("new text", &config, &mut runner).expect("paste should succeed");
+
+        let paste = runner.commands.last().expect("paste command");
+        assert_eq!(
+            String::from_utf8_lossy(&paste.stdin),
+            "keydown k:29\nkey k:52\nkeyup k:29\n"
+        );
+
+        let config = OutputConfig {
+            paste_keycode: Some(0),
+            ..config
+        };
+        assert!(output_text_with_runner("new text", &config, &mut runner).is_err());
+    }
+
     #[test]
     fn paste_mode_restores_original_clipboard_with_mime_type() {
         let _guard = EnvGuard::set("WAYLAND_DISPLAY", "wayland-0");
@@ -629,6 +3173,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn paste_mode_can_leave_text_visible_to_clipboard_history() {
+        let mut runner = TestRunner::default();
+        runner.push_status(0);
+        let config = OutputConfig {
+            restore_clipboard: false,
+            hide_from_clipboard_history: false,
+            ..OutputConfig::default()
+        };
+
+        output_text_with_runner("new text", &config, &mut runner).expect("paste should succeed");
+
+        assert_eq!(runner.commands[0].program, "temporary-clipboard-copy");
+        assert_eq!(runner.commands[0].args, ["text/plain"]);
+    }
+
     #[test]
     fn type_mode_uses_dotool_without_wtype_fallback() {
         let mut runner = TestRunner::default();
@@ -686,6 +3246,111 @@ mod tests {
         assert!(err.to_string().contains("ensure ydotoold is running"));
     }
 
+    #[test]
+    fn typing_modes_paste_text_beyond_the_bmp() {
+        let text = "Party time 🎉 at the café";
+        assert!(has_astral_chars(text));
+        assert!(!has_astral_chars("café, naïve, ∑"));
+
+        for mode in [OutputMode::Type, OutputMode::Ydotool] {
+            if OutputMode::Paste.supported() != OutputMode::Paste {
+                break;
+            }
+            let mut runner = TestRunner::default();
+            runner.push_status(0);
+            let config = OutputConfig {
+                mode,
+                restore_clipboard: false,
+                ..OutputConfig::default()
+            };
+
+            output_text_with_runner(text, &config, &mut runner).expect("paste should succeed");
+
+            assert_eq!(runner.commands[0].program, "temporary-clipboard-copy");
+            assert_eq!(runner.commands[0].stdin, text.as_bytes());
+            assert_eq!(runner.commands[1].program, "dotool");
+        }
+
+        let mut runner = TestRunner::default();
+        runner.missing_programs = vec!["dotool"];
+        runner.push_status(0);
+        let config = OutputConfig {
+            mode: OutputMode::Ydotool,
+            ..OutputConfig::default()
+        };
+        output_text_with_runner(text, &config, &mut runner).expect("typing should go ahead");
+        assert_eq!(runner.commands.len(), 1);
+        assert_eq!(runner.commands[0].program, "ydotool");
+    }
+
+    #[test]
+    fn paste_mode_types_into_blocklisted_windows() {
+        let mut runner = TestRunner::default();
+        runner.focused_window_class = Some("org.remmina.Remmina".to_string());
+        runner.push_status(0);
+        let config = OutputConfig {
+            clipboard_blocklist: vec!["remmina".to_string()],
+            ..OutputConfig::default()
+        };
+
+        output_text_with_runner("typed text 🎉", &config, &mut runner)
+            .expect("typing should succeed");
+
+        assert_eq!(runner.commands.len(), 1);
+        assert_eq!(runner.commands[0].program, "dotool");
+        assert_eq!(runner.commands[0].stdin, "type typed text 🎉\n".as_bytes());
+    }
+
+    #[test]
+    fn ydotool_mode_retries_once_after_ydotoold_restarts() {
+        let mut runner = TestRunner::default();
+        runner.push_status(YDOTOOL_CONNECT_FAILED);
+        runner.push_status(0);
+        let config = OutputConfig {
+            mode: OutputMode::Ydotool,
+            ..OutputConfig::default()
+        };
+
+        output_text_with_runner("typed text", &config, &mut runner)
+            .expect("the retry should reach the restarted ydotoold");
+
+        assert_eq!(runner.commands.len(), 2);
+        assert_eq!(runner.commands[1].stdin, b"typed text");
+        assert_eq!(runner.sleeps, [YDOTOOL_RECONNECT_DELAY]);
+    }
+
+    #[test]
+    fn ydotool_errors_tell_a_missing_socket_from_an_unresponsive_daemon() {
+        let config = OutputConfig {
+            mode: OutputMode::Ydotool,
+            ..OutputConfig::default()
+        };
+        let socket = ydotool_socket_path();
+
+        let mut runner = TestRunner::default();
+        runner.ydotool_socket_missing = true;
+        let err = output_text_with_runner("typed text", &config, &mut runner)
+            .expect_err("no socket means no ydotoold");
+        assert_eq!(err.kind, OutputErrorKind::Unavailable);
+        assert_eq!(
+            err.ydotool_error(),
+            Some(&YdotoolError::SocketAbsent(socket.clone()))
+        );
+        assert!(runner.commands.is_empty());
+
+        let mut runner = TestRunner::default();
+        runner.push_status(YDOTOOL_CONNECT_FAILED);
+        runner.push_status(YDOTOOL_CONNECT_FAILED);
+        let err = output_text_with_runner("typed text", &config, &mut runner)
+            .expect_err("ydotoold never answered");
+        assert_eq!(err.kind, OutputErrorKind::Failed);
+        assert_eq!(
+            err.ydotool_error(),
+            Some(&YdotoolError::DaemonUnresponsive(socket))
+        );
+        assert_eq!(runner.commands.len(), 2);
+    }
+
     #[test]
     fn type_mode_splits_multiline_text_into_safe_dotool_commands() {
         let mut runner = TestRunner::default();
@@ -703,4 +3368,86 @@ mod tests {
             "type first\nkey enter\ntype key leftctrl+v\nkey enter\ntype third\n"
         );
     }
+
+    #[test]
+    fn terminal_paste_keys_match_known_classes_case_insensitively() {
+        assert_eq!(
+            terminal_paste_keys("com.mitchellh.ghostty"),
+            Some("ctrl+shift+v")
+        );
+        assert_eq!(terminal_paste_keys(" Kitty "), Some("ctrl+shift+v"));
+        // Shift+Insert pastes the primary selection there, not the clipboard.
+        assert_eq!(terminal_paste_keys("XTerm"), None);
+        assert_eq!(terminal_paste_keys("URxvt"), None);
+        assert_eq!(terminal_paste_keys("firefox"), None);
+        for (_, keys) in TERMINAL_PASTE_KEYS {
+            ParsedPasteKey::parse(keys).expect("built-in paste chord should parse");
+        }
+    }
+
+    #[test]
+    fn apply_affixes_wraps_text_when_configured() {
+        let config = OutputConfig {
+            prefix: Some("> ".to_string()),
+            suffix: Some(" ".to_string()),
+            ..OutputConfig::default()
+        };
+
+        assert_eq!(apply_affixes("hello", &config), "> hello ");
+        assert_eq!(apply_affixes("hello", &OutputConfig::default()), "hello");
+    }
+
+    #[test]
+    fn terminal_paste_keys_accept_xwayland_wm_class() {
+        assert_eq!(
+            terminal_paste_keys(r#""konsole", "konsole""#),
+            Some("ctrl+shift+v")
+        );
+        assert_eq!(
+            terminal_paste_keys(r#"WM_CLASS(STRING) = "wezterm", "org.wezfurlong.wezterm""#),
+            Some("ctrl+shift+v")
+        );
+        assert_eq!(
+            terminal_paste_keys("gnome-terminal-server"),
+            Some("ctrl+shift+v")
+        );
+        assert_eq!(terminal_paste_keys(r#""Navigator", "firefox""#), None);
+    }
+
+    #[test]
+    fn terminal_detection_survives_raw_class_output() {
+        for raw in [
+            "konsole\0konsole\0\n",
+            "org.kde.konsole\0\n",
+            "  Alacritty \r\n",
+            "kitty kitty\n",
+            "foot\tfoot\0",
+        ] {
+            let class = plain_window_class(raw).expect("class should be read");
+            assert!(terminal_paste_keys(&class).is_some(), "{raw:?}");
+        }
+        assert_eq!(plain_window_class("\0\n"), None);
+        assert_eq!(
+            terminal_paste_keys(&plain_window_class("Navigator\0firefox\0").expect("class")),
+            None
+        );
+    }
+
+    #[test]
+    fn erase_chars_sends_one_backspace_per_character() {
+        let mut runner = TestRunner::default();
+        runner.push_status(0);
+        let config = OutputConfig {
+            mode: OutputMode::Type,
+            ..OutputConfig::default()
+        };
+
+        erase_chars_with_runner(2, &config, &mut runner).expect("erase should succeed");
+
+        assert_eq!(runner.commands[0].program, "dotool");
+        assert_eq!(
+            String::from_utf8_lossy(&runner.commands[0].stdin),
+            "key backspace\nkey backspace\n\n"
+        );
+    }
 }
@@ -0,0 +1,156 @@
+//! Keyboard input through the xdg-desktop-portal RemoteDesktop interface,
+//! the sanctioned way to synthesize input on GNOME Wayland.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use ashpd::desktop::remote_desktop::{DeviceType, KeyState, RemoteDesktop};
+use ashpd::desktop::{PersistMode, Session};
+
+use crate::output::OutputError;
+
+const XK_BACKSPACE: i32 = 0xff08;
+const XK_TAB: i32 = 0xff09;
+const XK_RETURN: i32 = 0xff0d;
+/// Keysyms outside Latin-1 are the Unicode code point plus this offset.
+const UNICODE_KEYSYM_OFFSET: i32 = 0x0100_0000;
+
+/// The open portal session. It lives as long as the process, so the
+/// permission prompt appears at most once per daemon run, and the saved
+/// restore token skips it on later runs.
+static SESSION: Mutex<Option<PortalSession>> = Mutex::new(None);
+
+struct PortalSession {
+    runtime: tokio::runtime::Runtime,
+    proxy: RemoteDesktop<'static>,
+    session: Session<'static, RemoteDesktop<'static>>,
+}
+
+/// Types `text` as keysyms, starting a portal session on first use.
+pub fn type_text(text: &str) -> Result<(), OutputError> {
+    let keysyms: Vec<i32> = text.chars().filter_map(keysym).collect();
+    with_session(|portal| portal.press_keysyms(&keysyms))
+}
+
+/// Presses Backspace `count` times.
+pub fn erase_chars(count: usize) -> Result<(), OutputError> {
+    with_session(|portal| portal.press_keysyms(&vec![XK_BACKSPACE; count]))
+}
+
+fn with_session(
+    send: impl FnOnce(&PortalSession) -> Result<(), ashpd::Error>,
+) -> Result<(), OutputError> {
+    let mut guard = SESSION
+        .lock()
+        .map_err(|_| OutputError::new("portal session lock poisoned"))?;
+    let portal = match guard.take() {
+        Some(portal) => portal,
+        None => PortalSession::start().map_err(|err| {
+            OutputError::unavailable(format!("remote desktop portal unavailable: {err}"))
+        })?,
+    };
+    // Access may have been revoked or the session closed; dropping the
+    // session on failure makes the next transcript ask the portal again.
+    send(&portal).map_err(|err| OutputError::new(format!("portal input failed: {err}")))?;
+    *guard = Some(portal);
+    Ok(())
+}
+
+impl PortalSession {
+    /// Requests keyboard access. Without a valid restore token the portal
+    /// shows its permission dialog, and this blocks until the user answers.
+    fn start() -> Result<Self, String> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| format!("failed to start async runtime: {err}"))?;
+        let restore_token = fs::read_to_string(restore_token_path()).ok();
+        let (proxy, session, token) = runtime
+            .block_on(async {
+                let proxy = RemoteDesktop::new().await?;
+                let session = proxy.create_session().await?;
+                proxy
+                    .select_devices(
+                        &session,
+                        DeviceType::Keyboard.into(),
+                        restore_token.as_deref().map(str::trim),
+                        PersistMode::ExplicitlyRevoked,
+                    )
+                    .await?;
+                let devices = proxy.start(&session, None).await?.response()?;
+                let token = devices.restore_token().map(str::to_string);
+                Ok::<_, ashpd::Error>((proxy, session, token))
+            })
+            .map_err(|err| err.to_string())?;
+        // Tokens are single use, so each session hands out the next one.
+        if let Some(token) = token {
+            if let Err(err) = save_restore_token(&token) {
+                log::warn!("failed to save portal restore token: {err}");
+            }
+        }
+        Ok(Self {
+            runtime,
+            proxy,
+            session,
+        })
+    }
+
+    fn press_keysyms(&self, keysyms: &[i32]) -> Result<(), ashpd::Error> {
+        self.runtime.block_on(async {
+            for &keysym in keysyms {
+                for state in [KeyState::Pressed, KeyState::Released] {
+                    self.proxy
+                        .notify_keyboard_keysym(&self.session, keysym, state)
+                        .await?;
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+/// The X keysym that types `ch`, or `None` for control characters with no key.
+fn keysym(ch: char) -> Option<i32> {
+    match ch {
+        '\n' => Some(XK_RETURN),
+        '\t' => Some(XK_TAB),
+        ' '..='~' | '\u{a0}'..='\u{ff}' => Some(ch as i32),
+        _ if ch.is_control() => None,
+        _ => Some(UNICODE_KEYSYM_OFFSET + ch as i32),
+    }
+}
+
+fn restore_token_path() -> PathBuf {
+    env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join("soundvibes")
+        .join("portal-restore-token")
+}
+
+fn save_restore_token(token: &str) -> std::io::Result<()> {
+    let path = restore_token_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_characters_to_keysyms() {
+        assert_eq!(keysym('a'), Some(0x61));
+        assert_eq!(keysym('Z'), Some(0x5a));
+        assert_eq!(keysym('ü'), Some(0xfc));
+        assert_eq!(keysym('€'), Some(0x0100_20ac));
+        assert_eq!(keysym('\n'), Some(XK_RETURN));
+        assert_eq!(keysym('\t'), Some(XK_TAB));
+        assert_eq!(keysym('\r'), None);
+    }
+}
@@ -0,0 +1,717 @@
+//! Transcript rewrites applied before output.
+
+use std::collections::BTreeMap;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterMode {
+    /// Replace each letter of a matched word with `*`.
+    #[default]
+    Mask,
+    /// Drop matched words entirely.
+    Remove,
+}
+
+/// Words to mask or remove from transcripts. An empty list disables the filter.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WordFilter {
+    pub words: Vec<String>,
+    pub mode: FilterMode,
+}
+
+/// Masks or removes whole-word, case-insensitive matches of the filter's words.
+/// A removed word takes a comma attached to it and the spaces around it
+/// along, so no gap is left before punctuation, and line breaks stay.
+pub fn filter_words(text: &str, filter: &WordFilter) -> String {
+    let alternatives = filter
+        .words
+        .iter()
+        .map(|word| word.trim())
+        .filter(|word| !word.is_empty())
+        .map(regex::escape)
+        .collect::<Vec<_>>();
+    if alternatives.is_empty() {
+        return text.to_string();
+    }
+
+    let words = alternatives.join("|");
+    match filter.mode {
+        FilterMode::Mask => {
+            let Ok(re) = Regex::new(&format!(r"(?i)\b(?:{words})\b")) else {
+                return text.to_string();
+            };
+            re.replace_all(text, |captures: &regex::Captures<'_>| {
+                "*".repeat(captures[0].chars().count())
+            })
+            .into_owned()
+        }
+        FilterMode::Remove => {
+            let Ok(re) = Regex::new(&format!(r"(?i)[ \t]*\b(?:{words})\b,?[ \t]*")) else {
+                return text.to_string();
+            };
+            let removed = re.replace_all(text, |captures: &regex::Captures<'_>| {
+                let Some(found) = captures.get(0) else {
+                    return "";
+                };
+                // One space is put back only between two words on one line.
+                let before = text[..found.start()].chars().next_back();
+                let after = text[found.end()..].chars().next();
+                match (before, after) {
+                    (Some(before), Some(after))
+                        if !before.is_whitespace()
+                            && !after.is_whitespace()
+                            && !is_closing_punctuation(after) =>
+                    {
+                        " "
+                    }
+                    _ => "",
+                }
+            });
+            if removed == text {
+                return text.to_string();
+            }
+            removed.trim().to_string()
+        }
+    }
+}
+
+/// Disfluency removal. Off by default so output stays verbatim.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FillerFilter {
+    pub enabled: bool,
+    /// Replaces the per-language default list, e.g. to add "like".
+    pub words: Option<Vec<String>>,
+}
+
+impl FillerFilter {
+    /// The configured filler list, or the default for `language`.
+    pub fn words_for(&self, language: &str) -> Vec<String> {
+        match &self.words {
+            Some(words) => words.clone(),
+            None => default_fillers(language)
+                .iter()
+                .map(|word| word.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Hesitation sounds whisper commonly transcribes, by language code. Real
+/// words such as "like" are left out; add them through `words` if wanted.
+/// Unknown languages and `auto` fall back to sounds that are not words in
+/// any of them; "er", for one, is a German and Dutch pronoun.
+pub fn default_fillers(language: &str) -> &'static [&'static str] {
+    match language {
+        "en" => &["um", "umm", "uh", "uhh", "er", "erm", "hmm", "mm"],
+        "de" => &["äh", "ähm", "öhm", "hm", "hmm"],
+        "fr" => &["euh", "heu", "hum", "bah"],
+        "es" => &["eh", "em", "mmm"],
+        "nl" => &["eh", "ehm", "uh", "uhm"],
+        _ => &["um", "uh", "hmm"],
+    }
+}
+
+/// Drops standalone filler tokens. A comma attached to a filler, or standing
+/// alone right before it, goes with it; sentence punctuation moves onto the
+/// previous word; a line break next to a filler stays; and a sentence that
+/// started with a capitalized filler keeps its capital.
+pub fn remove_fillers(text: &str, fillers: &[String]) -> String {
+    let fillers = fillers
+        .iter()
+        .map(|word| word.trim().to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+    if fillers.is_empty() {
+        return text.to_string();
+    }
+
+    let mut output = String::with_capacity(text.len());
+    let mut dropped_leading = false;
+    let mut capitalize_next = false;
+    // Whitespace before a dropped filler, kept when it breaks the line.
+    let mut dropped_space: Option<&str> = None;
+    let mut rest = text;
+    while let Some(start) = rest.find(|ch: char| !ch.is_whitespace()) {
+        let (space, tail) = rest.split_at(start);
+        let end = tail.find(char::is_whitespace).unwrap_or(tail.len());
+        let (token, after) = tail.split_at(end);
+        rest = after;
+
+        let core = token.trim_matches(|ch: char| !ch.is_alphanumeric());
+        let leading = &token[..token.find(core).unwrap_or(0)];
+        if !core.is_empty() && leading.is_empty() && fillers.contains(&core.to_lowercase()) {
+            let trailing = &token[leading.len() + core.len()..];
+            let closing = trailing.trim_start_matches(',');
+            if let Some(before_comma) = output.strip_suffix(',') {
+                if before_comma.ends_with(char::is_whitespace) {
+                    output.truncate(before_comma.trim_end().len());
+                }
+            }
+            if output.is_empty() {
+                dropped_leading = true;
+            } else {
+                output.push_str(closing);
+                if dropped_space.is_none_or(|kept| !kept.contains('\n')) {
+                    dropped_space = Some(space);
+                }
+            }
+            let sentence_start = output.is_empty() || output.ends_with(['.', '!', '?']);
+            capitalize_next = sentence_start && core.starts_with(char::is_uppercase);
+            continue;
+        }
+
+        let space = match dropped_space.take() {
+            Some(kept) if kept.contains('\n') && !space.contains('\n') => kept,
+            // Punctuation the filler stood before joins the previous word.
+            Some(_) if !space.contains('\n') && token.chars().all(is_closing_punctuation) => "",
+            _ => space,
+        };
+        if !(output.is_empty() && dropped_leading) {
+            output.push_str(space);
+        }
+        if std::mem::take(&mut capitalize_next) {
+            let mut chars = token.chars();
+            if let Some(first) = chars.next() {
+                output.extend(first.to_uppercase());
+                output.push_str(chars.as_str());
+                continue;
+            }
+        }
+        output.push_str(token);
+    }
+    if !output.is_empty() {
+        output.push_str(rest);
+    }
+    output
+}
+
+fn is_closing_punctuation(ch: char) -> bool {
+    matches!(ch, '.' | ',' | '!' | '?' | ';' | ':')
+}
+
+/// Abbreviations whose trailing period does not end a sentence, lowercase.
+const ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "approx", "ca", "bzw", "nr", "hr",
+];
+
+/// Uppercases the first letter of the text and of every word that follows
+/// sentence-ending punctuation. Nothing is lowercased, and words that
+/// already contain a capital (`iPhone`) are left as they are. Periods in
+/// initialisms (`U.S.A.`, `e.g.`), after single initials, known
+/// abbreviations, and ellipses do not end a sentence.
+pub fn capitalize_sentence_starts(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut sentence_start = true;
+    let mut rest = text;
+    while let Some(start) = rest.find(|ch: char| !ch.is_whitespace()) {
+        let (space, tail) = rest.split_at(start);
+        let end = tail.find(char::is_whitespace).unwrap_or(tail.len());
+        let (token, after) = tail.split_at(end);
+        rest = after;
+
+        output.push_str(space);
+        if sentence_start {
+            push_capitalized(&mut output, token);
+        } else {
+            output.push_str(token);
+        }
+        sentence_start = ends_sentence(token);
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Splits text after each token that ends a sentence, by the same rules as
+/// [`capitalize_sentence_starts`]. Whitespace after a sentence stays with
+/// it, so the pieces join back into `text`.
+pub fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut position = 0;
+    while let Some(offset) = text[position..].find(|ch: char| !ch.is_whitespace()) {
+        let token_start = position + offset;
+        let token_end = text[token_start..]
+            .find(char::is_whitespace)
+            .map_or(text.len(), |end| token_start + end);
+        position = token_end;
+        if ends_sentence(&text[token_start..token_end]) {
+            let after = &text[token_end..];
+            let end = token_end + after.len() - after.trim_start().len();
+            sentences.push(&text[start..end]);
+            start = end;
+        }
+    }
+    if start < text.len() {
+        sentences.push(&text[start..]);
+    }
+    sentences
+}
+
+fn push_capitalized(output: &mut String, token: &str) {
+    let Some(index) = token.find(char::is_alphanumeric) else {
+        output.push_str(token);
+        return;
+    };
+    let (opening, word) = token.split_at(index);
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) if first.is_lowercase() && !word.chars().any(char::is_uppercase) => {
+            output.push_str(opening);
+            output.extend(first.to_uppercase());
+            output.push_str(chars.as_str());
+        }
+        _ => output.push_str(token),
+    }
+}
+
+fn ends_sentence(token: &str) -> bool {
+    let core = token.trim_end_matches(['"', '\'', ')', ']', '”', '’', '»']);
+    if core.ends_with(['!', '?']) {
+        return true;
+    }
+    let Some(word) = core.strip_suffix('.') else {
+        return false;
+    };
+    if word.ends_with('.') {
+        return false;
+    }
+    let word = word.trim_start_matches(|ch: char| !ch.is_alphanumeric());
+    let mut chars = word.chars();
+    let single_initial = chars
+        .next()
+        .is_some_and(|first| first.is_uppercase() && chars.next().is_none());
+    !(word.contains('.') || single_initial || ABBREVIATIONS.contains(&word.to_lowercase().as_str()))
+}
+
+/// Spoken phrases that replace a whole utterance with fixed text.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct VoiceCommands {
+    /// Spoken phrase mapped to the text it produces, e.g. `"new line" = "\n"`.
+    pub phrases: BTreeMap<String, String>,
+    /// Whole utterances that erase the last injected transcript instead of
+    /// typing anything, e.g. `["scratch that", "undo"]`.
+    pub undo: Vec<String>,
+    /// Also accept utterances a few edits away from a phrase.
+    pub fuzzy: bool,
+    /// Largest edit distance a fuzzy match may have. A match is also capped
+    /// at a third of the phrase length, so short phrases need near-exact hits.
+    pub max_distance: usize,
+    /// Turn spoken punctuation such as "period" or "question mark" inside an
+    /// utterance into the mark itself.
+    pub punctuation: bool,
+}
+
+impl Default for VoiceCommands {
+    fn default() -> Self {
+        Self {
+            phrases: BTreeMap::new(),
+            undo: Vec::new(),
+            fuzzy: false,
+            max_distance: 2,
+            punctuation: false,
+        }
+    }
+}
+
+impl VoiceCommands {
+    /// Returns the command text when the whole utterance, ignoring case and
+    /// punctuation, is a registered phrase (or, with `fuzzy`, close to one).
+    /// Phrases inside longer utterances never match.
+    pub fn resolve(&self, utterance: &str) -> Option<&str> {
+        let phrases = self
+            .phrases
+            .iter()
+            .map(|(phrase, text)| (phrase.as_str(), text.as_str()));
+        self.closest(utterance, phrases)
+    }
+
+    /// Whether the whole utterance is one of the `undo` phrases, matched the
+    /// same way as `resolve` matches command phrases.
+    pub fn is_undo(&self, utterance: &str) -> bool {
+        let phrases = self.undo.iter().map(|phrase| (phrase.as_str(), ()));
+        self.closest(utterance, phrases).is_some()
+    }
+
+    /// The value of the phrase that equals `utterance`, or with `fuzzy` the
+    /// value of the nearest phrase within the edit limit.
+    fn closest<'a, T>(
+        &self,
+        utterance: &str,
+        phrases: impl Iterator<Item = (&'a str, T)>,
+    ) -> Option<T> {
+        let spoken = normalize_phrase(utterance);
+        if spoken.is_empty() {
+            return None;
+        }
+        let mut best: Option<(usize, T)> = None;
+        for (phrase, value) in phrases {
+            let phrase = normalize_phrase(phrase);
+            if phrase == spoken {
+                return Some(value);
+            }
+            if !self.fuzzy {
+                continue;
+            }
+            let distance = edit_distance(&spoken, &phrase);
+            let limit = self.max_distance.min(phrase.chars().count() / 3);
+            if distance <= limit && best.as_ref().is_none_or(|(closest, _)| distance < *closest) {
+                best = Some((distance, value));
+            }
+        }
+        best.map(|(_, value)| value)
+    }
+}
+
+/// Spoken names of punctuation marks, by number of words, longest first.
+const SPOKEN_PUNCTUATION: &[(&[&str], &str)] = &[
+    (&["exclamation", "mark"], "!"),
+    (&["exclamation", "point"], "!"),
+    (&["question", "mark"], "?"),
+    (&["full", "stop"], "."),
+    (&["period"], "."),
+    (&["comma"], ","),
+    (&["semicolon"], ";"),
+    (&["colon"], ":"),
+];
+
+/// Replaces spoken punctuation with the mark, attached to the word before
+/// it. Punctuation whisper wrote around the spoken name is dropped, so
+/// "Hello, period." becomes "Hello.", and whitespace collapses to single
+/// spaces. A name at the very start has no word to attach to and stays.
+pub fn expand_spoken_punctuation(text: &str) -> String {
+    let tokens = text.split_whitespace().collect::<Vec<_>>();
+    let mut output: Vec<String> = Vec::with_capacity(tokens.len());
+    let mut index = 0;
+    while index < tokens.len() {
+        let spoken = SPOKEN_PUNCTUATION.iter().find(|(words, _)| {
+            tokens.len() - index >= words.len()
+                && words
+                    .iter()
+                    .zip(&tokens[index..])
+                    .all(|(word, token)| normalize_phrase(token) == *word)
+        });
+        match (spoken, output.last_mut()) {
+            (Some((words, mark)), Some(previous)) => {
+                let kept = previous
+                    .trim_end_matches([',', '.', ';', ':', '!', '?'])
+                    .len();
+                previous.truncate(kept);
+                previous.push_str(mark);
+                index += words.len();
+            }
+            _ => {
+                output.push(tokens[index].to_string());
+                index += 1;
+            }
+        }
+    }
+    output.join(" ")
+}
+
+fn normalize_phrase(text: &str) -> String {
+    text.chars()
+        .map(|ch| {
+            if ch.is_alphanumeric() || ch == '\'' {
+                ch
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Levenshtein distance over characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(words: &[&str], mode: FilterMode) -> WordFilter {
+        WordFilter {
+            words: words.iter().map(|word| word.to_string()).collect(),
+            mode,
+        }
+    }
+
+    #[test]
+    fn empty_filter_leaves_text_unchanged() {
+        assert_eq!(
+            filter_words("keep  this", &WordFilter::default()),
+            "keep  this"
+        );
+    }
+
+    #[test]
+    fn masks_whole_words_case_insensitively() {
+        let words = filter(&["darn"], FilterMode::Mask);
+
+        assert_eq!(
+            filter_words("Darn it, darned darn.", &words),
+            "**** it, darned ****."
+        );
+    }
+
+    #[test]
+    fn removes_words_and_collapses_spacing() {
+        let words = filter(&["really", "very"], FilterMode::Remove);
+
+        assert_eq!(
+            filter_words("this is really very good", &words),
+            "this is good"
+        );
+    }
+
+    #[test]
+    fn removing_words_keeps_line_breaks() {
+        let words = filter(&["darn"], FilterMode::Remove);
+
+        assert_eq!(
+            filter_words("first darn\ndarn second\n\nthird", &words),
+            "first\nsecond\n\nthird"
+        );
+    }
+
+    #[test]
+    fn removing_words_leaves_no_space_before_punctuation() {
+        let words = filter(&["really", "darn"], FilterMode::Remove);
+
+        assert_eq!(filter_words("this is really, good", &words), "this is good");
+        assert_eq!(filter_words("that was darn.", &words), "that was.");
+        assert_eq!(filter_words("Darn, it works!", &words), "it works!");
+    }
+
+    #[test]
+    fn matches_non_english_words_by_character() {
+        let words = filter(&["Mist", "ärger"], FilterMode::Mask);
+
+        assert_eq!(
+            filter_words("So ein Mist, Ärger überall", &words),
+            "So ein ****, ***** überall"
+        );
+    }
+
+    fn commands(fuzzy: bool) -> VoiceCommands {
+        VoiceCommands {
+            phrases: BTreeMap::from([
+                ("new line".to_string(), "\n".to_string()),
+                ("new paragraph".to_string(), "\n\n".to_string()),
+            ]),
+            fuzzy,
+            ..VoiceCommands::default()
+        }
+    }
+
+    #[test]
+    fn command_matches_whole_utterance_ignoring_case_and_punctuation() {
+        let commands = commands(false);
+
+        assert_eq!(commands.resolve("New line."), Some("\n"));
+        assert_eq!(commands.resolve("new lion"), None);
+        assert_eq!(commands.resolve("start a new line here"), None);
+    }
+
+    #[test]
+    fn fuzzy_command_accepts_near_misses_only() {
+        let commands = commands(true);
+
+        assert_eq!(commands.resolve("New lion!"), Some("\n"));
+        assert_eq!(commands.resolve("new paragraphs"), Some("\n\n"));
+        assert_eq!(commands.resolve("nine"), None);
+        assert_eq!(commands.resolve("we need a new line"), None);
+    }
+
+    #[test]
+    fn fuzzy_limit_shrinks_for_short_phrases() {
+        let commands = VoiceCommands {
+            phrases: BTreeMap::from([("undo".to_string(), "".to_string())]),
+            fuzzy: true,
+            max_distance: 2,
+            ..VoiceCommands::default()
+        };
+
+        assert_eq!(commands.resolve("Undo."), Some(""));
+        assert_eq!(commands.resolve("unto"), Some(""));
+        assert_eq!(commands.resolve("into"), None);
+    }
+
+    #[test]
+    fn undo_phrases_match_whole_utterances() {
+        let commands = VoiceCommands {
+            undo: vec!["scratch that".to_string(), "undo".to_string()],
+            ..commands(true)
+        };
+
+        assert!(commands.is_undo("Scratch that."));
+        assert!(commands.is_undo("scratch dat"));
+        assert!(!commands.is_undo("please scratch that line"));
+        assert!(!commands.is_undo("new line"));
+        assert!(!VoiceCommands::default().is_undo("undo"));
+    }
+
+    #[test]
+    fn edit_distance_counts_character_edits() {
+        assert_eq!(edit_distance("new lion", "new line"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("straße", "strasse"), 2);
+    }
+
+    #[test]
+    fn capitalizes_sentence_starts_without_touching_other_casing() {
+        assert_eq!(
+            capitalize_sentence_starts("the NASA launch worked. then iPhone sales rose! ok?  sure"),
+            "The NASA launch worked. Then iPhone sales rose! Ok?  Sure"
+        );
+        assert_eq!(
+            capitalize_sentence_starts("he said \"done.\" \"next\" one"),
+            "He said \"done.\" \"Next\" one"
+        );
+    }
+
+    #[test]
+    fn initialisms_and_abbreviations_do_not_end_sentences() {
+        assert_eq!(
+            capitalize_sentence_starts("the U.S.A. is big, e.g. texas. ask Dr. smith or J. doe"),
+            "The U.S.A. is big, e.g. texas. Ask Dr. smith or J. doe"
+        );
+        assert_eq!(
+            capitalize_sentence_starts("well... maybe. 3 apples"),
+            "Well... maybe. 3 apples"
+        );
+    }
+
+    #[test]
+    fn splits_sentences_keeping_abbreviations_and_whitespace() {
+        let text = "Ask Dr. Smith about the U.S.A. trip. Really?  Yes! and more";
+        let sentences = split_sentences(text);
+        assert_eq!(
+            sentences,
+            [
+                "Ask Dr. Smith about the U.S.A. trip. ",
+                "Really?  ",
+                "Yes! ",
+                "and more"
+            ]
+        );
+        assert_eq!(sentences.concat(), text);
+        assert_eq!(split_sentences("Done. "), ["Done. "]);
+        assert!(split_sentences("").is_empty());
+    }
+
+    #[test]
+    fn expands_spoken_punctuation_onto_the_previous_word() {
+        assert_eq!(
+            expand_spoken_punctuation("new sentence period new sentence period"),
+            "new sentence. new sentence."
+        );
+        assert_eq!(
+            expand_spoken_punctuation("Are you there, question mark.  See you comma Bob."),
+            "Are you there? See you, Bob."
+        );
+        assert_eq!(
+            expand_spoken_punctuation("Period of time"),
+            "Period of time"
+        );
+    }
+
+    fn fillers(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn removes_standalone_fillers_with_their_commas() {
+        let words = fillers(&["um", "uh"]);
+
+        assert_eq!(
+            remove_fillers("So uh we should, um, ship it.", &words),
+            "So we should, ship it."
+        );
+        assert_eq!(remove_fillers("That was it uh.", &words), "That was it.");
+    }
+
+    #[test]
+    fn removing_fillers_keeps_line_breaks() {
+        let words = fillers(&["um", "uh"]);
+
+        assert_eq!(
+            remove_fillers("First line\num second line", &words),
+            "First line\nsecond line"
+        );
+        assert_eq!(
+            remove_fillers("Done. Uh\n\nNext part", &words),
+            "Done.\n\nNext part"
+        );
+        assert_eq!(
+            remove_fillers("Hi all.\nUm, thanks.", &words),
+            "Hi all.\nThanks."
+        );
+    }
+
+    #[test]
+    fn removing_fillers_leaves_no_space_before_punctuation() {
+        let words = fillers(&["um", "uh"]);
+
+        assert_eq!(remove_fillers("so , um.", &words), "so.");
+        assert_eq!(remove_fillers("so um .", &words), "so.");
+        assert_eq!(remove_fillers("Wait uh ! Now", &words), "Wait! Now");
+    }
+
+    #[test]
+    fn leading_filler_keeps_sentence_capitalized() {
+        let words = fillers(&["um", "uh"]);
+
+        assert_eq!(
+            remove_fillers("Um, so we go. Uh, then stop.", &words),
+            "So we go. Then stop."
+        );
+        assert_eq!(remove_fillers("Uh", &words), "");
+    }
+
+    #[test]
+    fn leaves_words_containing_fillers_untouched() {
+        let words = fillers(&["um", "er"]);
+
+        assert_eq!(
+            remove_fillers("Umbrella? Never. \"um\" stays quoted", &words),
+            "Umbrella? Never. \"um\" stays quoted"
+        );
+    }
+
+    #[test]
+    fn default_fillers_depend_on_language_and_skip_real_words() {
+        assert!(default_fillers("de").contains(&"ähm"));
+        assert!(default_fillers("auto").contains(&"um"));
+        assert!(default_fillers("en").contains(&"er"));
+        assert!(!default_fillers("auto").contains(&"er"));
+        assert!(!default_fillers("en").contains(&"like"));
+
+        let custom = FillerFilter {
+            enabled: true,
+            words: Some(fillers(&["like"])),
+        };
+        assert_eq!(custom.words_for("en"), ["like"]);
+    }
+}
@@ -0,0 +1,156 @@
+//! Per-application settings picked by the focused window's class.
+
+use serde::{Deserialize, Serialize};
+
+use crate::daemon::DaemonConfig;
+use crate::output::{self, OutputMode, PasteVia, TypeMode};
+use crate::types::Casing;
+
+/// Settings that replace the configured ones while a matching window has
+/// focus. Anything left out keeps the value from the rest of the config,
+/// which acts as the default profile.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Profile {
+    /// Window class or app id the profile applies to, compared like the
+    /// terminal detection does: case-insensitive, and `konsole` also matches
+    /// `org.kde.konsole`.
+    pub match_class: String,
+    pub output: ProfileOutput,
+    pub postprocess: ProfilePostprocess,
+}
+
+/// Overrides for the `[output]` settings of the same names.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProfileOutput {
+    pub mode: Option<OutputMode>,
+    pub paste_keys: Option<String>,
+    pub paste_via: Option<PasteVia>,
+    pub type_mode: Option<TypeMode>,
+    pub bracketed_paste: Option<bool>,
+    pub inject_sentences: Option<bool>,
+    /// An empty string removes the configured prefix.
+    pub prefix: Option<String>,
+    /// An empty string removes the configured suffix.
+    pub suffix: Option<String>,
+}
+
+/// Overrides for transcript cleanup.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProfilePostprocess {
+    pub casing: Option<Casing>,
+    /// Turns filler word removal on or off; the word list stays as configured.
+    pub fillers: Option<bool>,
+}
+
+impl Profile {
+    pub fn matches(&self, window_class: &str) -> bool {
+        !self.match_class.is_empty()
+            && output::window_class_matches(window_class, &self.match_class)
+    }
+
+    /// Writes the profile's settings over `config`.
+    pub fn apply(&self, config: &mut DaemonConfig) {
+        let overrides = &self.output;
+        let target = &mut config.output;
+        if let Some(mode) = overrides.mode {
+            target.mode = mode.supported();
+        }
+        if let Some(paste_keys) = &overrides.paste_keys {
+            target.paste_keys = Some(paste_keys.clone());
+        }
+        if let Some(paste_via) = overrides.paste_via {
+            target.paste_via = paste_via;
+        }
+        if let Some(type_mode) = overrides.type_mode {
+            target.type_mode = type_mode;
+        }
+        if let Some(bracketed_paste) = overrides.bracketed_paste {
+            target.bracketed_paste = bracketed_paste;
+        }
+        if let Some(inject_sentences) = overrides.inject_sentences {
+            target.inject_sentences = inject_sentences;
+        }
+        if let Some(prefix) = &overrides.prefix {
+            target.prefix = Some(prefix.clone()).filter(|prefix| !prefix.is_empty());
+        }
+        if let Some(suffix) = &overrides.suffix {
+            target.suffix = Some(suffix.clone()).filter(|suffix| !suffix.is_empty());
+        }
+        if let Some(casing) = self.postprocess.casing {
+            config.casing = casing;
+        }
+        if let Some(fillers) = self.postprocess.fillers {
+            config.fillers.enabled = fillers;
+        }
+    }
+}
+
+/// The first profile in `profiles` that matches `window_class`.
+pub fn find_profile<'a>(profiles: &'a [Profile], window_class: &str) -> Option<&'a Profile> {
+    profiles
+        .iter()
+        .find(|profile| profile.matches(window_class))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::daemon::test_support::daemon_config;
+
+    fn profile(match_class: &str) -> Profile {
+        Profile {
+            match_class: match_class.to_string(),
+            ..Profile::default()
+        }
+    }
+
+    #[test]
+    fn first_matching_profile_wins() {
+        let profiles = [
+            profile("kitty"),
+            profile("konsole"),
+            profile("org.kde.konsole"),
+        ];
+
+        assert_eq!(
+            find_profile(&profiles, "org.kde.konsole").map(|p| p.match_class.as_str()),
+            Some("konsole")
+        );
+        assert_eq!(
+            find_profile(&profiles, "Kitty").map(|p| p.match_class.as_str()),
+            Some("kitty")
+        );
+        assert_eq!(find_profile(&profiles, "firefox"), None);
+        assert_eq!(find_profile(&[profile("")], "firefox"), None);
+    }
+
+    #[test]
+    fn applies_only_the_settings_it_names() {
+        let mut config = daemon_config();
+        config.output.suffix = Some("\n".to_string());
+        config.fillers.enabled = true;
+        let terminal = Profile {
+            match_class: "kitty".to_string(),
+            output: ProfileOutput {
+                paste_keys: Some("ctrl+shift+v".to_string()),
+                suffix: Some(String::new()),
+                ..ProfileOutput::default()
+            },
+            postprocess: ProfilePostprocess {
+                casing: Some(Casing::SentenceStartOnly),
+                ..ProfilePostprocess::default()
+            },
+        };
+
+        terminal.apply(&mut config);
+
+        assert_eq!(config.output.paste_keys.as_deref(), Some("ctrl+shift+v"));
+        assert_eq!(config.output.suffix, None);
+        assert_eq!(config.casing, Casing::SentenceStartOnly);
+        assert!(config.fillers.enabled);
+        assert_eq!(config.output.mode, daemon_config().output.mode);
+    }
+}
@@ -12,6 +12,11 @@ pub struct SegmentConfig {
     pub grace_samples: usize,
     pub overlap_samples: usize,
     pub min_segment_samples: usize,
+    /// Loud audio shorter than this inside a pause still counts as silence.
+    pub speech_confirm_samples: usize,
+    /// Quiet audio shorter than this after the target length is a gap
+    /// between words, not a pause to cut at.
+    pub silence_confirm_samples: usize,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -46,7 +51,10 @@ pub fn decide_segment(
         };
     }
 
-    if buffer_len >= config.target_samples && latest_rms < config.vad_threshold {
+    if buffer_len >= config.target_samples
+        && latest_rms < config.vad_threshold
+        && trailing_silence_samples >= config.silence_confirm_samples
+    {
         return SegmentDecision::Cut {
             speech_end: buffer_len,
             reason: CutReason::SoftLimitPause,
@@ -63,18 +71,53 @@ pub fn decide_segment(
     SegmentDecision::Continue
 }
 
+/// Counts the silence at the end of a live recording. A burst of loud audio
+/// only ends the silence once it lasts `speech_confirm_samples`, so clicks
+/// and noise in a pause do not keep a segment open.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SilenceTracker {
+    trailing_silence_samples: usize,
+    loud_samples: usize,
+}
+
+impl SilenceTracker {
+    pub fn process(&mut self, config: &SegmentConfig, samples: usize, rms: f32) {
+        if rms < config.vad_threshold {
+            self.loud_samples = 0;
+            self.trailing_silence_samples += samples;
+            return;
+        }
+        self.loud_samples += samples;
+        if self.loud_samples >= config.speech_confirm_samples {
+            self.trailing_silence_samples = 0;
+        } else {
+            self.trailing_silence_samples += samples;
+        }
+    }
+
+    pub fn trailing_silence_samples(&self) -> usize {
+        self.trailing_silence_samples
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Cuts `buffer` down to the audio carried into the next segment. Works in
+/// place so the recording buffer keeps its capacity.
 pub fn carry_after_cut(
-    samples: &[f32],
+    buffer: &mut Vec<f32>,
     speech_end: usize,
     config: &SegmentConfig,
     reason: CutReason,
-) -> Vec<f32> {
+) {
     match reason {
-        CutReason::Silence => Vec::new(),
+        CutReason::Silence => buffer.clear(),
         CutReason::SoftLimitPause | CutReason::HardLimit => {
-            let bounded_end = speech_end.min(samples.len());
+            let bounded_end = speech_end.min(buffer.len());
             let start = bounded_end.saturating_sub(config.overlap_samples);
-            samples[start..].to_vec()
+            buffer.drain(..start);
         }
     }
 }
@@ -113,6 +156,31 @@ pub fn append_segment_space(text: &str) -> String {
     }
 }
 
+/// Returns the part of a partial transcript that later partials are unlikely to revise:
+/// everything before the last word, which may still be cut off mid-utterance.
+pub fn stable_prefix(partial: &str) -> &str {
+    let partial = partial.trim_end();
+    match partial.rfind(char::is_whitespace) {
+        Some(index) => partial[..index].trim_end(),
+        None => "",
+    }
+}
+
+/// Compares already-typed text with a newer transcript. Returns how many trailing
+/// characters of `typed` must be erased and the text to type after erasing.
+pub fn incremental_correction<'a>(typed: &str, text: &'a str) -> (usize, &'a str) {
+    let mut common_chars = 0;
+    let mut common_bytes = 0;
+    for (left, right) in typed.chars().zip(text.chars()) {
+        if left != right {
+            break;
+        }
+        common_chars += 1;
+        common_bytes += right.len_utf8();
+    }
+    (typed.chars().count() - common_chars, &text[common_bytes..])
+}
+
 #[derive(Debug, PartialEq, Eq)]
 struct NormalizedWord {
     normalized: String,
@@ -190,6 +258,8 @@ mod tests {
             grace_samples: 2_000,
             overlap_samples: 400,
             min_segment_samples: 1_200,
+            speech_confirm_samples: 0,
+            silence_confirm_samples: 0,
         }
     }
 
@@ -226,6 +296,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn soft_limit_waits_for_confirmed_silence() {
+        let config = SegmentConfig {
+            silence_confirm_samples: 150,
+            ..config()
+        };
+
+        assert_eq!(
+            decide_segment(&config, 10_160, 100, 0.05),
+            SegmentDecision::Continue
+        );
+        assert_eq!(
+            decide_segment(&config, 10_210, 150, 0.05),
+            SegmentDecision::Cut {
+                speech_end: 10_210,
+                reason: CutReason::SoftLimitPause,
+            }
+        );
+    }
+
+    #[test]
+    fn short_noise_in_a_pause_keeps_counting_silence() {
+        let config = SegmentConfig {
+            speech_confirm_samples: 100,
+            ..config()
+        };
+        let mut silence = SilenceTracker::default();
+
+        silence.process(&config, 200, 0.0);
+        silence.process(&config, 50, 0.5);
+        silence.process(&config, 100, 0.0);
+        assert_eq!(silence.trailing_silence_samples(), 350);
+
+        silence.process(&config, 50, 0.5);
+        silence.process(&config, 50, 0.5);
+        assert_eq!(silence.trailing_silence_samples(), 0);
+    }
+
     #[test]
     fn hard_limit_cuts_without_waiting_for_pause_after_grace_window() {
         let decision = decide_segment(&config(), 12_000, 0, 0.2);
@@ -241,9 +349,9 @@ mod tests {
 
     #[test]
     fn carries_overlap_for_timed_cuts() {
-        let samples = (0..1_200).map(|index| index as f32).collect::<Vec<_>>();
+        let mut carried = (0..1_200).map(|index| index as f32).collect::<Vec<_>>();
 
-        let carried = carry_after_cut(&samples, 1_000, &config(), CutReason::HardLimit);
+        carry_after_cut(&mut carried, 1_000, &config(), CutReason::HardLimit);
 
         assert_eq!(carried.len(), 600);
         assert_eq!(carried.first().copied(), Some(600.0));
@@ -253,12 +361,23 @@ mod tests {
     #[test]
     fn clamps_carry_cut_points_to_sample_bounds() {
         let samples = (0..10).map(|index| index as f32).collect::<Vec<_>>();
+        let mut carried = samples.clone();
 
-        let carried = carry_after_cut(&samples, 20, &config(), CutReason::HardLimit);
+        carry_after_cut(&mut carried, 20, &config(), CutReason::HardLimit);
 
         assert_eq!(carried, samples);
     }
 
+    #[test]
+    fn silence_cut_carries_nothing_but_keeps_capacity() {
+        let mut carried = vec![0.5; 1_200];
+
+        carry_after_cut(&mut carried, 1_000, &config(), CutReason::Silence);
+
+        assert!(carried.is_empty());
+        assert!(carried.capacity() >= 1_200);
+    }
+
     #[test]
     fn removes_duplicate_words_at_overlap_boundary() {
         assert_eq!(dedupe_boundary("hello world", "world again"), "again");
@@ -303,4 +422,24 @@ mod tests {
         assert_eq!(append_segment_space("hello"), "hello ");
         assert_eq!(append_segment_space("hello "), "hello ");
     }
+
+    #[test]
+    fn stable_prefix_holds_back_the_last_word() {
+        assert_eq!(stable_prefix("hello wor"), "hello");
+        assert_eq!(stable_prefix("hello world, this is "), "hello world, this");
+        assert_eq!(stable_prefix("hello"), "");
+    }
+
+    #[test]
+    fn incremental_correction_appends_or_erases_the_diverging_tail() {
+        assert_eq!(incremental_correction("", "hello"), (0, "hello"));
+        assert_eq!(
+            incremental_correction("hello world", "hello world again."),
+            (0, " again.")
+        );
+        assert_eq!(
+            incremental_correction("hello wörld", "hello word."),
+            (4, "ord.")
+        );
+    }
 }
@@ -0,0 +1,126 @@
+//! Running record of every dictation the daemon injected since it started.
+
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local};
+
+use crate::error::AppError;
+use crate::output::{self, OutputConfig, OutputMode};
+
+/// Where `dump` writes the session transcript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DumpTarget {
+    /// Overwrites this file.
+    File(PathBuf),
+    Clipboard,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptEntry {
+    pub timestamp: DateTime<Local>,
+    pub text: String,
+}
+
+/// Injected dictations in the order they went out, kept in memory for the
+/// daemon's lifetime. It is gone once the daemon exits, so `dump` is the
+/// way to keep it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionTranscript {
+    entries: Vec<TranscriptEntry>,
+}
+
+impl SessionTranscript {
+    /// Appends `text` stamped with the current time. Whitespace-only text,
+    /// such as a spoken "new line", adds nothing worth reading back.
+    pub fn push(&mut self, text: &str) {
+        self.push_at(Local::now(), text);
+    }
+
+    pub fn push_at(&mut self, timestamp: DateTime<Local>, text: &str) {
+        let text = text.trim();
+        if !text.is_empty() {
+            self.entries.push(TranscriptEntry {
+                timestamp,
+                text: text.to_string(),
+            });
+        }
+    }
+
+    pub fn entries(&self) -> &[TranscriptEntry] {
+        &self.entries
+    }
+
+    /// One `[HH:MM:SS] text` line per dictation.
+    pub fn render(&self) -> String {
+        self.entries
+            .iter()
+            .map(|entry| format!("[{}] {}\n", entry.timestamp.format("%H:%M:%S"), entry.text))
+            .collect()
+    }
+
+    /// Writes the rendered transcript to `target`.
+    pub fn dump(&self, target: &DumpTarget) -> Result<(), AppError> {
+        if self.entries.is_empty() {
+            return Err(AppError::runtime("session transcript is empty"));
+        }
+        let text = self.render();
+        match target {
+            DumpTarget::File(path) => fs::write(path, text).map_err(|err| {
+                AppError::runtime(format!("failed to write {}: {err}", path.display()))
+            }),
+            DumpTarget::Clipboard => {
+                let config = OutputConfig {
+                    mode: OutputMode::Clipboard,
+                    ..OutputConfig::default()
+                };
+                output::output_text(&text, &config)
+                    .map_err(|err| AppError::runtime(err.to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2024, 5, 1, hour, minute, 0)
+            .single()
+            .expect("valid local time")
+    }
+
+    #[test]
+    fn renders_each_dictation_with_its_time() {
+        let mut session = SessionTranscript::default();
+        session.push_at(at(9, 5), "First draft of the intro. ");
+        session.push_at(at(9, 7), "\n");
+        session.push_at(at(9, 12), "Second paragraph.");
+
+        assert_eq!(session.entries().len(), 2);
+        assert_eq!(
+            session.render(),
+            "[09:05:00] First draft of the intro.\n[09:12:00] Second paragraph.\n"
+        );
+    }
+
+    #[test]
+    fn dumps_to_a_file_and_refuses_an_empty_session() {
+        let path = std::env::temp_dir().join(format!("sv-session-{}.txt", std::process::id()));
+        let mut session = SessionTranscript::default();
+        let target = DumpTarget::File(path.clone());
+
+        assert!(session.dump(&target).is_err());
+        session.push_at(at(14, 30), "hello there");
+        session.dump(&target).expect("dump should succeed");
+
+        assert_eq!(
+            fs::read_to_string(&path).expect("read dump"),
+            "[14:30:00] hello there\n"
+        );
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,135 @@
+//! SRT and WebVTT captions for prerecorded WAV files.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::audio;
+use crate::error::AppError;
+use crate::types::SubtitleFormat;
+use crate::whisper::{DecodeOptions, TimedSegment, WhisperContext, WHISPER_SAMPLE_RATE};
+
+/// Model and decoder settings for one subtitle transcription.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleOptions {
+    pub model_path: PathBuf,
+    /// Language code passed to whisper; `auto` detects it.
+    pub language: String,
+    pub decode: DecodeOptions,
+}
+
+pub fn transcribe_to_srt(path: &Path, opts: &SubtitleOptions) -> Result<String, AppError> {
+    transcribe_to_subtitles(path, opts, SubtitleFormat::Srt)
+}
+
+pub fn transcribe_to_vtt(path: &Path, opts: &SubtitleOptions) -> Result<String, AppError> {
+    transcribe_to_subtitles(path, opts, SubtitleFormat::Vtt)
+}
+
+/// Transcribes a WAV file and formats its timed segments as captions.
+pub fn transcribe_to_subtitles(
+    path: &Path,
+    opts: &SubtitleOptions,
+    format: SubtitleFormat,
+) -> Result<String, AppError> {
+    let samples =
+        audio::read_wav(path, WHISPER_SAMPLE_RATE).map_err(|err| AppError::audio(err.message))?;
+    let context = WhisperContext::from_file(&opts.model_path)
+        .map_err(|err| AppError::runtime(err.to_string()))?;
+    let segments = context
+        .transcribe_segments(&samples, Some(&opts.language), &opts.decode)
+        .map_err(|err| AppError::runtime(err.to_string()))?;
+    Ok(format_subtitles(&segments, format))
+}
+
+pub fn format_subtitles(segments: &[TimedSegment], format: SubtitleFormat) -> String {
+    match format {
+        SubtitleFormat::Srt => format_srt(segments),
+        SubtitleFormat::Vtt => format_vtt(segments),
+    }
+}
+
+/// Numbered cues with `HH:MM:SS,mmm` timecodes.
+pub fn format_srt(segments: &[TimedSegment]) -> String {
+    let mut output = String::new();
+    for (index, segment) in segments.iter().enumerate() {
+        let _ = write!(
+            output,
+            "{}\n{} --> {}\n{}\n\n",
+            index + 1,
+            timecode(segment.start_ms, ','),
+            timecode(segment.end_ms, ','),
+            segment.text
+        );
+    }
+    output
+}
+
+/// A `WEBVTT` header followed by cues with `HH:MM:SS.mmm` timecodes.
+pub fn format_vtt(segments: &[TimedSegment]) -> String {
+    let mut output = String::from("WEBVTT\n\n");
+    for segment in segments {
+        let _ = write!(
+            output,
+            "{} --> {}\n{}\n\n",
+            timecode(segment.start_ms, '.'),
+            timecode(segment.end_ms, '.'),
+            segment.text
+        );
+    }
+    output
+}
+
+fn timecode(ms: u64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1_000 % 60,
+        ms % 1_000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Vec<TimedSegment> {
+        vec![
+            TimedSegment {
+                start_ms: 0,
+                end_ms: 2_500,
+                text: "And so my fellow Americans,".to_string(),
+            },
+            TimedSegment {
+                start_ms: 3_723_040,
+                end_ms: 3_725_000,
+                text: "ask not.".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn formats_srt_cues_with_comma_timecodes() {
+        assert_eq!(
+            format_srt(&fixture()),
+            "1\n00:00:00,000 --> 00:00:02,500\nAnd so my fellow Americans,\n\n\
+             2\n01:02:03,040 --> 01:02:05,000\nask not.\n\n"
+        );
+    }
+
+    #[test]
+    fn formats_vtt_with_header_and_dot_timecodes() {
+        assert_eq!(
+            format_subtitles(&fixture(), SubtitleFormat::Vtt),
+            "WEBVTT\n\n\
+             00:00:00.000 --> 00:00:02.500\nAnd so my fellow Americans,\n\n\
+             01:02:03.040 --> 01:02:05.000\nask not.\n\n"
+        );
+    }
+
+    #[test]
+    fn empty_transcript_yields_empty_srt_and_bare_vtt() {
+        assert_eq!(format_srt(&[]), "");
+        assert_eq!(format_vtt(&[]), "WEBVTT\n\n");
+    }
+}
@@ -1,10 +1,68 @@
-use std::sync::mpsc::{self, Receiver, Sender};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Condvar, Mutex};
 use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 use crate::error::AppError;
+use crate::types::QueueOverflow;
+use crate::whisper::{Candidate, TimedSegment};
+
+/// Called with a piece's index and its `(start, end)` time in seconds.
+pub type SegmentCallback = dyn FnMut(usize, (f32, f32)) + Send;
+
+/// How often a blocking wait checks for reported pieces.
+const SEGMENT_POLL_INTERVAL: Duration = Duration::from_millis(20);
 
 pub trait Transcriber: Send {
     fn transcribe(&self, samples: &[f32], language: Option<&str>) -> Result<String, AppError>;
+
+    /// Alternative transcripts, most likely first. Transcribers that cannot
+    /// score alternatives return their single transcript.
+    fn transcribe_candidates(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+    ) -> Result<Vec<Candidate>, AppError> {
+        let text = self.transcribe(samples, language)?;
+        Ok(vec![Candidate {
+            text,
+            avg_logprob: 0.0,
+        }])
+    }
+
+    /// Words of the last transcription with their confidence in `0.0..=1.0`.
+    /// Transcribers that cannot score words return none.
+    fn word_confidences(&self) -> Vec<(String, f32)> {
+        Vec::new()
+    }
+
+    /// The transcript split into segments with their times in the input.
+    /// Transcribers that cannot time segments return one untimed segment.
+    fn transcribe_segments(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+    ) -> Result<Vec<TimedSegment>, AppError> {
+        let text = self.transcribe(samples, language)?;
+        Ok(vec![TimedSegment {
+            start_ms: 0,
+            end_ms: 0,
+            text,
+        }])
+    }
+
+    /// A flag that stops the running decode when set from another thread.
+    /// Transcribers that cannot be interrupted return none.
+    fn abort_flag(&self) -> Option<Arc<AtomicBool>> {
+        None
+    }
+
+    /// Installs the callback each piece of a split recording is reported to
+    /// before it is decoded. Transcribers that never split recordings
+    /// ignore it.
+    fn set_segment_callback(&mut self, _callback: Box<SegmentCallback>) {}
 }
 
 pub struct TranscriptionJob {
@@ -12,85 +70,294 @@ pub struct TranscriptionJob {
     pub duration_ms: u64,
     pub language: Option<String>,
     pub had_overlap: bool,
+    /// Transcribes audio captured so far; the recording is still running.
+    pub partial: bool,
 }
 
 pub struct TranscriptionResult {
     pub index: u64,
     pub duration_ms: u64,
     pub transcript: Result<String, AppError>,
+    /// Per-word confidences of the transcript, when the transcriber has them.
+    pub words: Vec<(String, f32)>,
+    /// Timed segments of a partial transcript, for skipping settled audio.
+    pub segments: Vec<TimedSegment>,
     pub had_overlap: bool,
+    pub partial: bool,
 }
 
-enum WorkerCommand {
-    Transcribe { index: u64, job: TranscriptionJob },
-    Shutdown,
+/// Buffers kept for reuse; a recording plus a few queued jobs rarely need more.
+const MAX_POOLED_BUFFERS: usize = 4;
+/// Capacity a pooled buffer keeps, a minute at 16 kHz. A buffer grown by a
+/// longer recording is shrunk to it, so one long dictation does not hold
+/// its memory for the rest of the daemon's life.
+const MAX_POOLED_CAPACITY: usize = 60 * 16_000;
+
+/// Sample buffers recycled across recordings, so a warm daemon keeps their
+/// capacity instead of allocating for every dictation. A job's buffer comes
+/// back only after the worker has transcribed or discarded it.
+#[derive(Clone, Default)]
+pub struct BufferPool {
+    free: Arc<Mutex<Vec<Vec<f32>>>>,
 }
 
+impl BufferPool {
+    /// An empty buffer, with spare capacity when a recycled one is available.
+    pub fn take(&self) -> Vec<f32> {
+        self.free
+            .lock()
+            .ok()
+            .and_then(|mut free| free.pop())
+            .unwrap_or_default()
+    }
+
+    pub fn recycle(&self, mut buffer: Vec<f32>) {
+        if buffer.capacity() == 0 {
+            return;
+        }
+        buffer.clear();
+        buffer.shrink_to(MAX_POOLED_CAPACITY);
+        if let Ok(mut free) = self.free.lock() {
+            if free.len() < MAX_POOLED_BUFFERS {
+                free.push(buffer);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.free.lock().map_or(0, |free| free.len())
+    }
+}
+
+/// Jobs waiting for the worker thread, oldest first.
+#[derive(Default)]
+struct JobQueue {
+    jobs: VecDeque<(u64, TranscriptionJob)>,
+    shutdown: bool,
+}
+
+type SharedQueue = Arc<(Mutex<JobQueue>, Condvar)>;
+
 pub struct TranscriptionWorker {
-    jobs: Sender<WorkerCommand>,
+    queue: SharedQueue,
+    results_sender: Sender<TranscriptionResult>,
     results: Receiver<TranscriptionResult>,
+    segments: Receiver<(usize, (f32, f32))>,
     handle: Option<JoinHandle<()>>,
+    abort: Option<Arc<AtomicBool>>,
     next_index: u64,
     pending: usize,
+    max_queued: usize,
+    overflow: QueueOverflow,
+    buffers: BufferPool,
 }
 
 impl TranscriptionWorker {
+    /// Starts a worker with an unbounded queue. Jobs run one at a time in order.
     pub fn start(transcriber: Box<dyn Transcriber>) -> Self {
-        let (job_sender, job_receiver) = mpsc::channel();
+        Self::start_with_buffers(transcriber, BufferPool::default())
+    }
+
+    fn start_with_buffers(mut transcriber: Box<dyn Transcriber>, buffers: BufferPool) -> Self {
+        let queue: SharedQueue = Arc::default();
         let (result_sender, result_receiver) = mpsc::channel();
+        let (segment_sender, segment_receiver) = mpsc::channel();
+        transcriber.set_segment_callback(Box::new(move |index, span| {
+            let _ = segment_sender.send((index, span));
+        }));
 
+        let worker_queue = Arc::clone(&queue);
+        let worker_results = result_sender.clone();
+        let worker_buffers = buffers.clone();
+        let abort = transcriber.abort_flag();
         let handle = thread::spawn(move || {
-            while let Ok(command) = job_receiver.recv() {
-                match command {
-                    WorkerCommand::Transcribe { index, job } => {
-                        let transcript =
-                            transcriber.transcribe(&job.samples, job.language.as_deref());
-                        let result = TranscriptionResult {
-                            index,
-                            duration_ms: job.duration_ms,
-                            transcript,
-                            had_overlap: job.had_overlap,
-                        };
-                        if result_sender.send(result).is_err() {
-                            break;
-                        }
+            while let Some((index, job)) = next_job(&worker_queue) {
+                let (transcript, segments) = if job.partial {
+                    match transcriber.transcribe_segments(&job.samples, job.language.as_deref()) {
+                        Ok(segments) => (Ok(join_segments(&segments)), segments),
+                        Err(err) => (Err(err), Vec::new()),
                     }
-                    WorkerCommand::Shutdown => break,
+                } else {
+                    let transcript = transcriber.transcribe(&job.samples, job.language.as_deref());
+                    (transcript, Vec::new())
+                };
+                let words = if transcript.is_ok() && !job.partial {
+                    transcriber.word_confidences()
+                } else {
+                    Vec::new()
+                };
+                let result = TranscriptionResult {
+                    index,
+                    duration_ms: job.duration_ms,
+                    transcript,
+                    words,
+                    segments,
+                    had_overlap: job.had_overlap,
+                    partial: job.partial,
+                };
+                worker_buffers.recycle(job.samples);
+                if worker_results.send(result).is_err() {
+                    break;
                 }
             }
         });
 
         Self {
-            jobs: job_sender,
+            queue,
+            results_sender: result_sender,
             results: result_receiver,
+            segments: segment_receiver,
             handle: Some(handle),
+            abort,
             next_index: 1,
             pending: 0,
+            max_queued: 0,
+            overflow: QueueOverflow::DropOldest,
+            buffers,
         }
     }
 
+    /// The pool job and recording buffers are drawn from and returned to.
+    pub fn buffers(&self) -> &BufferPool {
+        &self.buffers
+    }
+
+    /// Caps the jobs waiting behind the running one; `0` leaves the queue unbounded.
+    pub fn with_queue_limit(mut self, max_queued: usize, overflow: QueueOverflow) -> Self {
+        self.max_queued = max_queued;
+        self.overflow = overflow;
+        self
+    }
+
+    /// Queues `job`. When the queue is full, the job discarded by the overflow
+    /// policy still produces a result, with an error transcript.
     pub fn submit(&mut self, job: TranscriptionJob) -> Result<(), AppError> {
         let index = self.next_index;
-        self.jobs
-            .send(WorkerCommand::Transcribe { index, job })
-            .map_err(|err| AppError::runtime(format!("transcription worker stopped: {err}")))?;
+        let (lock, ready) = &*self.queue;
+        let mut queue = lock
+            .lock()
+            .map_err(|_| AppError::runtime("transcription queue lock poisoned"))?;
+        if queue.shutdown || self.handle.is_none() {
+            return Err(AppError::runtime("transcription worker stopped"));
+        }
         self.next_index += 1;
         self.pending += 1;
+
+        if self.max_queued > 0 && queue.jobs.len() >= self.max_queued {
+            let discarded = match self.overflow {
+                QueueOverflow::DropOldest => {
+                    let oldest = queue.jobs.pop_front();
+                    queue.jobs.push_back((index, job));
+                    ready.notify_one();
+                    oldest
+                }
+                QueueOverflow::RejectNewest => Some((index, job)),
+            };
+            drop(queue);
+            if let Some((index, job)) = discarded {
+                self.discard(index, job);
+            }
+            return Ok(());
+        }
+
+        queue.jobs.push_back((index, job));
+        ready.notify_one();
         Ok(())
     }
 
+    fn discard(&self, index: u64, job: TranscriptionJob) {
+        let _ = self.results_sender.send(TranscriptionResult {
+            index,
+            duration_ms: job.duration_ms,
+            transcript: Err(AppError::runtime(format!(
+                "transcription queue full; utterance {index} dropped"
+            ))),
+            words: Vec::new(),
+            segments: Vec::new(),
+            had_overlap: job.had_overlap,
+            partial: job.partial,
+        });
+        self.buffers.recycle(job.samples);
+    }
+
     pub fn try_recv(&mut self) -> Option<TranscriptionResult> {
         let result = self.results.try_recv().ok()?;
         self.pending = self.pending.saturating_sub(1);
         Some(result)
     }
 
+    #[cfg(test)]
     pub fn recv(&mut self) -> Option<TranscriptionResult> {
         let result = self.results.recv().ok()?;
         self.pending = self.pending.saturating_sub(1);
         Some(result)
     }
 
+    /// Waits for the next result like `recv`, passing the pieces the running
+    /// job reports meanwhile to `on_segment` as they arrive.
+    pub fn recv_reporting_segments(
+        &mut self,
+        mut on_segment: impl FnMut(usize, (f32, f32)),
+    ) -> Option<TranscriptionResult> {
+        loop {
+            let received = self.results.recv_timeout(SEGMENT_POLL_INTERVAL);
+            // The worker reports a job's pieces before sending its result, so
+            // they are all here once the result is.
+            while let Some((index, span)) = self.try_recv_segment() {
+                on_segment(index, span);
+            }
+            match received {
+                Ok(result) => {
+                    self.pending = self.pending.saturating_sub(1);
+                    return Some(result);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+
+    /// The next piece the running job reported, if any.
+    pub fn try_recv_segment(&self) -> Option<(usize, (f32, f32))> {
+        self.segments.try_recv().ok()
+    }
+
+    /// Waits for the next result like `recv`, calling `on_wait` every
+    /// `interval` until it arrives. Gives up with `None` at `deadline`.
+    pub fn recv_polling(
+        &mut self,
+        interval: Duration,
+        deadline: Option<Instant>,
+        mut on_wait: impl FnMut(),
+    ) -> Option<TranscriptionResult> {
+        loop {
+            let mut wait = interval;
+            if let Some(deadline) = deadline {
+                let left = deadline.saturating_duration_since(Instant::now());
+                if left.is_zero() {
+                    return None;
+                }
+                wait = wait.min(left);
+            }
+            match self.results.recv_timeout(wait) {
+                Ok(result) => {
+                    self.pending = self.pending.saturating_sub(1);
+                    return Some(result);
+                }
+                Err(RecvTimeoutError::Timeout) => on_wait(),
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+
+    #[cfg(test)]
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Option<TranscriptionResult> {
+        let result = self.results.recv_timeout(timeout).ok()?;
+        self.pending = self.pending.saturating_sub(1);
+        Some(result)
+    }
+
     pub fn has_pending(&self) -> bool {
         self.pending > 0
     }
@@ -102,14 +369,57 @@ impl TranscriptionWorker {
             ));
         }
         let next_index = self.next_index;
+        let (max_queued, overflow) = (self.max_queued, self.overflow);
         self.shutdown()?;
-        *self = Self::start(transcriber);
+        let buffers = self.buffers.clone();
+        *self =
+            Self::start_with_buffers(transcriber, buffers).with_queue_limit(max_queued, overflow);
         self.next_index = next_index;
         Ok(())
     }
 
+    /// Stops the worker thread and drops its transcriber. `reload` starts it again.
+    pub fn unload(&mut self) -> Result<(), AppError> {
+        if self.has_pending() {
+            return Err(AppError::runtime(
+                "cannot unload transcriber while transcription is pending",
+            ));
+        }
+        self.shutdown()
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.handle.is_some()
+    }
+
+    /// Stops the worker without waiting for its queue. Queued jobs are
+    /// dropped; a job still running is aborted and the thread joined when
+    /// the transcriber can be interrupted, and otherwise finishes on the
+    /// detached thread with nobody reading its result. Returns how many
+    /// jobs never produced a result.
+    pub fn abandon(&mut self) -> usize {
+        let (lock, ready) = &*self.queue;
+        if let Ok(mut queue) = lock.lock() {
+            queue.shutdown = true;
+            for (_, job) in queue.jobs.drain(..) {
+                self.buffers.recycle(job.samples);
+            }
+        }
+        ready.notify_all();
+        let handle = self.handle.take();
+        if let (Some(handle), Some(abort)) = (handle, &self.abort) {
+            abort.store(true, Ordering::Relaxed);
+            let _ = handle.join();
+        }
+        std::mem::take(&mut self.pending)
+    }
+
     pub fn shutdown(&mut self) -> Result<(), AppError> {
-        let _ = self.jobs.send(WorkerCommand::Shutdown);
+        let (lock, ready) = &*self.queue;
+        if let Ok(mut queue) = lock.lock() {
+            queue.shutdown = true;
+        }
+        ready.notify_all();
         if let Some(handle) = self.handle.take() {
             handle
                 .join()
@@ -119,20 +429,82 @@ impl TranscriptionWorker {
     }
 }
 
+/// Blocks until a job is queued; returns `None` once the queue is shut down and empty.
+fn next_job(queue: &SharedQueue) -> Option<(u64, TranscriptionJob)> {
+    let (lock, ready) = &**queue;
+    let mut queue = lock.lock().ok()?;
+    loop {
+        if let Some(job) = queue.jobs.pop_front() {
+            return Some(job);
+        }
+        if queue.shutdown {
+            return None;
+        }
+        queue = ready.wait(queue).ok()?;
+    }
+}
+
+/// Segment texts joined the way whisper's own transcript joins them.
+pub fn join_segments(segments: &[TimedSegment]) -> String {
+    segments
+        .iter()
+        .map(|segment| segment.text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 impl Drop for TranscriptionWorker {
     fn drop(&mut self) {
         let _ = self.shutdown();
     }
 }
 
+#[cfg(test)]
+pub mod test_support {
+    use std::sync::Mutex;
+
+    use super::{SegmentCallback, Transcriber};
+    use crate::error::AppError;
+
+    /// Reports two pieces from inside the decode, like a code-switched one,
+    /// and transcribes them as "two pieces".
+    #[derive(Default)]
+    pub struct PieceTranscriber {
+        callback: Mutex<Option<Box<SegmentCallback>>>,
+    }
+
+    impl Transcriber for PieceTranscriber {
+        fn transcribe(
+            &self,
+            _samples: &[f32],
+            _language: Option<&str>,
+        ) -> Result<String, AppError> {
+            if let Some(callback) = self.callback.lock().expect("callback lock").as_mut() {
+                callback(0, (0.0, 2.0));
+                callback(1, (2.0, 4.5));
+            }
+            Ok("two pieces".to_string())
+        }
+
+        fn set_segment_callback(&mut self, callback: Box<SegmentCallback>) {
+            self.callback = Mutex::new(Some(callback));
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::sync::mpsc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Arc};
     use std::thread;
     use std::time::Duration;
 
-    use super::{Transcriber, TranscriptionJob, TranscriptionWorker};
+    use super::{BufferPool, Transcriber, TranscriptionJob, TranscriptionWorker};
     use crate::error::AppError;
+    use crate::types::QueueOverflow;
+
+    use super::test_support::PieceTranscriber;
 
     struct BlockingTranscriber {
         started: mpsc::Sender<()>,
@@ -166,6 +538,7 @@ mod tests {
                 duration_ms: 10,
                 language: Some("en".to_string()),
                 had_overlap: false,
+                partial: false,
             })
             .expect("submit job");
 
@@ -187,6 +560,167 @@ mod tests {
         worker.shutdown().expect("shutdown worker");
     }
 
+    #[test]
+    fn abandon_drops_queued_jobs_without_waiting_for_the_running_one() {
+        let (started_sender, started_receiver) = mpsc::channel();
+        let (release_sender, release_receiver) = mpsc::channel();
+        let mut worker = TranscriptionWorker::start(Box::new(BlockingTranscriber {
+            started: started_sender,
+            release: release_receiver,
+        }));
+        for _ in 0..2 {
+            worker
+                .submit(TranscriptionJob {
+                    samples: vec![0.2; 160],
+                    duration_ms: 10,
+                    language: None,
+                    had_overlap: false,
+                    partial: false,
+                })
+                .expect("submit job");
+        }
+        started_receiver
+            .recv_timeout(Duration::from_secs(1))
+            .expect("worker started job");
+
+        assert!(worker.recv_timeout(Duration::from_millis(10)).is_none());
+        assert_eq!(worker.abandon(), 2);
+        assert!(!worker.has_pending());
+        assert!(!worker.is_loaded());
+        assert!(worker.buffers().len() >= 1);
+        let _ = release_sender.send(());
+    }
+
+    struct AbortableTranscriber {
+        started: mpsc::Sender<()>,
+        abort: Arc<AtomicBool>,
+    }
+
+    impl Transcriber for AbortableTranscriber {
+        fn transcribe(
+            &self,
+            _samples: &[f32],
+            _language: Option<&str>,
+        ) -> Result<String, AppError> {
+            self.started.send(()).expect("started signal");
+            while !self.abort.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(1));
+            }
+            Err(AppError::runtime("aborted"))
+        }
+
+        fn abort_flag(&self) -> Option<Arc<AtomicBool>> {
+            Some(Arc::clone(&self.abort))
+        }
+    }
+
+    #[test]
+    fn abandon_aborts_and_joins_an_interruptible_decode() {
+        let (started_sender, started_receiver) = mpsc::channel();
+        let abort = Arc::new(AtomicBool::new(false));
+        let mut worker = TranscriptionWorker::start(Box::new(AbortableTranscriber {
+            started: started_sender,
+            abort: Arc::clone(&abort),
+        }));
+        worker
+            .submit(TranscriptionJob {
+                samples: vec![0.2; 160],
+                duration_ms: 10,
+                language: None,
+                had_overlap: false,
+                partial: false,
+            })
+            .expect("submit job");
+        started_receiver
+            .recv_timeout(Duration::from_secs(1))
+            .expect("worker started job");
+
+        assert_eq!(worker.abandon(), 1);
+        assert!(abort.load(Ordering::Relaxed));
+        // The transcriber was moved into the thread; only the join dropped it.
+        assert_eq!(Arc::strong_count(&abort), 2);
+        assert!(!worker.is_loaded());
+    }
+
+    #[test]
+    fn recv_reporting_segments_passes_pieces_before_the_result() {
+        let mut worker = TranscriptionWorker::start(Box::new(PieceTranscriber::default()));
+        worker
+            .submit(TranscriptionJob {
+                samples: vec![0.2; 160],
+                duration_ms: 10,
+                language: None,
+                had_overlap: false,
+                partial: false,
+            })
+            .expect("submit job");
+
+        let mut pieces = Vec::new();
+        let result = worker
+            .recv_reporting_segments(|index, span| pieces.push((index, span)))
+            .expect("result");
+
+        assert_eq!(pieces, [(0, (0.0, 2.0)), (1, (2.0, 4.5))]);
+        assert_eq!(result.transcript.expect("transcript"), "two pieces");
+        assert!(!worker.has_pending());
+        assert!(worker.try_recv_segment().is_none());
+    }
+
+    #[test]
+    fn job_buffer_returns_to_pool_only_after_transcription() {
+        let (started_sender, started_receiver) = mpsc::channel();
+        let (release_sender, release_receiver) = mpsc::channel();
+        let mut worker = TranscriptionWorker::start(Box::new(BlockingTranscriber {
+            started: started_sender,
+            release: release_receiver,
+        }));
+        let mut samples = worker.buffers().take();
+        samples.resize(1_600, 0.2);
+
+        worker
+            .submit(TranscriptionJob {
+                samples,
+                duration_ms: 100,
+                language: None,
+                had_overlap: false,
+                partial: false,
+            })
+            .expect("submit job");
+        started_receiver
+            .recv_timeout(Duration::from_secs(1))
+            .expect("worker started job");
+        assert_eq!(worker.buffers().len(), 0);
+
+        release_sender.send(()).expect("release worker");
+        worker.recv().expect("result");
+        let reused = worker.buffers().take();
+
+        assert!(reused.is_empty());
+        assert!(reused.capacity() >= 1_600);
+        worker.shutdown().expect("shutdown worker");
+    }
+
+    #[test]
+    fn buffer_pool_keeps_a_bounded_number_of_buffers() {
+        let pool = BufferPool::default();
+        for _ in 0..10 {
+            pool.recycle(vec![0.0; 16]);
+        }
+        pool.recycle(Vec::new());
+
+        assert_eq!(pool.len(), super::MAX_POOLED_BUFFERS);
+    }
+
+    #[test]
+    fn buffer_pool_shrinks_oversized_buffers() {
+        let pool = BufferPool::default();
+        pool.recycle(Vec::with_capacity(super::MAX_POOLED_CAPACITY * 4));
+
+        let buffer = pool.take();
+        assert!(buffer.capacity() >= super::MAX_POOLED_CAPACITY);
+        assert!(buffer.capacity() < super::MAX_POOLED_CAPACITY * 4);
+    }
+
     struct LanguageProbeTranscriber {
         seen_language: mpsc::Sender<Option<String>>,
     }
@@ -213,6 +747,7 @@ mod tests {
                 duration_ms: 10,
                 language: None,
                 had_overlap: false,
+                partial: false,
             })
             .expect("submit job");
 
@@ -243,6 +778,7 @@ mod tests {
                     duration_ms: 1,
                     language: None,
                     had_overlap: false,
+                    partial: false,
                 })
                 .expect("submit job");
         }
@@ -268,6 +804,7 @@ mod tests {
                 duration_ms: 1,
                 language: None,
                 had_overlap: false,
+                partial: false,
             })
             .expect("submit first job");
         assert_eq!(worker.recv().expect("first result").index, 1);
@@ -281,9 +818,81 @@ mod tests {
                 duration_ms: 1,
                 language: None,
                 had_overlap: false,
+                partial: false,
             })
             .expect("submit second job");
 
         assert_eq!(worker.recv().expect("second result").index, 2);
     }
+
+    #[test]
+    fn full_queue_applies_overflow_policy_and_still_reports_each_job() {
+        for (overflow, dropped) in [
+            (QueueOverflow::DropOldest, 2),
+            (QueueOverflow::RejectNewest, 3),
+        ] {
+            let (started_sender, started_receiver) = mpsc::channel();
+            let (release_sender, release_receiver) = mpsc::channel();
+            let mut worker = TranscriptionWorker::start(Box::new(BlockingTranscriber {
+                started: started_sender,
+                release: release_receiver,
+            }))
+            .with_queue_limit(1, overflow);
+
+            let job = || TranscriptionJob {
+                samples: vec![0.2],
+                duration_ms: 1,
+                language: None,
+                had_overlap: false,
+                partial: false,
+            };
+            worker.submit(job()).expect("submit first job");
+            started_receiver
+                .recv_timeout(Duration::from_secs(1))
+                .expect("first job started");
+            worker.submit(job()).expect("submit second job");
+            worker.submit(job()).expect("submit third job");
+            for _ in 0..2 {
+                release_sender.send(()).expect("release worker");
+            }
+
+            let mut results = (0..3)
+                .map(|_| worker.recv().expect("result"))
+                .collect::<Vec<_>>();
+            results.sort_by_key(|result| result.index);
+            let failed = results
+                .iter()
+                .filter(|result| result.transcript.is_err())
+                .map(|result| result.index)
+                .collect::<Vec<_>>();
+            assert_eq!(failed, [dropped], "{overflow:?}");
+            assert!(!worker.has_pending());
+            worker.shutdown().expect("shutdown worker");
+        }
+    }
+
+    #[test]
+    fn unloaded_worker_resumes_sequence_after_reload() {
+        let mut worker = TranscriptionWorker::start(Box::new(SampleTranscriber));
+        worker.unload().expect("unload transcriber");
+        assert!(!worker.is_loaded());
+
+        worker
+            .reload(Box::new(SampleTranscriber))
+            .expect("reload transcriber");
+        assert!(worker.is_loaded());
+        worker
+            .submit(TranscriptionJob {
+                samples: vec![3.0],
+                duration_ms: 1,
+                language: None,
+                had_overlap: false,
+                partial: false,
+            })
+            .expect("submit job");
+
+        let result = worker.recv().expect("result");
+        assert_eq!(result.index, 1);
+        assert_eq!(result.transcript.expect("transcript"), "3");
+    }
 }
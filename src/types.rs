@@ -1,14 +1,16 @@
 use clap::ValueEnum;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Copy, Clone, ValueEnum, Deserialize)]
+#[derive(Debug, Copy, Clone, ValueEnum, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum OutputFormat {
     Plain,
     Jsonl,
+    /// One result object per transcript; fields this run cannot compute are `null`.
+    Json,
 }
 
-#[derive(Debug, Copy, Clone, ValueEnum, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum AudioHost {
     Default,
@@ -21,10 +23,63 @@ impl AudioHost {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum VadMode {
     On,
     Off,
     Continuous,
 }
+
+/// What a full transcription queue does with one more job.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum QueueOverflow {
+    /// Discard the longest-waiting job to make room.
+    DropOldest,
+    /// Discard the job being submitted.
+    RejectNewest,
+}
+
+/// How transcript casing is adjusted after decoding.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Casing {
+    /// Keep whisper's casing as decoded.
+    #[default]
+    Whisper,
+    /// Uppercase the first letter of each sentence; leave all other casing alone.
+    SentenceStartOnly,
+    /// Lowercase everything, for tools that match text case-sensitively.
+    ForceLower,
+}
+
+/// Byte layout of raw PCM read from stdin: mono, little-endian, no header.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PcmFormat {
+    /// 32-bit IEEE float samples in [-1.0, 1.0].
+    F32le,
+    /// Signed 16-bit integer samples.
+    S16le,
+}
+
+/// Caption file layout written by `sv subtitles`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum SubtitleFormat {
+    /// SubRip: numbered cues, `HH:MM:SS,mmm` timecodes.
+    Srt,
+    /// WebVTT: `WEBVTT` header, `HH:MM:SS.mmm` timecodes.
+    Vtt,
+}
+
+/// File written next to each input by `sv batch`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum SidecarFormat {
+    /// The plain transcript on one line.
+    Txt,
+    /// SubRip captions.
+    Srt,
+    /// WebVTT captions.
+    Vtt,
+}
@@ -0,0 +1,96 @@
+//! Transcripts broadcast as JSON text frames to websocket clients, for
+//! consumers on other machines such as a browser-based notes app.
+
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use tungstenite::{Message, WebSocket};
+
+use crate::error::AppError;
+
+/// Time a client gets to finish the handshake, and the longest a frame may
+/// block on a stalled client before that client is dropped.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+type Clients = Arc<Mutex<Vec<WebSocket<TcpStream>>>>;
+
+/// Accepts websocket clients in the background and sends each of them every
+/// frame. Clients only listen; anything they send is ignored.
+pub struct Broadcaster {
+    clients: Clients,
+    local_addr: SocketAddr,
+}
+
+impl Broadcaster {
+    pub fn start(addr: &str) -> Result<Self, AppError> {
+        let listener = TcpListener::bind(addr).map_err(|err| {
+            AppError::runtime(format!("failed to start websocket server on {addr}: {err}"))
+        })?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|err| AppError::runtime(format!("websocket server address: {err}")))?;
+        let clients = Clients::default();
+        let accepted = Arc::clone(&clients);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = stream.set_read_timeout(Some(CLIENT_TIMEOUT));
+                let _ = stream.set_write_timeout(Some(CLIENT_TIMEOUT));
+                match tungstenite::accept(stream) {
+                    Ok(client) => match accepted.lock() {
+                        Ok(mut clients) => clients.push(client),
+                        Err(_) => break,
+                    },
+                    Err(err) => log::debug!("websocket handshake failed: {err}"),
+                }
+            }
+        });
+        Ok(Self {
+            clients,
+            local_addr,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Sends `frame` to every client, dropping those that disconnected.
+    pub fn send(&self, frame: &serde_json::Value) {
+        let text = frame.to_string();
+        let Ok(mut clients) = self.clients.lock() else {
+            return;
+        };
+        clients.retain_mut(|client| client.send(Message::text(text.clone())).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connected_clients_receive_each_frame() {
+        let broadcaster = Broadcaster::start("127.0.0.1:0").expect("start server");
+        let url = format!("ws://{}", broadcaster.local_addr());
+        let stream = TcpStream::connect(broadcaster.local_addr()).expect("connect");
+        let (mut client, _) = tungstenite::client(url, stream).expect("handshake");
+        let started = std::time::Instant::now();
+        while broadcaster.clients.lock().expect("clients").is_empty() {
+            assert!(
+                started.elapsed() < CLIENT_TIMEOUT,
+                "client was not accepted"
+            );
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        broadcaster.send(&serde_json::json!({ "type": "final", "text": "hello" }));
+
+        let frame = client.read().expect("read frame");
+        assert_eq!(
+            frame.into_text().expect("text frame"),
+            r#"{"text":"hello","type":"final"}"#
+        );
+    }
+}
@@ -4,10 +4,11 @@ use std::os::unix::ffi::OsStrExt;
 use std::path::Path;
 use std::ptr::NonNull;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Mutex, OnceLock};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
 
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 
 #[allow(
     dead_code,
@@ -29,14 +30,200 @@ unsafe extern "C" {
         language: *const c_char,
         detect_language: bool,
         n_threads: c_int,
+        no_context: bool,
+        single_segment: bool,
+        suppress_nst: bool,
+        suppress_regex: *const c_char,
+        temperature: f32,
+        temperature_inc: f32,
+        logprob_thold: f32,
+        timeout_ms: c_int,
+        abort: *const bool,
     ) -> c_int;
+
+    fn sv_whisper_full_sampled(
+        ctx: *mut whisper_context,
+        samples: *const f32,
+        n_samples: c_int,
+        language: *const c_char,
+        detect_language: bool,
+        n_threads: c_int,
+        no_context: bool,
+        single_segment: bool,
+        suppress_nst: bool,
+        suppress_regex: *const c_char,
+        temperature: f32,
+        best_of: c_int,
+        timeout_ms: c_int,
+        abort: *const bool,
+    ) -> c_int;
+
+    fn sv_whisper_full_timed(
+        ctx: *mut whisper_context,
+        samples: *const f32,
+        n_samples: c_int,
+        language: *const c_char,
+        detect_language: bool,
+        n_threads: c_int,
+        suppress_nst: bool,
+        suppress_regex: *const c_char,
+        temperature: f32,
+        temperature_inc: f32,
+        logprob_thold: f32,
+        timeout_ms: c_int,
+        abort: *const bool,
+    ) -> c_int;
+
+    fn sv_whisper_lang_probs(
+        ctx: *mut whisper_context,
+        samples: *const f32,
+        n_samples: c_int,
+        n_threads: c_int,
+        lang_probs: *mut f32,
+    ) -> c_int;
+}
+
+/// Input rate whisper models are trained on; other rates decode as noise.
+pub const WHISPER_SAMPLE_RATE: u32 = 16_000;
+
+/// whisper.cpp's default starting temperature (greedy decoding).
+pub const DEFAULT_TEMPERATURE: f32 = 0.0;
+/// whisper.cpp's default step added on each fallback retry.
+pub const DEFAULT_TEMPERATURE_INC: f32 = 0.2;
+/// whisper.cpp's default average log probability below which a decode is retried.
+pub const DEFAULT_LOGPROB_THOLD: f32 = -1.0;
+
+/// Shim return code for a `suppress_regex` that does not compile.
+const SV_ERR_SUPPRESS_REGEX: c_int = -1000;
+/// Shim return code for a decode aborted after running past its timeout.
+const SV_ERR_TIMED_OUT: c_int = -1001;
+/// Shim return code for a decode stopped through the context's abort flag.
+const SV_ERR_ABORTED: c_int = -1002;
+
+/// Decoder settings passed to each `whisper_full` call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeOptions {
+    /// Ignore text decoded by earlier calls on the same context.
+    pub no_context: bool,
+    /// Decode the whole buffer as one segment.
+    pub single_segment: bool,
+    /// Sampling temperature of the first decode attempt.
+    pub temperature: f32,
+    /// Temperature added on each retry; `0.0` disables the fallback.
+    pub temperature_inc: f32,
+    /// Retry when a decode's average token log probability falls below this.
+    pub logprob_thold: f32,
+    /// Suppress non-speech tokens such as `[MUSIC]` or `(laughs)`.
+    pub suppress_nst: bool,
+    /// Suppress every token whose text matches this ECMAScript regex.
+    pub suppress_regex: Option<String>,
+    /// Abort a decode that runs longer than this many milliseconds; `0`
+    /// never aborts.
+    pub timeout_ms: u64,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            no_context: true,
+            single_segment: true,
+            temperature: DEFAULT_TEMPERATURE,
+            temperature_inc: DEFAULT_TEMPERATURE_INC,
+            logprob_thold: DEFAULT_LOGPROB_THOLD,
+            suppress_nst: false,
+            suppress_regex: None,
+            timeout_ms: 0,
+        }
+    }
+}
+
+/// Sampling settings for [`WhisperContext::transcribe_candidates`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandidateOptions {
+    /// One decode runs per temperature; `0.0` is the greedy pick.
+    pub temperatures: Vec<f32>,
+    /// Samples drawn per non-zero temperature, keeping the most likely.
+    pub best_of: u32,
+}
+
+impl Default for CandidateOptions {
+    fn default() -> Self {
+        Self {
+            temperatures: vec![0.0, 0.4, 0.8],
+            best_of: 5,
+        }
+    }
+}
+
+/// Per-piece language detection for speech that mixes languages. The
+/// recording is split at pauses and each piece is decoded in the language
+/// detected for it.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CodeSwitching {
+    pub enabled: bool,
+    /// Languages detection may choose from, e.g. `["de", "en"]`; empty
+    /// allows every language whisper knows.
+    pub languages: Vec<String>,
+    /// Shortest pause that separates two pieces.
+    pub min_pause_ms: u64,
+}
+
+impl Default for CodeSwitching {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            languages: Vec::new(),
+            min_pause_ms: 300,
+        }
+    }
+}
+
+/// One decoded alternative with its mean token log-probability.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub text: String,
+    pub avg_logprob: f32,
+}
+
+/// One decoded segment with its position in the input, in milliseconds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimedSegment {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+}
+
+/// Which shim entry point a decode goes through.
+#[derive(Debug, Copy, Clone)]
+enum FullRun {
+    /// The configured decode, with whisper's temperature fallback.
+    Configured,
+    /// One fixed temperature and no fallback, for candidate sampling.
+    Sampled { temperature: f32, best_of: c_int },
+    /// The configured decode with timestamps and segment splitting on.
+    Timed,
 }
 
 #[derive(Debug)]
 pub enum WhisperError {
     InvalidPath(NulError),
     InitFailed,
+    /// The sample buffer was empty.
+    EmptyInput,
+    /// The sample count does not fit the `int` length whisper.cpp takes.
+    InputTooLong(usize),
     TranscriptionFailed(i32),
+    /// whisper.cpp rejected the `suppress_regex` pattern.
+    InvalidSuppressRegex(String),
+    /// The decode ran past `DecodeOptions::timeout_ms` and was aborted.
+    /// `partial` holds the segments finished before that, possibly none.
+    TimedOut {
+        timeout_ms: u64,
+        partial: String,
+    },
+    /// The decode stopped because the abort flag was set.
+    Aborted,
 }
 
 struct LogCapture {
@@ -118,9 +305,23 @@ impl std::fmt::Display for WhisperError {
         match self {
             WhisperError::InvalidPath(error) => write!(f, "invalid model path: {error}"),
             WhisperError::InitFailed => write!(f, "failed to initialize whisper context"),
+            WhisperError::EmptyInput => write!(f, "no audio samples to transcribe"),
+            WhisperError::InputTooLong(samples) => {
+                write!(f, "audio too long to transcribe: {samples} samples")
+            }
             WhisperError::TranscriptionFailed(code) => {
                 write!(f, "whisper transcription failed with code {code}")
             }
+            WhisperError::InvalidSuppressRegex(pattern) => {
+                write!(
+                    f,
+                    "suppress_regex is not a valid regular expression: {pattern}"
+                )
+            }
+            WhisperError::TimedOut { timeout_ms, .. } => {
+                write!(f, "transcription timed out after {timeout_ms} ms")
+            }
+            WhisperError::Aborted => write!(f, "transcription aborted"),
         }
     }
 }
@@ -136,6 +337,7 @@ impl std::error::Error for WhisperError {
 
 pub struct WhisperContext {
     ctx: NonNull<whisper_context>,
+    abort: Arc<AtomicBool>,
 }
 
 unsafe impl Send for WhisperContext {}
@@ -160,7 +362,16 @@ impl WhisperContext {
         let ctx = unsafe { whisper_init_from_file_with_params(path_c.as_ptr(), params) };
         let ctx = NonNull::new(ctx).ok_or(WhisperError::InitFailed)?;
         eprintln!("{}", log_capture.summary());
-        Ok(Self { ctx })
+        Ok(Self {
+            ctx,
+            abort: Arc::default(),
+        })
+    }
+
+    /// Setting this flag aborts the running decode, and every later one,
+    /// from any thread.
+    pub fn abort_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.abort)
     }
 
     pub fn transcribe(
@@ -168,8 +379,105 @@ impl WhisperContext {
         samples: &[f32],
         language: Option<&str>,
     ) -> Result<String, WhisperError> {
-        let available_threads = thread::available_parallelism().map_or(1, std::num::NonZero::get);
-        let n_threads = (available_threads / 2).max(1) as i32;
+        self.transcribe_with(samples, language, &DecodeOptions::default())
+    }
+
+    pub fn transcribe_with(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+        decode: &DecodeOptions,
+    ) -> Result<String, WhisperError> {
+        self.run_full(samples, language, decode, FullRun::Configured)?;
+        Ok(self.segment_text())
+    }
+
+    /// Decodes with timestamps and returns each non-empty segment with its
+    /// start and end time. `no_context` and `single_segment` are ignored:
+    /// every call starts fresh and whisper splits segments itself.
+    pub fn transcribe_segments(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+        decode: &DecodeOptions,
+    ) -> Result<Vec<TimedSegment>, WhisperError> {
+        self.run_full(samples, language, decode, FullRun::Timed)?;
+        Ok(self.timed_segments())
+    }
+
+    /// Decodes once per configured temperature and returns the distinct
+    /// transcripts, most likely first.
+    pub fn transcribe_candidates(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+        decode: &DecodeOptions,
+        options: &CandidateOptions,
+    ) -> Result<Vec<Candidate>, WhisperError> {
+        let mut candidates = Vec::with_capacity(options.temperatures.len());
+        for &temperature in &options.temperatures {
+            self.run_full(
+                samples,
+                language,
+                decode,
+                FullRun::Sampled {
+                    temperature,
+                    best_of: options.best_of.max(1) as c_int,
+                },
+            )?;
+            candidates.push(Candidate {
+                text: self.segment_text(),
+                avg_logprob: self.average_logprob(),
+            });
+        }
+        Ok(rank_candidates(candidates))
+    }
+
+    /// Detects the spoken language of `samples`, restricted to `allowed`
+    /// language codes when that is not empty. Unknown codes are ignored.
+    pub fn detect_language(
+        &self,
+        samples: &[f32],
+        allowed: &[String],
+    ) -> Result<String, WhisperError> {
+        let n_samples = sample_count(samples)?;
+        let max_id = unsafe { whisper_lang_max_id() };
+        let mut probs = vec![0.0f32; max_id.max(0) as usize + 1];
+        let best = unsafe {
+            sv_whisper_lang_probs(
+                self.ctx.as_ptr(),
+                samples.as_ptr(),
+                n_samples,
+                n_threads(),
+                probs.as_mut_ptr(),
+            )
+        };
+        if best < 0 {
+            return Err(WhisperError::TranscriptionFailed(best));
+        }
+        let mut allowed_ids = Vec::with_capacity(allowed.len());
+        for language in allowed {
+            let language = CString::new(language.as_str()).map_err(WhisperError::InvalidPath)?;
+            let id = unsafe { whisper_lang_id(language.as_ptr()) };
+            if id >= 0 {
+                allowed_ids.push(id);
+            }
+        }
+        let id = pick_language(&probs, &allowed_ids, best);
+        let code = unsafe { CStr::from_ptr(whisper_lang_str(id)) };
+        Ok(code.to_string_lossy().into_owned())
+    }
+
+    fn run_full(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+        decode: &DecodeOptions,
+        run: FullRun,
+    ) -> Result<(), WhisperError> {
+        let n_samples = sample_count(samples)?;
+        let n_threads = n_threads();
+        let timeout_ms = decode.timeout_ms.min(c_int::MAX as u64) as c_int;
 
         let detect_language = language.is_none();
         let language_cstring;
@@ -179,21 +487,86 @@ impl WhisperContext {
         } else {
             std::ptr::null()
         };
+        let suppress_regex = suppress_regex_cstring(decode.suppress_regex.as_deref())?;
+        let suppress_regex_ptr = suppress_regex
+            .as_ref()
+            .map_or(std::ptr::null(), |regex| regex.as_ptr());
 
         let result = unsafe {
-            sv_whisper_full_configured(
-                self.ctx.as_ptr(),
-                samples.as_ptr(),
-                samples.len() as i32,
-                language_ptr,
-                detect_language,
-                n_threads,
-            )
+            match run {
+                FullRun::Configured => sv_whisper_full_configured(
+                    self.ctx.as_ptr(),
+                    samples.as_ptr(),
+                    n_samples,
+                    language_ptr,
+                    detect_language,
+                    n_threads,
+                    decode.no_context,
+                    decode.single_segment,
+                    decode.suppress_nst,
+                    suppress_regex_ptr,
+                    decode.temperature,
+                    decode.temperature_inc,
+                    decode.logprob_thold,
+                    timeout_ms,
+                    self.abort.as_ptr(),
+                ),
+                FullRun::Sampled {
+                    temperature,
+                    best_of,
+                } => sv_whisper_full_sampled(
+                    self.ctx.as_ptr(),
+                    samples.as_ptr(),
+                    n_samples,
+                    language_ptr,
+                    detect_language,
+                    n_threads,
+                    decode.no_context,
+                    decode.single_segment,
+                    decode.suppress_nst,
+                    suppress_regex_ptr,
+                    temperature,
+                    best_of,
+                    timeout_ms,
+                    self.abort.as_ptr(),
+                ),
+                FullRun::Timed => sv_whisper_full_timed(
+                    self.ctx.as_ptr(),
+                    samples.as_ptr(),
+                    n_samples,
+                    language_ptr,
+                    detect_language,
+                    n_threads,
+                    decode.suppress_nst,
+                    suppress_regex_ptr,
+                    decode.temperature,
+                    decode.temperature_inc,
+                    decode.logprob_thold,
+                    timeout_ms,
+                    self.abort.as_ptr(),
+                ),
+            }
         };
+        if result == SV_ERR_SUPPRESS_REGEX {
+            let pattern = decode.suppress_regex.clone().unwrap_or_default();
+            return Err(WhisperError::InvalidSuppressRegex(pattern));
+        }
+        if result == SV_ERR_ABORTED {
+            return Err(WhisperError::Aborted);
+        }
+        if result == SV_ERR_TIMED_OUT {
+            return Err(WhisperError::TimedOut {
+                timeout_ms: decode.timeout_ms,
+                partial: self.segment_text(),
+            });
+        }
         if result != 0 {
             return Err(WhisperError::TranscriptionFailed(result));
         }
+        Ok(())
+    }
 
+    fn segment_text(&self) -> String {
         let segments = unsafe { whisper_full_n_segments(self.ctx.as_ptr()) };
         let mut output = String::new();
         for i in 0..segments {
@@ -204,10 +577,181 @@ impl WhisperContext {
                 output.push(' ');
             }
         }
-        Ok(filter_nonverbal(&output))
+        filter_nonverbal(&output)
+    }
+
+    /// Segments of the last decode; whisper reports times in centiseconds.
+    fn timed_segments(&self) -> Vec<TimedSegment> {
+        let ctx = self.ctx.as_ptr();
+        let segments = unsafe { whisper_full_n_segments(ctx) };
+        let mut output = Vec::new();
+        for i in 0..segments {
+            let text_ptr = unsafe { whisper_full_get_segment_text(ctx, i) };
+            if text_ptr.is_null() {
+                continue;
+            }
+            let text = unsafe { CStr::from_ptr(text_ptr) }.to_string_lossy();
+            let text = filter_nonverbal(text.trim());
+            if text.is_empty() {
+                continue;
+            }
+            let t0 = unsafe { whisper_full_get_segment_t0(ctx, i) };
+            let t1 = unsafe { whisper_full_get_segment_t1(ctx, i) };
+            output.push(TimedSegment {
+                start_ms: t0.max(0) as u64 * 10,
+                end_ms: t1.max(t0).max(0) as u64 * 10,
+                text,
+            });
+        }
+        output
+    }
+
+    /// Each word of the last decode with its confidence, the mean probability
+    /// of its tokens. Special tokens are skipped.
+    pub fn word_confidences(&self) -> Vec<(String, f32)> {
+        let ctx = self.ctx.as_ptr();
+        let eot = unsafe { whisper_token_eot(ctx) };
+        let segments = unsafe { whisper_full_n_segments(ctx) };
+        let mut tokens = Vec::new();
+        for segment in 0..segments {
+            let count = unsafe { whisper_full_n_tokens(ctx, segment) };
+            for token in 0..count {
+                if unsafe { whisper_full_get_token_id(ctx, segment, token) } >= eot {
+                    continue;
+                }
+                let text_ptr = unsafe { whisper_full_get_token_text(ctx, segment, token) };
+                if text_ptr.is_null() {
+                    continue;
+                }
+                let bytes = unsafe { CStr::from_ptr(text_ptr) }.to_bytes().to_vec();
+                let p = unsafe { whisper_full_get_token_p(ctx, segment, token) };
+                tokens.push((bytes, p));
+            }
+        }
+        group_words(tokens)
+    }
+
+    /// Mean log-probability of the text tokens in the last decode; special
+    /// tokens (timestamps, language, end of text) are skipped.
+    fn average_logprob(&self) -> f32 {
+        let ctx = self.ctx.as_ptr();
+        let eot = unsafe { whisper_token_eot(ctx) };
+        let segments = unsafe { whisper_full_n_segments(ctx) };
+        let mut sum = 0.0f32;
+        let mut count = 0usize;
+        for segment in 0..segments {
+            let tokens = unsafe { whisper_full_n_tokens(ctx, segment) };
+            for token in 0..tokens {
+                if unsafe { whisper_full_get_token_id(ctx, segment, token) } >= eot {
+                    continue;
+                }
+                let p = unsafe { whisper_full_get_token_p(ctx, segment, token) };
+                sum += p.max(f32::MIN_POSITIVE).ln();
+                count += 1;
+            }
+        }
+        if count == 0 {
+            f32::NEG_INFINITY
+        } else {
+            sum / count as f32
+        }
     }
 }
 
+/// Version of the whisper.cpp this binary links, such as `1.7.4`.
+pub fn version() -> String {
+    unsafe { CStr::from_ptr(whisper_version()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// CPU and backend features whisper.cpp was compiled with, as it reports them.
+pub fn system_info() -> String {
+    unsafe { CStr::from_ptr(whisper_print_system_info()) }
+        .to_string_lossy()
+        .trim()
+        .to_string()
+}
+
+/// Decoding threads: half the available cores, at least one.
+fn n_threads() -> c_int {
+    let available_threads = thread::available_parallelism().map_or(1, std::num::NonZero::get);
+    (available_threads / 2).max(1) as c_int
+}
+
+/// The most probable of `allowed` language ids, or `best` when none is allowed.
+fn pick_language(probs: &[f32], allowed: &[c_int], best: c_int) -> c_int {
+    allowed
+        .iter()
+        .copied()
+        .filter(|&id| (id as usize) < probs.len())
+        .max_by(|&a, &b| probs[a as usize].total_cmp(&probs[b as usize]))
+        .unwrap_or(best)
+}
+
+/// The `suppress_regex` pattern as a C string. A NUL byte cannot cross the
+/// FFI boundary, so it makes the pattern invalid.
+fn suppress_regex_cstring(pattern: Option<&str>) -> Result<Option<CString>, WhisperError> {
+    pattern
+        .map(|pattern| {
+            CString::new(pattern)
+                .map_err(|_| WhisperError::InvalidSuppressRegex(pattern.to_string()))
+        })
+        .transpose()
+}
+
+/// Checks a buffer before it crosses the FFI boundary and returns its length
+/// as the `int` whisper.cpp expects.
+fn sample_count(samples: &[f32]) -> Result<c_int, WhisperError> {
+    if samples.is_empty() {
+        return Err(WhisperError::EmptyInput);
+    }
+    c_int::try_from(samples.len()).map_err(|_| WhisperError::InputTooLong(samples.len()))
+}
+
+/// Drops duplicate transcripts, keeping the best score, and sorts by score.
+fn rank_candidates(candidates: Vec<Candidate>) -> Vec<Candidate> {
+    let mut ranked: Vec<Candidate> = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        match ranked
+            .iter_mut()
+            .find(|existing| existing.text == candidate.text)
+        {
+            Some(existing) if existing.avg_logprob < candidate.avg_logprob => {
+                existing.avg_logprob = candidate.avg_logprob;
+            }
+            Some(_) => {}
+            None => ranked.push(candidate),
+        }
+    }
+    ranked.sort_by(|a, b| b.avg_logprob.total_cmp(&a.avg_logprob));
+    ranked
+}
+
+/// Joins BPE tokens into words. A token starting with a space begins a new
+/// word; anything else, punctuation included, continues the current one.
+/// Bytes are joined before decoding, since a token may end mid character.
+fn group_words(tokens: impl IntoIterator<Item = (Vec<u8>, f32)>) -> Vec<(String, f32)> {
+    let mut words: Vec<(Vec<u8>, Vec<f32>)> = Vec::new();
+    for (bytes, p) in tokens {
+        match words.last_mut() {
+            Some((word, probs)) if !bytes.starts_with(b" ") => {
+                word.extend_from_slice(&bytes);
+                probs.push(p);
+            }
+            _ => words.push((bytes, vec![p])),
+        }
+    }
+    words
+        .into_iter()
+        .filter_map(|(bytes, probs)| {
+            let word = String::from_utf8_lossy(&bytes).trim().to_string();
+            let confidence = probs.iter().sum::<f32>() / probs.len() as f32;
+            (!word.is_empty()).then_some((word, confidence))
+        })
+        .collect()
+}
+
 /// Remove non-verbal annotations like [no sound], (metal clinking), *sighs*, etc.
 fn filter_nonverbal(text: &str) -> String {
     let re = Regex::new(r"\s*[\[\(][^\]\)]*[\]\)]|\s*\*[^*]+\*").unwrap();
@@ -221,3 +765,77 @@ impl Drop for WhisperContext {
         unsafe { whisper_free(self.ctx.as_ptr()) };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(text: &str, avg_logprob: f32) -> Candidate {
+        Candidate {
+            text: text.to_string(),
+            avg_logprob,
+        }
+    }
+
+    #[test]
+    fn sample_count_rejects_empty_buffers() {
+        assert!(matches!(sample_count(&[]), Err(WhisperError::EmptyInput)));
+        assert_eq!(sample_count(&[0.0; 16]).ok(), Some(16));
+    }
+
+    #[test]
+    fn suppress_regex_with_a_nul_byte_is_an_invalid_pattern() {
+        assert!(matches!(suppress_regex_cstring(None), Ok(None)));
+        assert!(matches!(
+            suppress_regex_cstring(Some("^um\0")),
+            Err(WhisperError::InvalidSuppressRegex(pattern)) if pattern == "^um\0"
+        ));
+    }
+
+    #[test]
+    fn group_words_joins_subword_tokens_and_averages_them() {
+        let token = |text: &[u8], p: f32| (text.to_vec(), p);
+        let words = group_words([
+            token(b" Hello", 0.9),
+            token(b",", 0.7),
+            token(b" caf", 0.6),
+            token(b"\xc3", 0.4),
+            token(b"\xa9", 0.2),
+            token(b" ", 0.5),
+        ]);
+
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].0, "Hello,");
+        assert!((words[0].1 - 0.8).abs() < 1e-6);
+        assert_eq!(words[1].0, "café");
+        assert!((words[1].1 - 0.4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn pick_language_prefers_allowed_languages() {
+        let probs = [0.1, 0.6, 0.25, 0.05];
+
+        assert_eq!(pick_language(&probs, &[], 1), 1);
+        assert_eq!(pick_language(&probs, &[0, 2], 1), 2);
+        assert_eq!(pick_language(&probs, &[3, 9], 1), 3);
+    }
+
+    #[test]
+    fn rank_candidates_dedupes_and_sorts_by_score() {
+        let ranked = rank_candidates(vec![
+            candidate("open the door", -0.4),
+            candidate("open the drawer", -0.2),
+            candidate("open the door", -0.1),
+            candidate("", f32::NEG_INFINITY),
+        ]);
+
+        assert_eq!(
+            ranked,
+            [
+                candidate("open the door", -0.1),
+                candidate("open the drawer", -0.2),
+                candidate("", f32::NEG_INFINITY),
+            ]
+        );
+    }
+}
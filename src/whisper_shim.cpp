@@ -1,26 +1,191 @@
 #include "whisper.h"
 
+#include <chrono>
+#include <regex>
 #include <stdbool.h>
 
-extern "C" int sv_whisper_full_configured(
-    whisper_context * ctx,
-    const float * samples,
-    int n_samples,
+// Returned instead of a whisper_full code when `suppress_regex` does not
+// compile; whisper.cpp throws std::regex_error for it.
+#define SV_ERR_SUPPRESS_REGEX (-1000)
+// Returned instead of a whisper_full code when the decode ran past its
+// timeout and was aborted. Segments finished before that stay readable.
+#define SV_ERR_TIMED_OUT (-1001)
+// Returned instead of a whisper_full code when another thread set the abort
+// flag during the decode.
+#define SV_ERR_ABORTED (-1002)
+
+struct sv_deadline {
+    std::chrono::steady_clock::time_point at;
+    bool limited;
+    bool passed;
+    // Set from another thread; read atomically.
+    const bool * abort;
+};
+
+// whisper.cpp polls this between graph computations and decoder steps.
+static bool sv_deadline_passed(void * user_data) {
+    sv_deadline * deadline = static_cast<sv_deadline *>(user_data);
+    if (deadline->abort != nullptr && __atomic_load_n(deadline->abort, __ATOMIC_RELAXED)) {
+        return true;
+    }
+    if (deadline->limited && std::chrono::steady_clock::now() >= deadline->at) {
+        deadline->passed = true;
+    }
+    return deadline->passed;
+}
+
+static whisper_full_params sv_whisper_params(
     const char * language,
     bool detect_language,
-    int n_threads
+    int n_threads,
+    bool no_context,
+    bool single_segment,
+    bool suppress_nst,
+    const char * suppress_regex
 ) {
     whisper_full_params params =
         whisper_full_default_params(WHISPER_SAMPLING_GREEDY);
     params.print_progress = false;
     params.print_realtime = false;
     params.print_timestamps = false;
+    // Dictation never reads segment times, so skip computing them; only the
+    // timed decode below turns them back on.
     params.no_timestamps = true;
-    params.single_segment = true;
+    params.no_context = no_context;
+    params.single_segment = single_segment;
     params.translate = false;
     params.n_threads = n_threads;
     params.language = language;
     params.detect_language = detect_language;
+    params.suppress_nst = suppress_nst;
+    params.suppress_regex = suppress_regex;
+    return params;
+}
+
+// Runs whisper_full, aborting it once `timeout_ms` passes (0 means no limit)
+// or once `*abort` becomes true.
+static int sv_run_full(
+    whisper_context * ctx,
+    whisper_full_params params,
+    const float * samples,
+    int n_samples,
+    int timeout_ms,
+    const bool * abort
+) {
+    sv_deadline deadline = {
+        std::chrono::steady_clock::now() + std::chrono::milliseconds(timeout_ms),
+        timeout_ms > 0,
+        false,
+        abort,
+    };
+    params.abort_callback = sv_deadline_passed;
+    params.abort_callback_user_data = &deadline;
+    try {
+        int result = whisper_full(ctx, params, samples, n_samples);
+        if (abort != nullptr && __atomic_load_n(abort, __ATOMIC_RELAXED)) {
+            return SV_ERR_ABORTED;
+        }
+        return deadline.passed ? SV_ERR_TIMED_OUT : result;
+    } catch (const std::regex_error &) {
+        return SV_ERR_SUPPRESS_REGEX;
+    }
+}
+
+extern "C" int sv_whisper_full_configured(
+    whisper_context * ctx,
+    const float * samples,
+    int n_samples,
+    const char * language,
+    bool detect_language,
+    int n_threads,
+    bool no_context,
+    bool single_segment,
+    bool suppress_nst,
+    const char * suppress_regex,
+    float temperature,
+    float temperature_inc,
+    float logprob_thold,
+    int timeout_ms,
+    const bool * abort
+) {
+    whisper_full_params params = sv_whisper_params(
+        language, detect_language, n_threads, no_context, single_segment,
+        suppress_nst, suppress_regex);
+    params.temperature = temperature;
+    params.temperature_inc = temperature_inc;
+    params.logprob_thold = logprob_thold;
 
-    return whisper_full(ctx, params, samples, n_samples);
+    return sv_run_full(ctx, params, samples, n_samples, timeout_ms, abort);
+}
+
+// Decodes with timestamps on and segment splitting allowed, so each segment
+// carries its own start and end time.
+extern "C" int sv_whisper_full_timed(
+    whisper_context * ctx,
+    const float * samples,
+    int n_samples,
+    const char * language,
+    bool detect_language,
+    int n_threads,
+    bool suppress_nst,
+    const char * suppress_regex,
+    float temperature,
+    float temperature_inc,
+    float logprob_thold,
+    int timeout_ms,
+    const bool * abort
+) {
+    whisper_full_params params = sv_whisper_params(
+        language, detect_language, n_threads, true, false,
+        suppress_nst, suppress_regex);
+    params.no_timestamps = false;
+    params.temperature = temperature;
+    params.temperature_inc = temperature_inc;
+    params.logprob_thold = logprob_thold;
+
+    return sv_run_full(ctx, params, samples, n_samples, timeout_ms, abort);
+}
+
+// Decodes at one fixed temperature, without whisper's temperature fallback,
+// so each call yields an independent candidate.
+extern "C" int sv_whisper_full_sampled(
+    whisper_context * ctx,
+    const float * samples,
+    int n_samples,
+    const char * language,
+    bool detect_language,
+    int n_threads,
+    bool no_context,
+    bool single_segment,
+    bool suppress_nst,
+    const char * suppress_regex,
+    float temperature,
+    int best_of,
+    int timeout_ms,
+    const bool * abort
+) {
+    whisper_full_params params = sv_whisper_params(
+        language, detect_language, n_threads, no_context, single_segment,
+        suppress_nst, suppress_regex);
+    params.temperature = temperature;
+    params.temperature_inc = 0.0f;
+    params.greedy.best_of = best_of;
+
+    return sv_run_full(ctx, params, samples, n_samples, timeout_ms, abort);
+}
+
+// Fills `lang_probs` (whisper_lang_max_id() + 1 entries) with the language
+// probabilities of the audio's first 30 seconds and returns the most likely
+// language id, or a negative value on failure.
+extern "C" int sv_whisper_lang_probs(
+    whisper_context * ctx,
+    const float * samples,
+    int n_samples,
+    int n_threads,
+    float * lang_probs
+) {
+    if (whisper_pcm_to_mel(ctx, samples, n_samples, n_threads) != 0) {
+        return -1;
+    }
+    return whisper_lang_auto_detect(ctx, 0, n_threads, lang_probs);
 }
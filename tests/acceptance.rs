@@ -32,6 +32,8 @@ use sv::daemon::ControlEvent;
 use sv::daemon::{DaemonConfig, DaemonDeps, DaemonOutput};
 #[cfg(feature = "test-support")]
 use sv::error::AppError;
+#[cfg(feature = "test-support")]
+use sv::feedback::NoopFeedback;
 use sv::hotkey::{self, HotkeyConfig};
 #[cfg(feature = "test-support")]
 use sv::model::{ModelLanguage, ModelSize};
@@ -256,6 +258,8 @@ fn at04_daemon_hold_key_captures_and_transcribes() -> Result<(), Box<dyn Error>>
             vec![vec![0.2; 160]],
         )),
         transcriber_factory: Box::new(TestTranscriberFactory::new(vec!["hello".to_string()])),
+        feedback: Box::new(NoopFeedback),
+        config_loader: None,
     };
     let config = daemon_config();
 
@@ -292,6 +296,8 @@ fn at05_jsonl_output_formatting() -> Result<(), Box<dyn Error>> {
         transcriber_factory: Box::new(TestTranscriberFactory::new(vec![
             "hello\n\"world\"\u{0008}".to_string(),
         ])),
+        feedback: Box::new(NoopFeedback),
+        config_loader: None,
     };
     let config = DaemonConfig {
         format: OutputFormat::Jsonl,
@@ -343,6 +349,8 @@ fn at05a_continuous_hold_key_transcribes_on_pause_before_release() -> Result<(),
         transcriber_factory: Box::new(TestTranscriberFactory::new(vec![
             "pause transcript".to_string()
         ])),
+        feedback: Box::new(NoopFeedback),
+        config_loader: None,
     };
     let config = DaemonConfig {
         vad: VadMode::Continuous,
@@ -402,6 +410,8 @@ fn at05b_continuous_long_speech_transcribes_before_release() -> Result<(), Box<d
         transcriber_factory: Box::new(TestTranscriberFactory::new(vec![
             "timed transcript".to_string()
         ])),
+        feedback: Box::new(NoopFeedback),
+        config_loader: None,
     };
     let config = DaemonConfig {
         sample_rate: 1_000,
@@ -833,6 +843,8 @@ fn at12_daemon_status_is_acknowledged() -> Result<(), Box<dyn Error>> {
     let deps = DaemonDeps {
         audio: Box::new(TestAudioBackend::new(vec!["Mic".to_string()], Vec::new())),
         transcriber_factory: Box::new(TestTranscriberFactory::new(Vec::new())),
+        feedback: Box::new(NoopFeedback),
+        config_loader: None,
     };
     let mut output = TestOutput::default();
     let shutdown = AtomicBool::new(false);
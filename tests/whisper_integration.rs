@@ -1,22 +1,14 @@
 use std::error::Error;
 use std::path::{Path, PathBuf};
 
-use sv::whisper::WhisperContext;
+use sv::batch::{self, BatchOptions};
+use sv::subtitles::{self, SubtitleOptions};
+use sv::types::SidecarFormat;
+use sv::whisper::{DecodeOptions, WhisperContext};
 
 #[test]
 fn transcribes_sample_audio() -> Result<(), Box<dyn Error>> {
-    let model_path = std::env::var("SV_MODEL_PATH").unwrap_or_else(|_| {
-        let data_home = std::env::var("XDG_DATA_HOME")
-            .map(PathBuf::from)
-            .or_else(|_| std::env::var("HOME").map(|home| PathBuf::from(home).join(".local/share")))
-            .unwrap_or_else(|_| PathBuf::from("/tmp"));
-        data_home
-            .join("soundvibes")
-            .join("models")
-            .join("ggml-base.en.bin")
-            .to_string_lossy()
-            .to_string()
-    });
+    let model_path = model_path();
     let model_path = Path::new(&model_path);
     if !model_path.exists() {
         eprintln!(
@@ -47,6 +39,79 @@ fn transcribes_sample_audio() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn writes_srt_for_sample_audio() -> Result<(), Box<dyn Error>> {
+    let model_path = PathBuf::from(model_path());
+    let sample_path = Path::new("vendor/whisper.cpp/samples/jfk.wav");
+    if !model_path.exists() || !sample_path.exists() {
+        eprintln!("Skipping test; model or sample wav missing");
+        return Ok(());
+    }
+
+    let opts = SubtitleOptions {
+        model_path,
+        language: "en".to_string(),
+        decode: DecodeOptions::default(),
+    };
+    let srt = subtitles::transcribe_to_srt(sample_path, &opts)?;
+    assert!(
+        srt.starts_with("1\n00:00:00,000 --> "),
+        "unexpected srt: {srt}"
+    );
+    assert!(
+        srt.to_lowercase().contains("ask not what your country"),
+        "unexpected srt: {srt}"
+    );
+    Ok(())
+}
+
+#[test]
+fn batch_keeps_going_past_unreadable_files() -> Result<(), Box<dyn Error>> {
+    let model_path = PathBuf::from(model_path());
+    let sample_path = Path::new("vendor/whisper.cpp/samples/jfk.wav");
+    if !model_path.exists() || !sample_path.exists() {
+        eprintln!("Skipping test; model or sample wav missing");
+        return Ok(());
+    }
+
+    let dir = std::env::temp_dir().join(format!("sv-batch-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    std::fs::copy(sample_path, dir.join("b-jfk.wav"))?;
+    std::fs::write(dir.join("a-broken.wav"), b"not a wav")?;
+    let opts = BatchOptions {
+        model_path,
+        language: "en".to_string(),
+        decode: DecodeOptions::default(),
+        sidecar: Some(SidecarFormat::Txt),
+    };
+
+    let results = batch::transcribe_dir(&dir, "*.wav", &opts)?;
+
+    assert_eq!(results.len(), 2);
+    assert!(results[0].1.is_err());
+    let text = results[1].1.as_ref().map_err(|err| err.to_string())?;
+    assert!(text.to_lowercase().contains("ask not what your country"));
+    let sidecar = std::fs::read_to_string(dir.join("b-jfk.txt"))?;
+    assert_eq!(sidecar.trim_end(), text);
+    let _ = std::fs::remove_dir_all(dir);
+    Ok(())
+}
+
+fn model_path() -> String {
+    std::env::var("SV_MODEL_PATH").unwrap_or_else(|_| {
+        let data_home = std::env::var("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|_| std::env::var("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+            .unwrap_or_else(|_| PathBuf::from("/tmp"));
+        data_home
+            .join("soundvibes")
+            .join("models")
+            .join("ggml-base.en.bin")
+            .to_string_lossy()
+            .to_string()
+    })
+}
+
 fn load_wav_samples(path: &Path) -> Result<Vec<f32>, Box<dyn Error>> {
     let mut reader = hound::WavReader::open(path)?;
     let spec = reader.spec();